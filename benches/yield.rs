@@ -0,0 +1,56 @@
+//! Measures the cost of handing values from producers over to consumers.
+//!
+//! Run with `cargo bench --bench yield`. Every line reports the time it took,
+//! on average, to get a single value out of a generator, which is mostly made
+//! up of the two context switches around it, and of whatever bookkeeping the
+//! runtime does on every yield. Producers that use none of the optional
+//! features, such as filtering or mapping on the producer side, should not pay
+//! for any of them.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use yeet::Generator;
+
+/// The number of values every measurement takes out of its generator.
+const ITEMS: u64 = 2_000_000;
+
+/// The number of times every measurement gets repeated, of which the fastest
+/// one gets reported.
+const ROUNDS: usize = 5;
+
+/// Produces the values every measurement takes.
+fn count() {
+	for i in 0..ITEMS {
+		yeet::yeet(i)
+	}
+}
+
+/// Reports the fastest time per value out of the generators made by `make`.
+fn measure(name: &str, make: impl Fn() -> Generator<u64>) {
+	let best = (0..ROUNDS)
+		.map(|_| {
+			let gen = make();
+			let started = Instant::now();
+			for value in gen {
+				black_box(value);
+			}
+			started.elapsed()
+		})
+		.min()
+		.unwrap_or(Duration::ZERO);
+
+	let per_item = best.as_secs_f64() * 1e9 / ITEMS as f64;
+	println!("{name:<16} {per_item:>8.2} ns/item");
+}
+
+fn main() {
+	measure("plain", || Generator::from_fn_ptr(count));
+	measure("filter_in_task", || Generator::from_fn_ptr(count).filter_in_task(|_| true));
+	measure("map_in_task", || {
+		Generator::<u32>::from_fn_ptr(|| {
+			for i in 0..ITEMS as u32 {
+				yeet::yeet(i)
+			}
+		})
+		.map_in_task(u64::from)
+	});
+}
@@ -0,0 +1,88 @@
+# GDB commands for inspecting suspended yeet generator tasks.
+#
+# Load this script with `source contrib/gdb/yeet.py` in a GDB session attached
+# to a program built with the `debugger` feature of the crate, and with the task
+# registry enabled on the threads of interest. It adds two commands:
+#
+#   yeet-tasks         Lists the tasks in the registry of the selected thread.
+#   yeet-bt <id>       Shows the backtrace of the suspended task with the given
+#                      identifier (x86_64 and AArch64 only).
+#
+# Both commands call into the inferior, so the process must be stopped.
+import struct
+
+import gdb
+
+RECORD = struct.Struct("<QQQIIQQQQQ")
+STATES = ["created", "running", "suspended", "finished"]
+
+
+def _records():
+    version = int(gdb.parse_and_eval("YEET_DEBUG_VERSION"))
+    if version != 2:
+        raise gdb.GdbError("unsupported yeet debug layout version %d" % version)
+
+    # The thread may have been stopped anywhere, allocator included, so the
+    # records get written to the scratch space the crate sets aside for us,
+    # rather than to memory we'd have to allocate.
+    inferior = gdb.selected_inferior()
+    count = int(gdb.parse_and_eval("yeet_debug_task_count()"))
+    buffer = int(gdb.parse_and_eval("(unsigned long) &YEET_DEBUG_SCRATCH"))
+    for index in range(count):
+        # Slots of tasks that are gone come out empty.
+        if not int(gdb.parse_and_eval("yeet_debug_task_get(%d, %d)" % (index, buffer))):
+            continue
+        fields = RECORD.unpack(bytes(inferior.read_memory(buffer, RECORD.size)))
+        (ident, name, name_len, state, _, pc, sp, fp, base, length) = fields
+        if name:
+            name = bytes(inferior.read_memory(name, name_len)).decode("utf-8", "replace")
+        else:
+            name = None
+        yield {
+            "id": ident, "name": name, "state": STATES[state],
+            "pc": pc, "sp": sp, "fp": fp, "stack": (base, base + length),
+        }
+
+
+class YeetTasks(gdb.Command):
+    """List the yeet generator tasks of the selected thread."""
+
+    def __init__(self):
+        super().__init__("yeet-tasks", gdb.COMMAND_STACK)
+
+    def invoke(self, arg, from_tty):
+        for task in _records():
+            print("task %-6d %-10s pc=%#018x sp=%#018x stack=%#x..%#x  %s" % (
+                task["id"], task["state"], task["pc"], task["sp"],
+                task["stack"][0], task["stack"][1], task["name"] or ""))
+
+
+class YeetBacktrace(gdb.Command):
+    """Show the backtrace of a suspended yeet generator task."""
+
+    def __init__(self):
+        super().__init__("yeet-bt", gdb.COMMAND_STACK)
+
+    def invoke(self, arg, from_tty):
+        ident = int(arg)
+        task = next((task for task in _records() if task["id"] == ident), None)
+        if task is None or task["state"] != "suspended" or not task["pc"]:
+            raise gdb.GdbError("no suspended task with identifier %d" % ident)
+
+        arch = gdb.selected_frame().architecture().name()
+        regs = ("$rip", "$rsp", "$rbp") if "x86-64" in arch else ("$pc", "$sp", "$x29")
+        saved = [int(gdb.parse_and_eval(reg)) for reg in regs]
+
+        # Temporarily point the registers of the thread at the saved context
+        # of the task, and put them back once we're done.
+        try:
+            for reg, value in zip(regs, (task["pc"], task["sp"], task["fp"])):
+                gdb.execute("set var %s = %d" % (reg, value), to_string=True)
+            gdb.execute("backtrace")
+        finally:
+            for reg, value in zip(regs, saved):
+                gdb.execute("set var %s = %d" % (reg, value), to_string=True)
+
+
+YeetTasks()
+YeetBacktrace()
@@ -0,0 +1,85 @@
+/* C interface to the yeet generator runtime.
+ *
+ * These functions are only available when the crate is built with the `capi`
+ * feature. See the documentation of the `yeet::capi` module for details. */
+#ifndef YEET_H
+#define YEET_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A generator of opaque pointers. */
+typedef struct YeetGenerator YeetGenerator;
+
+/* Signature of the functions that can be used as producers. */
+typedef void (*YeetProducer)(void *ctx);
+
+/* Return values of `yeet_generator_next`. */
+#define YEET_VALUE 1
+#define YEET_DONE 0
+#define YEET_PANIC (-1)
+
+/* Creates a new generator that runs `func` with `ctx` as its argument. */
+YeetGenerator *yeet_generator_new(YeetProducer func, void *ctx);
+
+/* Requests the next value from the given generator, writing it to `out`. */
+int yeet_generator_next(YeetGenerator *gen, void **out);
+
+/* Frees the given generator, cancelling its producer if needed. */
+void yeet_generator_free(YeetGenerator *gen);
+
+/* Yields a value from inside a producer. Returns non-zero on cancellation. */
+int yeet_yield(void *value);
+
+/* The functions below are only available when the crate is built with the
+ * `capi-switch` feature, on x86_64 and aarch64 targets where tasks run on the
+ * built-in context switching code. See the documentation of the
+ * `yeet::raw` module for details. */
+
+/* A suspended context, pointing to the snapshot of its registers.
+ *
+ * Snapshots are laid out as follows, in slots of eight bytes:
+ *
+ *   x86_64, 136 bytes:  RBX at 8, RSP at 48, RBP at 56, R12 through R15 from
+ *                       96, and the resume address at 128.
+ *   aarch64, 336 bytes: X19 through X29 from 152, the resume address at 256,
+ *                       SP at 264, and D8 through D15 from 272.
+ *
+ * Snapshots are only there until their context gets switched to. */
+typedef struct YeetContext YeetContext;
+
+/* What comes out of a switch, on the side that was switched to. */
+typedef struct YeetTransfer {
+	/* The context that switched away. */
+	YeetContext *context;
+	/* The pointer handed over by the context that switched away. */
+	void *data;
+} YeetTransfer;
+
+/* Signature of the functions that can be used at the root of a context. They
+ * must never return. */
+typedef void (*YeetContextFn)(YeetTransfer transfer);
+
+/* Size of the snapshots pointed to by contexts, in bytes. */
+#if defined(__x86_64__) || defined(_M_X64)
+#define YEET_SNAPSHOT_SIZE 136
+#elif defined(__aarch64__) || defined(_M_ARM64)
+#define YEET_SNAPSHOT_SIZE 336
+#endif
+
+/* Sets up a context running `entry` on the stack spanning `size` bytes from
+ * `stack`, once it gets switched to. */
+YeetContext *yeet_make_context(void *stack, size_t size, YeetContextFn entry);
+
+/* Suspends the current context and switches over to `to`, handing it `data`.
+ * Returns once another context switches back. */
+YeetTransfer yeet_switch(YeetContext *to, void *data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* YEET_H */
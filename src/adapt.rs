@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::mem::ManuallyDrop;
+use crate::{sys, Generator, Resume};
+use crate::leftover::Leftover;
+
+impl<T: 'static> Generator<T> {
+	/// Only hands the consumer the values that match the given predicate.
+	///
+	/// Unlike [`Iterator::filter`], the predicate runs on the producer, right
+	/// as it yields each value, and values that don't match it are dropped
+	/// there, without ever switching over to the consumer. For predicates that
+	/// reject most values, this saves a pair of context switches per value.
+	///
+	/// Filters may be stacked, in which case a value has to match all of them
+	/// in order to get to the consumer. Values yielded from inside a predicate
+	/// are not filtered.
+	pub fn filter_in_task(mut self, mut pred: impl FnMut(&T) -> bool + 'static) -> Self {
+		let filter = self.task.filter.take();
+		self.task.filter = Some(match filter {
+			Some(mut filter) => Box::new(move |value| filter(value) && pred(value)),
+			None => Box::new(pred),
+		});
+
+		self
+	}
+
+	/// Maps the values yielded by the producer with the given function.
+	///
+	/// Unlike [`Iterator::map`], the function runs on the producer, right as it
+	/// yields each value, so only the mapped value ever gets handed over to the
+	/// consumer. The producer keeps yielding values of type `T`, and any filters
+	/// installed with [`Generator::filter_in_task`] before the mapping still see
+	/// the values before they are mapped.
+	///
+	/// # Panic
+	/// This function panics if the generator has already been resumed.
+	pub fn map_in_task<U: 'static>(self, func: impl FnMut(T) -> U + 'static) -> Generator<U> {
+		if !self.first {
+			panic!("Tried to map the values of a generator that has already been resumed!")
+		}
+
+		/* The generator hasn't been started, so there's nothing to clean up,
+		 * other than the task, which we're carrying over. */
+		let this = ManuallyDrop::new(self);
+		let task = unsafe { std::ptr::read(&this.task) };
+
+		/* Whatever was to be done with values left over from a cancellation
+		 * was meant for values of the wrong type now. */
+		drop(unsafe { std::ptr::read(&this.leftover) });
+
+		Generator {
+			task: sys::map_task(task, func),
+			first: true,
+			buffer: VecDeque::new(),
+			poisoned: false,
+			panic: None,
+			hash: None,
+			leftover: Leftover::Discard,
+		}
+	}
+
+	/// Turns this generator into an iterator over chunks of up to `size`
+	/// values.
+	///
+	/// Rather than switching over to the consumer for every value, the
+	/// producer collects the values it yields, and only hands them over once
+	/// it has a full chunk, so that the cost of the context switches gets
+	/// split between all the values in the chunk. Only the last chunk may have
+	/// fewer than `size` values in it.
+	///
+	/// # Panic
+	/// This function panics if `size` is zero.
+	pub fn chunks(mut self, size: usize) -> Chunks<T> {
+		assert!(size > 0, "Chunk size must be greater than zero!");
+
+		/* Values already buffered by the consumer get picked up by the
+		 * iterator before the producer is resumed again. */
+		self.task.batch = Some(Batch {
+			items: Vec::with_capacity(size),
+			size,
+		});
+
+		Chunks { gen: self }
+	}
+}
+
+impl<T: 'static> Generator<Generator<T>> {
+	/// Turns this generator of generators into an iterator over the values of
+	/// all the generators it yields, in order.
+	///
+	/// The generators yielded by the producer are handed over to the consumer,
+	/// which then drives them directly, so that their values don't have to go
+	/// through the producer, which would cost an extra pair of switches for
+	/// every value. From then on, they count as children of the consumer,
+	/// rather than of the producer that created them.
+	pub fn flatten(self) -> Flatten<T> {
+		Flatten {
+			outer: self,
+			inner: None,
+		}
+	}
+}
+
+/// Values collected by a producer, to be handed over to the consumer at once.
+pub(crate) struct Batch<T> {
+	/// The values collected so far.
+	items: Vec<T>,
+	/// The number of values to collect before handing them over.
+	size: usize,
+}
+impl<T> Batch<T> {
+	/// Adds a value to the batch.
+	pub fn push(&mut self, value: T) {
+		self.items.push(value)
+	}
+
+	/// Whether the batch is ready to be handed over.
+	pub fn is_full(&self) -> bool {
+		self.items.len() >= self.size
+	}
+
+	/// Takes the values collected so far, leaving the batch empty.
+	fn take(&mut self) -> Vec<T> {
+		std::mem::replace(&mut self.items, Vec::with_capacity(self.size))
+	}
+}
+
+/// Iterator over chunks of the values of a generator.
+///
+/// This is created by [`Generator::chunks`].
+pub struct Chunks<T: 'static> {
+	gen: Generator<T>,
+}
+impl<T: 'static> Chunks<T> {
+	/// The batch of the generator.
+	fn batch(&mut self) -> &mut Batch<T> {
+		self.gen.task.batch.as_mut().unwrap()
+	}
+}
+impl<T: 'static> Iterator for Chunks<T> {
+	type Item = Vec<T>;
+
+	fn next(&mut self) -> Option<Vec<T>> {
+		loop {
+			let complete = match self.gen.resume() {
+				Resume::Value(value) => {
+					/* Values always get batched, but handle them anyway. */
+					self.batch().push(value);
+					false
+				}
+				Resume::Pending => false,
+				Resume::Complete => true,
+			};
+
+			let batch = self.batch();
+			if batch.is_full() {
+				return Some(batch.take())
+			}
+			if complete {
+				return (!batch.items.is_empty()).then(|| batch.take())
+			}
+		}
+	}
+}
+
+/// Iterator over the values of the generators yielded by a generator.
+///
+/// This is created by [`Generator::flatten`].
+pub struct Flatten<T: 'static> {
+	/// The generator yielding the generators.
+	outer: Generator<Generator<T>>,
+	/// The generator currently being drained.
+	inner: Option<Generator<T>>,
+}
+impl<T: 'static> Iterator for Flatten<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		loop {
+			if let Some(inner) = &mut self.inner {
+				match inner.next() {
+					Some(value) => return Some(value),
+					None => self.inner = None,
+				}
+			}
+			self.inner = Some(self.outer.next()?);
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let (lower, _) = self.inner.as_ref().map_or((0, None), Iterator::size_hint);
+		(lower, None)
+	}
+}
@@ -0,0 +1,132 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use crate::{with_lent, Generator};
+
+/// An arena owned by the consumer, in which the producer may allocate values.
+///
+/// Producers that yield tree-like values, such as syntax trees, would normally
+/// have to box every node. Instead, a consumer may hand one of these to the
+/// producer every time it resumes it, through [`Generator::next_in`], and the
+/// producer may then place nodes in it with [`alloc_in_consumer`], linking
+/// them together with the [`ArenaRef`] handles it gets back.
+pub struct Arena<U> {
+	/// The values in the arena.
+	values: Vec<U>,
+}
+impl<U> Arena<U> {
+	/// Creates a new empty arena.
+	pub fn new() -> Self {
+		Self { values: Vec::new() }
+	}
+
+	/// Places a value in the arena, returning a handle to it.
+	pub fn alloc(&mut self, value: U) -> ArenaRef<U> {
+		self.values.push(value);
+		ArenaRef {
+			index: self.values.len() - 1,
+			_marker: PhantomData,
+		}
+	}
+
+	/// The value behind the given handle.
+	///
+	/// Returns `None` if the handle doesn't belong to this arena.
+	pub fn get(&self, handle: ArenaRef<U>) -> Option<&U> {
+		self.values.get(handle.index)
+	}
+
+	/// The value behind the given handle.
+	///
+	/// Returns `None` if the handle doesn't belong to this arena.
+	pub fn get_mut(&mut self, handle: ArenaRef<U>) -> Option<&mut U> {
+		self.values.get_mut(handle.index)
+	}
+
+	/// The number of values in the arena.
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	/// Whether there are no values in the arena.
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Removes all the values from the arena, invalidating all of its handles.
+	pub fn clear(&mut self) {
+		self.values.clear()
+	}
+}
+impl<U> Default for Arena<U> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+impl<U> Index<ArenaRef<U>> for Arena<U> {
+	type Output = U;
+
+	fn index(&self, handle: ArenaRef<U>) -> &U {
+		&self.values[handle.index]
+	}
+}
+impl<U> IndexMut<ArenaRef<U>> for Arena<U> {
+	fn index_mut(&mut self, handle: ArenaRef<U>) -> &mut U {
+		&mut self.values[handle.index]
+	}
+}
+
+/// Handle to a value in an [`Arena`].
+///
+/// Handles don't borrow from the arena, so they may be freely yielded, stored
+/// in other values in the arena, and copied around.
+pub struct ArenaRef<U> {
+	/// The index of the value in the arena.
+	index: usize,
+	_marker: PhantomData<fn() -> U>,
+}
+impl<U> Clone for ArenaRef<U> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+impl<U> Copy for ArenaRef<U> {}
+impl<U> PartialEq for ArenaRef<U> {
+	fn eq(&self, other: &Self) -> bool {
+		self.index == other.index
+	}
+}
+impl<U> Eq for ArenaRef<U> {}
+impl<U> Hash for ArenaRef<U> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.index.hash(state)
+	}
+}
+impl<U> fmt::Debug for ArenaRef<U> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "ArenaRef({})", self.index)
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// Requests the next value from the generator, handing it the given arena
+	/// to allocate values in until it yields.
+	///
+	/// This is a shorthand for lending the arena with
+	/// [`Generator::next_lending`].
+	pub fn next_in<U: 'static>(&mut self, arena: &mut Arena<U>) -> Option<T> {
+		self.next_lending(arena)
+	}
+}
+
+/// Places a value in the arena handed to the producer by the consumer,
+/// returning a handle to it.
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator,
+/// or if the consumer has not handed an arena of values of type `U` to the
+/// current resume of the generator.
+pub fn alloc_in_consumer<U: 'static>(value: U) -> ArenaRef<U> {
+	with_lent(|arena: &mut Arena<U>| arena.alloc(value))
+}
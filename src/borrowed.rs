@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use crate::{yeet, Generator};
+
+/// A slice borrowed from the stack of a suspended producer.
+struct RawSlice<U> {
+	ptr: *const U,
+	len: usize,
+}
+impl<U> RawSlice<U> {
+	/// The slice itself.
+	///
+	/// # Safety
+	/// The producer the slice came from must not have been resumed since it
+	/// lent the slice, as it is kept alive by the call to `yeet_borrowed` the
+	/// producer is suspended in.
+	unsafe fn as_slice<'a>(&self) -> &'a [U] {
+		std::slice::from_raw_parts(self.ptr, self.len)
+	}
+}
+
+/// A generator whose producer yields slices it keeps ownership of.
+///
+/// Producers working on large buffers would normally have to copy them in
+/// order to yield them. Producers driven by this structure instead lend the
+/// buffers to the consumer with [`yeet_borrowed`], and the consumer gets a
+/// [`Borrowed`] guard for each of them, which keeps it from resuming the
+/// producer, and thus from invalidating the buffer, for as long as it exists.
+///
+/// ```rust,compile_fail
+/// fn gen() {
+///     let buf = [0u8; 16];
+///     yeet::yeet_borrowed(&buf);
+///     yeet::yeet_borrowed(&buf);
+/// }
+///
+/// let mut gen = yeet::LendingGenerator::<u8>::from_fn_ptr(gen);
+/// let first = gen.next().unwrap();
+/// let second = gen.next().unwrap();
+/// assert_eq!(*first, *second);
+/// ```
+///
+/// With the `streaming-iterator` feature, this structure also implements the
+/// `StreamingIterator` trait.
+pub struct LendingGenerator<U: 'static> {
+	gen: Generator<RawSlice<U>>,
+	/// The slice most recently lent by the producer, if it is still valid.
+	current: Option<RawSlice<U>>,
+}
+impl<U: 'static> LendingGenerator<U> {
+	/// Creates a new instance of this structure from a raw function pointer.
+	pub fn from_fn_ptr(func: fn()) -> Self {
+		Self {
+			gen: Generator::from_fn_ptr(func),
+			current: None,
+		}
+	}
+
+	/// Requests the next slice from the producer.
+	///
+	/// The slice may only be accessed until the generator is used again.
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> Option<Borrowed<'_, U>> {
+		self.advance_raw();
+		self.current.as_ref().map(|raw| Borrowed {
+			raw: RawSlice { ptr: raw.ptr, len: raw.len },
+			_marker: PhantomData,
+		})
+	}
+
+	/// Resumes the producer, replacing the current slice with the one it lends
+	/// next, if any.
+	fn advance_raw(&mut self) {
+		/* The current slice becomes invalid as soon as the producer resumes. */
+		self.current = None;
+		self.current = self.gen.next();
+	}
+}
+#[cfg(feature = "streaming-iterator")]
+impl<U: 'static> streaming_iterator::StreamingIterator for LendingGenerator<U> {
+	type Item = [U];
+
+	fn advance(&mut self) {
+		self.advance_raw()
+	}
+
+	fn get(&self) -> Option<&[U]> {
+		self.current.as_ref().map(|raw| unsafe { raw.as_slice() })
+	}
+}
+
+/// Guard for a slice lent to the consumer by a suspended producer.
+///
+/// The guard borrows the [`LendingGenerator`] it came from, so the producer
+/// can't be resumed, or dropped, while the guard exists.
+pub struct Borrowed<'a, U> {
+	raw: RawSlice<U>,
+	_marker: PhantomData<&'a [U]>,
+}
+impl<U> Deref for Borrowed<'_, U> {
+	type Target = [U];
+	fn deref(&self) -> &[U] {
+		/* The producer can't be resumed while we're borrowing the generator. */
+		unsafe { self.raw.as_slice() }
+	}
+}
+
+/// Lends the given slice to the consumer, without copying it.
+///
+/// The consumer may access the slice until it resumes the producer, at which
+/// point this function returns, and the producer may do whatever it wants with
+/// the slice.
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator
+/// driven by a [`LendingGenerator`] of values of type `U`.
+pub fn yeet_borrowed<U: 'static>(slice: &[U]) {
+	yeet(RawSlice {
+		ptr: slice.as_ptr(),
+		len: slice.len(),
+	})
+}
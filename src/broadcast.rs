@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use crate::Generator;
+use crate::panic::Payload;
+
+/// What happens when the history of a broadcast generator is full, because one
+/// of its consumers is lagging behind the others.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Lag {
+	/// The producer waits for the slowest consumer to catch up, so no consumer
+	/// ever misses a value.
+	#[default]
+	Block,
+	/// The producer carries on, dropping the oldest value in the history, which
+	/// the consumers that haven't taken it yet miss.
+	DropOldest,
+}
+
+/// A generator whose values go to every one of its consumers.
+///
+/// The producer runs as a regular generator on a thread of its own, and the
+/// values it yields are kept in a bounded history, which all of the consumers
+/// read from, each at its own pace, through handles that keep track of where
+/// they are in it. Every consumer gets a clone of every value, and values get
+/// dropped from the history once every consumer has taken them. What happens
+/// once the history is full, because a slow consumer has fallen too far behind,
+/// depends on the [`Lag`] policy of the generator.
+///
+/// New consumers are opened with [`Broadcast::subscribe`], and start off where
+/// the consumer they were opened from is. Handles may be sent to other threads,
+/// and iterating over one waits for the producer to yield the next value, if
+/// the consumer has already taken all of the values in the history.
+///
+/// ```rust
+/// use yeet::{Broadcast, Lag};
+///
+/// let first = Broadcast::<u32>::from_fn_ptr(4, Lag::Block, || yeet::yeet_all(0u32..10));
+/// let second = first.subscribe();
+///
+/// let second = std::thread::spawn(move || second.sum::<u32>());
+/// assert_eq!(first.collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+/// assert_eq!(second.join().unwrap(), 45);
+/// ```
+///
+/// Once all of the handles are gone, the producer gets cancelled, on its thread.
+///
+/// # Panic
+/// Panics raised by the producer are propagated to the first consumer that
+/// reaches the point at which it panicked. The other consumers see the
+/// generator as being done there.
+pub struct Broadcast<T: Clone + std::marker::Send + 'static> {
+	/// The state shared with the producer and the other consumers.
+	shared: Arc<Shared<T>>,
+	/// The slot holding the cursor of this consumer.
+	slot: usize,
+	/// The number of values this consumer has missed.
+	missed: u64,
+}
+impl<T: Clone + std::marker::Send + 'static> Broadcast<T> {
+	/// Runs the given function as a producer on a new thread, keeping up to
+	/// `capacity` values in its history.
+	///
+	/// # Panic
+	/// This function panics if `capacity` is zero, or if the thread could not
+	/// be spawned.
+	pub fn from_fn_ptr(capacity: usize, lag: Lag, func: fn()) -> Self {
+		Self::from_closure(capacity, lag, func)
+	}
+
+	/// Runs the given closure as a producer on a new thread, keeping up to
+	/// `capacity` values in its history.
+	///
+	/// # Panic
+	/// This function panics if `capacity` is zero, or if the thread could not
+	/// be spawned.
+	pub fn from_closure(capacity: usize, lag: Lag, func: impl FnOnce() + std::marker::Send + 'static) -> Self {
+		assert!(capacity > 0, "A broadcast generator must keep at least one value!");
+
+		let shared = Arc::new(Shared {
+			state: Mutex::new(State {
+				values: VecDeque::with_capacity(capacity),
+				first: 0,
+				cursors: vec![Some(0)],
+				done: false,
+				panic: None,
+			}),
+			changed: Condvar::new(),
+			capacity,
+			lag,
+		});
+
+		let producer = shared.clone();
+		std::thread::Builder::new()
+			.name("yeet-broadcast".into())
+			.spawn(move || produce(Generator::<T>::from_closure(func), &producer))
+			.expect("Could not spawn producer thread");
+
+		Self {
+			shared,
+			slot: 0,
+			missed: 0,
+		}
+	}
+
+	/// Opens a new consumer, which starts off at the same point in the history
+	/// as this one.
+	pub fn subscribe(&self) -> Self {
+		let mut state = self.shared.lock();
+		let cursor = state.cursors[self.slot];
+		let slot = match state.cursors.iter().position(Option::is_none) {
+			Some(slot) => {
+				state.cursors[slot] = cursor;
+				slot
+			},
+			None => {
+				state.cursors.push(cursor);
+				state.cursors.len() - 1
+			}
+		};
+
+		Self {
+			shared: self.shared.clone(),
+			slot,
+			missed: 0,
+		}
+	}
+
+	/// The number of values this consumer has missed, because they had been
+	/// dropped from the history before it got to them.
+	///
+	/// This is always zero with [`Lag::Block`].
+	pub fn missed(&self) -> u64 {
+		self.missed
+	}
+
+	/// The number of values in the history this consumer has yet to take.
+	pub fn pending(&self) -> usize {
+		let state = self.shared.lock();
+		let cursor = state.cursors[self.slot].unwrap().max(state.first);
+		(state.first + state.values.len() as u64 - cursor) as usize
+	}
+}
+impl<T: Clone + std::marker::Send + 'static> Iterator for Broadcast<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		let mut state = self.shared.lock();
+		loop {
+			let cursor = state.cursors[self.slot].unwrap();
+			if cursor < state.first {
+				self.missed += state.first - cursor;
+			}
+
+			let cursor = cursor.max(state.first);
+			if let Some(value) = state.values.get((cursor - state.first) as usize) {
+				let value = value.clone();
+				state.cursors[self.slot] = Some(cursor + 1);
+				if state.trim() {
+					self.shared.changed.notify_all();
+				}
+				return Some(value)
+			}
+			state.cursors[self.slot] = Some(cursor);
+
+			if state.done {
+				if let Some(what) = state.panic.take() {
+					drop(state);
+					std::panic::resume_unwind(what)
+				}
+				return None
+			}
+			state = self.shared.changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+		}
+	}
+}
+impl<T: Clone + std::marker::Send + 'static> Clone for Broadcast<T> {
+	fn clone(&self) -> Self {
+		self.subscribe()
+	}
+}
+impl<T: Clone + std::marker::Send + 'static> Drop for Broadcast<T> {
+	fn drop(&mut self) {
+		let mut state = self.shared.lock();
+		state.cursors[self.slot] = None;
+		state.trim();
+
+		/* The producer may be waiting on this consumer, or it may have to find
+		 * out there are no consumers left. */
+		self.shared.changed.notify_all();
+	}
+}
+
+/// The state shared between the producer and the consumers.
+struct Shared<T> {
+	/// The state itself.
+	state: Mutex<State<T>>,
+	/// Signals changes to the state.
+	changed: Condvar,
+	/// The number of values kept in the history.
+	capacity: usize,
+	/// What to do once the history is full.
+	lag: Lag,
+}
+impl<T> Shared<T> {
+	/// Locks the state.
+	fn lock(&self) -> MutexGuard<'_, State<T>> {
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+/// The history of a broadcast generator, and where its consumers are in it.
+struct State<T> {
+	/// The values that haven't been taken by every consumer yet.
+	values: VecDeque<T>,
+	/// The index of the first value in the history, counting from the first
+	/// value yielded by the producer.
+	first: u64,
+	/// The index of the next value each consumer takes, with slots for
+	/// consumers that are gone left empty.
+	cursors: Vec<Option<u64>>,
+	/// Whether the producer is done.
+	done: bool,
+	/// The payload of the panic raised by the producer, until a consumer takes
+	/// it.
+	panic: Option<Payload>,
+}
+impl<T> State<T> {
+	/// Drops the values every consumer has taken, and returns whether any were
+	/// dropped.
+	fn trim(&mut self) -> bool {
+		let slowest = self.cursors.iter()
+			.flatten()
+			.copied()
+			.min()
+			.unwrap_or(u64::MAX);
+		let taken = slowest.saturating_sub(self.first).min(self.values.len() as u64);
+		self.values.drain(..taken as usize);
+		self.first += taken;
+
+		taken > 0
+	}
+
+	/// Whether there are consumers left.
+	fn subscribed(&self) -> bool {
+		self.cursors.iter().any(Option::is_some)
+	}
+}
+
+/// Drives the given generator, adding the values it yields to the history,
+/// until either it is done, or the consumers go away.
+fn produce<T: 'static>(mut gen: Generator<T>, shared: &Shared<T>) {
+	let panic = loop {
+		let value = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next())) {
+			Ok(Some(value)) => value,
+			Ok(None) => break None,
+			Err(what) => break Some(what),
+		};
+
+		let mut state = shared.lock();
+		while state.subscribed() && state.values.len() == shared.capacity {
+			match shared.lag {
+				Lag::Block => state = shared.changed.wait(state).unwrap_or_else(PoisonError::into_inner),
+				Lag::DropOldest => {
+					state.values.pop_front();
+					state.first += 1;
+				}
+			}
+		}
+		if !state.subscribed() {
+			return
+		}
+
+		state.values.push_back(value);
+		drop(state);
+		shared.changed.notify_all();
+	};
+
+	let mut state = shared.lock();
+	state.done = true;
+	state.panic = panic;
+	drop(state);
+	shared.changed.notify_all();
+}
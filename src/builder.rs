@@ -0,0 +1,358 @@
+use std::rc::Rc;
+use crate::{depth, typed, CancelBound, CancelToken, DepthExceeded, Generator, Overrun, Yielder};
+use crate::sys::{self, AnyTask, Entry, Stack};
+use crate::sys::signal_stack::SignalStack;
+
+/// Configuration for creating [`Generator`] instances.
+///
+/// Generators created through [`Generator::from_fn_ptr`] all use the default
+/// configuration. This structure allows for the parameters of the task that
+/// runs the generator to be tweaked before it is created.
+///
+/// ```rust
+/// fn generator() {
+///     yeet::yeet(1u8);
+/// }
+///
+/// let mut gen = yeet::GeneratorBuilder::new()
+///     .stack_size(64 * 1024)
+///     .build::<u8>(generator);
+/// assert_eq!(gen.next(), Some(1));
+/// ```
+pub struct GeneratorBuilder {
+	/// The stack the task will run on.
+	stack: StackConfig,
+	/// The alignment of the stack, if it isn't the size of a page.
+	stack_alignment: Option<usize>,
+	/// The name of the task.
+	name: Option<Rc<str>>,
+	/// Whether the backtrace of the consumer should be attached to panics.
+	capture_backtraces: bool,
+	/// Whether the identity of the task should be attached to panics.
+	identify_panics: bool,
+	/// Whether every page of the stack should be touched up front.
+	pretouch: bool,
+	/// Whether the time spent running the producer should be measured.
+	measure_time: bool,
+	/// Whether the thread should be named after the task while it runs.
+	name_thread: bool,
+	/// The tokens that cancel the task.
+	tokens: Vec<CancelToken>,
+	/// Whether the producer should be leaked if dropped during an unwind.
+	leak_on_unwind: bool,
+	/// How far the producer may get once cancelled, and what happens past it.
+	cancel_bound: Option<(CancelBound, Overrun)>,
+	/// Whether the producer should be run up to its first value right away.
+	eager: bool,
+	/// The size of the alternate signal stack of the task, if it gets one.
+	signal_stack: Option<usize>,
+}
+impl GeneratorBuilder {
+	/// Creates a new builder with the default configuration.
+	pub fn new() -> Self {
+		Self {
+			stack: StackConfig::Owned(sys::DEFAULT_STACK_SIZE),
+			stack_alignment: None,
+			name: None,
+			capture_backtraces: false,
+			identify_panics: false,
+			pretouch: false,
+			measure_time: false,
+			name_thread: false,
+			tokens: Vec::new(),
+			leak_on_unwind: false,
+			cancel_bound: None,
+			eager: false,
+			signal_stack: None,
+		}
+	}
+
+	/// Gives the task a name, which is used to identify it in diagnostics.
+	pub fn name(mut self, name: impl Into<Rc<str>>) -> Self {
+		self.name = Some(name.into());
+		self
+	}
+
+	/// Attaches the backtrace of the consumer to the panics propagated from the
+	/// producer, in a [`TaskPanic`].
+	///
+	/// This changes the payload of the propagated panics, so code that expects
+	/// a specific payload must look for it through [`TaskPanic::payload`].
+	///
+	/// [`TaskPanic`]: crate::TaskPanic
+	/// [`TaskPanic::payload`]: crate::TaskPanic::payload
+	pub fn capture_backtraces(mut self, capture: bool) -> Self {
+		self.capture_backtraces = capture;
+		self
+	}
+
+	/// Names the operating system thread after the task, for as long as the
+	/// producer is running.
+	///
+	/// This lets profilers and tools such as `top -H` attribute the time spent
+	/// running the producer to it, rather than to the thread as a whole. The
+	/// thread gets its previous name back every time the producer yields. This
+	/// does nothing for tasks without a name, and on systems where threads
+	/// can't be named. Names longer than 15 bytes get truncated.
+	pub fn name_thread(mut self, name_thread: bool) -> Self {
+		self.name_thread = name_thread;
+		self
+	}
+
+	/// Measures the time spent running the producer, which gets reported in
+	/// [`Stats::time_in_producer`].
+	///
+	/// [`Stats::time_in_producer`]: crate::Stats::time_in_producer
+	pub fn measure_time(mut self, measure: bool) -> Self {
+		self.measure_time = measure;
+		self
+	}
+
+	/// Attaches the identity of the task to the panics propagated from the
+	/// producer, in a [`TaskPanic`].
+	///
+	/// The identity includes the name of the task, how deep in the tree of
+	/// generators it is, and how many values it had yielded before it
+	/// panicked. As with [`GeneratorBuilder::capture_backtraces`], this changes
+	/// the payload of the propagated panics.
+	///
+	/// [`TaskPanic`]: crate::TaskPanic
+	pub fn identify_panics(mut self, identify: bool) -> Self {
+		self.identify_panics = identify;
+		self
+	}
+
+	/// Attaches the given token to the task, so that the producer gets
+	/// cancelled once the token is.
+	///
+	/// See [`Generator::attach`].
+	pub fn cancel_token(mut self, token: &CancelToken) -> Self {
+		self.tokens.push(token.clone());
+		self
+	}
+
+	/// Leaks the producer, rather than cancelling it, if the generator gets
+	/// dropped while the consumer is unwinding from a panic.
+	///
+	/// Cancelling the producer unwinds its stack, and, if that happens while
+	/// the consumer is unwinding, a destructor in the producer that panics
+	/// aborts the whole process. Leaking the producer means none of its code
+	/// runs again, so this can't happen, at the cost of none of the values
+	/// the producer holds ever being dropped, and of its stack never being
+	/// freed. Generators that get dropped at any other time are cancelled as
+	/// usual.
+	pub fn leak_on_unwind(mut self, leak: bool) -> Self {
+		self.leak_on_unwind = leak;
+		self
+	}
+
+	/// Bounds how far the producer may get once it has been cancelled, and
+	/// chooses what happens to it if it gets past the bound.
+	///
+	/// Producers that yield from code that runs while they are being
+	/// cancelled, such as destructors, keep the consumer cancelling them for as
+	/// long as they do, which may be forever. See [`CancelBound`].
+	pub fn cancel_bound(mut self, bound: CancelBound, overrun: Overrun) -> Self {
+		self.cancel_bound = Some((bound, overrun));
+		self
+	}
+
+	/// Gives the task an alternate signal stack of the given size, which gets
+	/// installed for the thread whenever the producer runs.
+	///
+	/// Handlers installed with `SA_ONSTACK` otherwise run on the alternate
+	/// stack of the thread, if it has one, or on the stack of the producer,
+	/// which is likely smaller than the handler expects. The previous alternate
+	/// stack of the thread gets reinstalled every time the producer yields. On
+	/// systems without alternate signal stacks, this does nothing.
+	pub fn signal_stack(mut self, size: usize) -> Self {
+		self.signal_stack = Some(size);
+		self
+	}
+
+	/// Runs the producer up to the first value it yields as soon as the
+	/// generator gets created, keeping the value for the first call to
+	/// [`Generator::next`].
+	///
+	/// This moves the cost of starting the task, and of producing the first
+	/// value, out of the first call to [`Generator::next`], and into the
+	/// creation of the generator. Panics raised by the producer before it
+	/// yields its first value also surface right away, propagating out of the
+	/// function that builds the generator.
+	pub fn eager(mut self, eager: bool) -> Self {
+		self.eager = eager;
+		self
+	}
+
+	/// Sets the size of the stack that gets allocated for the task.
+	///
+	/// The size may get rounded up to satisfy the alignment requirements of
+	/// the stack.
+	///
+	/// Stacks never grow once the task has started: frames can't be moved
+	/// over to a larger stack, as the producer may be holding pointers into
+	/// them, and Rust code can't be made to carry on on another segment of
+	/// stack halfway through a function. On most targets, though, stacks are
+	/// only reserved up front, and their memory only gets committed as the
+	/// task reaches into it, so producers that may need a deep stack can ask
+	/// for a large one, at little cost other than address space. Producers
+	/// whose depth can't be bounded can also move their deepest parts over to
+	/// child tasks with [`maybe_grow`].
+	///
+	/// [`maybe_grow`]: crate::maybe_grow
+	pub fn stack_size(mut self, size: usize) -> Self {
+		self.stack = StackConfig::Owned(size);
+		self
+	}
+
+	/// Sets the alignment of the stack that gets allocated for the task.
+	///
+	/// Stacks are aligned to the size of a page by default, which is all most
+	/// producers need. Stacks that must start at a larger boundary, such as
+	/// those that get looked up by masking off the low bits of the stack
+	/// pointer, may ask for a larger alignment, which may cost more memory, or
+	/// address space, as the allocation gets padded to reach it. Windows fibers
+	/// are always aligned by the system, which ignores this.
+	///
+	/// # Panic
+	/// This function panics if the alignment is not a power of two.
+	pub fn stack_alignment(mut self, align: usize) -> Self {
+		assert!(align.is_power_of_two(), "Stacks must be aligned to a power of two!");
+		self.stack_alignment = Some(align);
+		self
+	}
+
+	/// Touches every page of the stack when the generator gets created.
+	///
+	/// Stack memory is normally only committed by the operating system as the
+	/// task first reaches into it, which means a producer may take page faults
+	/// in the middle of an iteration, whenever its call stack grows deeper than
+	/// it has ever been. Latency sensitive producers may instead choose to pay
+	/// for all of those page faults up front.
+	pub fn pretouch_stack(mut self, pretouch: bool) -> Self {
+		self.pretouch = pretouch;
+		self
+	}
+
+	/// Runs the task on the given memory region, rather than on a stack
+	/// allocated by the crate.
+	///
+	/// This allows users who manage their own memory to make sure the crate
+	/// never touches the global allocator for stacks.
+	///
+	/// # Safety
+	/// The memory region starting at `ptr` and spanning `len` bytes must be
+	/// valid for both reads and writes, must not be accessed by anything else,
+	/// and must outlive the generator, for as long as the generator exists.
+	///
+	/// The region must also be large enough to hold the deepest call stack the
+	/// generator function may reach. Overflowing it is undefined behavior.
+	///
+	/// # Panic
+	/// This function panics if the region is smaller than 4160 bytes, which is
+	/// a page of 4 KiB, plus the room taken by the first frame on the stack and
+	/// by the bookkeeping around it. No producer fits in any less than that.
+	pub unsafe fn with_stack(mut self, ptr: *mut u8, len: usize) -> Self {
+		assert!(len >= sys::MIN_STACK_SIZE, "Stacks must be at least {} bytes long!", sys::MIN_STACK_SIZE);
+		self.stack = StackConfig::External(ptr, len);
+		self
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// function as its producer.
+	pub fn build<T: 'static>(self, func: fn()) -> Generator<T> {
+		self.build_entry(Entry::Ptr(func))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// function as its producer, calling it with the given argument.
+	pub fn build_with<T: 'static, A: 'static>(self, arg: A, func: fn(A)) -> Generator<T> {
+		self.build_closure(move || func(arg))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// function as its producer, handing it a [`Yielder`].
+	///
+	/// See [`Generator::from_typed_fn`].
+	pub fn build_typed<T: 'static>(self, func: fn(Yielder<T>)) -> Generator<T> {
+		self.build_entry(typed::entry(func))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// closure as its producer.
+	pub fn build_closure<T: 'static>(self, func: impl FnOnce() + 'static) -> Generator<T> {
+		self.build_entry(Entry::Boxed(Box::new(func)))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// function as its producer, failing if it would be deeper than the limit
+	/// set with [`set_max_depth`].
+	///
+	/// The stack of the generator doesn't get allocated if it fails.
+	///
+	/// [`set_max_depth`]: crate::set_max_depth
+	pub fn try_build<T: 'static>(self, func: fn()) -> Result<Generator<T>, DepthExceeded> {
+		depth::check()?;
+		Ok(self.build(func))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// closure as its producer, failing if it would be deeper than the limit
+	/// set with [`set_max_depth`].
+	///
+	/// [`set_max_depth`]: crate::set_max_depth
+	pub fn try_build_closure<T: 'static>(self, func: impl FnOnce() + 'static) -> Result<Generator<T>, DepthExceeded> {
+		depth::check()?;
+		Ok(self.build_closure(func))
+	}
+
+	/// Creates a new generator with this configuration, which runs the given
+	/// entry as its producer.
+	fn build_entry<T: 'static>(self, func: Entry) -> Generator<T> {
+		let stack = self.stack();
+		if self.pretouch {
+			stack.touch()
+		}
+
+		let mut gen = Generator::from_parts(func, stack, self.name);
+		let header = gen.task.header();
+		header.capture_backtraces = self.capture_backtraces;
+		header.identify_panics = self.identify_panics;
+		header.measure_time = self.measure_time;
+		header.name_thread = self.name_thread;
+		header.tokens = self.tokens;
+		header.leak_on_unwind = self.leak_on_unwind;
+		header.cancel_bound = self.cancel_bound;
+		header.signal_stack = self.signal_stack.map(SignalStack::new);
+
+		if self.eager {
+			gen.peek();
+		}
+
+		gen
+	}
+
+	/// Creates the stack described by this configuration.
+	fn stack(&self) -> Stack {
+		match self.stack {
+			StackConfig::Owned(size) => match self.stack_alignment {
+				Some(align) => Stack::with_alignment(size, align),
+				None => Stack::new(size),
+			},
+			StackConfig::External(ptr, len) => unsafe { Stack::external(ptr, len) },
+		}
+	}
+}
+impl Default for GeneratorBuilder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Where the stack of a task comes from.
+enum StackConfig {
+	/// A stack of the given size, allocated by us.
+	Owned(usize),
+	/// A memory region provided by the user.
+	External(*mut u8, usize),
+}
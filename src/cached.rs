@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::Generator;
+
+impl<T: Clone + 'static> Generator<T> {
+	/// Turns this generator into a sequence that can be iterated over any
+	/// number of times, remembering the values the producer yields.
+	///
+	/// The returned handle is a cursor over the sequence, and cursors opened
+	/// from it, with [`Cached::replay`] or by cloning it, share the values.
+	/// The producer only gets resumed when a cursor reaches past the values
+	/// that have been yielded so far, so it never runs any further than the
+	/// cursor that is furthest along, and every value gets yielded once, no
+	/// matter how many cursors go over it. Cursors get clones of the values.
+	///
+	/// ```rust
+	/// use yeet::Generator;
+	///
+	/// let squares = Generator::<u64>::from_fn_ptr(|| {
+	///     yeet::yeet_all((1u64..).map(|i| i * i))
+	/// }).cached();
+	///
+	/// assert_eq!(squares.replay().take(3).collect::<Vec<_>>(), [1, 4, 9]);
+	/// assert_eq!(squares.replay().take(4).collect::<Vec<_>>(), [1, 4, 9, 16]);
+	/// assert_eq!(squares.cached_len(), 4);
+	/// ```
+	pub fn cached(self) -> Cached<T> {
+		Cached {
+			cache: Rc::new(RefCell::new(Cache {
+				gen: Some(self),
+				values: Vec::new(),
+			})),
+			position: 0,
+		}
+	}
+}
+
+/// The values shared by the cursors over a cached sequence.
+struct Cache<T: 'static> {
+	/// The generator the values come from, until it is done.
+	gen: Option<Generator<T>>,
+	/// The values yielded by the generator so far.
+	values: Vec<T>,
+}
+
+/// A cursor over a sequence of values yielded by a generator, which get
+/// remembered, so that the sequence can be iterated over any number of times.
+///
+/// See [`Generator::cached`].
+pub struct Cached<T: 'static> {
+	/// The values shared by all of the cursors.
+	cache: Rc<RefCell<Cache<T>>>,
+	/// The index of the next value this cursor hands out.
+	position: usize,
+}
+impl<T: Clone + 'static> Cached<T> {
+	/// Opens a new cursor over the sequence, starting from its first value.
+	pub fn replay(&self) -> Self {
+		Self {
+			cache: self.cache.clone(),
+			position: 0,
+		}
+	}
+
+	/// The number of values the producer has yielded so far.
+	pub fn cached_len(&self) -> usize {
+		self.cache.borrow().values.len()
+	}
+
+	/// Whether the producer is done yielding values, and the whole sequence
+	/// has been remembered.
+	pub fn is_complete(&self) -> bool {
+		self.cache.borrow().gen.is_none()
+	}
+}
+impl<T: Clone + 'static> Iterator for Cached<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		let mut cache = self.cache.borrow_mut();
+		if self.position == cache.values.len() {
+			/* This is the cursor that is furthest along, so it gets to take
+			 * the next value from the producer. */
+			match cache.gen.as_mut()?.next() {
+				Some(value) => cache.values.push(value),
+				None => {
+					cache.gen = None;
+					return None
+				}
+			}
+		}
+
+		let value = cache.values[self.position].clone();
+		self.position += 1;
+		Some(value)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let cache = self.cache.borrow();
+		let cached = cache.values.len() - self.position;
+		match &cache.gen {
+			Some(gen) => {
+				let (lower, upper) = gen.size_hint();
+				(cached.saturating_add(lower), upper.and_then(|upper| upper.checked_add(cached)))
+			},
+			None => (cached, Some(cached)),
+		}
+	}
+}
+impl<T: 'static> Clone for Cached<T> {
+	fn clone(&self) -> Self {
+		Self {
+			cache: self.cache.clone(),
+			position: self.position,
+		}
+	}
+}
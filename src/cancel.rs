@@ -0,0 +1,315 @@
+use std::any::Any;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::{cancel, try_current_header, Generator};
+use crate::panic::Payload;
+use crate::sys::AnyTask;
+
+/// Type of the reasons producers may be cancelled for.
+type Reason = Arc<dyn Any + std::marker::Send + Sync>;
+
+/// The payload producers unwind with when they get cancelled.
+///
+/// Cancelling a producer means running the destructors for every function in
+/// its call stack, and in the call stacks of every generator it has spawned.
+/// This is done with the regular panic mechanism, with a payload of this type,
+/// which gets caught at the base of the task and never reaches the consumer.
+/// Code in producers that calls [`std::panic::catch_unwind`] must resume the
+/// unwind when it catches a payload of this type, or the producer will carry
+/// on as if it had not been cancelled.
+///
+/// ```rust
+/// use yeet::Cancelled;
+///
+/// fn producer() {
+///     let result = std::panic::catch_unwind(|| yeet::yeet_all(0u32..));
+///     if let Err(what) = result {
+///         if what.is::<Cancelled>() {
+///             std::panic::resume_unwind(what)
+///         }
+///         /* Handle other panics. */
+///     }
+/// }
+/// # drop(yeet::Generator::<u32>::from_fn_ptr(producer).next());
+/// ```
+///
+/// Cancellations may carry a reason, given through [`Generator::cancel_with`]
+/// or [`CancelToken::cancel_with`], which destructors in the producer may look
+/// at through [`cancellation`], to tell, say, a shutdown from a timeout.
+#[derive(Debug, Clone, Default)]
+pub struct Cancelled {
+	/// Why the producer was cancelled, if a reason was given.
+	reason: Option<Reason>,
+}
+impl Cancelled {
+	/// The reason the producer was cancelled for, if it was given one of type
+	/// `R`.
+	pub fn reason<R: 'static>(&self) -> Option<&R> {
+		self.reason.as_deref()?.downcast_ref()
+	}
+
+	/// Whether the producer was given a reason for being cancelled.
+	pub fn has_reason(&self) -> bool {
+		self.reason.is_some()
+	}
+}
+
+/// The cancellation the current task is going through, if it is being
+/// cancelled.
+///
+/// This is meant for destructors in producers, which may behave differently
+/// depending on why the producer is being cancelled. Outside of a generator,
+/// this always returns `None`.
+pub fn cancellation() -> Option<Cancelled> {
+	let header = try_current_header()?;
+	unsafe { (*header).cancelled.clone() }
+}
+
+/// Runs the given function with the cancellation of the producer deferred
+/// until it returns, for regions of code that must not be unwound through,
+/// such as callbacks called from C.
+///
+/// Producers get cancelled by unwinding their stack, and unwinding through C
+/// frames is undefined behavior. Producers that yield from inside a callback
+/// called from C, which has frames of its own on the stack of the producer,
+/// must therefore not start unwinding at that yield. Inside the given function,
+/// a cancellation seen at a yield doesn't unwind. The yield returns as if the
+/// producer had been resumed, and, once the function returns, and the C frames
+/// are off the stack, the producer starts unwinding from there instead. Values
+/// yielded while the cancellation is deferred get dropped, and never reach the
+/// consumer.
+///
+/// The function should finish up quickly once the producer has been cancelled,
+/// which it can find out through [`cancellation`], as the consumer doing the
+/// cancelling keeps resuming the producer until it unwinds. Guards may be
+/// nested, in which case the producer unwinds once the outermost one returns.
+/// Outside of a generator, the function just gets called.
+///
+/// ```rust
+/// use yeet::Generator;
+///
+/// /// Stands in for a C function calling back into the producer.
+/// extern "C" fn for_each(callback: extern "C" fn(u32)) {
+///     for i in 0..3 {
+///         callback(i)
+///     }
+/// }
+///
+/// extern "C" fn callback(value: u32) {
+///     yeet::yeet(value)
+/// }
+///
+/// let mut gen = Generator::<u32>::from_fn_ptr(|| {
+///     yeet::ffi_guard(|| for_each(callback));
+/// });
+/// assert_eq!(gen.next(), Some(0));
+/// drop(gen);
+/// ```
+pub fn ffi_guard<R>(func: impl FnOnce() -> R) -> R {
+	defer_cancellation(func)
+}
+
+/// Runs the given function as a critical section of the producer, which a
+/// cancellation can't tear in the middle of.
+///
+/// Producers normally start unwinding as soon as they get cancelled, at
+/// whichever yield they were at, which can leave things they were halfway
+/// through, such as a file that has only been partly written, or a
+/// transaction that has only been partly applied, in a state they never meant
+/// anything else to see. Inside of the given function, a cancellation seen at
+/// a yield is instead deferred until the function returns, at which point the
+/// producer starts unwinding. The yield returns as if the producer had been
+/// resumed, and the values yielded while the cancellation is deferred get
+/// dropped. The function may check for the cancellation with
+/// [`cancellation`], so as to wrap up early.
+///
+/// This works the same as [`ffi_guard`], and the two may be nested within one
+/// another. Outside of a generator, the function just gets called.
+///
+/// ```rust
+/// use yeet::Generator;
+///
+/// let mut gen = Generator::<&str>::from_fn_ptr(|| {
+///     yeet::shield(|| {
+///         yeet::yeet("begin");
+///         yeet::yeet("write");
+///         yeet::yeet("commit");
+///     });
+///     unreachable!("the producer unwinds as soon as the shield is down")
+/// });
+/// assert_eq!(gen.next(), Some("begin"));
+///
+/// /* Dropping the generator lets the producer run up to the commit. */
+/// drop(gen);
+/// ```
+pub fn shield<R>(func: impl FnOnce() -> R) -> R {
+	defer_cancellation(func)
+}
+
+/// Runs the given function with the cancellation of the producer deferred
+/// until it returns.
+fn defer_cancellation<R>(func: impl FnOnce() -> R) -> R {
+	let Some(header) = try_current_header() else { return func() };
+	unsafe { (*header).guards += 1 }
+
+	/* Panics other than cancellations may still unwind out of the function,
+	 * in which case the guard has to come down with them. */
+	struct Release;
+	impl Drop for Release {
+		fn drop(&mut self) {
+			/* The task may have moved while the function was running. */
+			if let Some(header) = try_current_header() {
+				unsafe { (*header).guards -= 1 }
+			}
+		}
+	}
+
+	let release = Release;
+	let result = func();
+	drop(release);
+
+	let header = try_current_header().unwrap();
+	unsafe {
+		if (*header).guards == 0 && std::mem::take(&mut (*header).deferred) {
+			cancel()
+		}
+	}
+
+	result
+}
+
+/// How far a producer may get once it has been cancelled, before it is
+/// considered stuck.
+///
+/// Producers keep running while they unwind from their cancellation, and code
+/// that runs during the unwind, such as destructors, may yield. A producer
+/// that keeps yielding forever would keep the consumer cancelling it forever
+/// too, so generators may be given a bound on how long that may take, with
+/// [`GeneratorBuilder::cancel_bound`], past which the producer gets dealt with
+/// as laid out by an [`Overrun`].
+///
+/// Bounds only get checked whenever the producer yields, so a producer stuck
+/// in a loop that doesn't yield still keeps the consumer waiting.
+///
+/// [`GeneratorBuilder::cancel_bound`]: crate::GeneratorBuilder::cancel_bound
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CancelBound {
+	/// The producer may yield up to this many times.
+	Yields(usize),
+	/// The producer may keep running for up to this long.
+	Time(Duration),
+}
+
+/// What happens to a producer that is still running past its [`CancelBound`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Overrun {
+	/// The producer is left where it is, and is leaked along with everything
+	/// on its stack, none of which ever gets dropped. Closing the generator
+	/// reports this as a panic raised by the producer.
+	#[default]
+	Leak,
+	/// The process gets aborted.
+	Abort,
+}
+
+/// A signal that cancels every generator it is attached to.
+///
+/// Tokens are cheap to clone, and all clones of a token share the same signal,
+/// which may be triggered from any thread, such as a handler for a shutdown
+/// request. Once a token has been cancelled, every producer it is attached to
+/// gets cancelled the next time it tries to yield, either a value or with
+/// [`yield_now`]: rather than suspending, the producer unwinds, as it would if
+/// its generator had been dropped, and the value it was trying to yield gets
+/// dropped along with it. Its consumer then sees the generator as done.
+///
+/// Attaching the same token to every stage of a tree of generators tears the
+/// whole tree down promptly, with every producer in it running its
+/// destructors, no matter which of them happens to be running at the time.
+///
+/// ```rust
+/// use yeet::{CancelToken, Generator};
+///
+/// let token = CancelToken::new();
+/// let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..));
+/// gen.attach(&token);
+///
+/// assert_eq!(gen.next(), Some(0));
+/// token.cancel();
+/// assert_eq!(gen.next(), None);
+/// ```
+///
+/// [`yield_now`]: crate::yield_now
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<TokenState>);
+impl CancelToken {
+	/// Creates a new token that has not been cancelled.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Cancels every generator this token is attached to.
+	pub fn cancel(&self) {
+		self.0.cancelled.store(true, Ordering::Release)
+	}
+
+	/// Cancels every generator this token is attached to, for the given
+	/// reason.
+	///
+	/// The reason is only kept if this token had not been cancelled before,
+	/// with a reason or without one.
+	pub fn cancel_with<R: Any + std::marker::Send + Sync>(&self, reason: R) {
+		/* The reason has to be in place before the token reads as cancelled,
+		 * so it can't be set by whoever flips the flag over. */
+		if self.is_cancelled() {
+			return
+		}
+		let _ = self.0.reason.set(Arc::new(reason));
+		self.cancel()
+	}
+
+	/// Whether this token has been cancelled.
+	pub fn is_cancelled(&self) -> bool {
+		self.0.cancelled.load(Ordering::Acquire)
+	}
+
+	/// The cancellation of this token, if it has been cancelled.
+	pub(crate) fn cancellation(&self) -> Option<Cancelled> {
+		self.is_cancelled().then(|| Cancelled {
+			reason: self.0.reason.get().cloned(),
+		})
+	}
+}
+
+/// State shared by all the clones of a [`CancelToken`].
+#[derive(Debug, Default)]
+struct TokenState {
+	/// Whether the token has been cancelled.
+	cancelled: AtomicBool,
+	/// The reason the token was cancelled for, if it was given one.
+	reason: OnceLock<Reason>,
+}
+
+impl<T: 'static> Generator<T> {
+	/// Attaches the given token to this generator, so that the producer gets
+	/// cancelled once the token is.
+	///
+	/// A generator may have any number of tokens attached to it, and gets
+	/// cancelled as soon as any one of them is.
+	pub fn attach(&mut self, token: &CancelToken) -> &mut Self {
+		self.task.header().tokens.push(token.clone());
+		self
+	}
+
+	/// Cancels the producer for the given reason, which its destructors may
+	/// look at through [`cancellation`], and reports any problems the producer
+	/// ran into while being cancelled, as [`Generator::close`] does.
+	///
+	/// Dropping the generator also cancels the producer, but without a reason.
+	pub fn cancel_with<R: Any + std::marker::Send + Sync>(mut self, reason: R) -> Result<(), Payload> {
+		self.task.header().cancelled = Some(Cancelled {
+			reason: Some(Arc::new(reason)),
+		});
+		self.close()
+	}
+}
@@ -0,0 +1,114 @@
+//! C interface to the generator runtime.
+//!
+//! This module exposes a small set of functions with C linkage, which allow
+//! C and C++ host applications to create and drive generators, and allow C
+//! functions running as producers to yield values back to their consumers. The
+//! declarations for these functions can be found in `include/yeet.h`.
+//!
+//! Values crossing this interface are always opaque pointers, and the crate
+//! makes no attempt at managing the memory they may point to.
+//!
+//! # Building
+//! This module is only available when the `capi` feature is enabled. In order
+//! to produce a library that can be linked into a C program, build the crate
+//! with the desired crate type, such as with
+//! `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! # Unwinding
+//! Unwinding across C frames is not something we can do, so none of these
+//! functions ever unwind. Panics raised by producers are reported through the
+//! return value of [`yeet_generator_next`], and cancellation is reported to C
+//! producers through the return value of [`yeet_yield`], in which case they
+//! are expected to return as soon as possible.
+//!
+//! # Context Switching
+//! With the `capi-switch` feature enabled, on targets where contexts get
+//! switched by our own assembly, this module also exposes the bare context
+//! switch in [`crate::raw`], through [`yeet_make_context`] and
+//! [`yeet_switch`], with a stable ABI, so that contexts may be switched to from
+//! either side of a mixed-language program.
+use std::ffi::c_void;
+use std::panic::AssertUnwindSafe;
+use crate::{Generator, Send, Yield, yield_internal};
+
+#[cfg(all(feature = "capi-switch", not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub use switch::{yeet_make_context, yeet_switch, YeetContextFn, YeetTransfer, YEET_SNAPSHOT_SIZE};
+
+#[cfg(all(feature = "capi-switch", not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod switch;
+
+/// A generator of opaque pointers, as seen from C.
+pub type YeetGenerator = Generator<*mut c_void>;
+
+/// Signature of the functions that can be used as C producers.
+pub type YeetProducer = extern "C" fn(*mut c_void);
+
+/// [`yeet_generator_next`] has yielded a value.
+pub const YEET_VALUE: i32 = 1;
+/// [`yeet_generator_next`] has found the producer to be done.
+pub const YEET_DONE: i32 = 0;
+/// [`yeet_generator_next`] has found the producer to have panicked.
+pub const YEET_PANIC: i32 = -1;
+
+/// Creates a new generator that runs `func` with `ctx` as its argument.
+///
+/// The returned generator must be freed with [`yeet_generator_free`].
+#[no_mangle]
+pub extern "C" fn yeet_generator_new(func: YeetProducer, ctx: *mut c_void) -> *mut YeetGenerator {
+	let ctx = ctx as usize;
+	let gen = YeetGenerator::from_boxed(Box::new(move || func(ctx as *mut c_void)));
+
+	Box::into_raw(Box::new(gen))
+}
+
+/// Requests the next value from the given generator.
+///
+/// Returns [`YEET_VALUE`] and writes the value to `out` if the producer has
+/// yielded a value, [`YEET_DONE`] if the producer is done, and [`YEET_PANIC`]
+/// if the producer has panicked. Once a producer has panicked, it should be
+/// considered to be done.
+///
+/// # Safety
+/// `gen` must have been created by [`yeet_generator_new`] on the current
+/// thread and must not have been freed, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn yeet_generator_next(gen: *mut YeetGenerator, out: *mut *mut c_void) -> i32 {
+	let gen = &mut *gen;
+	match std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())) {
+		Ok(Some(value)) => {
+			out.write(value);
+			YEET_VALUE
+		}
+		Ok(None) => YEET_DONE,
+		Err(_) => YEET_PANIC,
+	}
+}
+
+/// Frees the given generator, cancelling its producer if it is still running.
+///
+/// # Safety
+/// `gen` must have been created by [`yeet_generator_new`] on the current
+/// thread and must not have been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn yeet_generator_free(gen: *mut YeetGenerator) {
+	let gen = Box::from_raw(gen);
+
+	/* Whatever happens during the cancellation must stay on this side. */
+	let _ = std::panic::catch_unwind(AssertUnwindSafe(move || drop(gen)));
+}
+
+/// Yields the given value to the consumer of the current generator.
+///
+/// Returns zero if the producer should continue running, and non-zero if the
+/// generator has been cancelled, in which case the producer should return as
+/// soon as it can. Yielding after cancellation is allowed, but any values
+/// yielded are discarded.
+///
+/// Calling this function from outside a generator aborts the process.
+#[no_mangle]
+pub extern "C" fn yeet_yield(value: *mut c_void) -> i32 {
+	match yield_internal(Yield::Value(value)) {
+		Send::Continue | Send::Skip(_) => 0,
+		Send::Cancel => 1,
+	}
+}
@@ -0,0 +1,79 @@
+//! C interface to the bare context switch in [`crate::raw`].
+//!
+//! These functions are the C side of [`raw::make_context`] and [`raw::switch`],
+//! with the same semantics, and contexts may go back and forth between the two
+//! sides, so that a context suspended from Rust may be switched to from C,
+//! and the other way around. A [`Context`] is passed to C as the pointer it
+//! holds, which is what [`Context::into_raw`] and [`Context::from_raw`] deal
+//! in, and [`Transfer`] has the same layout as `YeetTransfer`.
+//!
+//! # Snapshots
+//! Context pointers point to the snapshot of the registers of the context they
+//! stand for, which lives on the stack of the context, and is only there until
+//! the context gets switched to. Snapshots hold the registers the calling
+//! convention of the target has the callee preserve, along with the address
+//! and the stack pointer the context resumes from. Their layout is fixed, and
+//! is as follows, with every slot being eight bytes wide:
+//!
+//! | Target    | Size  | Offset | Register                              |
+//! |-----------|-------|--------|---------------------------------------|
+//! | `x86_64`  | 136   | 8      | `RBX`                                 |
+//! |           |       | 48     | `RSP`                                 |
+//! |           |       | 56     | `RBP`                                 |
+//! |           |       | 96     | `R12` through `R15`, in order         |
+//! |           |       | 128    | Resume address                        |
+//! | `aarch64` | 336   | 152    | `X19` through `X29`, in order         |
+//! |           |       | 240    | `X30`, loaded, but never saved        |
+//! |           |       | 256    | Resume address                        |
+//! |           |       | 264    | `SP`                                  |
+//! |           |       | 272    | `D8` through `D15`, in order          |
+//!
+//! Slots not listed are unused. Snapshots are meant for inspection, such as by
+//! debuggers and profilers unwinding suspended contexts: writing to them, or
+//! loading them through anything other than [`yeet_switch`], is not supported.
+//!
+//! [`raw::make_context`]: crate::raw::make_context
+//! [`raw::switch`]: crate::raw::switch
+use std::ffi::c_void;
+use crate::raw::{self, Context, Start, Transfer};
+use crate::sys::Snapshot;
+
+/// The result of a switch, as seen from C.
+pub type YeetTransfer = Transfer;
+
+/// Signature of the C functions that can be used at the root of a context.
+///
+/// They must never return, as there is nothing to return to. Those that do
+/// abort the process.
+pub type YeetContextFn = extern "C" fn(YeetTransfer);
+
+/// The size of the snapshots context pointers point to, in bytes.
+pub const YEET_SNAPSHOT_SIZE: usize = size_of::<Snapshot>();
+
+/// Sets up a context that runs `entry` on the stack spanning `size` bytes from
+/// `stack`, once it gets switched to with [`yeet_switch`].
+///
+/// # Safety
+/// The stack must be valid for reads and writes, outlive the context, and not
+/// be used by anything else while the context is live. It must also be large
+/// enough to hold the snapshot of the context, and whatever `entry` needs.
+#[no_mangle]
+pub unsafe extern "C" fn yeet_make_context(stack: *mut c_void, size: usize, entry: YeetContextFn) -> Context {
+	raw::make_context_below(stack as usize + size, Start::C(entry))
+}
+
+/// Suspends the current context, and switches over to `to`, handing it `data`.
+///
+/// This returns once some other context switches back to this one, with the
+/// context that did, and the pointer it handed over.
+///
+/// # Safety
+/// `to` must have been set up by [`yeet_make_context`] or by
+/// [`raw::make_context`], or have come out of a switch, on the current thread,
+/// and its stack must still be alive. Nothing may unwind across the switch.
+///
+/// [`raw::make_context`]: crate::raw::make_context
+#[no_mangle]
+pub unsafe extern "C" fn yeet_switch(to: Context, data: *mut c_void) -> YeetTransfer {
+	raw::switch(to, data as *mut ())
+}
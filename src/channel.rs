@@ -0,0 +1,61 @@
+//! Bridges between generators and [`crossbeam_channel`] channels.
+//!
+//! Generators never leave the thread they were created on, but the values they
+//! yield often have to. The functions in this module connect generators to
+//! channels, so that pipelines of generators may be split across threads.
+use crossbeam_channel::{Receiver, Sender};
+use crate::Generator;
+
+impl<T: std::marker::Send + 'static> Generator<T> {
+	/// Connects this generator to a new bounded channel with the given
+	/// capacity.
+	///
+	/// The values yielded by the generator only get sent over the channel as
+	/// the returned [`Pump`] drives the generator, which must happen on the
+	/// thread the generator was created on. The receiving end of the channel
+	/// may be sent to any thread.
+	pub fn into_channel(self, capacity: usize) -> (Receiver<T>, Pump<T>) {
+		let (tx, rx) = crossbeam_channel::bounded(capacity);
+		(rx, Pump { gen: self, tx })
+	}
+
+	/// Creates a generator that yields the values received from the given
+	/// channel, until all of its senders are gone.
+	pub fn from_crossbeam(rx: Receiver<T>) -> Self {
+		Self::from_closure(move || {
+			for value in rx {
+				crate::yeet(value)
+			}
+		})
+	}
+}
+
+/// Sends the values yielded by a generator over a channel.
+///
+/// This is created by [`Generator::into_channel`]. Panics raised by the
+/// producer are propagated out of the functions that drive it, at which point
+/// the channel gets disconnected.
+pub struct Pump<T: 'static> {
+	gen: Generator<T>,
+	tx: Sender<T>,
+}
+impl<T: std::marker::Send + 'static> Pump<T> {
+	/// Sends the next value yielded by the generator over the channel, blocking
+	/// until there is room for it.
+	///
+	/// Returns whether there might be more values to send, which is not the
+	/// case once the generator is done, or once the receiving end of the
+	/// channel is gone.
+	pub fn step(&mut self) -> bool {
+		match self.gen.next() {
+			Some(value) => self.tx.send(value).is_ok(),
+			None => false,
+		}
+	}
+
+	/// Sends all the values yielded by the generator over the channel, until
+	/// either it is done, or the receiving end of the channel is gone.
+	pub fn run(mut self) {
+		while self.step() {}
+	}
+}
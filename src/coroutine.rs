@@ -0,0 +1,67 @@
+//! A resume interface shaped after the unstable `Coroutine` trait.
+//!
+//! The standard library has a trait for coroutines, `std::ops::Coroutine`,
+//! which is not available on stable Rust yet. The [`Coroutine`] trait in this
+//! module mirrors its shape, down to the names of its items, so that code
+//! written against it today maps onto the standard trait one to one once it
+//! gets stabilized.
+//!
+//! ```rust
+//! use std::pin::Pin;
+//! use yeet::Generator;
+//! use yeet::coroutine::{Coroutine, CoroutineState};
+//!
+//! let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32));
+//! assert_eq!(Pin::new(&mut gen).resume(()), CoroutineState::Yielded(1));
+//! assert_eq!(Pin::new(&mut gen).resume(()), CoroutineState::Complete(()));
+//! ```
+use std::pin::Pin;
+use crate::Generator;
+
+/// The outcome of resuming a [`Coroutine`].
+///
+/// This mirrors `std::ops::CoroutineState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CoroutineState<Y, R> {
+	/// The coroutine has suspended with the given value.
+	Yielded(Y),
+	/// The coroutine has completed with the given value.
+	Complete(R),
+}
+
+/// A coroutine that can be resumed with an argument of type `R`.
+///
+/// This mirrors `std::ops::Coroutine`.
+pub trait Coroutine<R = ()> {
+	/// The type of the values the coroutine yields.
+	type Yield;
+	/// The type of the value the coroutine completes with.
+	type Return;
+
+	/// Resumes the coroutine with the given argument, until it either yields
+	/// or completes.
+	///
+	/// # Panic
+	/// Implementations may panic if the coroutine is resumed after it has
+	/// completed.
+	fn resume(self: Pin<&mut Self>, arg: R) -> CoroutineState<Self::Yield, Self::Return>;
+}
+
+/// Generators go through the checkpoints reached by their producers as they
+/// would with [`Generator::next`], and complete with the unit value. Resuming
+/// them after they have completed has them complete again.
+impl<T: 'static> Coroutine for Generator<T> {
+	type Yield = T;
+	type Return = ();
+
+	fn resume(self: Pin<&mut Self>, _: ()) -> CoroutineState<T, ()> {
+		match self.get_mut().next() {
+			Some(value) => CoroutineState::Yielded(value),
+			None => CoroutineState::Complete(()),
+		}
+	}
+}
+
+/* Values are never pinned while they're in the generator, and the task gets
+ * moved around freely between resumes. */
+impl<T: 'static> Unpin for Generator<T> {}
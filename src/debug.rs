@@ -0,0 +1,119 @@
+//! Debugger integration.
+//!
+//! This module exports a handful of symbols with C linkage that debugger
+//! scripts can call into in order to enumerate the tasks in the [registry] of
+//! the thread being inspected, along with the registers they had saved the last
+//! time they yielded. This allows a debugger to show backtraces for generators
+//! that are suspended in a hung process, which would otherwise be invisible.
+//!
+//! A script for GDB that makes use of these symbols can be found in
+//! `contrib/gdb/yeet.py`.
+//!
+//! Only tasks created while the registry was enabled on a thread can be found
+//! through these symbols, so programs that want to be debuggable this way must
+//! call [`registry::enable`] on the threads they care about.
+//!
+//! [registry]: crate::registry
+//! [`registry::enable`]: crate::registry::enable
+use crate::registry::{self, TaskState};
+
+/// Version of the layout of [`YeetDebugTask`], and of the way the functions
+/// in here get called.
+///
+/// This gets bumped every time either of them changes, so that scripts can
+/// make sure they are reading the records correctly.
+#[no_mangle]
+pub static YEET_DEBUG_VERSION: u32 = 2;
+
+/// Description of a task, laid out in a way debugger scripts can rely on.
+///
+/// All fields are either 64-bit or 32-bit wide on 64-bit targets, and there is
+/// a 32-bit padding after `state`, for a total of 72 bytes.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct YeetDebugTask {
+	/// The identifier of the task.
+	pub id: u64,
+	/// Pointer to the UTF-8 name of the task, or null if it has none.
+	pub name: *const u8,
+	/// Length of the name of the task, in bytes.
+	pub name_len: usize,
+	/// The state of the task: 0 if created, 1 if running, 2 if suspended, and
+	/// 3 if finished.
+	pub state: u32,
+	/// The address the task will resume from, or zero if not known.
+	pub pc: usize,
+	/// The saved stack pointer of the task, or zero if not known.
+	pub sp: usize,
+	/// The saved frame pointer of the task, or zero if not known.
+	pub fp: usize,
+	/// The lowest address of the stack of the task.
+	pub stack_base: usize,
+	/// The length of the stack of the task, in bytes.
+	pub stack_len: usize,
+}
+
+/// Room for a single record, for scripts to hand to [`yeet_debug_task_get`],
+/// so that they don't have to allocate memory in the process being debugged,
+/// which may well have been stopped inside of the allocator.
+#[no_mangle]
+pub static mut YEET_DEBUG_SCRATCH: YeetDebugTask = YeetDebugTask {
+	id: 0,
+	name: std::ptr::null(),
+	name_len: 0,
+	state: 0,
+	pc: 0,
+	sp: 0,
+	fp: 0,
+	stack_base: 0,
+	stack_len: 0,
+};
+
+/// Returns the number of slots in the registry of the current thread, which is
+/// zero if the thread was stopped in the middle of an update to it.
+///
+/// Slots hold on to tasks that are gone until the registry gets around to
+/// pruning them, which this leaves to the thread, so some of them may turn
+/// out to be empty.
+#[no_mangle]
+pub extern "C" fn yeet_debug_task_count() -> usize {
+	registry::try_len().unwrap_or(0)
+}
+
+/// Writes the description of the task in the slot at the given index in the
+/// registry of the current thread to `out`.
+///
+/// Returns whether there was a live task in the slot, which scripts should
+/// skip over if there wasn't, rather than stop at it. The name pointer in the
+/// description is only valid for as long as the task is alive.
+///
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn yeet_debug_task_get(index: usize, out: *mut YeetDebugTask) -> bool {
+	let Some(task) = registry::try_get(index) else { return false };
+	let context = task.context.unwrap_or_default();
+	let (name, name_len) = match task.name.as_deref() {
+		Some(name) => (name.as_ptr(), name.len()),
+		None => (std::ptr::null(), 0),
+	};
+
+	out.write(YeetDebugTask {
+		id: task.id.as_u64(),
+		name,
+		name_len,
+		state: match task.state {
+			TaskState::Created => 0,
+			TaskState::Running => 1,
+			TaskState::Suspended => 2,
+			TaskState::Finished => 3,
+		},
+		pc: context.pc,
+		sp: context.sp,
+		fp: context.fp,
+		stack_base: task.stack.start,
+		stack_len: task.stack.len(),
+	});
+
+	true
+}
@@ -0,0 +1,33 @@
+use crate::{current_header, Generator};
+use crate::sys::AnyTask;
+
+impl<T: 'static> Generator<T> {
+	/// Tells the producer how many more values the consumer intends to take.
+	///
+	/// The producer may look at the demand through [`remaining_demand`], and
+	/// use it to avoid preparing values that will never be asked for. Every
+	/// value the producer yields from then on counts towards the demand, until
+	/// it runs out, or until it gets replaced or cleared. This is only a hint:
+	/// the consumer may still ask for more values, and the producer may still
+	/// yield them.
+	pub fn set_demand(&mut self, n: usize) {
+		self.task.header().demand = Some(n);
+	}
+
+	/// Takes back the demand set with [`Generator::set_demand`], leaving it
+	/// unknown to the producer.
+	pub fn clear_demand(&mut self) {
+		self.task.header().demand = None;
+	}
+}
+
+/// How many more values the consumer intends to take, if it has said so.
+///
+/// See [`Generator::set_demand`].
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn remaining_demand() -> Option<usize> {
+	let header = current_header();
+	unsafe { (*header).demand }
+}
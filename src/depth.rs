@@ -0,0 +1,69 @@
+use std::cell::Cell;
+use std::fmt;
+use crate::{position, Generator};
+
+thread_local! {
+	/// The deepest a task may be in the tree of generators of this thread.
+	static MAX_DEPTH: Cell<Option<usize>> = const { Cell::new(None) }
+}
+
+/// Limits how deep the tree of generators of the current thread may grow.
+///
+/// Every generator runs on a stack of its own, so runaway recursion through
+/// generators eats up memory a whole stack at a time, and may take a long time
+/// to fail. Once a limit has been set, generators that would be deeper than the
+/// limit, with generators created from outside of any generator being one deep,
+/// can't be created: fallible constructors, such as
+/// [`Generator::try_from_fn_ptr`] and [`GeneratorBuilder::try_build`], return
+/// [`DepthExceeded`], and infallible ones panic.
+///
+/// Passing `None` lifts the limit, which is the default.
+///
+/// [`GeneratorBuilder::try_build`]: crate::GeneratorBuilder::try_build
+pub fn set_max_depth(limit: Option<usize>) {
+	MAX_DEPTH.set(limit)
+}
+
+/// The limit set on the depth of the tree of generators of the current thread,
+/// if there is one.
+pub fn max_depth() -> Option<usize> {
+	MAX_DEPTH.get()
+}
+
+/// Checks whether a generator created by the current task would be within the
+/// depth limit.
+pub(crate) fn check() -> Result<(), DepthExceeded> {
+	let Some(limit) = MAX_DEPTH.get() else { return Ok(()) };
+	let (_, depth) = position();
+	if depth > limit {
+		return Err(DepthExceeded { depth, limit })
+	}
+
+	Ok(())
+}
+
+impl<T: 'static> Generator<T> {
+	/// Creates a new instance of this structure from a raw function pointer,
+	/// failing if it would be deeper than the limit set with
+	/// [`set_max_depth`].
+	pub fn try_from_fn_ptr(func: fn()) -> Result<Self, DepthExceeded> {
+		check()?;
+		Ok(Self::from_fn_ptr(func))
+	}
+}
+
+/// Error returned when a generator would be deeper than the limit set with
+/// [`set_max_depth`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DepthExceeded {
+	/// How deep the generator would have been.
+	pub depth: usize,
+	/// The limit in effect.
+	pub limit: usize,
+}
+impl fmt::Display for DepthExceeded {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "generator would be {} deep, past the limit of {}", self.depth, self.limit)
+	}
+}
+impl std::error::Error for DepthExceeded {}
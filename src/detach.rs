@@ -0,0 +1,116 @@
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+use crate::{current_header, try_current_header, Generator};
+use crate::sys::AnyTask;
+
+impl<T: 'static> Generator<T> {
+	/// Takes the generators with values of type `U` that the producer has
+	/// detached so far, in the order they were detached.
+	///
+	/// Generators get detached through [`detach`] and [`Detach`].
+	pub fn take_detached<U: 'static>(&mut self) -> Vec<Generator<U>> {
+		let detached = std::mem::take(&mut self.task.header().detached);
+		let (taken, kept) = detached.into_iter()
+			.partition::<Vec<_>, _>(|gen| gen.is::<Generator<U>>());
+		self.task.header().detached = kept;
+
+		taken.into_iter()
+			.map(|gen| *gen.downcast::<Generator<U>>().unwrap())
+			.collect()
+	}
+
+	/// Cancels the producer, and takes the generators with values of type `U`
+	/// it had detached, including the ones it detached while being cancelled.
+	///
+	/// This lets the consumer of an intermediate stage in a pipeline tear the
+	/// stage down without losing the children it was driving, which may still
+	/// have useful values to yield.
+	///
+	/// Panics raised by the producer while it is being cancelled are ignored,
+	/// as they would be if the generator were dropped.
+	pub fn into_detached<U: 'static>(mut self) -> Vec<Generator<U>> {
+		let _ = self.cancel_task();
+		self.take_detached()
+	}
+}
+
+/// Hands the given generator over to the consumer of the current task.
+///
+/// By default, generators created by a producer are owned by it, and get
+/// cancelled along with it. Detached generators instead get kept by the
+/// generator running the current task, alongside it, and outlive the producer,
+/// until the consumer takes them with [`Generator::take_detached`] or
+/// [`Generator::into_detached`]. If the consumer never takes them, they get
+/// cancelled when the generator running the current task is dropped.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn detach<U: 'static>(gen: Generator<U>) {
+	let header = current_header();
+	unsafe { (*header).detached.push(Box::new(gen) as Box<dyn Any>) }
+}
+
+/// A generator that gets detached, rather than cancelled, if the producer that
+/// owns it unwinds.
+///
+/// This changes the policy for a single child of a producer: when the producer
+/// gets cancelled, or panics, the child is handed over to the consumer as if
+/// through [`detach`], rather than being cancelled along with the producer. If
+/// the producer drops the guard without unwinding, the child is dropped as
+/// usual.
+///
+/// ```rust
+/// use yeet::{Detach, Generator};
+///
+/// fn stage() {
+///     let mut child = Detach::new(Generator::<u32>::from_fn_ptr(|| {
+///         yeet::yeet_all(0u32..3)
+///     }));
+///     for value in child.by_ref() {
+///         yeet::yeet(value)
+///     }
+/// }
+///
+/// let mut gen = Generator::<u32>::from_fn_ptr(stage);
+/// assert_eq!(gen.next(), Some(0));
+///
+/// let mut children = gen.into_detached::<u32>();
+/// assert_eq!(children[0].by_ref().collect::<Vec<_>>(), [1, 2]);
+/// ```
+pub struct Detach<U: 'static>(Option<Generator<U>>);
+impl<U: 'static> Detach<U> {
+	/// Wraps the given generator.
+	pub fn new(gen: Generator<U>) -> Self {
+		Self(Some(gen))
+	}
+
+	/// Takes the generator back, giving up on having it detached.
+	pub fn into_inner(mut self) -> Generator<U> {
+		self.0.take().unwrap()
+	}
+}
+impl<U: 'static> Deref for Detach<U> {
+	type Target = Generator<U>;
+
+	fn deref(&self) -> &Generator<U> {
+		self.0.as_ref().unwrap()
+	}
+}
+impl<U: 'static> DerefMut for Detach<U> {
+	fn deref_mut(&mut self) -> &mut Generator<U> {
+		self.0.as_mut().unwrap()
+	}
+}
+impl<U: 'static> Drop for Detach<U> {
+	fn drop(&mut self) {
+		let Some(gen) = self.0.take() else { return };
+		if !std::thread::panicking() {
+			return
+		}
+
+		/* Outside of a generator there is no consumer to hand it over to. */
+		if let Some(header) = try_current_header() {
+			unsafe { (*header).detached.push(Box::new(gen) as Box<dyn Any>) }
+		}
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{panic, position, Cancelled, Generator, Send, Yield};
+
+impl<T: 'static> Generator<T> {
+	/// Runs the producer to completion, and returns all of the values it yields
+	/// along the way.
+	///
+	/// This is the same as collecting the generator into a vector, but the
+	/// values get taken in a tight loop, which skips over the bookkeeping that
+	/// goes on when they get taken one by one, such as handing batches of them
+	/// out through the buffer of the generator. Values that have already been
+	/// stashed by the generator, such as the one kept by
+	/// [`Generator::peek`], come first.
+	///
+	/// # Panic
+	/// Panics raised by the producer are propagated to the caller, as with
+	/// [`Generator::resume`], after which the values yielded before the panic
+	/// are gone.
+	pub fn drain(&mut self) -> Vec<T> {
+		let (lower, _) = self.size_hint();
+		let mut values = Vec::with_capacity(lower);
+		self.drain_with(|value| values.push(value));
+
+		values
+	}
+
+	/// Runs the producer to completion, handing all of the values it yields
+	/// along the way to the given function.
+	///
+	/// See [`Generator::drain`].
+	///
+	/// # Panic
+	/// Panics raised by the producer are propagated to the caller, as with
+	/// [`Generator::resume`].
+	pub fn drain_with(&mut self, mut func: impl FnMut(T)) {
+		for value in self.buffer.drain(..) {
+			func(value)
+		}
+		if self.poisoned {
+			return
+		}
+
+		self.first = false;
+		loop {
+			match self.enter_with(Send::Continue) {
+				Yield::Value(value) => func(value),
+				Yield::Batch(values) => values.into_iter().for_each(&mut func),
+				Yield::Pending => {}
+				Yield::StopIteration => break,
+				Yield::Panic(what) if what.is::<Cancelled>() => {
+					/* The producer has been cancelled through one of its tokens. */
+					break
+				}
+				Yield::Panic(what) => {
+					self.poisoned = true;
+					let (_, depth) = position();
+					std::panic::resume_unwind(panic::stitch(what, self.task.header_ref(), depth))
+				}
+			}
+		}
+	}
+}
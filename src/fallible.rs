@@ -0,0 +1,33 @@
+//! Support for the [`fallible_iterator`] crate.
+use fallible_iterator::FallibleIterator;
+use crate::Generator;
+
+impl<T: 'static, E: 'static> Generator<Result<T, E>> {
+	/// Turns this generator into a [`FallibleIterator`], which yields the
+	/// values in the results yielded by the producer, and reports the errors in
+	/// them as errors of the iterator.
+	pub fn fallible(self) -> Fallible<T, E> {
+		Fallible { gen: self }
+	}
+}
+
+/// A generator of results, as a [`FallibleIterator`].
+///
+/// This is created by [`Generator::fallible`].
+pub struct Fallible<T: 'static, E: 'static> {
+	gen: Generator<Result<T, E>>,
+}
+impl<T: 'static, E: 'static> Fallible<T, E> {
+	/// Turns this back into the generator it was created from.
+	pub fn into_inner(self) -> Generator<Result<T, E>> {
+		self.gen
+	}
+}
+impl<T: 'static, E: 'static> FallibleIterator for Fallible<T, E> {
+	type Item = T;
+	type Error = E;
+
+	fn next(&mut self) -> Result<Option<T>, E> {
+		self.gen.next().transpose()
+	}
+}
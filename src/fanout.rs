@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::mpsc::{self, Receiver, Sender};
+use crate::Generator;
+use crate::panic::Payload;
+
+/// A generator that any number of threads may take values from.
+///
+/// Generators never move between threads, so the producer runs as a regular
+/// generator on a thread of its own, which only resumes it when a consumer
+/// asks for a value, and never gets ahead of the consumers. Requests for
+/// values are served one at a time, in the order they come in, so that every
+/// value goes to exactly one consumer. This is the simplest way of handing
+/// work items coming out of a single generator to a pool of workers, which
+/// pull items out of it competitively.
+///
+/// Handles to the generator are cheap to clone, and all of them take values
+/// from the same producer. Iterating over a handle, or over a reference to
+/// one, takes values until the producer is done. The producer gets cancelled,
+/// on its thread, once all of the handles are gone.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use yeet::SharedGenerator;
+///
+/// let jobs = SharedGenerator::<u32>::from_fn_ptr(|| yeet::yeet_all(1u32..=100));
+/// let total = AtomicU32::new(0);
+///
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             for job in &jobs {
+///                 total.fetch_add(job, Ordering::Relaxed);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(total.into_inner(), 5050);
+/// ```
+///
+/// # Panic
+/// Panics raised by the producer are propagated to the consumer whose request
+/// the producer was serving when it panicked. The generator is done after
+/// that, and every further request gets nothing.
+pub struct SharedGenerator<T: std::marker::Send + 'static> {
+	/// The link to the producer thread.
+	link: Arc<Mutex<Link<T>>>,
+}
+impl<T: std::marker::Send + 'static> SharedGenerator<T> {
+	/// Runs the given function as a producer on a new thread.
+	///
+	/// # Panic
+	/// This function panics if the thread could not be spawned.
+	pub fn from_fn_ptr(func: fn()) -> Self {
+		Self::from_closure(func)
+	}
+
+	/// Runs the given closure as a producer on a new thread.
+	///
+	/// # Panic
+	/// This function panics if the thread could not be spawned.
+	pub fn from_closure(func: impl FnOnce() + std::marker::Send + 'static) -> Self {
+		let (requests, requests_rx) = mpsc::channel();
+		let (replies_tx, replies) = mpsc::channel();
+		std::thread::Builder::new()
+			.name("yeet-shared".into())
+			.spawn(move || serve(Generator::<T>::from_closure(func), requests_rx, replies_tx))
+			.expect("Could not spawn producer thread");
+
+		Self {
+			link: Arc::new(Mutex::new(Link {
+				requests,
+				replies,
+				done: false,
+			}))
+		}
+	}
+
+	/// Takes the next value from the producer, waiting for the requests that
+	/// came in before this one to be served first.
+	fn request(&self) -> Option<T> {
+		let mut link = self.link.lock().unwrap_or_else(PoisonError::into_inner);
+		if link.done {
+			return None
+		}
+
+		let reply = link.requests.send(())
+			.ok()
+			.and_then(|_| link.replies.recv().ok());
+		match reply {
+			Some(Reply::Value(value)) => Some(value),
+			Some(Reply::Panic(what)) => {
+				link.done = true;
+				drop(link);
+				std::panic::resume_unwind(what)
+			},
+			None => {
+				link.done = true;
+				None
+			}
+		}
+	}
+}
+impl<T: std::marker::Send + 'static> Iterator for SharedGenerator<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		self.request()
+	}
+}
+impl<T: std::marker::Send + 'static> Iterator for &SharedGenerator<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		self.request()
+	}
+}
+impl<T: std::marker::Send + 'static> Clone for SharedGenerator<T> {
+	fn clone(&self) -> Self {
+		Self { link: self.link.clone() }
+	}
+}
+
+/// The consumer end of the link to the producer thread.
+struct Link<T> {
+	/// Requests for values.
+	requests: Sender<()>,
+	/// Replies to the requests, in the order they came in.
+	replies: Receiver<Reply<T>>,
+	/// Whether the producer is done.
+	done: bool,
+}
+
+/// Replies sent from the producer thread to the consumers.
+enum Reply<T> {
+	/// The producer has yielded the given value.
+	Value(T),
+	/// The producer has panicked with the given payload.
+	Panic(Payload),
+}
+
+/// Resumes the given generator once for every request, sending back the values
+/// it yields, until either it is done, or the consumers go away.
+fn serve<T: 'static>(mut gen: Generator<T>, requests: Receiver<()>, replies: Sender<Reply<T>>) {
+	while requests.recv().is_ok() {
+		let next = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+		let reply = match next {
+			Ok(Some(value)) => Reply::Value(value),
+			Ok(None) => break,
+			Err(what) => Reply::Panic(what),
+		};
+		let panicked = matches!(reply, Reply::Panic(_));
+		if replies.send(reply).is_err() || panicked {
+			break
+		}
+	}
+}
@@ -0,0 +1,204 @@
+//! Compatibility with the API of the `genawaiter` crate.
+//!
+//! Producers written for `genawaiter` are async closures that yield by
+//! awaiting [`Co::yield_`]. The types in this module mirror the ones in
+//! `genawaiter::rc`, so that projects moving over to stackful generators can
+//! do so by changing their imports, leaving their producers as they are:
+//!
+//! ```rust
+//! use yeet::genawaiter::GeneratorState;
+//! use yeet::genawaiter::rc::{Co, Gen};
+//!
+//! async fn producer(co: Co<u32, u32>) -> &'static str {
+//!     let mut total = 0;
+//!     for i in 0..3 {
+//!         total += co.yield_(i).await;
+//!     }
+//!     assert_eq!(total, 60);
+//!     "done"
+//! }
+//!
+//! let mut gen = Gen::new(producer);
+//! assert_eq!(gen.resume_with(0), GeneratorState::Yielded(0));
+//! assert_eq!(gen.resume_with(10), GeneratorState::Yielded(1));
+//! assert_eq!(gen.resume_with(20), GeneratorState::Yielded(2));
+//! assert_eq!(gen.resume_with(30), GeneratorState::Complete("done"));
+//! ```
+//!
+//! The future returned by the producer gets polled from inside a task, and
+//! awaiting [`Co::yield_`] switches straight over to the consumer, rather than
+//! going through the executor machinery `genawaiter` relies on. Other futures
+//! awaited by the producer that aren't ready get polled again right away, with
+//! a waker that does nothing, as they would be by `genawaiter`, so they should
+//! only ever be ones that become ready on their own.
+//!
+//! Unlike in `genawaiter`, the values yielded by the producer, its resume
+//! arguments, and its return value must all be `'static`.
+
+/// The outcome of resuming a [`rc::Gen`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneratorState<Y, C> {
+	/// The producer has yielded the given value.
+	Yielded(Y),
+	/// The producer has returned the given value.
+	Complete(C),
+}
+
+/// Generators whose producers hold on to their [`Co`](rc::Co) through a
+/// reference-counted pointer.
+pub mod rc {
+	use std::cell::Cell;
+	use std::future::Future;
+	use std::marker::PhantomData;
+	use std::pin::{pin, Pin};
+	use std::rc::Rc;
+	use std::task::{Context, Poll, Waker};
+	use crate::{Generator, Resume};
+	use crate::coroutine::{Coroutine, CoroutineState};
+	use crate::registry::TaskState;
+	use super::GeneratorState;
+
+	/// A generator yielding values of type `Y`, whose producer gets resumed
+	/// with arguments of type `R`, and returns a value of type `C`.
+	///
+	/// See the [module documentation](super) for how this relates to the
+	/// generators of `genawaiter`.
+	pub struct Gen<Y: 'static, R: 'static = (), C: 'static = ()> {
+		/// The generator running the producer.
+		gen: Generator<Y>,
+		/// The argument the producer was last resumed with, if it is yet to
+		/// be taken.
+		arg: Rc<Cell<Option<R>>>,
+		/// The value the producer returned, if it is yet to be taken.
+		output: Rc<Cell<Option<C>>>,
+	}
+	impl<Y: 'static, R: 'static, C: 'static> Gen<Y, R, C> {
+		/// Creates a new generator, whose producer is the future returned by
+		/// the given function.
+		///
+		/// The function doesn't get called until the generator is first
+		/// resumed.
+		pub fn new<F>(producer: impl FnOnce(Co<Y, R>) -> F + 'static) -> Self
+			where F: Future<Output = C> {
+
+			let arg = Rc::new(Cell::new(None));
+			let output = Rc::new(Cell::new(None));
+			let co = Co { arg: arg.clone(), _values: PhantomData };
+
+			let returned = output.clone();
+			let gen = Generator::from_closure(move || {
+				returned.set(Some(drive(pin!(producer(co)))))
+			});
+
+			Self { gen, arg, output }
+		}
+
+		/// Resumes the producer with the given argument, until it either
+		/// yields or returns a value.
+		///
+		/// The argument is what the last [`Co::yield_`] awaited by the
+		/// producer resolves to. The argument of the first resume gets
+		/// discarded, as the producer hasn't awaited any yet.
+		///
+		/// # Panic
+		/// This function panics if the producer has already returned, and
+		/// propagates panics raised by the producer.
+		pub fn resume_with(&mut self, arg: R) -> GeneratorState<Y, C> {
+			self.arg.set(Some(arg));
+			loop {
+				match self.gen.resume() {
+					Resume::Value(value) => break GeneratorState::Yielded(value),
+					Resume::Pending => continue,
+					Resume::Complete => match self.output.take() {
+						Some(output) => break GeneratorState::Complete(output),
+						None => panic!("Tried to resume a generator that has already completed!"),
+					},
+				}
+			}
+		}
+	}
+	impl<Y: 'static, C: 'static> Gen<Y, (), C> {
+		/// Resumes the producer until it either yields or returns a value.
+		///
+		/// See [`Gen::resume_with`].
+		pub fn resume(&mut self) -> GeneratorState<Y, C> {
+			self.resume_with(())
+		}
+	}
+
+	/// Iterates over the values yielded by the producer, discarding the value
+	/// it returns.
+	impl<Y: 'static, C: 'static> Iterator for Gen<Y, (), C> {
+		type Item = Y;
+
+		fn next(&mut self) -> Option<Y> {
+			if self.gen.state() == TaskState::Finished {
+				return None
+			}
+			match self.resume() {
+				GeneratorState::Yielded(value) => Some(value),
+				GeneratorState::Complete(_) => None,
+			}
+		}
+	}
+
+	/// Resuming the generator after it has completed panics, as it would
+	/// through [`Gen::resume_with`].
+	impl<Y: 'static, R: 'static, C: 'static> Coroutine<R> for Gen<Y, R, C> {
+		type Yield = Y;
+		type Return = C;
+
+		fn resume(self: Pin<&mut Self>, arg: R) -> CoroutineState<Y, C> {
+			match self.get_mut().resume_with(arg) {
+				GeneratorState::Yielded(value) => CoroutineState::Yielded(value),
+				GeneratorState::Complete(output) => CoroutineState::Complete(output),
+			}
+		}
+	}
+
+	/// Handle through which the producer of a [`Gen`] yields values.
+	pub struct Co<Y: 'static, R: 'static = ()> {
+		/// The argument the producer was last resumed with, if it is yet to
+		/// be taken.
+		arg: Rc<Cell<Option<R>>>,
+		_values: PhantomData<fn(Y)>,
+	}
+	impl<Y: 'static, R: 'static> Co<Y, R> {
+		/// Yields the given value to the consumer, resolving to the argument
+		/// the producer gets resumed with next.
+		///
+		/// The value gets yielded when the returned future is first polled,
+		/// which must happen from inside the producer of the generator.
+		pub fn yield_(&self, value: Y) -> impl Future<Output = R> + '_ {
+			let mut value = Some(value);
+			std::future::poll_fn(move |_| {
+				if let Some(value) = value.take() {
+					crate::yeet(value);
+				}
+				match self.arg.take() {
+					Some(arg) => Poll::Ready(arg),
+					None => panic!("Tried to resume a producer without an argument!"),
+				}
+			})
+		}
+	}
+	impl<Y: 'static, R: 'static> Clone for Co<Y, R> {
+		fn clone(&self) -> Self {
+			Self { arg: self.arg.clone(), _values: PhantomData }
+		}
+	}
+
+	/// Polls the given future to completion, from inside a producer.
+	///
+	/// Futures other than the ones returned by [`Co::yield_`] that aren't
+	/// ready get polled again the next time the producer is resumed.
+	fn drive<F: Future>(mut future: Pin<&mut F>) -> F::Output {
+		let mut cx = Context::from_waker(Waker::noop());
+		loop {
+			match future.as_mut().poll(&mut cx) {
+				Poll::Ready(output) => break output,
+				Poll::Pending => crate::yield_now(),
+			}
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use crate::{Generator, Resume};
+use crate::panic::Payload;
+
+/// The consumer side of a generator.
+///
+/// Consumer logic written against this trait, rather than against
+/// [`Generator`], can be tested without spinning up any producers, by handing
+/// it a [`MockGenerator`] that plays back a script of steps instead.
+///
+/// [`MockGenerator`]: crate::testing::MockGenerator
+pub trait Generate<T> {
+	/// Resumes the producer until it either yields a value, reaches a
+	/// checkpoint, or finishes.
+	///
+	/// See [`Generator::resume`].
+	fn resume(&mut self) -> Resume<T>;
+
+	/// Resumes the producer until it either yields a value or finishes,
+	/// going through any checkpoints it reaches.
+	///
+	/// See [`Generator::next`].
+	fn next_value(&mut self) -> Option<T> {
+		loop {
+			match self.resume() {
+				Resume::Value(value) => break Some(value),
+				Resume::Pending => continue,
+				Resume::Complete => break None,
+			}
+		}
+	}
+
+	/// Cancels the producer, and reports any problems it ran into while being
+	/// cancelled.
+	///
+	/// See [`Generator::close`].
+	fn close(self) -> Result<(), Payload> where Self: Sized;
+}
+
+impl<T: 'static> Generate<T> for Generator<T> {
+	fn resume(&mut self) -> Resume<T> {
+		Generator::resume(self)
+	}
+
+	fn next_value(&mut self) -> Option<T> {
+		self.next()
+	}
+
+	fn close(self) -> Result<(), Payload> {
+		Generator::close(self)
+	}
+}
@@ -0,0 +1,157 @@
+use std::time::Instant;
+use crate::{Generator, Resume};
+
+/// Owns a set of generators, and merges the values they yield into a single
+/// stream, tagging each value with the index of the member it came from.
+///
+/// Members take turns in a round-robin, so that a member that always has a
+/// value ready can't starve the others: every time a member yields a value,
+/// the next call to [`next`] starts with the member after it. Members that
+/// reach a checkpoint, such as through [`yield_now`] or [`sleep`], get passed
+/// over for the rest of the turn. If every member is sleeping, the thread
+/// blocks until the first one of them wakes up.
+///
+/// Members may be inserted and removed at any time, and finished members get
+/// removed on their own. The index of a member never changes for as long as
+/// it is in the group, but indices of removed members get reused by members
+/// inserted later. Panics raised by members are propagated to the consumer,
+/// after which the member that panicked gets removed.
+///
+/// ```rust
+/// use yeet::{Generator, GeneratorGroup};
+///
+/// let mut group = GeneratorGroup::<u32>::new();
+/// let a = group.insert(Generator::from_fn_ptr(|| yeet::yeet_all(0..2u32)));
+/// let b = group.insert(Generator::from_fn_ptr(|| yeet::yeet_all(10..13u32)));
+///
+/// assert_eq!(
+///     group.collect::<Vec<_>>(),
+///     [(a, 0), (b, 10), (a, 1), (b, 11), (b, 12)]);
+/// ```
+///
+/// [`next`]: Iterator::next
+/// [`yield_now`]: crate::yield_now
+/// [`sleep`]: crate::sleep
+pub struct GeneratorGroup<T: 'static> {
+	/// The members of the group, by their index.
+	members: Vec<Option<Generator<T>>>,
+	/// The indices in `members` that are free to be reused.
+	free: Vec<usize>,
+	/// The index of the member whose turn it is.
+	cursor: usize,
+}
+impl<T: 'static> GeneratorGroup<T> {
+	/// Creates a new group with no members.
+	pub fn new() -> Self {
+		Self {
+			members: Vec::new(),
+			free: Vec::new(),
+			cursor: 0,
+		}
+	}
+
+	/// Adds the given generator to the group, returning its index.
+	pub fn insert(&mut self, gen: Generator<T>) -> usize {
+		match self.free.pop() {
+			Some(index) => {
+				self.members[index] = Some(gen);
+				index
+			}
+			None => {
+				self.members.push(Some(gen));
+				self.members.len() - 1
+			}
+		}
+	}
+
+	/// Takes the member with the given index out of the group, handing it
+	/// back, if there is one.
+	pub fn remove(&mut self, index: usize) -> Option<Generator<T>> {
+		let gen = self.members.get_mut(index)?.take()?;
+		self.free.push(index);
+
+		Some(gen)
+	}
+
+	/// The member with the given index, if there is one.
+	pub fn get(&self, index: usize) -> Option<&Generator<T>> {
+		self.members.get(index)?.as_ref()
+	}
+
+	/// The member with the given index, if there is one.
+	pub fn get_mut(&mut self, index: usize) -> Option<&mut Generator<T>> {
+		self.members.get_mut(index)?.as_mut()
+	}
+
+	/// Whether there is a member with the given index.
+	pub fn contains(&self, index: usize) -> bool {
+		self.get(index).is_some()
+	}
+
+	/// The indices of the members of the group, in order.
+	pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+		self.members.iter()
+			.enumerate()
+			.filter(|(_, member)| member.is_some())
+			.map(|(index, _)| index)
+	}
+
+	/// The number of members in the group.
+	pub fn len(&self) -> usize {
+		self.members.len() - self.free.len()
+	}
+
+	/// Whether the group has no members.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+impl<T: 'static> Iterator for GeneratorGroup<T> {
+	type Item = (usize, T);
+
+	fn next(&mut self) -> Option<(usize, T)> {
+		while !self.is_empty() {
+			let mut wake: Option<Instant> = None;
+			let mut pending = false;
+
+			for _ in 0..self.members.len() {
+				let index = self.cursor;
+				self.cursor = (self.cursor + 1) % self.members.len();
+
+				let Some(gen) = &mut self.members[index] else { continue };
+				if let Some(deadline) = gen.deadline().filter(|deadline| *deadline > Instant::now()) {
+					wake = Some(wake.map_or(deadline, |wake| wake.min(deadline)));
+					continue
+				}
+
+				/* Members that panic are done for, so they get removed before
+				 * the panic goes through. */
+				let resume = match gen.try_resume(0) {
+					Ok(resume) => resume,
+					Err(what) => {
+						self.remove(index);
+						std::panic::resume_unwind(what)
+					}
+				};
+				match resume {
+					Resume::Value(value) => return Some((index, value)),
+					Resume::Pending => pending = true,
+					Resume::Complete => { self.remove(index); }
+				}
+			}
+
+			/* Members that suspended for any other reason than sleeping are
+			 * to be resumed right away on the next turn. */
+			if let (false, Some(wake)) = (pending, wake) {
+				std::thread::sleep(wake.saturating_duration_since(Instant::now()))
+			}
+		}
+
+		None
+	}
+}
+impl<T: 'static> Default for GeneratorGroup<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,148 @@
+//! A double-buffered handoff between a pair of threads.
+//!
+//! The handoff has two slots, so that the producing thread can fill one of
+//! them while the consuming thread is still working on the value it took out of
+//! the other. For pipelines where both sides take about as long per value, this
+//! keeps both of them busy, without the bookkeeping of a general purpose queue.
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+/// Creates a new handoff, returning both of its ends.
+pub fn handoff<T>() -> (Sender<T>, Receiver<T>) {
+	let shared = Arc::new(Shared {
+		state: Mutex::new(State {
+			slots: [None, None],
+			front: 0,
+			sender: true,
+			receiver: true,
+		}),
+		filled: Condvar::new(),
+		emptied: Condvar::new(),
+	});
+
+	(Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+/// State shared between both ends of a handoff.
+struct Shared<T> {
+	state: Mutex<State<T>>,
+	/// Signalled when a slot gets filled, or the sender goes away.
+	filled: Condvar,
+	/// Signalled when a slot gets emptied, or the receiver goes away.
+	emptied: Condvar,
+}
+impl<T> Shared<T> {
+	fn lock(&self) -> MutexGuard<'_, State<T>> {
+		/* Neither end panics while holding the lock. */
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+/// The slots of a handoff.
+struct State<T> {
+	/// The slots themselves.
+	slots: [Option<T>; 2],
+	/// The slot the next value will be taken from.
+	front: usize,
+	/// Whether the sender is still around.
+	sender: bool,
+	/// Whether the receiver is still around.
+	receiver: bool,
+}
+impl<T> State<T> {
+	/// Takes the next value out of the slots, if there is one.
+	fn take(&mut self) -> Option<T> {
+		let value = self.slots[self.front].take()?;
+		self.front ^= 1;
+		Some(value)
+	}
+}
+
+/// The outcome of trying to receive a value.
+pub enum TryRecv<T> {
+	/// A value was received.
+	Value(T),
+	/// No value was ready.
+	Empty,
+	/// The sender has gone away, and there are no more values.
+	Closed,
+}
+
+/// The sending end of a handoff.
+pub struct Sender<T> {
+	shared: Arc<Shared<T>>,
+}
+impl<T> Sender<T> {
+	/// Sends a value, waiting for a slot to become free.
+	///
+	/// Fails, handing the value back, if the receiver has gone away.
+	pub fn send(&self, value: T) -> Result<(), T> {
+		let mut state = self.shared.lock();
+		loop {
+			if !state.receiver {
+				return Err(value)
+			}
+
+			let back = if state.slots[state.front].is_none() { state.front } else { state.front ^ 1 };
+			if state.slots[back].is_none() {
+				state.slots[back] = Some(value);
+				self.shared.filled.notify_one();
+				return Ok(())
+			}
+
+			state = self.shared.emptied.wait(state).unwrap_or_else(PoisonError::into_inner);
+		}
+	}
+}
+impl<T> Drop for Sender<T> {
+	fn drop(&mut self) {
+		self.shared.lock().sender = false;
+		self.shared.filled.notify_one();
+	}
+}
+
+/// The receiving end of a handoff.
+pub struct Receiver<T> {
+	shared: Arc<Shared<T>>,
+}
+impl<T> Receiver<T> {
+	/// Receives a value, waiting until one is ready, or until the given
+	/// deadline, if there is one.
+	pub fn recv_until(&self, deadline: Option<Instant>) -> TryRecv<T> {
+		let mut state = self.shared.lock();
+		loop {
+			if let Some(value) = state.take() {
+				self.shared.emptied.notify_one();
+				return TryRecv::Value(value)
+			}
+			if !state.sender {
+				return TryRecv::Closed
+			}
+
+			state = match deadline {
+				Some(deadline) => {
+					let timeout = deadline.saturating_duration_since(Instant::now());
+					if timeout.is_zero() {
+						return TryRecv::Empty
+					}
+					self.shared.filled.wait_timeout(state, timeout)
+						.unwrap_or_else(PoisonError::into_inner)
+						.0
+				}
+				None => self.shared.filled.wait(state).unwrap_or_else(PoisonError::into_inner),
+			};
+		}
+	}
+
+	/// Receives a value, if one is ready.
+	#[cfg(feature = "stream")]
+	pub fn try_recv(&self) -> TryRecv<T> {
+		self.recv_until(Some(Instant::now()))
+	}
+}
+impl<T> Drop for Receiver<T> {
+	fn drop(&mut self) {
+		self.shared.lock().receiver = false;
+		self.shared.emptied.notify_one();
+	}
+}
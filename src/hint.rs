@@ -0,0 +1,25 @@
+use crate::current_header;
+
+/// Tells the consumer how many more values the producer is going to yield,
+/// from this point on.
+///
+/// This gets forwarded to the [`Iterator::size_hint`] of the generator, which
+/// lets consumers, such as [`Iterator::collect`], size their buffers up front.
+/// The hint counts down by itself as the producer yields values, and follows
+/// the same rules as [`Iterator::size_hint`]: the producer must not yield fewer
+/// values than the lower bound, nor more values than the upper bound, if there
+/// is one. [`yeet_all`] sets the lower bound from the iterator it was given
+/// for as long as it runs.
+///
+/// Hints are ignored by generators that filter, map, or collect the values of
+/// the producer in chunks, as the producer can't know how many values will
+/// actually come out of them.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+///
+/// [`yeet_all`]: crate::yeet_all
+pub fn size_hint(lower: usize, upper: Option<usize>) {
+	let header = current_header();
+	unsafe { (*header).hint = (lower, upper) }
+}
@@ -0,0 +1,120 @@
+//! Instrumentation hooks.
+//!
+//! Hooks are functions that get called every time control moves between a
+//! consumer and a producer, which allows profilers and monitoring agents to
+//! observe the scheduling behavior of generators without having to wrap every
+//! single call site.
+//!
+//! Hooks can either be installed globally, through [`add_hook`], in which case
+//! they observe every generator in the process, or they can be installed for a
+//! single generator, through [`Generator::on_switch`](crate::Generator::on_switch).
+//!
+//! All hooks run on the consumer side of the switch, on the thread that owns
+//! the generator. Global hooks get called before per-generator hooks.
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use crate::{Generator, TaskId};
+use crate::sys::{AnyTask, Header};
+
+/// The direction of a switch between a consumer and a producer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+	/// Control is about to be handed from the consumer to the producer.
+	Resume,
+	/// Control has just been handed back from the producer to the consumer.
+	Yield,
+}
+
+/// Describes a switch between a consumer and a producer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SwitchEvent {
+	/// The task being switched into or out of.
+	pub task: TaskId,
+	/// Whether control is moving into or out of the task.
+	pub direction: Direction,
+}
+
+/// Handle to a global hook, which may be used to remove it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// A hook installed on a single generator.
+pub(crate) type LocalHook = Box<dyn FnMut(&SwitchEvent)>;
+
+/// A global hook, along with its handle.
+type Hook = (HookId, Arc<dyn Fn(&SwitchEvent) + std::marker::Send + Sync>);
+
+/// The list of global hooks.
+///
+/// The list gets replaced whenever a hook is added or removed, so that callers
+/// only need to hold the lock for long enough to clone the [`Arc`], and may
+/// then freely call into the hooks, even if those end up changing the list.
+static HOOKS: RwLock<Option<Arc<[Hook]>>> = RwLock::new(None);
+
+/// Whether there are any global hooks installed, so that the common case where
+/// there aren't any doesn't need to touch the lock.
+static HOOKS_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Installs a hook that observes every switch of every generator.
+pub fn add_hook(hook: impl Fn(&SwitchEvent) + std::marker::Send + Sync + 'static) -> HookId {
+	static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+	let id = HookId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+	let mut hooks = HOOKS.write().unwrap_or_else(|what| what.into_inner());
+	let mut list = hooks.as_deref().unwrap_or(&[]).to_vec();
+	list.push((id, Arc::new(hook)));
+
+	*hooks = Some(list.into());
+	HOOKS_ACTIVE.store(true, Ordering::Release);
+
+	id
+}
+
+/// Removes a global hook previously installed with [`add_hook`].
+///
+/// Returns whether the hook was installed.
+pub fn remove_hook(id: HookId) -> bool {
+	let mut hooks = HOOKS.write().unwrap_or_else(|what| what.into_inner());
+	let mut list = hooks.as_deref().unwrap_or(&[]).to_vec();
+	let len = list.len();
+	list.retain(|(hook, _)| *hook != id);
+
+	let removed = list.len() != len;
+	HOOKS_ACTIVE.store(!list.is_empty(), Ordering::Release);
+	*hooks = if list.is_empty() { None } else { Some(list.into()) };
+
+	removed
+}
+
+/// Calls all of the hooks that apply to the given task.
+pub(crate) fn dispatch(header: &mut Header, direction: Direction) {
+	if !HOOKS_ACTIVE.load(Ordering::Acquire) && header.hooks.is_empty() {
+		return
+	}
+
+	let event = SwitchEvent {
+		task: header.id,
+		direction,
+	};
+
+	if HOOKS_ACTIVE.load(Ordering::Acquire) {
+		let hooks = HOOKS.read()
+			.unwrap_or_else(|what| what.into_inner())
+			.clone();
+		for (_, hook) in hooks.as_deref().unwrap_or(&[]) {
+			hook(&event)
+		}
+	}
+
+	for hook in &mut header.hooks {
+		hook(&event)
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// Installs a hook that observes every switch into and out of this
+	/// generator.
+	pub fn on_switch(&mut self, hook: impl FnMut(&SwitchEvent) + 'static) {
+		self.task.header().hooks.push(Box::new(hook))
+	}
+}
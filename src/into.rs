@@ -0,0 +1,64 @@
+//! Conversion of values into generators.
+//!
+//! Library code that consumes a stream of values can accept anything that
+//! implements [`IntoGenerator`], and turn it into a [`Generator`] uniformly,
+//! the way [`IntoIterator`] works for iterators. This covers generators
+//! themselves, closures that act as producers, and anything that can be
+//! iterated over.
+//!
+//! A type could be both a closure and something that can be iterated over,
+//! so the trait takes a second parameter telling the two apart, which is one
+//! of the marker types in this module. It is always inferred, so code taking
+//! values that can be turned into generators just needs to be generic over
+//! it:
+//!
+//! ```rust
+//! use yeet::IntoGenerator;
+//!
+//! fn sum<M>(source: impl IntoGenerator<u32, M>) -> u32 {
+//!     source.into_generator().sum()
+//! }
+//!
+//! assert_eq!(sum(vec![1, 2, 3]), 6);
+//! assert_eq!(sum(|| yeet::yeet_all(1..4u32)), 6);
+//! assert_eq!(sum(yeet::Generator::from_fn_ptr(|| yeet::yeet(6u32))), 6);
+//! ```
+use std::any::Any;
+use crate::Generator;
+
+/// Conversion into a [`Generator`] yielding values of type `T`.
+///
+/// See the [module documentation](self) for what the `M` parameter is for.
+pub trait IntoGenerator<T: 'static, M> {
+	/// Turns this value into a generator.
+	fn into_generator(self) -> Generator<T>;
+}
+
+/// Marker for closures that act as producers, yielding through [`yeet`].
+///
+/// [`yeet`]: crate::yeet
+pub struct ClosureMarker;
+
+/// Marker for anything that can be iterated over, including generators.
+pub struct IterMarker;
+
+impl<T: 'static, F: FnOnce() + 'static> IntoGenerator<T, ClosureMarker> for F {
+	fn into_generator(self) -> Generator<T> {
+		Generator::from_closure(self)
+	}
+}
+
+/// Generators are also iterators, so they go through here, but they get handed
+/// back as they are, rather than being wrapped in a producer iterating over
+/// them.
+impl<T: 'static, I: IntoIterator<Item = T> + 'static> IntoGenerator<T, IterMarker> for I {
+	fn into_generator(self) -> Generator<T> {
+		let mut this = Some(self);
+		if let Some(gen) = (&mut this as &mut dyn Any).downcast_mut::<Option<Generator<T>>>() {
+			return gen.take().unwrap()
+		}
+
+		let iter = this.unwrap();
+		Generator::from_closure(move || crate::yeet_all(iter.into_iter()))
+	}
+}
@@ -0,0 +1,209 @@
+//! Non-blocking I/O for producers.
+//!
+//! Producers reading from or writing to non-blocking file descriptors, such as
+//! sockets put in non-blocking mode, would otherwise have to either spin on
+//! [`WouldBlock`] or block the whole thread, stalling the consumer along with
+//! every other generator it drives. The helpers in this module instead suspend
+//! the producer with [`Resume::Pending`] whenever the descriptor isn't ready,
+//! after noting down what it is waiting for, which the consumer can find out
+//! through [`Generator::waiting_on`] and wait on however it sees fit.
+//!
+//! ```rust
+//! use std::io::{Read, Write};
+//! use std::os::unix::net::UnixStream;
+//! use yeet::{Generator, Resume};
+//!
+//! let (mut theirs, ours) = UnixStream::pair().unwrap();
+//! ours.set_nonblocking(true).unwrap();
+//!
+//! let mut gen = Generator::<u8>::from_closure(move || {
+//!     let mut ours = ours;
+//!     let mut buf = [0u8; 4];
+//!     let read = yeet::io::read(&mut ours, &mut buf).unwrap();
+//!     yeet::yeet_slice(&buf[..read]);
+//! });
+//!
+//! /* Nothing has been written yet, so the producer has to wait. */
+//! assert!(matches!(gen.resume(), Resume::Pending));
+//! let wait = gen.waiting_on().unwrap();
+//!
+//! theirs.write_all(b"hi").unwrap();
+//! assert!(wait.block(None).unwrap());
+//! assert_eq!(gen.collect::<Vec<_>>(), b"hi");
+//! ```
+//!
+//! [`WouldBlock`]: std::io::ErrorKind::WouldBlock
+//! [`Resume::Pending`]: crate::Resume::Pending
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+use crate::{current_header, Generator};
+use crate::registry::TaskState;
+
+/// The kind of readiness a producer is waiting for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Interest {
+	/// The descriptor has data to be read.
+	Readable,
+	/// The descriptor has room for data to be written.
+	Writable,
+}
+
+/// A file descriptor a producer is waiting on, along with what it is waiting
+/// for it to become.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Wait {
+	/// The descriptor being waited on.
+	pub fd: RawFd,
+	/// What the descriptor is being waited for to become.
+	pub interest: Interest,
+}
+impl Wait {
+	/// Blocks the thread until the descriptor becomes ready, or until the given
+	/// amount of time has passed, and returns whether it became ready.
+	///
+	/// Descriptors that get closed or run into an error count as ready, as the
+	/// producer is going to find out about it once it tries again.
+	pub fn block(&self, timeout: Option<Duration>) -> io::Result<bool> {
+		let mut poll = libc::pollfd {
+			fd: self.fd,
+			events: self.events(),
+			revents: 0,
+		};
+		let timeout = match timeout {
+			Some(timeout) => timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int,
+			None => -1,
+		};
+
+		loop {
+			match unsafe { libc::poll(&mut poll, 1, timeout) } {
+				-1 if io::Error::last_os_error().kind() == ErrorKind::Interrupted => continue,
+				-1 => break Err(io::Error::last_os_error()),
+				ready => break Ok(ready > 0),
+			}
+		}
+	}
+
+	/// The events `poll` should wait for on the descriptor.
+	pub(crate) fn events(&self) -> libc::c_short {
+		match self.interest {
+			Interest::Readable => libc::POLLIN,
+			Interest::Writable => libc::POLLOUT,
+		}
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// The descriptor the producer is waiting on, if it is suspended in one of
+	/// the helpers in [`io`].
+	///
+	/// The producer is ready to make progress once the descriptor is, and
+	/// resuming it any earlier just has it suspend again.
+	///
+	/// [`io`]: crate::io
+	pub fn waiting_on(&self) -> Option<Wait> {
+		let header = self.task.header_ref();
+		match header.state() {
+			TaskState::Suspended => header.waiting,
+			_ => None,
+		}
+	}
+}
+
+/// Suspends the producer until the given descriptor is ready for the given
+/// kind of operation, as far as the consumer can tell.
+///
+/// Consumers are free to resume the producer before the descriptor is ready,
+/// so callers should be ready to find it isn't, and wait again.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn wait(fd: RawFd, interest: Interest) {
+	unsafe { (*current_header()).waiting = Some(Wait { fd, interest }) }
+	crate::yield_now();
+
+	/* The task may have moved while it was suspended. */
+	unsafe { (*current_header()).waiting = None }
+}
+
+/// Runs the given operation until it stops failing with [`WouldBlock`],
+/// waiting on the given descriptor in between.
+///
+/// [`WouldBlock`]: std::io::ErrorKind::WouldBlock
+fn retry<R>(fd: RawFd, interest: Interest, mut op: impl FnMut() -> io::Result<R>) -> io::Result<R> {
+	loop {
+		match op() {
+			Err(error) if error.kind() == ErrorKind::WouldBlock => wait(fd, interest),
+			Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+			result => break result,
+		}
+	}
+}
+
+/// Reads from the given source into the given buffer, suspending the producer
+/// for as long as the source has nothing to read.
+///
+/// The source must be in non-blocking mode, or the thread blocks as usual.
+///
+/// # Panic
+/// This function panics if the source isn't ready, and it is not being called
+/// from inside a generator.
+pub fn read<S: Read + AsRawFd>(source: &mut S, buf: &mut [u8]) -> io::Result<usize> {
+	let fd = source.as_raw_fd();
+	retry(fd, Interest::Readable, || source.read(buf))
+}
+
+/// Fills the given buffer with data from the given source, suspending the
+/// producer for as long as the source has nothing to read.
+///
+/// Fails with [`UnexpectedEof`] if the source runs out of data first.
+///
+/// # Panic
+/// This function panics if the source isn't ready, and it is not being called
+/// from inside a generator.
+///
+/// [`UnexpectedEof`]: std::io::ErrorKind::UnexpectedEof
+pub fn read_exact<S: Read + AsRawFd>(source: &mut S, mut buf: &mut [u8]) -> io::Result<()> {
+	while !buf.is_empty() {
+		match read(source, buf)? {
+			0 => return Err(ErrorKind::UnexpectedEof.into()),
+			read => buf = &mut buf[read..],
+		}
+	}
+
+	Ok(())
+}
+
+/// Writes data from the given buffer into the given sink, suspending the
+/// producer for as long as the sink has no room for it.
+///
+/// The sink must be in non-blocking mode, or the thread blocks as usual.
+///
+/// # Panic
+/// This function panics if the sink isn't ready, and it is not being called
+/// from inside a generator.
+pub fn write<S: Write + AsRawFd>(sink: &mut S, buf: &[u8]) -> io::Result<usize> {
+	let fd = sink.as_raw_fd();
+	retry(fd, Interest::Writable, || sink.write(buf))
+}
+
+/// Writes all of the data in the given buffer into the given sink, suspending
+/// the producer for as long as the sink has no room for it.
+///
+/// Fails with [`WriteZero`] if the sink stops accepting data.
+///
+/// # Panic
+/// This function panics if the sink isn't ready, and it is not being called
+/// from inside a generator.
+///
+/// [`WriteZero`]: std::io::ErrorKind::WriteZero
+pub fn write_all<S: Write + AsRawFd>(sink: &mut S, mut buf: &[u8]) -> io::Result<()> {
+	while !buf.is_empty() {
+		match write(sink, buf)? {
+			0 => return Err(ErrorKind::WriteZero.into()),
+			written => buf = &buf[written..],
+		}
+	}
+
+	Ok(())
+}
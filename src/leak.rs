@@ -0,0 +1,178 @@
+//! Census of live generators, for finding the ones that were forgotten.
+//!
+//! Generators that get created and then neither run to completion nor get
+//! dropped hold on to their stacks, and to whatever their producers hold, for
+//! as long as they exist. In long-running services, these tend to come from
+//! pipelines that were built and then forgotten about somewhere, which are hard
+//! to track down from a heap profile alone.
+//!
+//! When enabled, through [`enable`], every generator created from then on, on
+//! any thread, gets recorded in a process-wide census, along with its name and
+//! the backtrace of where it was created, until it either finishes or gets
+//! dropped. The generators still in the census can then be listed through
+//! [`leak_report`], oldest first.
+//!
+//! The census is disabled by default, as capturing a backtrace for every
+//! generator is expensive.
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+use crate::TaskId;
+
+/// Whether generators being created should be recorded in the census.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The generators in the census, by the numeric value of their identifiers,
+/// which is also the order they were created in.
+static CENSUS: Mutex<BTreeMap<u64, Entry>> = Mutex::new(BTreeMap::new());
+
+/// The entry of a generator in the census.
+struct Entry {
+	/// The name of the generator.
+	name: Option<String>,
+	/// The thread the generator was created on.
+	thread: ThreadId,
+	/// The point in time the generator was created at.
+	created: Instant,
+	/// Where the generator was created.
+	backtrace: Arc<Backtrace>,
+}
+
+/// Enables the census for the whole process.
+///
+/// Only generators created after the census has been enabled get recorded.
+pub fn enable() {
+	ENABLED.store(true, Ordering::Relaxed)
+}
+
+/// Disables the census for the whole process.
+///
+/// Generators already in the census stay there, until they either finish or
+/// get dropped.
+pub fn disable() {
+	ENABLED.store(false, Ordering::Relaxed)
+}
+
+/// Whether the census is enabled.
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// Lists the generators in the census, which are all of the generators created
+/// while the census was enabled that have neither finished nor been dropped.
+pub fn leak_report() -> LeakReport {
+	let now = Instant::now();
+	let census = CENSUS.lock().unwrap_or_else(|error| error.into_inner());
+	let tasks = census.iter()
+		.map(|(id, entry)| LeakedTask {
+			id: TaskId(*id),
+			name: entry.name.clone(),
+			thread: entry.thread,
+			age: now.saturating_duration_since(entry.created),
+			backtrace: entry.backtrace.clone(),
+		})
+		.collect();
+
+	LeakReport { tasks }
+}
+
+/// The generators in the census at the time a report was made.
+#[derive(Debug, Clone)]
+pub struct LeakReport {
+	/// The generators, oldest first.
+	tasks: Vec<LeakedTask>,
+}
+impl LeakReport {
+	/// The generators in the report, oldest first.
+	pub fn tasks(&self) -> &[LeakedTask] {
+		&self.tasks
+	}
+
+	/// The generators in the report that had been alive for at least the given
+	/// amount of time when it was made, oldest first.
+	///
+	/// Pipelines that are merely slow show up in every report, so it is usually
+	/// the old generators that are of interest.
+	pub fn older_than(&self, age: Duration) -> impl Iterator<Item = &LeakedTask> {
+		self.tasks.iter().filter(move |task| task.age >= age)
+	}
+
+	/// The number of generators in the report.
+	pub fn len(&self) -> usize {
+		self.tasks.len()
+	}
+
+	/// Whether there are no generators in the report.
+	pub fn is_empty(&self) -> bool {
+		self.tasks.is_empty()
+	}
+}
+impl fmt::Display for LeakReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} live generators", self.tasks.len())?;
+		for task in &self.tasks {
+			write!(f, "\n\n{task}")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// A generator in a [`LeakReport`].
+#[derive(Debug, Clone)]
+pub struct LeakedTask {
+	/// The identifier of the task running the generator.
+	pub id: TaskId,
+	/// The name of the task running the generator, if it was given one.
+	pub name: Option<String>,
+	/// The thread the generator was created on.
+	pub thread: ThreadId,
+	/// How long the generator had been alive for when the report was made.
+	pub age: Duration,
+	/// Where the generator was created.
+	pub backtrace: Arc<Backtrace>,
+}
+impl fmt::Display for LeakedTask {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "generator ")?;
+		if let Some(name) = &self.name {
+			write!(f, "{name:?} ")?;
+		}
+		write!(f, "(#{}) created on {:?} {:?} ago, at:\n{}",
+			self.id.as_u64(), self.thread, self.age, self.backtrace)
+	}
+}
+
+/// Guard keeping a generator in the census for as long as it is alive.
+pub(crate) struct Census(u64);
+impl Census {
+	/// Records a new generator in the census, if it is enabled.
+	pub(crate) fn register(id: TaskId, name: Option<&str>) -> Option<Self> {
+		if !is_enabled() {
+			return None
+		}
+
+		let entry = Entry {
+			name: name.map(str::to_owned),
+			thread: std::thread::current().id(),
+			created: Instant::now(),
+			backtrace: Arc::new(Backtrace::force_capture()),
+		};
+		CENSUS.lock()
+			.unwrap_or_else(|error| error.into_inner())
+			.insert(id.as_u64(), entry);
+
+		Some(Self(id.as_u64()))
+	}
+}
+impl Drop for Census {
+	fn drop(&mut self) {
+		CENSUS.lock()
+			.unwrap_or_else(|error| error.into_inner())
+			.remove(&self.0);
+	}
+}
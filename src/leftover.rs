@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use crate::Generator;
+
+/// What happens to the values a producer yields while it is being cancelled.
+pub(crate) enum Leftover<T> {
+	/// The values get dropped.
+	Discard,
+	/// The values get handed to the given function.
+	Call(Box<dyn FnMut(T)>),
+	/// The values get queued up for the consumer to take through [`Leftovers`].
+	Collect(Rc<RefCell<VecDeque<T>>>),
+}
+impl<T> Leftover<T> {
+	/// Deals with a value yielded by a producer that is being cancelled.
+	pub(crate) fn take(&mut self, value: T) {
+		match self {
+			Leftover::Discard => drop(value),
+			Leftover::Call(func) => func(value),
+			Leftover::Collect(queue) => queue.borrow_mut().push_back(value),
+		}
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// Drops the values the producer yields while it is being cancelled, which
+	/// is what happens unless asked otherwise.
+	///
+	/// Producers keep running while they unwind from their cancellation, and
+	/// code that runs during the unwind, such as destructors, may yield, with
+	/// the values they yield never making it to the consumer.
+	pub fn discard_leftovers(&mut self) {
+		self.leftover = Leftover::Discard;
+	}
+
+	/// Hands the values the producer yields while it is being cancelled to the
+	/// given function, one by one, as they get yielded.
+	///
+	/// This replaces whatever was being done with them before. See
+	/// [`Generator::discard_leftovers`].
+	pub fn on_leftover(&mut self, func: impl FnMut(T) + 'static) {
+		self.leftover = Leftover::Call(Box::new(func));
+	}
+
+	/// Keeps the values the producer yields while it is being cancelled, for
+	/// the consumer to take through the returned handle once the generator has
+	/// been closed or dropped.
+	///
+	/// Handles opened while the values are already being kept share them. This
+	/// replaces whatever was being done with them before otherwise. See
+	/// [`Generator::discard_leftovers`].
+	///
+	/// ```rust
+	/// use yeet::Generator;
+	///
+	/// struct Flush;
+	/// impl Drop for Flush {
+	///     fn drop(&mut self) {
+	///         yeet::yeet("flushed");
+	///     }
+	/// }
+	///
+	/// let mut gen = Generator::<&str>::from_fn_ptr(|| {
+	///     let _flush = Flush;
+	///     loop {
+	///         yeet::yeet("working");
+	///     }
+	/// });
+	/// let leftovers = gen.collect_leftovers();
+	///
+	/// assert_eq!(gen.next(), Some("working"));
+	/// assert!(gen.close().is_ok());
+	/// assert_eq!(leftovers.collect::<Vec<_>>(), ["flushed"]);
+	/// ```
+	pub fn collect_leftovers(&mut self) -> Leftovers<T> {
+		let queue = match &self.leftover {
+			Leftover::Collect(queue) => queue.clone(),
+			_ => {
+				let queue = Rc::new(RefCell::new(VecDeque::new()));
+				self.leftover = Leftover::Collect(queue.clone());
+				queue
+			}
+		};
+		Leftovers { queue }
+	}
+}
+
+/// Handle to the values a producer has yielded while it was being cancelled.
+///
+/// Iterating over the handle takes the values that have been kept so far, and
+/// stops once there are none left. Handles may outlive their generator, which
+/// is the point of them, as the producer gets cancelled when the generator gets
+/// closed or dropped.
+pub struct Leftovers<T> {
+	/// The values yielded by the producer.
+	queue: Rc<RefCell<VecDeque<T>>>,
+}
+impl<T> Leftovers<T> {
+	/// The number of values waiting to be taken.
+	pub fn len(&self) -> usize {
+		self.queue.borrow().len()
+	}
+
+	/// Whether there are no values waiting to be taken.
+	pub fn is_empty(&self) -> bool {
+		self.queue.borrow().is_empty()
+	}
+}
+impl<T> Iterator for Leftovers<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		self.queue.borrow_mut().pop_front()
+	}
+}
+impl<T> Clone for Leftovers<T> {
+	fn clone(&self) -> Self {
+		Self { queue: self.queue.clone() }
+	}
+}
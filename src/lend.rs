@@ -0,0 +1,97 @@
+use std::any::TypeId;
+use crate::{current_header, Generator};
+use crate::sys::{AnyTask, Header};
+
+/// A value lent by the consumer to the producer for the duration of a resume.
+///
+/// The consumer keeps the actual `&mut S` on its own stack, for as long as the
+/// producer is running, and what gets stored here is a pointer to it. Because
+/// `S` may be unsized, we can't just store a `*mut S` and still forget about
+/// its type, so we store a pointer to the reference instead, which is always a
+/// thin pointer.
+pub struct Lend {
+	/// The type of the value being lent.
+	ty: TypeId,
+	/// Pointer to the `&mut S` reference being lent.
+	ptr: *mut (),
+	/// Whether the producer is currently holding on to the value.
+	borrowed: bool,
+}
+impl Lend {
+	/// Whether the producer is currently holding on to the lent value.
+	pub fn is_borrowed(&self) -> bool {
+		self.borrowed
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// Requests the next value from the generator, lending it the given value
+	/// until it yields.
+	///
+	/// The producer may access the value through [`with_lent`], for as long as
+	/// it doesn't yield. This allows producers to, for instance, decode data
+	/// directly into buffers owned by the consumer, without any extra copies.
+	///
+	/// If a value has been stashed by [`Generator::peek`], or is left over
+	/// from a slice yielded all at once with [`yeet_vec`], it is returned
+	/// right away, and the producer never gets to see the lent value.
+	///
+	/// [`yeet_vec`]: crate::yeet_vec
+	pub fn next_lending<S: ?Sized + 'static>(&mut self, lent: &mut S) -> Option<T> {
+		let mut lent = lent;
+		self.task.header().lent = Some(Lend {
+			ty: TypeId::of::<S>(),
+			ptr: &mut lent as *mut &mut S as *mut (),
+			borrowed: false,
+		});
+
+		/* Make sure the lent value is gone even if the producer panics. */
+		struct Reclaim(*mut Header);
+		impl Drop for Reclaim {
+			fn drop(&mut self) {
+				unsafe { (*self.0).lent = None }
+			}
+		}
+		let _reclaim = Reclaim(self.task.header());
+
+		self.next()
+	}
+}
+
+/// Gives the given function access to the value lent by the consumer.
+///
+/// Values are lent to producers through [`Generator::next_lending`], and are
+/// only available until the producer yields again. Trying to yield from inside
+/// of the given function will cause it to panic.
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator,
+/// if the consumer has not lent a value of type `S` to the current resume of
+/// the generator, or if the value is already being accessed.
+pub fn with_lent<S: ?Sized + 'static, R>(f: impl FnOnce(&mut S) -> R) -> R {
+	let header = current_header();
+	let lent = match unsafe { (*header).lent.as_mut() } {
+		Some(lent) => lent,
+		None => panic!("The consumer has not lent a value to this generator!")
+	};
+	if lent.ty != TypeId::of::<S>() {
+		panic!("Tried to access a lent value of the wrong type!")
+	}
+	if lent.borrowed {
+		panic!("Tried to access a lent value that is already being accessed!")
+	}
+
+	/* Keep the value marked as borrowed for as long as the function runs, so
+	 * that any attempt to yield from inside it fails instead of leaving a
+	 * dangling reference behind. */
+	struct Release(*mut Lend);
+	impl Drop for Release {
+		fn drop(&mut self) {
+			unsafe { (*self.0).borrowed = false }
+		}
+	}
+	lent.borrowed = true;
+	let _release = Release(lent as *mut _);
+
+	f(unsafe { &mut **(lent.ptr as *mut &mut S) })
+}
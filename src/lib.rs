@@ -1,8 +1,118 @@
-use std::any::Any;
-use std::cell::RefCell;
-use crate::sys::Task;
+use std::any::{Any, TypeId};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use crate::hook::Direction;
+use crate::registry::TaskState;
+use crate::sys::{AnyTask, Entry, Header, Stack, Task};
 
+pub use adapt::{Chunks, Flatten};
+pub use arena::{alloc_in_consumer, Arena, ArenaRef};
+pub use borrowed::{yeet_borrowed, Borrowed, LendingGenerator};
+pub use broadcast::{Broadcast, Lag};
+pub use builder::GeneratorBuilder;
+pub use cached::Cached;
+pub use cancel::{cancellation, ffi_guard, shield, CancelBound, CancelToken, Cancelled, Overrun};
+pub use demand::remaining_demand;
+pub use depth::{max_depth, set_max_depth, DepthExceeded};
+pub use detach::{detach, Detach};
+pub use fanout::SharedGenerator;
+pub use generate::Generate;
+pub use group::GeneratorGroup;
+pub use hint::size_hint;
+pub use into::IntoGenerator;
+pub use leak::leak_report;
+pub use leftover::Leftovers;
+pub use lend::with_lent;
+pub use merge::{merge_sorted, MergeSorted};
+pub use panic::{TaskIdentity, TaskPanic};
+pub use poison::Panicked;
+pub use pool::GeneratorPool;
+pub use profile::profile_report;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd", target_os = "dragonfly"))]
+pub use reactor::Reactor;
+pub use recurse::{recurse, recurse_with_stack_size, Recurse, RECURSE_STACK_SIZE};
+pub use registry::{current_task, tasks};
+pub use restart::{Cycle, RestartableGenerator};
+pub use report::{report, report_error};
+pub use shared::with_state;
+pub use side::{yeet_to, Side, SideChannel};
+pub use sleep::{sleep, sleep_until};
+pub use stack::{maybe_grow, remaining_stack};
+pub use stats::Stats;
+pub use thread::ThreadGenerator;
+pub use timeout::Timeout;
+pub use trampoline::{trampoline, Bounce};
+pub use typed::Yielder;
+
+mod adapt;
+mod arena;
+mod borrowed;
+mod broadcast;
+mod builder;
+mod cached;
+mod cancel;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "crossbeam")]
+pub mod channel;
+pub mod coroutine;
+#[cfg(feature = "debugger")]
+pub mod debug;
+mod demand;
+mod depth;
+mod detach;
+mod drain;
+#[cfg(feature = "fallible-iterator")]
+pub mod fallible;
+mod fanout;
+#[cfg(feature = "genawaiter")]
+pub mod genawaiter;
+mod generate;
+mod group;
+pub mod hook;
+mod handoff;
+mod hint;
+pub mod into;
+#[cfg(unix)]
+pub mod io;
+pub mod leak;
+mod leftover;
+mod lend;
+mod merge;
+mod panic;
+mod poison;
+#[cfg(all(unix, not(feature = "corosensei"), not(feature = "ucontext"), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub mod perf;
+mod pool;
+pub mod profile;
+pub mod raw;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd", target_os = "dragonfly"))]
+mod reactor;
+pub mod record;
+mod recurse;
+pub mod registry;
+mod restart;
+mod report;
+mod shared;
+mod side;
+mod sleep;
+mod stack;
+mod stats;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod sync;
 mod sys;
+pub mod testing;
+mod thread;
+mod timeout;
+#[cfg(feature = "debug-log")]
+mod trace;
+mod trampoline;
+mod typed;
+pub mod walk;
 
 /// A generator task.
 /// 
@@ -37,129 +147,752 @@ mod sys;
 /// Instances of this struct may be created using the [`Generator::from_fn_ptr`]
 /// function, which will run the given function as a generator task. It is
 /// expected that all the values yielded by the function are of type `T`.
+///
+/// Producers that need startup parameters may be created with
+/// [`Generator::from_fn_with`], or from a closure, with
+/// [`Generator::from_closure`].
+///
+/// Generators with parameters other than the default ones may be created using
+/// a [`GeneratorBuilder`].
 /// 
 pub struct Generator<T: 'static> {
 	task: Task<T>,
 	first: bool,
+	buffer: VecDeque<T>,
+	poisoned: bool,
+	panic: Option<panic::Payload>,
+	hash: Option<record::HashValue<T>>,
+	leftover: leftover::Leftover<T>,
 }
 impl<T: 'static> Generator<T> {
 	/// Creates a new instance of this structure from a raw function pointer.
 	pub fn from_fn_ptr(func: fn()) -> Self {
+		Self::from_entry(Entry::Ptr(func))
+	}
+
+	/// Creates a new instance of this structure from a raw function pointer,
+	/// which gets called with the given argument.
+	///
+	/// This allows producers to receive startup parameters without having to
+	/// go through global state.
+	pub fn from_fn_with<A: 'static>(arg: A, func: fn(A)) -> Self {
+		Self::from_closure(move || func(arg))
+	}
+
+	/// Creates a new instance of this structure from a closure.
+	///
+	/// Unlike functions, closures may carry state along with them, at the cost
+	/// of an allocation.
+	pub fn from_closure(func: impl FnOnce() + 'static) -> Self {
+		Self::from_boxed(Box::new(func))
+	}
+
+	/// Creates a new instance of this structure from a boxed closure.
+	pub(crate) fn from_boxed(func: Box<dyn FnOnce()>) -> Self {
+		Self::from_entry(Entry::Boxed(func))
+	}
+
+	/// Creates a new instance of this structure running the given entry on a
+	/// stack of the default size.
+	fn from_entry(func: Entry) -> Self {
+		Self::from_parts(func, Stack::new(sys::DEFAULT_STACK_SIZE), None)
+	}
+
+	/// Creates a new instance of this structure running the given entry on the
+	/// given stack, with the given name.
+	fn from_parts(func: Entry, stack: Stack, name: Option<Rc<str>>) -> Self {
+		if let Err(error) = depth::check() {
+			panic!("Tried to create a generator past the depth limit: {error}")
+		}
+
+		let mut task = sys::new_task(func, stack);
+		let bounds = task.stack_bounds();
+
+		let (parent, depth) = position();
+		let header = task.header();
+		header.record = registry::register(header.id, name.clone(), bounds);
+		header.census = leak::Census::register(header.id, name.as_deref());
+		header.name = name;
+		header.set_parent(parent, depth);
+
 		Self {
-			task: sys::new_task(func),
+			task,
 			first: true,
+			buffer: VecDeque::new(),
+			poisoned: false,
+			panic: None,
+			hash: None,
+			leftover: leftover::Leftover::Discard,
 		}
 	}
 	
+	/// The unique identifier of the task running this generator.
+	pub fn id(&self) -> TaskId {
+		self.task.id()
+	}
+
+	/// The name of the task running this generator, if it was given one.
+	pub fn name(&self) -> Option<&str> {
+		self.task.header_ref().name.as_deref()
+	}
+
+	/// The current state of the task running this generator.
+	pub fn state(&self) -> TaskState {
+		self.task.header_ref().state()
+	}
+	
 	/// Enters the task sending the given resume value.
 	fn enter_with(&mut self, val: Send) -> Yield<T> {
+		/* Switching into the task from another thread would push it onto the
+		 * stack of tasks of the wrong thread, and hand the producer values
+		 * that belong to this one. */
+		let header = self.task.header_ref();
+		if header.thread != sys::thread_id() {
+			match &header.name {
+				Some(name) => panic!("Tried to resume generator {name:?} (#{}) on a thread other than the one it was created on!", header.id.as_u64()),
+				None => panic!("Tried to resume generator #{} on a thread other than the one it was created on!", header.id.as_u64()),
+			}
+		}
+
+		/* Producers that have been abandoned never run again, and their
+		 * stacks are gone for all we care. */
+		if header.abandoned {
+			return Yield::StopIteration
+		}
+
+		hook::dispatch(self.task.header(), Direction::Resume);
+		let recording = record::is_recording();
+		if recording {
+			let header = self.task.header_ref();
+			record::resumed(header.id, header.name.as_deref());
+		}
+		let (parent, depth) = position();
+		let header = self.task.header();
+		header.set_state(TaskState::Running);
+		header.set_parent(parent, depth);
+		match val {
+			Send::Continue | Send::Skip(_) => header.stats.resumes += 1,
+			Send::Cancel => header.stats.cancels += 1,
+		}
+		let started = header.measure_time.then(Instant::now);
+		let thread_name = match (&header.name, header.name_thread) {
+			(Some(name), true) => sys::thread_name::set(name),
+			_ => None,
+		};
+		let signal_stack = header.signal_stack.as_ref().and_then(sys::signal_stack::install);
+		let activation = profile::resumed(header);
+		#[cfg(feature = "debug-log")]
+		trace::resume(header, &val);
+
 		let this = &mut self.task as *mut _;
-		TASK_STACK.with_borrow_mut(|stack| {
-			stack.push(this as *mut dyn Any);
-		});
+		push_task(this as *mut dyn AnyTask);
 		
 		/* This cannot panic. */
 		let result = unsafe {
 			sys::enter(this, val)
 		};
 
-		/* We want to stop any possible unwinds here, because if we're running
-		 * inside a task, the start function might want to call `yield_internal`
-		 * to report the panic to the parent task.
-		 * 
-		 * Right at this moment, though, `yield_internal` will consider this the
-		 * parent task. Which is a problem, because if we panic before the
-		 * context switch, the yield address for this task might be complete
-		 * nonsense, and if we panic after the context switch, we will end up
-		 * re-running destructors. Both of these are horrible outcomes. */  
-		let try_pop = std::panic::catch_unwind(|| {
-			TASK_STACK.with_borrow_mut(|stack| {
-				stack.pop();
+		/* Nothing may unwind between the switch back and the pop, because if
+		 * we're running inside a task, the start function might want to call
+		 * `yield_internal` to report the panic to the parent task, which would
+		 * then still consider this the parent task. The pop can't fail, so
+		 * there is nothing to guard against here. */
+		pop_task();
+
+		if let Some(activation) = activation {
+			activation.yielded()
+		}
+		if let Some(thread_name) = thread_name {
+			sys::thread_name::restore(thread_name)
+		}
+		if let Some(signal_stack) = signal_stack {
+			sys::signal_stack::restore(signal_stack)
+		}
+
+		/* A producer that wrote past the ends of its stack can't be trusted
+		 * to run again, and whatever it wrote over can't be trusted to be
+		 * freed, so the whole task gets abandoned. */
+		if let Err(canary) = self.task.check_canaries() {
+			self.task.leak_stack();
+			self.poisoned = true;
+			let header = self.task.header();
+			header.corrupted = true;
+			header.set_state(TaskState::Finished);
+
+			let which = match canary {
+				sys::Canary::Bottom => "overflowed its stack",
+				sys::Canary::Top => "wrote over the top of its stack",
+			};
+			match &header.name {
+				Some(name) => panic!("The producer of generator {name:?} (#{}) {which}!", header.id.as_u64()),
+				None => panic!("The producer of generator #{} {which}!", header.id.as_u64()),
+			}
+		}
+		if let Some(started) = started {
+			self.task.header().stats.time_in_producer += started.elapsed();
+		}
+
+		/* Producers that can't unwind are left where they were cancelled, and
+		 * everything they have on their stacks goes down with them. */
+		let result = if self.task.header_ref().abandoned {
+			self.task.leak_stack();
+			Yield::StopIteration
+		} else {
+			result
+		};
+
+		let state = match result {
+			Yield::Value(_) | Yield::Batch(_) | Yield::Pending => TaskState::Suspended,
+			Yield::StopIteration | Yield::Panic(_) => TaskState::Finished,
+		};
+		self.task.header().set_state(state);
+		if let Some(record) = &self.task.header_ref().record {
+			record.set_context(match state {
+				TaskState::Suspended => sys::saved_context(&self.task),
+				_ => None,
 			})
-		});
-		if let Err(_) = try_pop {
-			/* If we fail to pop the stack, we're done for. Stop here. */
-			std::process::abort()
 		}
+		#[cfg(feature = "debug-log")]
+		trace::yielded(self.task.header_ref(), &result);
+		if recording {
+			let header = self.task.header_ref();
+			record::yielded(header.id, header.name.as_deref(), &result, self.hash);
+		}
+		hook::dispatch(self.task.header(), Direction::Yield);
 		
 		result
 	}
+
+	/// Resumes the producer until it either yields a value, reaches a
+	/// checkpoint, or finishes.
+	///
+	/// Unlike [`Generator::next`], which keeps the producer running through
+	/// the checkpoints it reaches with [`yield_now`], this function hands
+	/// control back to the consumer at every one of them, reporting them as
+	/// [`Resume::Pending`]. This lets the consumer interleave long-running
+	/// producers with other work, or stop driving them altogether.
+	///
+	/// # Panic
+	/// Panics raised by the producer are propagated to the caller, after
+	/// which the generator is poisoned, and reports itself as complete.
+	pub fn resume(&mut self) -> Resume<T> {
+		self.resume_skipping(0)
+	}
+
+	/// Resumes the producer, discarding the given number of values it yields,
+	/// until it either yields a value past those, reaches a checkpoint, or
+	/// finishes.
+	///
+	/// The values get discarded on the side of the producer, as it yields
+	/// them, so skipping over any number of values costs a single switch,
+	/// rather than a pair of switches per value. Values that are left to be
+	/// skipped when the producer reaches a checkpoint still get skipped once it
+	/// is resumed again. Values that are stashed by the generator, such as the
+	/// one kept by [`Generator::peek`], are skipped first.
+	///
+	/// # Panic
+	/// Panics raised by the producer are propagated to the caller, as with
+	/// [`Generator::resume`].
+	pub fn resume_skipping(&mut self, n: usize) -> Resume<T> {
+		match self.try_resume(n) {
+			Ok(resume) => resume,
+			Err(what) => std::panic::resume_unwind(what),
+		}
+	}
+
+	/// Same as [`Generator::resume_skipping`], but panics raised by the
+	/// producer are returned, rather than propagated.
+	fn try_resume(&mut self, skip: usize) -> Result<Resume<T>, panic::Payload> {
+		let buffered = skip.min(self.buffer.len());
+		self.buffer.drain(..buffered);
+		let skip = skip - buffered;
+
+		if skip == 0 {
+			if let Some(value) = self.buffer.pop_front() {
+				return Ok(Resume::Value(value))
+			}
+		}
+		if self.poisoned {
+			return Ok(Resume::Complete)
+		}
+
+		self.first = false;
+		let send = match skip {
+			0 => Send::Continue,
+			skip => Send::Skip(skip),
+		};
+		match self.enter_with(send) {
+			Yield::StopIteration => Ok(Resume::Complete),
+			Yield::Panic(what) if what.is::<Cancelled>() => {
+				/* The producer has been cancelled through one of its tokens. */
+				Ok(Resume::Complete)
+			}
+			Yield::Panic(what) => {
+				self.poisoned = true;
+				let (_, depth) = position();
+				Err(panic::stitch(what, self.task.header_ref(), depth))
+			}
+			Yield::Pending => Ok(Resume::Pending),
+			Yield::Value(value) => Ok(Resume::Value(value)),
+			Yield::Batch(values) => {
+				/* Batches are never empty. */
+				self.buffer.extend(values);
+				Ok(Resume::Value(self.buffer.pop_front().unwrap()))
+			}
+		}
+	}
+
+	/// Starts the task running this generator, without running any of the
+	/// producer.
+	///
+	/// The first resume of a generator sets its task up, and switches over to
+	/// its stack for the first time, which costs noticeably more than any of
+	/// the resumes that follow it. This lets the consumer pay for all of that
+	/// at a point of its choosing, rather than on the first call to
+	/// [`Generator::next`]. Generators that have already been started are
+	/// left untouched.
+	pub fn start(&mut self) {
+		if !self.first {
+			return
+		}
+
+		self.first = false;
+		self.task.header().park = true;
+		let _ = self.enter_with(Send::Continue);
+	}
+
+	/// Discards the next `n` values, and returns how many values actually got
+	/// discarded, which is less than `n` only if the producer finished first.
+	///
+	/// Like [`Iterator::nth`], this skips over the values on the side of the
+	/// producer, without switching over to the consumer for every one of them.
+	pub fn skip_items(&mut self, n: usize) -> usize {
+		if n == 0 {
+			return 0
+		}
+
+		let buffered = self.buffer.len() as u64;
+		let yielded = self.task.header_ref().stats.yielded;
+		match self.nth(n - 1) {
+			Some(_) => n,
+			None => (self.task.header_ref().stats.yielded - yielded + buffered) as usize,
+		}
+	}
+
+	/// Returns a reference to the next value, without consuming it.
+	///
+	/// The producer gets resumed if it hasn't yielded a value that is yet to
+	/// be consumed, and the value it yields is kept by the generator, to be
+	/// returned by the next call to [`Generator::next`] or
+	/// [`Generator::resume`].
+	pub fn peek(&mut self) -> Option<&T> {
+		if self.buffer.is_empty() {
+			let value = self.next()?;
+			self.buffer.push_front(value);
+		}
+		self.buffer.front()
+	}
 }
 impl<T: 'static> Iterator for Generator<T> {
 	type Item = T;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		self.first = false;
-		match self.enter_with(Send::Continue) {
-			Yield::StopIteration => None,
-			Yield::Panic(what) => std::panic::resume_unwind(what),
-			Yield::Value(value) => Some(value)
+		loop {
+			match self.resume() {
+				Resume::Value(value) => break Some(value),
+				Resume::Pending => self.wait_deadline(None),
+				Resume::Complete => break None,
+			}
 		}
 	}
+
+	fn nth(&mut self, n: usize) -> Option<Self::Item> {
+		/* The values before the one we're after never leave the producer. */
+		let mut resume = self.resume_skipping(n);
+		loop {
+			match resume {
+				Resume::Value(value) => break Some(value),
+				Resume::Pending => {
+					self.wait_deadline(None);
+					resume = self.resume()
+				}
+				Resume::Complete => break None,
+			}
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let buffered = self.buffer.len();
+		let task = &self.task;
+		if task.filter.is_some() || task.map.is_some() || task.batch.is_some() {
+			return (buffered, None)
+		}
+
+		let header = task.header_ref();
+		let (lower, upper) = match header.state() {
+			TaskState::Finished => (0, Some(0)),
+			_ => header.hint,
+		};
+		(buffered.saturating_add(lower), upper.and_then(|upper| upper.checked_add(buffered)))
+	}
 }
 impl<T: 'static> Drop for Generator<T> {
 	fn drop(&mut self) {
+		/* Cancelling a producer while the consumer is unwinding nests its
+		 * unwind inside of the one of the consumer, so a destructor in the
+		 * producer that panics aborts the process. Producers that might do
+		 * that may ask to be left alone instead. */
+		if !self.first && std::thread::panicking() && self.task.header_ref().leak_on_unwind {
+			self.task.leak_stack();
+			self.task.kill();
+			return
+		}
+
+		/* Producers can't be cancelled from a thread other than their own, and
+		 * panicking here would likely abort, as the generator most likely ended
+		 * up here through the unwind of a failed resume, so it gets leaked. */
+		if !self.first && self.task.header_ref().thread != sys::thread_id() {
+			self.task.leak_stack();
+			self.task.kill();
+			return
+		}
+
+		/* Dropping is the fallback for generators that haven't been closed,
+		 * and panicking in here is nasty, particularly if we're already
+		 * unwinding, so problems just get ignored. */
+		let _ = self.cancel_task();
+		self.task.kill();
+	}
+}
+impl<T: 'static> Generator<T> {
+	/// Cancels the producer, unwinding its stack, and reports any problems the
+	/// producer ran into while being cancelled.
+	///
+	/// Dropping a generator also cancels its producer, but any panic raised
+	/// by the producer while it is being cancelled, say, by code that caught
+	/// the cancellation and panicked in its place, gets silently ignored. This
+	/// function instead returns the payload of such a panic, so that the
+	/// consumer may decide what to do about it.
+	pub fn close(mut self) -> Result<(), panic::Payload> {
+		self.cancel_task()
+	}
+
+	/// Cancels the producer, unwinding its stack.
+	fn cancel_task(&mut self) -> Result<(), panic::Payload> {
 		if self.first {
 			/* Tasks that haven't been started don't need cleanup. */
-			return
+			return Ok(())
 		}
-		
+		if self.task.header_ref().corrupted {
+			/* Tasks that have been abandoned can't be cleaned up. */
+			return Ok(())
+		}
+		self.task.header().cancelled.get_or_insert_with(Cancelled::default);
+
+		let bound = self.task.header_ref().cancel_bound;
+		let started = Instant::now();
+		let mut yields = 0usize;
 		loop {
 			match self.enter_with(Send::Cancel) {
 				Yield::StopIteration => 
 					/* The task had already ended before we cancelled it */
-					break,
+					break Ok(()),
 				Yield::Panic(what) => {
-					if what.is::<CancelTask>() {
+					if what.is::<Cancelled>() {
 						/* This is confirmation that the task was cancelled. */
-						break
+						break Ok(())
 					} else {
 						/* Something else happened that we weren't expecting.
-						 * Propagate the exception up. */
-						std::panic::resume_unwind(what)
+						 * Let the caller decide what to do about it. */
+						break Err(what)
 					}
 				}
-				Yield::Value(_) => {
+				Yield::Value(value) => {
 					/* This may happen if there's a yield in destructor code. 
-					 * Just drop whatever value we receive. */
-				}
+					 * The value goes wherever the consumer asked for. */
+					self.leftover.take(value)
+				},
+				Yield::Batch(values) => {
+					for value in values {
+						self.leftover.take(value)
+					}
+				},
+				Yield::Pending => {}
+			}
+
+			yields += 1;
+			let Some((bound, overrun)) = bound else { continue };
+			let overran = match bound {
+				CancelBound::Yields(limit) => yields > limit,
+				CancelBound::Time(limit) => started.elapsed() > limit,
+			};
+			if overran {
+				break Err(self.overrun(overrun))
 			}
 		}
 	}
+
+	/// Deals with a producer that has kept running past the bound on its
+	/// cancellation, and returns the payload the overrun gets reported with.
+	fn overrun(&mut self, overrun: Overrun) -> panic::Payload {
+		let header = self.task.header_ref();
+		let message = match &header.name {
+			Some(name) => format!("The producer of generator {name:?} (#{}) kept running past the bound on its cancellation!", header.id.as_u64()),
+			None => format!("The producer of generator #{} kept running past the bound on its cancellation!", header.id.as_u64()),
+		};
+
+		match overrun {
+			Overrun::Leak => {
+				self.task.leak_stack();
+				let header = self.task.header();
+				header.abandoned = true;
+				header.set_state(TaskState::Finished);
+				Box::new(message)
+			},
+			Overrun::Abort => {
+				eprintln!("{message}");
+				std::process::abort()
+			}
+		}
+	}
+}
+
+/// The outcome of resuming a producer with [`Generator::resume`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resume<T> {
+	/// The producer has yielded the given value.
+	Value(T),
+	/// The producer has reached a checkpoint set with [`yield_now`], without
+	/// yielding a value.
+	Pending,
+	/// The producer is done yielding values.
+	Complete,
+}
+
+/// Unique identifier of a generator task.
+///
+/// Identifiers are never reused for as long as the process runs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+impl TaskId {
+	/// Allocates a new identifier.
+	fn new() -> Self {
+		static NEXT: AtomicU64 = AtomicU64::new(1);
+		Self(NEXT.fetch_add(1, Ordering::Relaxed))
+	}
+
+	/// The numeric value of this identifier.
+	pub fn as_u64(self) -> u64 {
+		self.0
+	}
 }
 
 thread_local! {
-	/// The current stack of executing tasks.
+	/// The top of the current stack of executing tasks.
 	/// 
-	/// Every time a task is entered the pointer to its context structure gets
-	/// pushed to this stack, and every time a task returns the pointer to its
-	/// context structure gets popped off the stack.
+	/// Every time a task is entered the pointer to its header gets pushed to
+	/// this stack, and every time a task returns the pointer to its header gets
+	/// popped off the stack. The stack is threaded through the headers of the
+	/// tasks in it, each of which points to the one below it.
 	///
-	/// In effect, this stack always points to the context structure for the
-	/// currently running task.
-	static TASK_STACK: RefCell<Vec<*mut dyn Any>> = Default::default()
+	/// In effect, this always points to the header of the currently running
+	/// task, or is null if there is none.
+	static TASK_STACK: Cell<*mut Header> = const { Cell::new(std::ptr::null_mut()) }
+}
+
+/// Pushes the given task onto the stack of executing tasks.
+fn push_task(task: *mut dyn AnyTask) {
+	unsafe {
+		let header = (*task).header() as *mut Header;
+		(*header).below = TASK_STACK.get();
+		(*header).this = Some(task);
+		TASK_STACK.set(header)
+	}
+}
+
+/// Pops the task on top of the stack of executing tasks.
+///
+/// This never panics. The stack has no destructor, so it is always there to be
+/// accessed, and the task was pushed to it before the switch, so it must still
+/// be on top.
+fn pop_task() {
+	let header = TASK_STACK.get();
+	TASK_STACK.set(unsafe { (*header).below })
+}
+
+/// Returns the currently running task, if there is one.
+fn top_task() -> Option<*mut dyn AnyTask> {
+	let header = TASK_STACK.get();
+	if header.is_null() {
+		return None
+	}
+	unsafe { (*header).this }
+}
+
+/// Returns the header of the currently running task.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+fn current_header() -> *mut Header {
+	match try_current_header() {
+		Some(header) => header,
+		None => panic!("Tried to access the current task from outside a generator!")
+	}
+}
+
+/// Returns the header of the currently running task, if there is one.
+fn try_current_header() -> Option<*mut Header> {
+	let header = TASK_STACK.get();
+	(!header.is_null()).then_some(header)
+}
+
+/// Calls the given function with the currently running task, if there is one.
+fn with_current_task<R>(f: impl FnOnce(&mut dyn AnyTask) -> R) -> Option<R> {
+	let top = top_task()?;
+	Some(f(unsafe { &mut *top }))
+}
+
+/// The identifier of the currently running task, if there is one, and how
+/// deep a task driven from it would be.
+///
+/// Tasks are as deep as the stack of executing tasks is tall when they run, so
+/// the depth of the task on top is the height of the stack.
+fn position() -> (Option<TaskId>, usize) {
+	match try_current_header() {
+		Some(header) => unsafe { (Some((*header).id), (*header).depth + 1) },
+		None => (None, 1),
+	}
 }
 
 /// Yields the given packet of data, and returns the data sent by the consumer.
 fn yield_internal<T: 'static>(val: Yield<T>) -> Send {
-	let task = TASK_STACK.with_borrow_mut(|stack| {
-		let top = match stack.last() {
-			Some(top) => *top,
-			None => panic!("Tried to yield from outside a generator!")
-		};
+	/* The header is read straight off the stack of executing tasks, as going
+	 * through the task would take a virtual call every time. */
+	let (header, top) = match try_current_header() {
+		Some(header) => unsafe { (header, (*header).this.unwrap()) },
+		None => panic!("Tried to yield from outside a generator!")
+	};
+	unsafe { (*top).validate("yield from") };
+
+	if unsafe { (*header).is_borrowing() } {
+		panic!("Tried to yield while holding a value shared by the consumer!")
+	}
+
+	/* Producers whose tokens have been cancelled don't get to yield again. */
+	if let Yield::Value(_) | Yield::Batch(_) = val {
+		if unsafe { (*header).check_tokens() } {
+			return Send::Cancel
+		}
+	}
+
+	/* Values that the consumer has asked to be mapped take a detour through
+	 * the mapping function, which knows the type the consumer expects. Only
+	 * tasks that have a mapping pay for finding out whether it applies. */
+	let val = match val {
+		Yield::Value(value) if unsafe { (*header).mapped } => {
+			let mut value = Some(value);
+			let ty = TypeId::of::<T>();
+			if let Some(send) = unsafe { (*top).exit_mapped(ty, &mut value as *mut Option<T> as *mut ()) } {
+				return send
+			}
+
+			/* The value is left untouched if there is no mapping for it. */
+			Yield::Value(value.unwrap())
+		}
+		/* Batches can only be handed over as they are to consumers that take
+		 * every value in them as is. Everyone else gets them one by one. */
+		Yield::Batch(values) => {
+			let plain = unsafe { (&*top as &dyn Any).downcast_ref::<Task<T>>() }
+				.is_some_and(|task| task.filter.is_none() && task.batch.is_none());
+			if !plain {
+				for value in values {
+					if let Send::Cancel = yield_internal(Yield::Value(value)) {
+						return Send::Cancel
+					}
+				}
+				return Send::Continue
+			}
+
+			Yield::Batch(values)
+		}
+		val => val,
+	};
+
+	match unsafe { (&mut *top as &mut dyn Any).downcast_mut::<Task<T>>() } {
+		Some(task) => unsafe { yield_to(task, val) },
+		None => panic!("Tried to yield a value of the wrong type!")
+	}
+}
+
+/// Yields the given packet of data from the given task, which must be the
+/// currently running task, and returns the data sent by the consumer.
+unsafe fn yield_to<T: 'static>(task: *mut Task<T>, val: Yield<T>) -> Send {
+	let val = match val {
+		Yield::Value(value) => {
+			/* Values rejected by the filter never leave the producer. Tasks
+			 * without one get away with checking for it. */
+			if (*task).filter.is_some() && !filter_value(task, &value) {
+				return Send::Continue
+			}
+
+			let header = (*task).header();
+			header.count_yielded(1);
+			if header.skip > 0 {
+				header.skip -= 1;
+				return Send::Continue
+			}
+
+			/* Batched values are kept by the producer until there's enough of
+			 * them, and then get picked up by the consumer all at once. */
+			match &mut (*task).batch {
+				Some(batch) => {
+					batch.push(value);
+					if !batch.is_full() {
+						return Send::Continue
+					}
+					Yield::Pending
+				}
+				None => Yield::Value(value),
+			}
+		}
+		Yield::Batch(mut values) => {
+			let header = (*task).header();
+			header.count_yielded(values.len());
 
-		let task = unsafe { &mut *top };
-		match task.downcast_mut::<Task<T>>() {
-			Some(task) => task as *mut _,
-			None => panic!("Tried to yield a value of the wrong type!")
+			let skipped = header.skip.min(values.len());
+			header.skip -= skipped;
+			values.drain(..skipped);
+			if values.is_empty() {
+				return Send::Continue
+			}
+
+			Yield::Batch(values)
 		}
-	});
+		val => val,
+	};
+	if let Yield::Value(_) | Yield::Batch(_) | Yield::Pending = val {
+		sync::check_yield();
+		(*task).note_stack_depth();
+	}
 	
-	let (_, value) = unsafe { sys::exit(task, val) };
+	let (_, value) = sys::exit(task, val);
 	
 	value
 }
 
+/// Runs the filter of the given task, which must have one, on the given value.
+///
+/// The filter is taken out of the task while it runs, so that yields made from
+/// inside of it don't get to it again.
+#[inline(never)]
+unsafe fn filter_value<T: 'static>(task: *mut Task<T>, value: &T) -> bool {
+	let Some(mut filter) = (*task).filter.take() else { return true };
+	let pass = filter(value);
+	(*task).filter = Some(filter);
+
+	pass
+}
+
 /// Yield the given value.
 ///
 /// This function will suspend the currently running function and return control
@@ -178,7 +911,7 @@ fn yield_internal<T: 'static>(val: Yield<T>) -> Send {
 /// generator, of if `T` is mismatched with the type expected by the consumer.  
 pub fn yeet<T: 'static>(val: T) {
 	match yield_internal(Yield::Value(val)) {
-		Send::Continue => {
+		Send::Continue | Send::Skip(_) => {
 			/* We've been requested to continue, so do nothing and let the
 			 * current task yield another value or enter the stop loop. */ 
 		}
@@ -187,36 +920,209 @@ pub fn yeet<T: 'static>(val: T) {
 			 * task, so that we can properly clean it up, and let the task start
 			 * function for the current system propagate the cancellation up to
 			 * the parent task. */
-			std::panic::panic_any(CancelTask)
+			cancel()
 		}
 	}
 }
 
+/// Suspends the producer without yielding a value.
+///
+/// This is a cooperative checkpoint for long computations: the consumer gets
+/// control back, and may choose to cancel the producer or to do some other
+/// work before resuming it, without the producer having to make up a value to
+/// yield. Consumers driving the generator through [`Generator::next`] never
+/// see these checkpoints, as the producer just gets resumed again right away.
+/// They are only visible through [`Generator::resume`].
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator.
+pub fn yield_now() {
+	if try_yield_now().is_err() {
+		/* Same as in `yeet`. */
+		cancel()
+	}
+}
+
+/// Yield the given value, returning the cancellation of the producer instead
+/// of unwinding from it.
+///
+/// This is [`yeet`] for producers that check for their cancellation
+/// explicitly, and return on their own once they've been cancelled, rather
+/// than having their stacks unwound by the runtime. It is mostly of use in
+/// builds with `panic = "abort"`, where producers that get cancelled at a
+/// plain [`yeet`] are abandoned, without anything on their stacks ever getting
+/// dropped.
+///
+/// ```rust
+/// use yeet::Generator;
+///
+/// let mut gen = Generator::<u32>::from_fn_ptr(|| {
+///     for i in 0u32.. {
+///         if yeet::try_yeet(i).is_err() {
+///             return
+///         }
+///     }
+/// });
+/// assert_eq!(gen.next(), Some(0));
+/// drop(gen);
+/// ```
+///
+/// # Panic
+/// This function panics under the same conditions as [`yeet`]. Producers that
+/// keep yielding after having been cancelled get cancelled at every yield.
+pub fn try_yeet<T: 'static>(val: T) -> Result<(), Cancelled> {
+	match yield_internal(Yield::Value(val)) {
+		Send::Continue | Send::Skip(_) => Ok(()),
+		Send::Cancel => Err(cancelled()),
+	}
+}
+
+/// Suspends the producer without yielding a value, returning the
+/// cancellation of the producer instead of unwinding from it.
+///
+/// This is [`yield_now`] for producers that check for their cancellation
+/// explicitly. See [`try_yeet`].
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator.
+pub fn try_yield_now() -> Result<(), Cancelled> {
+	sync::check_yield();
+
+	let task = match top_task() {
+		Some(top) => top,
+		None => panic!("Tried to yield from outside a generator!")
+	};
+	if unsafe { (*task).header() }.is_borrowing() {
+		panic!("Tried to yield while holding a value shared by the consumer!")
+	}
+
+	if unsafe { (*task).header() }.check_tokens() {
+		return Err(cancelled())
+	}
+	match unsafe { (*task).exit_pending() } {
+		Send::Cancel => Err(cancelled()),
+		_ => Ok(()),
+	}
+}
+
 /// Yield all the values in the given iterator.
-pub fn yeet_all<T: 'static, I: Iterator<Item = T>>(iter: I) {
-	for i in iter {
+///
+/// The lower bound of the size hint of the iterator gets forwarded to the
+/// consumer, as if through [`size_hint`], for as long as this runs. Once it is
+/// done, the hint the producer had before goes back in place, counted down by
+/// the values that were yielded, as it would have been if this hadn't touched
+/// it.
+pub fn yeet_all<T: 'static, I: Iterator<Item = T>>(mut iter: I) {
+	/* Iterators with nothing in them never get to touch the task, so they may
+	 * be drained outside of generators, as they always could. */
+	let Some(first) = iter.next() else { return };
+	let header = current_header();
+	let (lower, upper) = unsafe { (*header).hint };
+	size_hint(iter.size_hint().0.saturating_add(1), None);
+
+	let mut count = 0usize;
+	for i in std::iter::once(first).chain(iter) {
+		count += 1;
 		yeet(i)
 	}
+
+	/* The task may have moved while it was suspended, so the header gets
+	 * looked up anew. */
+	size_hint(lower.saturating_sub(count), upper.map(|upper| upper.saturating_sub(count)))
 }
 
-/// Internal signal associated with task cancellation.
+/// Yields all the values in the given vector at once.
 ///
-/// # Task Cancellation
-/// Cancelling generator tasks is not a trivial endeavor, seeing as we need to
-/// run the destructors for all the functions in the call stacks of the tree of
-/// tasks that any given generator may have spawned.
+/// Unlike [`yeet_all`], which switches over to the consumer and back for every
+/// value, this hands all the values over to the consumer in a single switch.
+/// The consumer keeps them in a buffer, and hands them out one by one from
+/// there, only resuming the producer once the buffer has run dry.
 ///
-/// To do this, we use the regular panic mechanism provided to us by Rust. This
-/// is generally fine as our code should always be at the base of all generator
-/// task call stacks. But it is a problem if users call [`std::panic::catch_unwind`].
-struct CancelTask;
+/// Values only get handed over all at once to consumers that take them as they
+/// are. For generators that filter, map, or collect the values of the producer
+/// in chunks, this is the same as [`yeet_all`].
+///
+/// # Panic
+/// This function panics under the same conditions as [`yeet`].
+pub fn yeet_vec<T: 'static>(values: Vec<T>) {
+	if values.is_empty() {
+		return
+	}
+	if let Send::Cancel = yield_internal(Yield::Batch(values)) {
+		/* Same as in `yeet`. */
+		cancel()
+	}
+}
+
+/// Yields a copy of all the values in the given slice at once.
+///
+/// See [`yeet_vec`].
+pub fn yeet_slice<T: Copy + 'static>(values: &[T]) {
+	yeet_vec(values.to_vec())
+}
+
+/// Starts unwinding the current task with the cancellation it is going
+/// through, unless the cancellation has been deferred by an [`ffi_guard`] or a
+/// [`shield`], in which case this returns, and the unwind starts once the
+/// guard is gone.
+///
+/// In builds where panics abort, the task can't be unwound, so it gets
+/// abandoned instead, and never runs again.
+fn cancel() {
+	let header = current_header();
+	unsafe {
+		if (*header).guards > 0 {
+			(*header).deferred = true;
+			return
+		}
+
+		/* Producers that yield while they are already unwinding from their
+		 * cancellation, such as from a destructor, can't start another unwind,
+		 * as that would abort the process. They carry on, and the values they
+		 * yield get dropped. The thread alone can't tell us, as it may also be
+		 * panicking because the consumer is unwinding. */
+		if (*header).unwinding && std::thread::panicking() {
+			return
+		}
+
+		#[cfg(panic = "abort")]
+		{
+			/* The consumer sees the task as finished from here on, and leaks
+			 * its stack, so whatever we send it never gets looked at. */
+			(*header).abandoned = true;
+			let task = top_task().unwrap();
+			loop {
+				let _ = (*task).exit_pending();
+			}
+		}
+
+		#[cfg(not(panic = "abort"))]
+		{
+			(*header).unwinding = true;
+			let cancelled = (*header).cancelled.clone();
+			std::panic::panic_any(cancelled.unwrap_or_default())
+		}
+	}
+}
+
+/// The cancellation the current task is going through.
+fn cancelled() -> Cancelled {
+	unsafe { (*current_header()).cancelled.clone().unwrap_or_default() }
+}
 
 /// Possible signals that may be sent to a producer.
 enum Send {
 	/// Continue until the next yield point.
 	Continue,
 	/// Cancel the task and free up all the resources associated with it.
-	Cancel
+	Cancel,
+	/// Discard the given number of values yielded by the producer, and then
+	/// continue until the next yield point.
+	///
+	/// This never reaches the code in the producer, which sees it as a plain
+	/// [`Send::Continue`]. The values get discarded by the runtime, on the
+	/// producer side, without ever switching over to the consumer.
+	Skip(usize),
 }
 
 /// Possible ways data may come out of a producer.
@@ -233,7 +1139,13 @@ enum Yield<T> {
 	/// 
 	/// We should propagate this panic forward, and we must ensure that any
 	/// subsequent request will yield a [`StopIteration`]. 
-	Panic(Box<dyn Any + std::marker::Send + 'static>),
+	Panic(panic::Payload),
+	/// The generator has reached a checkpoint without yielding any data.
+	Pending,
 	/// The generator has yielded another piece of data.
-	Value(T)
+	Value(T),
+	/// The generator has yielded several pieces of data at once.
+	///
+	/// This is never empty.
+	Batch(Vec<T>),
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+use crate::Generator;
+use crate::panic::Payload;
+
+/// Merges generators that each yield their values in sorted order into a
+/// single iterator over all of their values, in sorted order.
+///
+/// The merge is lazy: generators only get resumed as their values are needed,
+/// with the next value of every generator that isn't done yet being kept in a
+/// heap, so taking a value costs a resume and a number of comparisons that
+/// grows with the logarithm of the number of generators. Values that compare
+/// equal come out in the order their generators were given in. Generators
+/// that are done get dropped right away, and dropping the merge cancels all of
+/// the generators that are left, as would dropping them one by one.
+///
+/// If any of the generators doesn't yield its values in sorted order, neither
+/// does the merge, although all of the values still come out of it.
+///
+/// ```rust
+/// use yeet::Generator;
+///
+/// let gens = [[1u32, 4, 7], [2, 5, 8], [3, 6, 9]]
+///     .map(|values| Generator::from_fn_with(values, |values| yeet::yeet_all(values.into_iter())));
+///
+/// let merged = yeet::merge_sorted(gens, u32::cmp).collect::<Vec<_>>();
+/// assert_eq!(merged, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+/// ```
+pub fn merge_sorted<T, F>(gens: impl IntoIterator<Item = Generator<T>>, cmp: F) -> MergeSorted<T, F>
+	where T: 'static,
+		F: FnMut(&T, &T) -> Ordering {
+
+	MergeSorted {
+		gens: gens.into_iter().map(Some).collect(),
+		heads: Vec::new(),
+		cmp,
+		started: false,
+	}
+}
+
+/// Iterator over the sorted merge of a set of sorted generators.
+///
+/// This is created by [`merge_sorted`].
+pub struct MergeSorted<T: 'static, F> {
+	/// The generators being merged, in the order they were given in, or none
+	/// for the ones that are done.
+	gens: Vec<Option<Generator<T>>>,
+	/// The next value of every generator that isn't done yet, along with the
+	/// index of its generator, as a binary min-heap.
+	heads: Vec<(T, usize)>,
+	/// The function values get compared with.
+	cmp: F,
+	/// Whether the first value of every generator has been taken yet.
+	started: bool,
+}
+impl<T: 'static, F: FnMut(&T, &T) -> Ordering> MergeSorted<T, F> {
+	/// The number of generators that are not done yet.
+	pub fn live(&self) -> usize {
+		self.gens.iter().filter(|gen| gen.is_some()).count()
+	}
+
+	/// Cancels all of the generators that are not done yet, and reports the
+	/// first problem any of them ran into while being cancelled.
+	///
+	/// See [`Generator::close`].
+	pub fn close(self) -> Result<(), Payload> {
+		self.gens.into_iter()
+			.flatten()
+			.map(Generator::close)
+			.fold(Ok(()), Result::and)
+	}
+
+	/// Takes the next value of the generator with the given index, and pushes
+	/// it onto the heap, dropping the generator if it is done.
+	fn advance(&mut self, index: usize) {
+		let Some(gen) = &mut self.gens[index] else { return };
+		match gen.next() {
+			Some(value) => self.push(value, index),
+			None => self.gens[index] = None,
+		}
+	}
+
+	/// Whether the first of the given heads goes before the second one.
+	fn before(&mut self, a: usize, b: usize) -> bool {
+		let (a, b) = (&self.heads[a], &self.heads[b]);
+		(self.cmp)(&a.0, &b.0).then(a.1.cmp(&b.1)) == Ordering::Less
+	}
+
+	/// Pushes the given head onto the heap.
+	fn push(&mut self, value: T, index: usize) {
+		self.heads.push((value, index));
+
+		let mut i = self.heads.len() - 1;
+		while i > 0 {
+			let parent = (i - 1) / 2;
+			if !self.before(i, parent) {
+				break
+			}
+			self.heads.swap(i, parent);
+			i = parent;
+		}
+	}
+
+	/// Pops the smallest head off of the heap.
+	fn pop(&mut self) -> Option<(T, usize)> {
+		if self.heads.is_empty() {
+			return None
+		}
+		let head = self.heads.swap_remove(0);
+
+		let mut i = 0;
+		loop {
+			let (left, right) = (2 * i + 1, 2 * i + 2);
+			let mut least = i;
+			if left < self.heads.len() && self.before(left, least) {
+				least = left;
+			}
+			if right < self.heads.len() && self.before(right, least) {
+				least = right;
+			}
+			if least == i {
+				break
+			}
+			self.heads.swap(i, least);
+			i = least;
+		}
+
+		Some(head)
+	}
+}
+impl<T: 'static, F: FnMut(&T, &T) -> Ordering> Iterator for MergeSorted<T, F> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		if !self.started {
+			self.started = true;
+			for index in 0..self.gens.len() {
+				self.advance(index)
+			}
+		}
+
+		let (value, index) = self.pop()?;
+		self.advance(index);
+
+		Some(value)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let (lower, upper) = self.gens.iter()
+			.flatten()
+			.map(Generator::size_hint)
+			.fold((0usize, Some(0usize)), |(lower, upper), (l, u)| {
+				(lower.saturating_add(l), upper.zip(u).and_then(|(upper, u)| upper.checked_add(u)))
+			});
+
+		let heads = self.heads.len();
+		(lower.saturating_add(heads), upper.and_then(|upper| upper.checked_add(heads)))
+	}
+}
@@ -0,0 +1,154 @@
+//! Reporting of panics raised by producers.
+//!
+//! By default, a panic raised by a producer gets propagated to its consumer
+//! with the exact payload it was raised with. Generators may opt in to having
+//! more information attached to the panic, in which case the payload that gets
+//! propagated is a [`TaskPanic`] wrapping the original payload.
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fmt;
+use crate::TaskId;
+use crate::sys::Header;
+
+/// Type of the payloads carried by panics.
+pub type Payload = Box<dyn Any + std::marker::Send + 'static>;
+
+/// A panic raised by a producer, along with information about where it
+/// happened.
+///
+/// When a producer panics, the report shows only the call stack of the task
+/// the producer runs on, which doesn't tell much about how the code got there.
+/// Generators created with [`GeneratorBuilder::capture_backtraces`] attach the
+/// backtrace of their consumer to the panics they propagate, and, as the panic
+/// travels up a tree of generators, every one of them that has opted in adds
+/// its own, so that the full logical call chain can be recovered.
+///
+/// Generators created with [`GeneratorBuilder::identify_panics`] instead
+/// attach a description of themselves to the panics they propagate, which says
+/// which generator in a deep tree actually failed.
+///
+/// [`GeneratorBuilder::capture_backtraces`]: crate::GeneratorBuilder::capture_backtraces
+/// [`GeneratorBuilder::identify_panics`]: crate::GeneratorBuilder::identify_panics
+pub struct TaskPanic {
+	/// The payload the panic was originally raised with.
+	payload: Payload,
+	/// The backtraces of the consumers the panic went through, starting from
+	/// the consumer closest to the producer that panicked.
+	backtraces: Vec<Backtrace>,
+	/// The generator closest to the producer that panicked that has opted in
+	/// to identifying itself.
+	task: Option<TaskIdentity>,
+}
+impl TaskPanic {
+	/// The payload the panic was originally raised with.
+	pub fn payload(&self) -> &(dyn Any + std::marker::Send + 'static) {
+		&*self.payload
+	}
+
+	/// Takes the payload the panic was originally raised with.
+	pub fn into_payload(self) -> Payload {
+		self.payload
+	}
+
+	/// The backtraces of the consumers the panic went through, starting from
+	/// the consumer closest to the producer that panicked.
+	pub fn backtraces(&self) -> &[Backtrace] {
+		&self.backtraces
+	}
+
+	/// The generator closest to the producer that panicked that has opted in
+	/// to identifying itself, if any has.
+	///
+	/// Unless every generator in the tree opts in, this might not be the one
+	/// whose producer originally panicked, but one of its consumers.
+	pub fn task(&self) -> Option<&TaskIdentity> {
+		self.task.as_ref()
+	}
+
+	/// The message the panic was raised with, if it was raised with one.
+	pub fn message(&self) -> Option<&str> {
+		match self.payload.downcast_ref::<&'static str>() {
+			Some(message) => Some(message),
+			None => self.payload.downcast_ref::<String>().map(String::as_str),
+		}
+	}
+}
+impl fmt::Debug for TaskPanic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TaskPanic")
+			.field("message", &self.message())
+			.field("backtraces", &self.backtraces)
+			.field("task", &self.task)
+			.finish_non_exhaustive()
+	}
+}
+impl fmt::Display for TaskPanic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.task {
+			Some(task) => write!(f, "generator {task} panicked")?,
+			None => write!(f, "generator panicked")?,
+		}
+		if let Some(message) = self.message() {
+			write!(f, ": {message}")?;
+		}
+		for (i, backtrace) in self.backtraces.iter().enumerate() {
+			write!(f, "\nconsumer backtrace #{i}:\n{backtrace}")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Description of the generator a panic came out of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskIdentity {
+	/// The identifier of the task running the generator.
+	pub id: TaskId,
+	/// The name of the task running the generator, if it was given one.
+	pub name: Option<String>,
+	/// How many generators deep the task was, with generators driven from
+	/// outside of any generator being one deep.
+	pub depth: usize,
+	/// The number of values the generator had yielded before it panicked.
+	pub yields: u64,
+}
+impl fmt::Display for TaskIdentity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(name) = &self.name {
+			write!(f, "{name:?} ")?;
+		}
+		write!(f, "(#{}, depth {}, after {} yields)", self.id.as_u64(), self.depth, self.yields)
+	}
+}
+
+/// Prepares the payload of a panic raised by the producer of the given task,
+/// which is the given number of generators deep, to be propagated by its
+/// consumer, attaching the backtrace of the consumer and the identity of the
+/// task to it, as requested by the task.
+pub(crate) fn stitch(payload: Payload, header: &Header, depth: usize) -> Payload {
+	if !header.capture_backtraces && !header.identify_panics {
+		return payload
+	}
+
+	let mut panic = match payload.downcast::<TaskPanic>() {
+		Ok(panic) => panic,
+		Err(payload) => Box::new(TaskPanic {
+			payload,
+			backtraces: Vec::new(),
+			task: None,
+		}),
+	};
+	if header.capture_backtraces {
+		panic.backtraces.push(Backtrace::force_capture());
+	}
+	if header.identify_panics && panic.task.is_none() {
+		panic.task = Some(TaskIdentity {
+			id: header.id,
+			name: header.name.as_deref().map(str::to_owned),
+			depth,
+			yields: header.stats.yielded,
+		})
+	}
+
+	panic
+}
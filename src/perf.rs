@@ -0,0 +1,209 @@
+//! Attribution of profiler samples taken on task stacks to their tasks.
+//!
+//! Statistical profilers unwind the stack they interrupt to find out where the
+//! time goes. On the stack of a task, the unwind ends at the function that
+//! starts the producer, which has nowhere to return to, so every sample taken
+//! inside of a producer looks the same past that point, no matter which
+//! generator it came from, and profiles of pipelines end up with all of their
+//! stages lumped together under an unknown root.
+//!
+//! When enabled, through [`enable`], tasks started from then on get a fake
+//! return address below their start function, which is different for every
+//! task name. The addresses lie in a region of readable, but not executable,
+//! memory, so nothing ever runs there, and every one of them gets named after
+//! its task in a perf map, at `/tmp/perf-<pid>.map`, which `perf`, and the
+//! tools built on top of its output, use to symbolize addresses that don't
+//! belong to any binary. Samples taken on the stack of a task then show
+//! up under a root frame named `yeet::task::<name>`, or `yeet::task` for tasks
+//! without names.
+//!
+//! In-process profilers, such as `pprof`, that don't read perf maps can look
+//! the addresses up through [`task_at`] instead, while post-processing their
+//! samples.
+//!
+//! ```rust,no_run
+//! use yeet::GeneratorBuilder;
+//!
+//! yeet::perf::enable().unwrap();
+//! let mut gen = GeneratorBuilder::new()
+//!     .name("parser")
+//!     .build::<u32>(|| yeet::yeet(0u32));
+//! assert_eq!(gen.next(), Some(0));
+//! ```
+//!
+//! Only our own context switching code can set up the return addresses, so
+//! this is only available when tasks run on top of it. Even then, the unwinder
+//! used by the profiler has to make it all the way down to the start function
+//! for the address to be of any use, which, with frame pointer unwinding,
+//! requires the producer to have been compiled with frame pointers.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The size of the region reserved for return addresses.
+const REGION_SIZE: usize = 1024 * 1024;
+
+/// The room taken by every return address, which is what it gets mapped to in
+/// the perf map.
+const SLOT_SIZE: usize = 16;
+
+/// The symbol tasks without names get, and which the tasks that don't fit in
+/// the region anymore get as well.
+const ROOT: &str = "yeet::task";
+
+/// Whether tasks being started should be given return addresses.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The return addresses handed out, and where they get written down.
+static MAP: Mutex<Option<Map>> = Mutex::new(None);
+
+/// The base of the region reserved for return addresses.
+static REGION: OnceLock<usize> = OnceLock::new();
+
+/// The return addresses handed out so far.
+struct Map {
+	/// The perf map of the process.
+	file: File,
+	/// The slot of every task name, in the region.
+	slots: HashMap<String, usize>,
+	/// The names in every slot, in the order of the slots, starting with the
+	/// one for tasks without names.
+	names: Vec<String>,
+}
+impl Map {
+	/// Finds the slot of the given task name, handing out a new one if it
+	/// doesn't have one yet, and there is room for it.
+	fn slot(&mut self, name: &str) -> usize {
+		if let Some(slot) = self.slots.get(name) {
+			return *slot
+		}
+		if self.names.len() >= REGION_SIZE / SLOT_SIZE {
+			return 0
+		}
+
+		let slot = self.names.len();
+		self.names.push(name.to_owned());
+		self.slots.insert(name.to_owned(), slot);
+
+		/* Symbols run until the end of the line, so they may hold anything but
+		 * line breaks. Failing to write them only costs us the symbols. */
+		let symbol = format!("{ROOT}::{}", name.replace(['\r', '\n'], " "));
+		let _ = self.write(slot, &symbol);
+		slot
+	}
+
+	/// Writes the symbol of the given slot down into the perf map.
+	fn write(&mut self, slot: usize, symbol: &str) -> io::Result<()> {
+		writeln!(self.file, "{:x} {:x} {symbol}", address(slot), SLOT_SIZE)?;
+		self.file.flush()
+	}
+}
+
+/// Locks the map.
+fn lock() -> MutexGuard<'static, Option<Map>> {
+	MAP.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// The base of the region reserved for return addresses, which gets reserved
+/// the first time this gets called.
+///
+/// Unwinders that don't know where an address came from may look at the code
+/// there, to see if it is a signal trampoline, so the region has to be
+/// readable. Nothing gets written to it, so it never takes up any memory.
+fn region() -> io::Result<usize> {
+	if let Some(base) = REGION.get() {
+		return Ok(*base)
+	}
+
+	let base = unsafe {
+		libc::mmap(
+			std::ptr::null_mut(),
+			REGION_SIZE,
+			libc::PROT_READ,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0)
+	};
+	if base == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error())
+	}
+
+	/* Threads racing to reserve the region leave all but one of theirs
+	 * behind, which is harmless, as none of them ever gets used. */
+	Ok(*REGION.get_or_init(|| base as usize))
+}
+
+/// The return address of the given slot.
+fn address(slot: usize) -> usize {
+	REGION.get().unwrap() + slot * SLOT_SIZE
+}
+
+/// Starts giving tasks return addresses named after them, in the perf map of
+/// the process, for the whole process.
+///
+/// Only tasks started after this has been called get return addresses.
+///
+/// # Errors
+/// This function fails if either the region for the return addresses could
+/// not be reserved, or the perf map could not be opened.
+pub fn enable() -> io::Result<()> {
+	let mut map = lock();
+	if map.is_none() {
+		region()?;
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(format!("/tmp/perf-{}.map", std::process::id()))?;
+
+		let mut new = Map {
+			file,
+			slots: HashMap::new(),
+			names: vec![String::new()],
+		};
+		new.write(0, ROOT)?;
+		*map = Some(new);
+	}
+
+	ENABLED.store(true, Ordering::Relaxed);
+	Ok(())
+}
+
+/// Stops giving tasks return addresses, for the whole process.
+///
+/// Tasks that have already been given return addresses keep them, and the
+/// perf map keeps the names of them.
+pub fn disable() {
+	ENABLED.store(false, Ordering::Relaxed)
+}
+
+/// Whether tasks are being given return addresses.
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// The name of the task the given return address was handed out for, if it
+/// is one of them.
+///
+/// Tasks without names, and tasks that didn't fit in the region, come out as
+/// empty names.
+pub fn task_at(address: usize) -> Option<String> {
+	let base = *REGION.get()?;
+	let offset = address.checked_sub(base).filter(|offset| *offset < REGION_SIZE)?;
+
+	let map = lock();
+	map.as_ref()?.names.get(offset / SLOT_SIZE).cloned()
+}
+
+/// The return address a task with the given name should get, or zero if tasks
+/// aren't being given return addresses.
+pub(crate) fn return_address(name: Option<&str>) -> usize {
+	if !is_enabled() {
+		return 0
+	}
+
+	let mut map = lock();
+	let Some(map) = map.as_mut() else { return 0 };
+	address(name.map_or(0, |name| map.slot(name)))
+}
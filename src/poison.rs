@@ -0,0 +1,65 @@
+use std::fmt;
+use crate::{Generator, Resume};
+use crate::panic::Payload;
+
+impl<T: 'static> Generator<T> {
+	/// Requests the next value from the generator, returning panics raised by
+	/// the producer as errors, rather than propagating them.
+	///
+	/// A producer that panics poisons its generator. From then on, this
+	/// function keeps returning [`Panicked`], and [`Generator::next`] keeps
+	/// returning `None`, until the poison is cleared with
+	/// [`Generator::clear_poison`]. The payload of the panic is kept by the
+	/// generator, and may be taken once with [`Generator::take_panic`].
+	pub fn try_next(&mut self) -> Result<Option<T>, Panicked> {
+		if self.poisoned {
+			return Err(Panicked)
+		}
+		loop {
+			match self.try_resume(0) {
+				Ok(Resume::Value(value)) => break Ok(Some(value)),
+				Ok(Resume::Pending) => continue,
+				Ok(Resume::Complete) => break Ok(None),
+				Err(what) => {
+					self.panic = Some(what);
+					break Err(Panicked)
+				}
+			}
+		}
+	}
+
+	/// Whether the producer has panicked.
+	pub fn is_poisoned(&self) -> bool {
+		self.poisoned
+	}
+
+	/// Takes the payload of the panic raised by the producer, if it was
+	/// caught by [`Generator::try_next`] and has not been taken yet.
+	///
+	/// Panics propagated by [`Generator::next`] and [`Generator::resume`] take
+	/// their payload along with them, so there is nothing left to take.
+	pub fn take_panic(&mut self) -> Option<Payload> {
+		self.panic.take()
+	}
+
+	/// Clears the poison left by a panic raised by the producer, dropping its
+	/// payload if it has not been taken.
+	///
+	/// The producer is done for good after it panics, so the generator then
+	/// behaves as one whose producer has returned.
+	pub fn clear_poison(&mut self) {
+		self.poisoned = false;
+		self.panic = None;
+	}
+}
+
+/// Error returned when requesting values from a generator whose producer has
+/// panicked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Panicked;
+impl fmt::Display for Panicked {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("producer panicked")
+	}
+}
+impl std::error::Error for Panicked {}
@@ -0,0 +1,195 @@
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use crate::Generator;
+use crate::panic::Payload;
+
+/// A fixed pool of threads driving a set of generators.
+///
+/// Generators never move between threads, so, instead of moving generators,
+/// the pool creates each of them on one of its threads, and that thread drives
+/// the generator for as long as it lives. Each thread
+/// resumes the generators it owns in turn, and the values they yield are all
+/// merged into a single stream, which may be consumed by iterating over the
+/// pool.
+///
+/// The pool is done iterating once all of the generators spawned in it so far
+/// are done, but more generators may still be spawned after that, at which
+/// point the pool may be iterated over again. Panics raised by producers are
+/// propagated to the thread iterating over the pool.
+///
+/// # Balancing
+/// New generators go to the thread with the fewest live generators. Once a
+/// generator has been created, however, it stays on its thread even if that
+/// thread ends up busier than the others, as suspended producers can't be
+/// stolen by other threads: a producer may be holding on to references into
+/// the thread-local storage of the thread it started on, or to values that
+/// are not [`Send`] at all, across its yields.
+pub struct GeneratorPool<T: std::marker::Send + 'static> {
+	/// The threads in the pool.
+	workers: Vec<Worker<T>>,
+	/// The values coming out of the threads.
+	rx: Receiver<Output<T>>,
+	/// The number of generators that are not done yet.
+	live: usize,
+}
+impl<T: std::marker::Send + 'static> GeneratorPool<T> {
+	/// Creates a new pool with the given number of threads.
+	///
+	/// The merged stream buffers up to one value per thread.
+	///
+	/// # Panic
+	/// This function panics if `threads` is zero, or if the threads could not
+	/// be spawned.
+	pub fn new(threads: usize) -> Self {
+		assert!(threads > 0, "A pool must have at least one thread!");
+
+		let (tx, rx) = mpsc::sync_channel(threads);
+		let workers = (0..threads)
+			.map(|i| {
+				let (jobs_tx, jobs_rx) = mpsc::channel();
+				let tx = tx.clone();
+				std::thread::Builder::new()
+					.name(format!("yeet-pool-{i}"))
+					.spawn(move || work(i, jobs_rx, tx))
+					.expect("Could not spawn pool thread");
+
+				Worker { jobs: jobs_tx, live: 0 }
+			})
+			.collect();
+
+		Self {
+			workers,
+			rx,
+			live: 0,
+		}
+	}
+
+	/// Runs the given function as a producer on one of the threads.
+	pub fn spawn(&mut self, func: fn()) {
+		self.spawn_job(Box::new(move || Generator::from_fn_ptr(func)))
+	}
+
+	/// Runs the given function as a producer on one of the threads, calling it
+	/// with the given argument.
+	pub fn spawn_with<A: std::marker::Send + 'static>(&mut self, arg: A, func: fn(A)) {
+		self.spawn_job(Box::new(move || Generator::from_fn_with(arg, func)))
+	}
+
+	/// Hands the given job to the thread with the fewest live generators.
+	fn spawn_job(&mut self, job: Job<T>) {
+		let worker = self.workers.iter_mut()
+			.min_by_key(|worker| worker.live)
+			.unwrap();
+
+		/* The threads only go away along with the pool. */
+		let _ = worker.jobs.send(job);
+		worker.live += 1;
+		self.live += 1;
+	}
+
+	/// The number of threads in the pool.
+	pub fn threads(&self) -> usize {
+		self.workers.len()
+	}
+
+	/// The number of generators in the pool that are not done yet.
+	pub fn live(&self) -> usize {
+		self.live
+	}
+
+	/// The number of generators that are not done yet on each of the threads.
+	pub fn load(&self) -> impl Iterator<Item = usize> + '_ {
+		self.workers.iter().map(|worker| worker.live)
+	}
+
+	/// Takes note that a generator on the given thread is done.
+	fn retire(&mut self, worker: usize) {
+		self.workers[worker].live -= 1;
+		self.live -= 1;
+	}
+}
+impl<T: std::marker::Send + 'static> Iterator for GeneratorPool<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		while self.live > 0 {
+			/* The threads hold on to their senders for as long as we hold on
+			 * to their queues, so this never disconnects. */
+			let Ok(output) = self.rx.recv() else { break };
+			match output {
+				Output::Value(value) => return Some(value),
+				Output::Done(worker) => self.retire(worker),
+				Output::Panic(worker, what) => {
+					self.retire(worker);
+					std::panic::resume_unwind(what)
+				}
+			}
+		}
+
+		None
+	}
+}
+
+/// A thread in the pool, as seen from the consumer.
+struct Worker<T: 'static> {
+	/// The queue of new generators for the thread.
+	jobs: Sender<Job<T>>,
+	/// The number of generators on the thread that are not done yet.
+	live: usize,
+}
+
+/// Creates a generator on the thread that is going to drive it.
+type Job<T> = Box<dyn FnOnce() -> Generator<T> + std::marker::Send>;
+
+/// Messages sent from the threads in the pool to the consumer.
+enum Output<T> {
+	/// A generator has yielded the given value.
+	Value(T),
+	/// A generator on the given thread is done.
+	Done(usize),
+	/// A generator on the given thread has panicked with the given payload,
+	/// and is done.
+	Panic(usize, Payload),
+}
+
+/// Drives the generators handed to the current thread, which is the thread
+/// with the given index in the pool, in turn, until the pool goes away.
+fn work<T: 'static>(index: usize, jobs: Receiver<Job<T>>, tx: SyncSender<Output<T>>) {
+	let mut gens = Vec::<Generator<T>>::new();
+	loop {
+		/* Pick up new generators, waiting for one if we have nothing to do. */
+		loop {
+			let job = if gens.is_empty() {
+				match jobs.recv() {
+					Ok(job) => job,
+					Err(_) => return,
+				}
+			} else {
+				match jobs.try_recv() {
+					Ok(job) => job,
+					Err(TryRecvError::Empty) => break,
+					Err(TryRecvError::Disconnected) => return,
+				}
+			};
+			gens.push(job());
+		}
+
+		let mut i = 0;
+		while i < gens.len() {
+			let next = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gens[i].next()));
+			let output = match next {
+				Ok(Some(value)) => Output::Value(value),
+				Ok(None) => Output::Done(index),
+				Err(what) => Output::Panic(index, what),
+			};
+			if !matches!(output, Output::Value(_)) {
+				gens.swap_remove(i);
+			} else {
+				i += 1;
+			}
+
+			if tx.send(output).is_err() {
+				return
+			}
+		}
+	}
+}
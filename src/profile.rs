@@ -0,0 +1,275 @@
+//! Attribution of wall-time to the producers it gets spent in.
+//!
+//! Pipelines built out of generators spend their time inside of several
+//! producers at once, each of which runs in short activations, from the moment
+//! its consumer resumes it to the moment it yields. Sampling profilers see
+//! all of those activations as frames of whatever code happened to resume
+//! them, which makes it hard to tell how much of the time went into which
+//! stage of the pipeline.
+//!
+//! When enabled, through [`enable`], the wall-time of every activation of every
+//! producer, on any thread, gets added up by task name, and by the names of the
+//! producers it was nested in at the time. Time a producer spends waiting for
+//! the producers nested in it is attributed to them, rather than to it, so the
+//! self-time of every stage adds up to the time spent in the pipeline. The
+//! totals can then be looked at through [`profile_report`], either grouped by
+//! task name, or by stack of task names, in the folded format flamegraph tools
+//! take as their input.
+//!
+//! ```rust
+//! use yeet::GeneratorBuilder;
+//!
+//! yeet::profile::enable();
+//! let mut outer = GeneratorBuilder::new()
+//!     .name("outer")
+//!     .build::<u32>(|| {
+//!         let inner = GeneratorBuilder::new()
+//!             .name("inner")
+//!             .build::<u32>(|| yeet::yeet_all(0u32..4));
+//!         yeet::yeet_all(inner.map(|value| value * 2));
+//!     });
+//! assert_eq!(outer.by_ref().sum::<u32>(), 12);
+//!
+//! let report = yeet::profile_report();
+//! let inner = report.tasks().iter().find(|task| task.name() == "inner").unwrap();
+//! assert!(inner.activations > 0);
+//! assert!(report.stacks().iter().any(|stack| stack.path == ["outer", "inner"]));
+//! ```
+//!
+//! Profiling is disabled by default, as it reads the clock twice, and takes a
+//! lock, every time a producer gets resumed.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::sys::Header;
+
+/// The name unnamed tasks get grouped under.
+pub const ANONYMOUS: &str = "<anonymous>";
+
+/// Whether activations should be timed.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The time spent in each stack of task names, in the order of their names.
+static PROFILE: Mutex<BTreeMap<Vec<String>, Totals>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+	/// The activations being timed on this thread, innermost last.
+	static ACTIVE: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An activation of a producer being timed.
+struct Frame {
+	/// The name of the task.
+	name: String,
+	/// The point in time the producer got resumed at.
+	started: Instant,
+	/// The time spent in the activations of producers nested in this one.
+	nested: Duration,
+}
+
+/// The time spent in a stack of task names.
+#[derive(Debug, Copy, Clone, Default)]
+struct Totals {
+	/// The number of activations.
+	activations: u64,
+	/// The time spent in the activations, nested activations included.
+	total: Duration,
+	/// The time spent in the activations, nested activations excluded.
+	own: Duration,
+}
+impl Totals {
+	/// Adds up the time spent in another set of activations.
+	fn add(&mut self, other: &Totals) {
+		self.activations += other.activations;
+		self.total += other.total;
+		self.own += other.own;
+	}
+}
+
+/// Enables profiling for the whole process.
+///
+/// Only activations that start after profiling has been enabled get timed.
+pub fn enable() {
+	ENABLED.store(true, Ordering::Relaxed)
+}
+
+/// Disables profiling for the whole process.
+///
+/// The time already accounted for stays in the profile, until it gets
+/// [`reset`].
+pub fn disable() {
+	ENABLED.store(false, Ordering::Relaxed)
+}
+
+/// Whether profiling is enabled.
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// Throws away all of the time accounted for so far.
+pub fn reset() {
+	lock().clear()
+}
+
+/// Locks the profile.
+fn lock() -> std::sync::MutexGuard<'static, BTreeMap<Vec<String>, Totals>> {
+	PROFILE.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// Summarizes the time accounted for since profiling was first enabled, or
+/// since it was last [`reset`].
+pub fn profile_report() -> ProfileReport {
+	let profile = lock();
+	let stacks = profile.iter()
+		.map(|(path, totals)| ProfileEntry::new(path.clone(), totals))
+		.collect::<Vec<_>>();
+
+	/* Activations nested in others of the same name are already part of the
+	 * total of the outer ones. */
+	let mut tasks = BTreeMap::<&str, Totals>::new();
+	for (path, totals) in profile.iter() {
+		let (name, outer) = path.split_last().unwrap();
+		let mut totals = *totals;
+		if outer.contains(name) {
+			totals.total = Duration::ZERO;
+		}
+		tasks.entry(name).or_default().add(&totals);
+	}
+	let mut tasks = tasks.into_iter()
+		.map(|(name, totals)| ProfileEntry::new(vec![name.to_owned()], &totals))
+		.collect::<Vec<_>>();
+	tasks.sort_by_key(|task| std::cmp::Reverse(task.self_time));
+
+	ProfileReport { tasks, stacks }
+}
+
+/// Starts timing an activation of the producer of the given task, if
+/// profiling is enabled.
+pub(crate) fn resumed(header: &Header) -> Option<Activation> {
+	if !is_enabled() {
+		return None
+	}
+
+	let name = header.name.as_deref().unwrap_or(ANONYMOUS).to_owned();
+	ACTIVE.with_borrow_mut(|active| active.push(Frame {
+		name,
+		started: Instant::now(),
+		nested: Duration::ZERO,
+	}));
+	Some(Activation(()))
+}
+
+/// An activation being timed, which must be ended with [`Activation::yielded`]
+/// once the producer yields.
+pub(crate) struct Activation(());
+impl Activation {
+	/// Stops timing the activation, and accounts for the time spent in it.
+	pub(crate) fn yielded(self) {
+		let ended = Instant::now();
+		let (path, totals) = ACTIVE.with_borrow_mut(|active| {
+			let frame = active.pop().unwrap();
+			let total = ended.saturating_duration_since(frame.started);
+			if let Some(parent) = active.last_mut() {
+				parent.nested += total;
+			}
+
+			let path = active.iter()
+				.map(|frame| frame.name.clone())
+				.chain(std::iter::once(frame.name))
+				.collect::<Vec<_>>();
+			let totals = Totals {
+				activations: 1,
+				total,
+				own: total.saturating_sub(frame.nested),
+			};
+			(path, totals)
+		});
+
+		lock().entry(path).or_default().add(&totals);
+	}
+}
+
+/// The time spent in producers, as accounted for at the time a report was
+/// made.
+///
+/// The report formats as folded stacks, with one line per stack of task names,
+/// made up of the names, outermost first, separated by semicolons, followed by
+/// the self-time of the stack in microseconds. This is the format tools such
+/// as `inferno` and `flamegraph.pl` build flamegraphs from.
+#[derive(Debug, Clone)]
+pub struct ProfileReport {
+	/// The time spent in each task name, most self-time first.
+	tasks: Vec<ProfileEntry>,
+	/// The time spent in each stack of task names.
+	stacks: Vec<ProfileEntry>,
+}
+impl ProfileReport {
+	/// The time spent in the producers of every task name, regardless of what
+	/// they were nested in, most self-time first.
+	///
+	/// Entries in here have a single name in their paths.
+	pub fn tasks(&self) -> &[ProfileEntry] {
+		&self.tasks
+	}
+
+	/// The time spent in the producers of every stack of task names, sorted by
+	/// their paths.
+	pub fn stacks(&self) -> &[ProfileEntry] {
+		&self.stacks
+	}
+
+	/// The total time spent in producers, nested producers included once.
+	pub fn total(&self) -> Duration {
+		self.stacks.iter().map(|stack| stack.self_time).sum()
+	}
+
+	/// Whether no time has been accounted for.
+	pub fn is_empty(&self) -> bool {
+		self.stacks.is_empty()
+	}
+}
+impl fmt::Display for ProfileReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for stack in &self.stacks {
+			writeln!(f, "{} {}", stack.path.join(";"), stack.self_time.as_micros())?;
+		}
+
+		Ok(())
+	}
+}
+
+/// The time spent in the producers of a task name, or of a stack of them, in
+/// a [`ProfileReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+	/// The names of the tasks, outermost first, with unnamed tasks showing up
+	/// as [`ANONYMOUS`].
+	pub path: Vec<String>,
+	/// The number of times the producers got resumed.
+	pub activations: u64,
+	/// The time spent in the producers, including the time spent in the
+	/// producers nested in them.
+	pub total: Duration,
+	/// The time spent in the producers, excluding the time spent in the
+	/// producers nested in them.
+	pub self_time: Duration,
+}
+impl ProfileEntry {
+	/// Creates a new entry for the given path.
+	fn new(path: Vec<String>, totals: &Totals) -> Self {
+		Self {
+			path,
+			activations: totals.activations,
+			total: totals.total,
+			self_time: totals.own,
+		}
+	}
+
+	/// The name of the innermost task.
+	pub fn name(&self) -> &str {
+		self.path.last().unwrap()
+	}
+}
@@ -0,0 +1,328 @@
+//! The context switching machinery behind generators, without the generators.
+//!
+//! [`Generator`] does a lot of bookkeeping on top of switching between tasks:
+//! it iterates, keeps statistics, runs hooks, registers its task, names
+//! threads, and so on. This module hands out the bare tasks underneath all of
+//! that, for those who want to build a runtime of their own, such as a
+//! scheduler juggling tasks that are all suspended in the middle of their
+//! work, or an interpreter with a task per script, without paying for, or
+//! having to fit into, the shape of an iterator.
+//!
+//! A [`Task`] is a function running on a [`Stack`] of its own. The consumer
+//! enters the task, with [`Task::resume`] or with [`enter`], which runs the
+//! function until it calls [`exit`] with a value, or [`exit_pending`], at which
+//! point the task gets suspended, and control goes back to the consumer. The
+//! way the task got suspended comes out as a [`Suspend`], and the consumer
+//! tells the task how to carry on with a [`Resume`] the next time it enters it.
+//!
+//! ```rust
+//! use yeet::raw::{self, Resume, Stack, Suspend, Task};
+//!
+//! let mut task = Task::<u32>::new(Stack::new(64 * 1024), || {
+//!     let mut i = 0u32;
+//!     while raw::exit(i) == Resume::Continue {
+//!         i += 1;
+//!     }
+//! });
+//!
+//! assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+//! assert!(matches!(task.resume(Resume::Continue), Suspend::Value(1)));
+//! assert!(matches!(task.resume(Resume::Cancel), Suspend::Done));
+//! ```
+//!
+//! Tasks started here are the same as those run by generators, so everything
+//! that works inside of a producer, [`yeet`] included, works inside of them.
+//!
+//! # Snapshots
+//! The registers of a task are saved into a snapshot inside of the task every
+//! time it gets suspended, and the snapshot gets loaded back when it gets
+//! entered. The snapshot of a task that hasn't been started yet is set up the
+//! first time it gets entered, so that it starts off running the function at
+//! the top of its stack. Tasks may be moved around between switches, as
+//! nothing points into them while they are suspended, but they must never
+//! move, or be dropped, while they are running.
+//!
+//! # Bare Contexts
+//! Underneath tasks, there is nothing more than a function switching between
+//! two sets of registers. On targets where that function is our own assembly,
+//! it is exported as [`switch`], along with [`make_context`], which sets up a
+//! new context on a stack, for coroutines that have nothing to do with the
+//! protocol tasks follow.
+//!
+//! [`Generator`]: crate::Generator
+//! [`yeet`]: crate::yeet
+use std::ops::Range;
+use crate::{pop_task, push_task, sys, try_yeet, try_yield_now, Send, TaskId, Yield};
+use crate::panic::Payload;
+use crate::registry::{SavedContext, TaskState};
+use crate::sys::{AnyTask, Entry};
+
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub use switch::{make_context, switch, Context, ContextFn, Transfer};
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub(crate) use switch::context_start;
+#[cfg(all(feature = "capi-switch", not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub(crate) use switch::{make_context_below, Start};
+
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod switch;
+
+/// The memory region a task runs on.
+pub struct Stack(sys::Stack);
+impl Stack {
+	/// Allocates a new stack of at least the given size, aligned to the size
+	/// of a page.
+	///
+	/// # Panic
+	/// This function panics if the memory could not be allocated.
+	pub fn new(size: usize) -> Self {
+		Self(sys::Stack::new(size))
+	}
+
+	/// Allocates a new stack of at least the given size, aligned to the given
+	/// alignment, rather than to the size of a page.
+	///
+	/// # Panic
+	/// This function panics if the alignment is not a power of two, or if the
+	/// memory could not be allocated.
+	pub fn with_alignment(size: usize, align: usize) -> Self {
+		Self(sys::Stack::with_alignment(size, align))
+	}
+
+	/// Uses the given memory region as a stack.
+	///
+	/// # Safety
+	/// The region must be valid for reads and writes for as long as the stack
+	/// is in use, and must not be accessed by anything other than the task.
+	///
+	/// # Panic
+	/// This function panics if the region is smaller than 4160 bytes, as with
+	/// [`GeneratorBuilder::with_stack`].
+	///
+	/// [`GeneratorBuilder::with_stack`]: crate::GeneratorBuilder::with_stack
+	pub unsafe fn external(base: *mut u8, len: usize) -> Self {
+		Self(sys::Stack::external(base, len))
+	}
+
+	/// The range of addresses spanned by the stack.
+	pub fn bounds(&self) -> Range<usize> {
+		self.0.base()..self.0.base() + self.0.len()
+	}
+}
+
+/// How a consumer wants a task it enters to carry on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Resume {
+	/// Continue until the next point at which the task gets suspended.
+	Continue,
+	/// Stop, unwinding the stack of the task.
+	///
+	/// Tasks that suspend themselves through [`exit`] are expected to return
+	/// when they get this, while tasks that suspend themselves through
+	/// [`yeet`] start unwinding from the point at which they were suspended.
+	///
+	/// [`yeet`]: crate::yeet
+	Cancel,
+}
+impl From<Resume> for Send {
+	fn from(resume: Resume) -> Self {
+		match resume {
+			Resume::Continue => Send::Continue,
+			Resume::Cancel => Send::Cancel,
+		}
+	}
+}
+
+/// How a task that was entered got suspended.
+pub enum Suspend<T> {
+	/// The task has suspended itself with the given value.
+	Value(T),
+	/// The task has suspended itself with several values at once, as it does
+	/// when it yields them through [`yeet_all`].
+	///
+	/// This is never empty.
+	///
+	/// [`yeet_all`]: crate::yeet_all
+	Batch(Vec<T>),
+	/// The task has suspended itself without a value.
+	Pending,
+	/// The function run by the task has returned, and the task may no longer
+	/// be entered, other than to get this again.
+	Done,
+	/// The function run by the task has panicked with the given payload.
+	///
+	/// Tasks that get cancelled through [`yeet`] report their cancellation
+	/// as a panic, with a payload of [`Cancelled`].
+	///
+	/// [`yeet`]: crate::yeet
+	/// [`Cancelled`]: crate::Cancelled
+	Panic(Payload),
+}
+impl<T> From<Yield<T>> for Suspend<T> {
+	fn from(result: Yield<T>) -> Self {
+		match result {
+			Yield::Value(value) => Suspend::Value(value),
+			Yield::Batch(values) => Suspend::Batch(values),
+			Yield::Pending => Suspend::Pending,
+			Yield::StopIteration => Suspend::Done,
+			Yield::Panic(what) => Suspend::Panic(what),
+		}
+	}
+}
+
+/// A function running on a stack of its own, which can be suspended and
+/// entered again at will.
+///
+/// Tasks that get dropped while they are suspended are cancelled, by entering
+/// them with [`Resume::Cancel`] until they are done, so that nothing is ever
+/// left on their stacks.
+pub struct Task<T: 'static> {
+	/// The task itself.
+	task: sys::Task<T>,
+}
+impl<T: 'static> Task<T> {
+	/// Sets up a task running the given function on the given stack.
+	///
+	/// The function only starts running the first time the task gets entered.
+	pub fn new(stack: Stack, func: impl FnOnce() + 'static) -> Self {
+		Self { task: sys::new_task(Entry::Boxed(Box::new(func)), stack.0) }
+	}
+
+	/// Sets up a task running the given function on the given stack.
+	///
+	/// The function only starts running the first time the task gets entered.
+	pub fn from_fn_ptr(stack: Stack, func: fn()) -> Self {
+		Self { task: sys::new_task(Entry::Ptr(func), stack.0) }
+	}
+
+	/// The unique identifier of this task.
+	pub fn id(&self) -> TaskId {
+		self.task.id()
+	}
+
+	/// The current state of this task.
+	pub fn state(&self) -> TaskState {
+		self.task.header_ref().state()
+	}
+
+	/// The range of addresses spanned by the stack of this task.
+	pub fn stack_bounds(&self) -> Range<usize> {
+		self.task.stack_bounds()
+	}
+
+	/// Reads the registers saved for this task when it was last suspended, if
+	/// it has been started, and the backend supports it.
+	pub fn saved_context(&self) -> Option<SavedContext> {
+		match self.state() {
+			TaskState::Suspended => sys::saved_context(&self.task),
+			_ => None,
+		}
+	}
+
+	/// Takes the stack back out of this task, so that another task can run on
+	/// it.
+	///
+	/// Tasks that are suspended get cancelled first. Stacks that have been left
+	/// behind by tasks that couldn't be cancelled are never handed back.
+	pub fn into_stack(mut self) -> Option<Stack> {
+		self.cancel();
+		if self.task.header_ref().abandoned {
+			return None
+		}
+
+		let this = std::mem::ManuallyDrop::new(self);
+		let task = unsafe { std::ptr::read(&this.task) };
+		Some(Stack(sys::into_stack(task)))
+	}
+
+	/// Enters this task with [`Resume::Cancel`] until it is done, if it is
+	/// suspended.
+	fn cancel(&mut self) {
+		if self.state() != TaskState::Suspended {
+			return
+		}
+		loop {
+			if let Suspend::Done | Suspend::Panic(_) = self.resume(Resume::Cancel) {
+				break
+			}
+		}
+	}
+
+	/// Enters this task, and runs it until it gets suspended.
+	///
+	/// # Panic
+	/// This function panics if it is called from a thread other than the one
+	/// the task was created on.
+	pub fn resume(&mut self, resume: Resume) -> Suspend<T> {
+		if self.task.header_ref().thread != sys::thread_id() {
+			panic!("Tried to enter task #{} on a thread other than the one it was created on!", self.id().as_u64())
+		}
+		unsafe { enter(self, resume) }
+	}
+}
+impl<T: 'static> Drop for Task<T> {
+	fn drop(&mut self) {
+		self.cancel()
+	}
+}
+
+/// Enters the given task, and runs it until it gets suspended.
+///
+/// This is what [`Task::resume`] does, without checking the thread it is being
+/// called from.
+///
+/// # Safety
+/// The task must be valid for as long as it runs, must have been created on
+/// the current thread, and must not be running already.
+pub unsafe fn enter<T: 'static>(task: *mut Task<T>, resume: Resume) -> Suspend<T> {
+	let task = &raw mut (*task).task;
+
+	/* Tasks that were left where they were cancelled never run again. */
+	if (*task).header_ref().abandoned {
+		return Suspend::Done
+	}
+
+	(*task).header().set_state(TaskState::Running);
+	push_task(task as *mut dyn AnyTask);
+	let result = sys::enter(task, resume.into());
+	pop_task();
+
+	let result = if (*task).header_ref().abandoned {
+		(*task).leak_stack();
+		Yield::StopIteration
+	} else {
+		result
+	};
+	(*task).header().set_state(match result {
+		Yield::Value(_) | Yield::Batch(_) | Yield::Pending => TaskState::Suspended,
+		Yield::StopIteration | Yield::Panic(_) => TaskState::Finished,
+	});
+
+	result.into()
+}
+
+/// Suspends the currently running task with the given value, and returns how
+/// the consumer wants it to carry on, once it enters the task again.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a task, or if
+/// `T` doesn't match the type of the values the task is expected to suspend
+/// itself with.
+pub fn exit<T: 'static>(value: T) -> Resume {
+	match try_yeet(value) {
+		Ok(()) => Resume::Continue,
+		Err(_) => Resume::Cancel,
+	}
+}
+
+/// Suspends the currently running task without a value, and returns how the
+/// consumer wants it to carry on, once it enters the task again.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a task.
+pub fn exit_pending() -> Resume {
+	match try_yield_now() {
+		Ok(()) => Resume::Continue,
+		Err(_) => Resume::Cancel,
+	}
+}
@@ -0,0 +1,177 @@
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use crate::raw::Stack;
+use crate::sys::{self, Snapshot};
+
+/// A context that has been suspended by [`switch`], or that has been set up
+/// by [`make_context`] and is yet to start.
+///
+/// A context is a handle to registers saved on the stack the context runs on,
+/// and switching to it consumes it, as the registers are gone once they've
+/// been loaded back. The context that switched away comes out of the switch,
+/// in a [`Transfer`], on the other side.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Context(NonNull<Snapshot>);
+impl Context {
+	/// Turns this context into a pointer to its registers, which may be handed
+	/// over to code that doesn't know about this type, such as C code.
+	pub fn into_raw(self) -> *mut () {
+		self.0.as_ptr() as *mut ()
+	}
+
+	/// Turns a pointer made by [`Context::into_raw`] back into a context.
+	///
+	/// # Safety
+	/// The pointer must have come out of [`Context::into_raw`], or out of the
+	/// C interface, and the context it points to must not have been switched to
+	/// since.
+	pub unsafe fn from_raw(ptr: *mut ()) -> Self {
+		Self(NonNull::new_unchecked(ptr as *mut Snapshot))
+	}
+}
+
+/// What comes out of a switch, on the side that was switched to.
+#[derive(Debug)]
+#[repr(C)]
+pub struct Transfer {
+	/// The context that switched away, which may be switched back to.
+	pub context: Context,
+	/// The pointer handed over by the context that switched away.
+	pub data: *mut (),
+}
+
+/// The function at the root of a context set up by [`make_context`].
+///
+/// It gets the context that first switched to it, and must never return, as
+/// there is nothing to return to. Panics that escape it abort the process.
+pub type ContextFn = fn(Transfer) -> !;
+
+/// The function at the root of a context.
+#[derive(Copy, Clone)]
+pub(crate) enum Start {
+	/// A Rust function.
+	Rust(ContextFn),
+	/// A C function, which isn't supposed to return, but might.
+	#[cfg(feature = "capi-switch")]
+	C(extern "C" fn(Transfer)),
+}
+
+/// What sits at the top of the stack of a context that is yet to start.
+#[repr(C)]
+struct Fresh {
+	/// The registers the context starts off with.
+	snap: Snapshot,
+	/// The function the context runs.
+	start: Start,
+}
+
+/// What a switch hands over to the other side, which lives in the frame of
+/// the side that switched away, for as long as it is suspended.
+struct Packet {
+	/// The registers of the side that switched away.
+	from: *mut Snapshot,
+	/// The registers of the side being switched to.
+	to: *mut Snapshot,
+	/// The pointer handed over by the side that switched away.
+	data: *mut (),
+}
+
+/// Sets up a context that runs the given function on the given stack, once it
+/// gets switched to.
+///
+/// This is a bare context switching primitive, in the vein of Boost.Context,
+/// which shares its assembly with tasks, but knows nothing about them. There
+/// is no telling how far along a context is, no catching of panics, and no
+/// cleaning up after contexts that never finish: all of that is up to whatever
+/// gets built on top.
+///
+/// ```rust
+/// use yeet::raw::{self, Stack, Transfer};
+///
+/// fn counter(mut transfer: Transfer) -> ! {
+///     let mut i = 0u32;
+///     loop {
+///         transfer = unsafe { raw::switch(transfer.context, &mut i as *mut u32 as *mut ()) };
+///         i += 1;
+///     }
+/// }
+///
+/// let stack = Stack::new(64 * 1024);
+/// let mut context = unsafe { raw::make_context(&stack, counter) };
+/// for expected in 0u32..3 {
+///     let transfer = unsafe { raw::switch(context, std::ptr::null_mut()) };
+///     assert_eq!(unsafe { *(transfer.data as *mut u32) }, expected);
+///     context = transfer.context;
+/// }
+/// ```
+///
+/// # Safety
+/// The stack must outlive the context, and must not be used by anything else
+/// while the context is live. Values left on the stack by a context that never
+/// finishes never get dropped.
+pub unsafe fn make_context(stack: &Stack, entry: ContextFn) -> Context {
+	make_context_below(stack.0.top(), Start::Rust(entry))
+}
+
+/// Sets up a context that runs the given function on the stack right below
+/// the given address.
+pub(crate) unsafe fn make_context_below(top: usize, start: Start) -> Context {
+	/* The stack starts off right below the registers. */
+	let fresh = ((top - size_of::<Fresh>()) & !15) as *mut Fresh;
+
+	let snap = &raw mut (*fresh).snap;
+	snap.write_bytes(0, 1);
+	(&raw mut (*fresh).start).write(start);
+	sys::prepare_context(snap, fresh as usize);
+
+	Context(NonNull::new_unchecked(snap))
+}
+
+/// Suspends the current context, and switches over to the given one, handing
+/// it the given pointer.
+///
+/// This returns once some other context switches back to this one, with what
+/// it handed over.
+///
+/// # Safety
+/// The context must have been set up by [`make_context`], or have come out of
+/// a switch, on the current thread, and its stack must still be alive. Nothing
+/// may unwind across the switch, and code switching between contexts in a
+/// task must switch back to the task before the task itself gets suspended.
+pub unsafe fn switch(to: Context, data: *mut ()) -> Transfer {
+	let mut here = MaybeUninit::<Snapshot>::uninit();
+	let mut packet = Packet {
+		from: here.as_mut_ptr(),
+		to: to.0.as_ptr(),
+		data,
+	};
+	let arg = sys::switch_context(&raw mut packet as *mut (), here.as_mut_ptr(), to.0.as_ptr());
+
+	/* The packet comes from whoever switched back to us, which is suspended,
+	 * so its frame is still there. */
+	let packet = &*(arg as *const Packet);
+	Transfer {
+		context: Context(NonNull::new_unchecked(packet.from)),
+		data: packet.data,
+	}
+}
+
+/// Starts running a context set up by [`make_context`].
+pub(crate) unsafe fn context_start(arg: *mut ()) -> ! {
+	let packet = &*(arg as *const Packet);
+	let start = (*(packet.to as *const Fresh)).start;
+	let transfer = Transfer {
+		context: Context(NonNull::new_unchecked(packet.from)),
+		data: packet.data,
+	};
+
+	/* Nothing above this function in the call stack can take an unwind, nor
+	 * is there anything to return to. */
+	let _ = std::panic::catch_unwind(move || match start {
+		Start::Rust(entry) => entry(transfer),
+		#[cfg(feature = "capi-switch")]
+		Start::C(entry) => entry(transfer),
+	});
+	std::process::abort()
+}
@@ -0,0 +1,325 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::io;
+use std::os::fd::RawFd;
+use std::time::{Duration, Instant};
+use crate::{Generator, Resume};
+use crate::io::Wait;
+
+/// Drives a set of generators doing I/O through the helpers in [`io`], on a
+/// single thread.
+///
+/// Producers that run into a descriptor that isn't ready get suspended, and the
+/// reactor only resumes them once the operating system says the descriptor
+/// they're waiting on is, using epoll or kqueue, so that any number of them
+/// can be waiting at once without the thread ever spinning. Producers that
+/// [`sleep`] get resumed once their deadline passes, and producers that
+/// suspend for any other reason, such as through [`yield_now`], get resumed
+/// again after every other producer that was ready has had its turn.
+///
+/// The values yielded by all of the generators get merged into a single
+/// stream, which may be consumed by iterating over the reactor. The reactor
+/// is done iterating once all of the generators spawned in it so far are done.
+/// Panics raised by producers are propagated to the consumer.
+///
+/// Only one producer should be waiting on any given descriptor at a time.
+/// Producers waiting on a descriptor the reactor can't watch, because it is
+/// already being watched or because it doesn't support readiness at all, get
+/// resumed as if it were always ready.
+///
+/// [`io`]: crate::io
+/// [`sleep`]: crate::sleep
+/// [`yield_now`]: crate::yield_now
+pub struct Reactor<T: 'static> {
+	/// The generators being driven, indexed by their key in the poller.
+	tasks: Vec<Option<Slot<T>>>,
+	/// The indices in `tasks` that are free to be reused.
+	free: Vec<usize>,
+	/// The generators ready to be resumed, in order.
+	ready: VecDeque<usize>,
+	/// The number of generators waiting on a descriptor.
+	waiting: usize,
+	/// The generators that are sleeping, by the time they wake up.
+	sleeping: BinaryHeap<Reverse<(Instant, usize)>>,
+	/// The readiness queue of the operating system.
+	poller: Poller,
+}
+
+/// A generator being driven by a [`Reactor`].
+struct Slot<T: 'static> {
+	/// The generator itself.
+	gen: Generator<T>,
+	/// The descriptor the generator is registered as waiting on, if any.
+	registered: Option<RawFd>,
+}
+
+impl<T: 'static> Reactor<T> {
+	/// Creates a new reactor with no generators.
+	pub fn new() -> io::Result<Self> {
+		Ok(Self {
+			tasks: Vec::new(),
+			free: Vec::new(),
+			ready: VecDeque::new(),
+			waiting: 0,
+			sleeping: BinaryHeap::new(),
+			poller: Poller::new()?,
+		})
+	}
+
+	/// Starts driving the given generator.
+	pub fn spawn(&mut self, gen: Generator<T>) {
+		let slot = Some(Slot { gen, registered: None });
+		let index = match self.free.pop() {
+			Some(index) => {
+				self.tasks[index] = slot;
+				index
+			}
+			None => {
+				self.tasks.push(slot);
+				self.tasks.len() - 1
+			}
+		};
+
+		self.ready.push_back(index)
+	}
+
+	/// The number of generators that are not done yet.
+	pub fn len(&self) -> usize {
+		self.tasks.len() - self.free.len()
+	}
+
+	/// Whether all of the generators are done.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// The number of generators waiting on a descriptor to become ready.
+	pub fn waiting(&self) -> usize {
+		self.waiting
+	}
+
+	/// The number of generators sleeping until a deadline passes.
+	pub fn sleeping(&self) -> usize {
+		self.sleeping.len()
+	}
+
+	/// Takes note of the generator at the given index being done.
+	fn remove(&mut self, index: usize) {
+		self.tasks[index] = None;
+		self.free.push(index);
+	}
+
+	/// Has the generator at the given index wait on the given descriptor, or
+	/// queues it up to be resumed right away if the descriptor can't be
+	/// watched.
+	fn register(&mut self, index: usize, wait: Wait) {
+		match self.poller.add(index, wait) {
+			Ok(()) => {
+				self.tasks[index].as_mut().unwrap().registered = Some(wait.fd);
+				self.waiting += 1;
+			}
+			Err(_) => self.ready.push_back(index),
+		}
+	}
+
+	/// Queues up the sleeping generators whose deadline has passed to be
+	/// resumed, and returns how long until the next one wakes up.
+	fn wake(&mut self) -> Option<Duration> {
+		let now = Instant::now();
+		while let Some(&Reverse((deadline, index))) = self.sleeping.peek() {
+			if deadline > now {
+				return Some(deadline - now)
+			}
+			self.sleeping.pop();
+			self.ready.push_back(index);
+		}
+
+		None
+	}
+
+	/// Blocks until at least one of the waiting generators is ready, or until
+	/// the given amount of time has passed, and queues the ready ones up to be
+	/// resumed.
+	fn poll(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+		if self.waiting == 0 {
+			std::thread::sleep(timeout.unwrap_or_default());
+			return Ok(())
+		}
+
+		let mut ready = Vec::new();
+		self.poller.wait(&mut ready, timeout)?;
+
+		for index in ready {
+			let Some(slot) = self.tasks[index].as_mut() else { continue };
+			let Some(fd) = slot.registered.take() else { continue };
+			self.poller.remove(fd);
+			self.waiting -= 1;
+			self.ready.push_back(index);
+		}
+
+		Ok(())
+	}
+}
+impl<T: 'static> Iterator for Reactor<T> {
+	type Item = T;
+
+	/// Resumes generators until one of them yields a value.
+	///
+	/// # Panic
+	/// This function panics if a producer panics, or if waiting on the
+	/// operating system fails.
+	fn next(&mut self) -> Option<T> {
+		loop {
+			let timeout = self.wake();
+			let Some(index) = self.ready.pop_front() else {
+				if self.waiting == 0 && self.sleeping.is_empty() {
+					return None
+				}
+				if let Err(error) = self.poll(timeout) {
+					panic!("Could not wait for descriptors to become ready: {error}")
+				}
+				continue
+			};
+
+			let slot = self.tasks[index].as_mut().unwrap();
+			match slot.gen.resume() {
+				Resume::Value(value) => {
+					self.ready.push_back(index);
+					return Some(value)
+				}
+				Resume::Pending => match (slot.gen.waiting_on(), slot.gen.deadline()) {
+					(Some(wait), _) => self.register(index, wait),
+					(None, Some(deadline)) => self.sleeping.push(Reverse((deadline, index))),
+					(None, None) => self.ready.push_back(index),
+				},
+				Resume::Complete => self.remove(index),
+			}
+		}
+	}
+}
+
+/// Readiness queue backed by epoll.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct Poller(RawFd);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Poller {
+	/// Creates a new, empty, queue.
+	fn new() -> io::Result<Self> {
+		match unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) } {
+			-1 => Err(io::Error::last_os_error()),
+			fd => Ok(Self(fd)),
+		}
+	}
+
+	/// Starts watching the given descriptor, on behalf of the given key.
+	fn add(&self, key: usize, wait: Wait) -> io::Result<()> {
+		let mut event = libc::epoll_event {
+			events: wait.events() as u32 | libc::EPOLLONESHOT as u32,
+			u64: key as u64,
+		};
+		match unsafe { libc::epoll_ctl(self.0, libc::EPOLL_CTL_ADD, wait.fd, &mut event) } {
+			-1 => Err(io::Error::last_os_error()),
+			_ => Ok(()),
+		}
+	}
+
+	/// Stops watching the given descriptor.
+	fn remove(&self, fd: RawFd) {
+		unsafe { libc::epoll_ctl(self.0, libc::EPOLL_CTL_DEL, fd, std::ptr::null_mut()); }
+	}
+
+	/// Blocks until at least one of the descriptors is ready, or until the
+	/// given amount of time has passed, and adds the keys of the ones that
+	/// are to the given list.
+	fn wait(&self, keys: &mut Vec<usize>, timeout: Option<Duration>) -> io::Result<()> {
+		/* Round up, so that we never wake up before the deadline. */
+		let timeout = match timeout {
+			Some(timeout) => timeout.as_nanos().div_ceil(1_000_000).min(libc::c_int::MAX as u128) as libc::c_int,
+			None => -1,
+		};
+
+		let mut events = [libc::epoll_event { events: 0, u64: 0 }; 64];
+		let count = loop {
+			match unsafe { libc::epoll_wait(self.0, events.as_mut_ptr(), events.len() as libc::c_int, timeout) } {
+				-1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+				-1 => return Err(io::Error::last_os_error()),
+				count => break count as usize,
+			}
+		};
+
+		keys.extend(events[..count].iter().map(|event| event.u64 as usize));
+		Ok(())
+	}
+}
+
+/// Readiness queue backed by kqueue.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+struct Poller(RawFd);
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+impl Poller {
+	/// Creates a new, empty, queue.
+	fn new() -> io::Result<Self> {
+		match unsafe { libc::kqueue() } {
+			-1 => Err(io::Error::last_os_error()),
+			fd => Ok(Self(fd)),
+		}
+	}
+
+	/// Starts watching the given descriptor, on behalf of the given key.
+	fn add(&self, key: usize, wait: Wait) -> io::Result<()> {
+		let mut event: libc::kevent = unsafe { std::mem::zeroed() };
+		event.ident = wait.fd as _;
+		event.filter = match wait.interest {
+			crate::io::Interest::Readable => libc::EVFILT_READ,
+			crate::io::Interest::Writable => libc::EVFILT_WRITE,
+		};
+		event.flags = libc::EV_ADD | libc::EV_ONESHOT | libc::EV_RECEIPT;
+		event.udata = key as _;
+
+		/* With EV_RECEIPT, the result of the registration comes back as an
+		 * event of its own. */
+		let mut receipt: libc::kevent = unsafe { std::mem::zeroed() };
+		match unsafe { libc::kevent(self.0, &event, 1, &mut receipt, 1, std::ptr::null()) } {
+			-1 => Err(io::Error::last_os_error()),
+			_ if receipt.flags & libc::EV_ERROR != 0 && receipt.data != 0 => {
+				Err(io::Error::from_raw_os_error(receipt.data as i32))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Stops watching the given descriptor.
+	///
+	/// Registrations are one-shot, so there is nothing left to remove once
+	/// the descriptor has been reported as ready.
+	fn remove(&self, _: RawFd) {}
+
+	/// Blocks until at least one of the descriptors is ready, or until the
+	/// given amount of time has passed, and adds the keys of the ones that
+	/// are to the given list.
+	fn wait(&self, keys: &mut Vec<usize>, timeout: Option<Duration>) -> io::Result<()> {
+		let timeout = timeout.map(|timeout| libc::timespec {
+			tv_sec: timeout.as_secs() as _,
+			tv_nsec: timeout.subsec_nanos() as _,
+		});
+		let timeout = timeout.as_ref().map_or(std::ptr::null(), |timeout| timeout as *const _);
+
+		let mut events: [libc::kevent; 64] = unsafe { std::mem::zeroed() };
+		let count = loop {
+			match unsafe { libc::kevent(self.0, std::ptr::null(), 0, events.as_mut_ptr(), events.len() as _, timeout) } {
+				-1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+				-1 => return Err(io::Error::last_os_error()),
+				count => break count as usize,
+			}
+		};
+
+		keys.extend(events[..count].iter().map(|event| event.udata as usize));
+		Ok(())
+	}
+}
+
+impl Drop for Poller {
+	fn drop(&mut self) {
+		unsafe { libc::close(self.0); }
+	}
+}
@@ -0,0 +1,206 @@
+//! Recording of the order in which generators run.
+//!
+//! Bugs in code that juggles several generators, such as schedulers and
+//! merges, often depend on the exact order in which the generators get
+//! resumed, and may not show up again on the next run. A [`Recorder`] keeps
+//! track of every switch into and out of every generator on its thread, along
+//! with a hash of the values that came out of them, so that the order can be
+//! inspected, and compared against that of another run.
+//!
+//! Recordings can be replayed through [`StepDriver::replay`], which resumes a
+//! set of named generators in the order they were resumed in the recording,
+//! and checks that every one of them does what it did back then.
+//!
+//! ```rust
+//! use yeet::{Generator, GeneratorBuilder};
+//! use yeet::record::{EventKind, Recorder};
+//!
+//! let recorder = Recorder::start();
+//! let mut gen = GeneratorBuilder::new()
+//!     .name("numbers")
+//!     .build::<u32>(|| yeet::yeet_all(0..2u32));
+//! gen.record_values();
+//! gen.by_ref().for_each(drop);
+//!
+//! let recording = recorder.finish();
+//! let kinds = recording.events().iter().map(|event| &event.kind).collect::<Vec<_>>();
+//! assert!(matches!(kinds[..], [
+//!     EventKind::Resume, EventKind::Yield(Some(_)),
+//!     EventKind::Resume, EventKind::Yield(Some(_)),
+//!     EventKind::Resume, EventKind::Complete,
+//! ]));
+//! ```
+//!
+//! [`StepDriver::replay`]: crate::testing::StepDriver::replay
+use std::cell::RefCell;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::{Generator, TaskId, Yield};
+
+thread_local! {
+	/// The events recorded by each of the recorders active on this thread.
+	static RECORDINGS: RefCell<Vec<Vec<Event>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Function feeding a value into a hasher.
+pub(crate) type HashValue<T> = fn(&T, &mut DefaultHasher);
+
+/// Records the switches of every generator on the current thread, for as long
+/// as it is alive.
+///
+/// Recorders may be nested, in which case every one of them sees all of the
+/// events that happen while it is alive.
+#[must_use = "the recording stops once the recorder is dropped"]
+pub struct Recorder {
+	/// The position of the recording of this recorder in the thread-local list.
+	index: usize,
+}
+impl Recorder {
+	/// Starts recording the switches of every generator on the current thread.
+	pub fn start() -> Self {
+		let index = RECORDINGS.with_borrow_mut(|recordings| {
+			recordings.push(Vec::new());
+			recordings.len() - 1
+		});
+
+		Self { index }
+	}
+
+	/// Stops recording, and returns what was recorded.
+	///
+	/// # Panic
+	/// This function panics if a recorder that was started after this one is
+	/// still active.
+	pub fn finish(self) -> Recording {
+		let events = RECORDINGS.with_borrow_mut(|recordings| {
+			assert_eq!(recordings.len(), self.index + 1, "Tried to finish a recorder before the ones nested in it!");
+			recordings.pop().unwrap()
+		});
+		std::mem::forget(self);
+
+		Recording { events }
+	}
+}
+impl Drop for Recorder {
+	fn drop(&mut self) {
+		RECORDINGS.with_borrow_mut(|recordings| recordings.truncate(self.index))
+	}
+}
+
+/// The switches recorded by a [`Recorder`], in the order they happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+	/// The recorded events.
+	events: Vec<Event>,
+}
+impl Recording {
+	/// The recorded events, in the order they happened.
+	pub fn events(&self) -> &[Event] {
+		&self.events
+	}
+}
+impl fmt::Display for Recording {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for event in &self.events {
+			writeln!(f, "{event}")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// A single switch into or out of a generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+	/// The task being switched into or out of.
+	pub task: TaskId,
+	/// The name of the task, if it was given one.
+	pub name: Option<String>,
+	/// What happened.
+	pub kind: EventKind,
+}
+impl fmt::Display for Event {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.name {
+			Some(name) => write!(f, "{name:?} ")?,
+			None => write!(f, "#{} ", self.task.as_u64())?,
+		}
+		match self.kind {
+			EventKind::Resume => f.write_str("resumed"),
+			EventKind::Yield(Some(hash)) => write!(f, "yielded {hash:016x}"),
+			EventKind::Yield(None) => f.write_str("yielded"),
+			EventKind::Pending => f.write_str("suspended"),
+			EventKind::Complete => f.write_str("finished"),
+			EventKind::Panic => f.write_str("panicked"),
+		}
+	}
+}
+
+/// What happened in a switch into or out of a generator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EventKind {
+	/// The consumer resumed the producer.
+	Resume,
+	/// The producer yielded values, along with their hash, if the generator
+	/// has opted in to hashing them with [`Generator::record_values`].
+	Yield(Option<u64>),
+	/// The producer suspended itself without yielding a value.
+	Pending,
+	/// The producer finished.
+	Complete,
+	/// The producer panicked.
+	Panic,
+}
+
+impl<T: Hash + 'static> Generator<T> {
+	/// Has the values yielded by this generator be hashed into the events of
+	/// any [`Recorder`] active while it runs.
+	pub fn record_values(&mut self) {
+		self.hash = Some(|value, hasher| value.hash(hasher))
+	}
+}
+
+/// Whether there is any recorder active on the current thread.
+pub(crate) fn is_recording() -> bool {
+	RECORDINGS.with_borrow(|recordings| !recordings.is_empty())
+}
+
+/// Adds the given event to every recording active on the current thread.
+fn push(task: TaskId, name: Option<&str>, kind: EventKind) {
+	let event = Event {
+		task,
+		name: name.map(str::to_owned),
+		kind,
+	};
+	RECORDINGS.with_borrow_mut(|recordings| {
+		for recording in recordings {
+			recording.push(event.clone())
+		}
+	})
+}
+
+/// Records the consumer resuming the given task.
+pub(crate) fn resumed(task: TaskId, name: Option<&str>) {
+	push(task, name, EventKind::Resume)
+}
+
+/// Records the given task switching back to the consumer with the given data,
+/// hashing the values in it with the given function, if there is one.
+pub(crate) fn yielded<T>(task: TaskId, name: Option<&str>, data: &Yield<T>, hash: Option<HashValue<T>>) {
+	let hash_all = |values: &[T]| hash.map(|hash| {
+		let mut hasher = DefaultHasher::new();
+		for value in values {
+			hash(value, &mut hasher)
+		}
+		hasher.finish()
+	});
+
+	let kind = match data {
+		Yield::Value(value) => EventKind::Yield(hash_all(std::slice::from_ref(value))),
+		Yield::Batch(values) => EventKind::Yield(hash_all(values)),
+		Yield::Pending => EventKind::Pending,
+		Yield::StopIteration => EventKind::Complete,
+		Yield::Panic(_) => EventKind::Panic,
+	};
+	push(task, name, kind)
+}
@@ -0,0 +1,96 @@
+use std::rc::Rc;
+use crate::GeneratorBuilder;
+
+/// The size of the stacks the levels of a recursion run on, unless asked
+/// otherwise with [`recurse_with_stack_size`].
+pub const RECURSE_STACK_SIZE: usize = 64 * 1024;
+
+/// Runs a recursive function with every level of the recursion on a stack of
+/// its own, so that recursions of any depth can't overflow the stack.
+///
+/// The function gets called with the argument, and with a [`Recurse`] handle,
+/// through which it recurses, instead of calling itself directly. Every call
+/// through the handle runs the function as a child task on a small, fresh
+/// stack, and hands the result back up once it yields it. The stack of each
+/// level only ever holds the frames of that level, so how deep the recursion
+/// can get is limited by memory rather than by the size of the stack it
+/// started on, with every level costing a stack of [`RECURSE_STACK_SIZE`]
+/// bytes for as long as it runs.
+///
+/// Panics raised by any of the levels propagate up through all of the levels
+/// above it, as they would through plain recursion.
+///
+/// ```rust
+/// let sum = yeet::recurse(20_000u64, |n, rec| {
+///     if n == 0 { 0 } else { n + rec.call(n - 1) }
+/// });
+/// assert_eq!(sum, 20_000 * 20_001 / 2);
+/// ```
+///
+/// # Panic
+/// Every level of the recursion counts towards the limit set with
+/// [`set_max_depth`], and recursing past it panics.
+///
+/// [`set_max_depth`]: crate::set_max_depth
+pub fn recurse<A, R>(arg: A, func: impl Fn(A, &Recurse<A, R>) -> R + 'static) -> R
+	where A: 'static,
+		R: 'static {
+
+	recurse_with_stack_size(RECURSE_STACK_SIZE, arg, func)
+}
+
+/// Same as [`recurse`], but every level of the recursion runs on a stack of
+/// the given size.
+pub fn recurse_with_stack_size<A, R>(stack_size: usize, arg: A, func: impl Fn(A, &Recurse<A, R>) -> R + 'static) -> R
+	where A: 'static,
+		R: 'static {
+
+	let rec = Recurse {
+		func: Rc::new(func),
+		stack_size,
+	};
+	rec.call(arg)
+}
+
+/// The function run at every level of a recursion.
+type Level<A, R> = Rc<dyn Fn(A, &Recurse<A, R>) -> R>;
+
+/// Handle through which the function run by [`recurse`] recurses.
+pub struct Recurse<A, R> {
+	/// The function run at every level of the recursion.
+	func: Level<A, R>,
+	/// The size of the stack of every level.
+	stack_size: usize,
+}
+impl<A: 'static, R: 'static> Recurse<A, R> {
+	/// Runs the function with the given argument one level deeper into the
+	/// recursion, on a stack of its own, and returns its result.
+	pub fn call(&self, arg: A) -> R {
+		let rec = self.clone();
+		let mut level = GeneratorBuilder::new()
+			.stack_size(self.stack_size)
+			.build_closure::<R>(move || {
+				let result = (rec.func)(arg, &rec);
+				crate::yeet(result)
+			});
+
+		/* Panics in the level propagate out of here on their own. */
+		let result = match level.next() {
+			Some(result) => result,
+			None => unreachable!("a level of the recursion finished without a result"),
+		};
+
+		/* Letting the level run to completion is a lot cheaper than having
+		 * it unwind through a cancellation. */
+		let _ = level.next();
+		result
+	}
+}
+impl<A, R> Clone for Recurse<A, R> {
+	fn clone(&self) -> Self {
+		Self {
+			func: self.func.clone(),
+			stack_size: self.stack_size,
+		}
+	}
+}
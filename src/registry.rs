@@ -0,0 +1,245 @@
+//! Registry of live tasks.
+//!
+//! When enabled on a given thread, through [`enable`], every generator created
+//! on that thread from then on gets recorded in the registry, for as long as it
+//! exists. The tasks in the registry can then be enumerated through [`tasks`],
+//! which is the foundation for debugging tools, leak reports, and crash dumps
+//! that need to know about suspended generators.
+//!
+//! Tasks also keep track of the task they are being driven from, which makes
+//! up the tree of generators, much like a call tree. The tree may be walked
+//! starting from any task, through [`TaskInfo::parent`] upwards, and through
+//! [`TaskInfo::children`] downwards. The current task can always be found
+//! through [`current_task`], even if the registry is disabled, but finding the
+//! children of a task requires the registry.
+//!
+//! The registry is disabled by default, as it costs an extra allocation per
+//! generator.
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::rc::{Rc, Weak};
+use crate::TaskId;
+
+/// The state a task may be in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TaskState {
+	/// The task has been created, but has not been started yet.
+	Created,
+	/// The task is running, or is waiting on a task it is the consumer of.
+	Running,
+	/// The task has yielded a value, and is waiting to be resumed.
+	Suspended,
+	/// The producer function has either returned or panicked.
+	Finished,
+}
+
+/// The registers of a suspended producer that are of interest to debuggers.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SavedContext {
+	/// The address execution will resume from.
+	pub pc: usize,
+	/// The stack pointer.
+	pub sp: usize,
+	/// The frame pointer.
+	pub fp: usize,
+}
+
+/// Information about a live task.
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+	/// The identifier of the task.
+	pub id: TaskId,
+	/// The name of the task, if it was given one.
+	pub name: Option<Rc<str>>,
+	/// The state the task was in when this information was gathered.
+	pub state: TaskState,
+	/// The task the task was last resumed from, or, if it has not been resumed
+	/// yet, the task it was created from, if any.
+	///
+	/// Tasks driven from outside of any generator have no parent.
+	pub parent: Option<TaskId>,
+	/// How many generators deep the task is, counting from its root, with
+	/// tasks that have no parent being one deep.
+	pub depth: usize,
+	/// The range of addresses spanned by the stack of the task.
+	pub stack: Range<usize>,
+	/// The registers of the producer, as of the last time it yielded.
+	///
+	/// This is only available for tasks that have been started, and only for
+	/// the backends that support it.
+	pub context: Option<SavedContext>,
+}
+impl TaskInfo {
+	/// Enumerates the live tasks recorded in the registry of the current thread
+	/// whose parent is this task, in the order they were created.
+	///
+	/// Tasks that are not recorded in the registry are never found, so this
+	/// always comes up empty if the registry is disabled.
+	pub fn children(&self) -> impl Iterator<Item = TaskInfo> {
+		let id = self.id;
+		tasks().filter(move |task| task.parent == Some(id))
+	}
+}
+
+/// The entry of a task in the registry, which is shared with the task.
+pub(crate) struct Record {
+	/// The identifier of the task.
+	id: TaskId,
+	/// The name of the task.
+	name: Option<Rc<str>>,
+	/// The current state of the task.
+	state: Cell<TaskState>,
+	/// The parent of the task, and how deep it is.
+	parent: Cell<(Option<TaskId>, usize)>,
+	/// The range of addresses spanned by the stack of the task.
+	stack: Range<usize>,
+	/// The registers of the producer, as of the last time it yielded.
+	context: Cell<Option<SavedContext>>,
+}
+impl Record {
+	/// Updates the state of the task.
+	pub(crate) fn set_state(&self, state: TaskState) {
+		self.state.set(state)
+	}
+
+	/// Updates the parent of the task, and how deep it is.
+	pub(crate) fn set_parent(&self, parent: Option<TaskId>, depth: usize) {
+		self.parent.set((parent, depth))
+	}
+
+	/// Updates the saved registers of the task.
+	pub(crate) fn set_context(&self, context: Option<SavedContext>) {
+		self.context.set(context)
+	}
+
+	/// Gathers information about the task.
+	fn info(&self) -> TaskInfo {
+		let (parent, depth) = self.parent.get();
+		TaskInfo {
+			id: self.id,
+			name: self.name.clone(),
+			state: self.state.get(),
+			parent,
+			depth,
+			stack: self.stack.clone(),
+			context: self.context.get(),
+		}
+	}
+}
+
+thread_local! {
+	/// The registry of the current thread, if it has been enabled.
+	static REGISTRY: RefCell<Option<Vec<Weak<Record>>>> = const { RefCell::new(None) }
+}
+
+/// Enables the registry on the current thread.
+///
+/// Only generators created after the registry has been enabled get recorded.
+pub fn enable() {
+	REGISTRY.with_borrow_mut(|registry| {
+		if registry.is_none() {
+			*registry = Some(Vec::new())
+		}
+	})
+}
+
+/// Disables the registry on the current thread, forgetting about all of the
+/// tasks recorded in it.
+pub fn disable() {
+	REGISTRY.with_borrow_mut(|registry| *registry = None)
+}
+
+/// Whether the registry is enabled on the current thread.
+pub fn is_enabled() -> bool {
+	REGISTRY.with_borrow(|registry| registry.is_some())
+}
+
+/// Enumerates the live tasks recorded in the registry of the current thread,
+/// in the order they were created.
+///
+/// The returned iterator works on a snapshot of the registry, taken at the
+/// time this function was called.
+pub fn tasks() -> impl Iterator<Item = TaskInfo> {
+	REGISTRY.with_borrow_mut(|registry| {
+		let Some(registry) = registry else { return Vec::new() };
+
+		registry.retain(|record| record.strong_count() > 0);
+		registry.iter()
+			.filter_map(Weak::upgrade)
+			.map(|record| record.info())
+			.collect::<Vec<_>>()
+	}).into_iter()
+}
+
+/// The number of slots in the registry of the current thread, some of which
+/// may hold tasks that are gone.
+///
+/// Unlike [`tasks`], this neither allocates, frees, nor panics, which makes it
+/// safe to call from a debugger that stopped the thread anywhere, including in
+/// the middle of an update to the registry, or of an allocation, in which case
+/// it returns `None`.
+#[cfg(feature = "debugger")]
+pub(crate) fn try_len() -> Option<usize> {
+	REGISTRY.try_with(|registry| {
+		let registry = registry.try_borrow().ok()?;
+		Some(registry.as_ref()?.len())
+	}).ok().flatten()
+}
+
+/// Gathers information about the task in the slot at the given index in the
+/// registry of the current thread, if it is still alive.
+///
+/// Like [`try_len`], this neither allocates, frees, nor panics, and returns
+/// `None` if the registry is being updated. The task holds on to its record,
+/// so letting go of the one taken here never frees it.
+#[cfg(feature = "debugger")]
+pub(crate) fn try_get(index: usize) -> Option<TaskInfo> {
+	REGISTRY.try_with(|registry| {
+		let registry = registry.try_borrow().ok()?;
+		let record = registry.as_ref()?.get(index)?.upgrade()?;
+		Some(record.info())
+	}).ok().flatten()
+}
+
+/// Gathers information about the task that is currently running, if there is
+/// one.
+///
+/// This works regardless of whether the registry is enabled.
+pub fn current_task() -> Option<TaskInfo> {
+	crate::with_current_task(|task| {
+		let header = task.header();
+		TaskInfo {
+			id: header.id,
+			name: header.name.clone(),
+			state: header.state(),
+			parent: header.parent,
+			depth: header.depth,
+			stack: task.stack_bounds(),
+			context: None,
+		}
+	})
+}
+
+/// Records a new task in the registry of the current thread, if it is enabled.
+pub(crate) fn register(id: TaskId, name: Option<Rc<str>>, stack: Range<usize>) -> Option<Rc<Record>> {
+	REGISTRY.with_borrow_mut(|registry| {
+		let registry = registry.as_mut()?;
+		let record = Rc::new(Record {
+			id,
+			name,
+			state: Cell::new(TaskState::Created),
+			parent: Cell::new((None, 1)),
+			stack,
+			context: Cell::new(None),
+		});
+
+		/* Dead entries keep the allocations of their records alive, so they
+		 * get pruned before the registry grows, so that it only ever grows
+		 * with the number of live tasks. */
+		if registry.len() == registry.capacity() {
+			registry.retain(|record| record.strong_count() > 0);
+		}
+		registry.push(Rc::downgrade(&record));
+		Some(record)
+	})
+}
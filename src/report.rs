@@ -0,0 +1,56 @@
+use std::any::Any;
+use std::error::Error;
+use crate::{current_header, Generator};
+use crate::sys::AnyTask;
+
+impl<T: 'static> Generator<T> {
+	/// The metadata most recently reported by the producer, if it is of type
+	/// `M`.
+	///
+	/// Reports are made through [`report`], and each new report replaces the
+	/// previous one, regardless of its type.
+	pub fn last_report<M: 'static>(&self) -> Option<&M> {
+		self.task.header_ref().report.as_ref()?.downcast_ref::<M>()
+	}
+
+	/// Takes the errors reported by the producer since the last time they were
+	/// taken, in the order they were reported.
+	///
+	/// Errors are reported through [`report_error`].
+	pub fn take_errors(&mut self) -> Vec<Box<dyn Error + Send + Sync>> {
+		std::mem::take(&mut self.task.header().errors)
+	}
+}
+
+/// Reports a piece of metadata to the consumer, without yielding.
+///
+/// Unlike [`yeet`], this function doesn't suspend the producer. The metadata is
+/// stored alongside the task, replacing whatever had been reported before it,
+/// and the consumer may look at it whenever it wants, through
+/// [`Generator::last_report`]. This is useful for reporting progress and
+/// statistics without having to make them part of the type of the values
+/// being yielded.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+///
+/// [`yeet`]: crate::yeet
+pub fn report<M: 'static>(meta: M) {
+	let header = current_header();
+	unsafe { (*header).report = Some(Box::new(meta) as Box<dyn Any>) }
+}
+
+/// Reports a recoverable error to the consumer, without yielding.
+///
+/// Like [`report`], this function doesn't suspend the producer, and doesn't end
+/// the stream of values either, so producers may keep going after an error they
+/// can recover from while still letting the consumer know about it. Errors pile
+/// up alongside the task until the consumer takes them out, through
+/// [`Generator::take_errors`].
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn report_error<E: Into<Box<dyn Error + Send + Sync>>>(error: E) {
+	let header = current_header();
+	unsafe { (*header).errors.push(error.into()) }
+}
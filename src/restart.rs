@@ -0,0 +1,157 @@
+use std::mem::ManuallyDrop;
+use std::rc::Rc;
+use crate::{sys, Generator};
+use crate::registry::TaskState;
+use crate::sys::{Entry, Stack};
+
+impl<T: 'static> Generator<T> {
+	/// Takes the stack of this generator back, for another generator to run
+	/// on, if the producer has either finished or never been started.
+	///
+	/// Producers that are still running get cancelled first. Stacks that have
+	/// been leaked along with their producers, or written over, are never
+	/// handed back.
+	fn into_stack(mut self) -> Option<Stack> {
+		if !self.first && self.task.header_ref().state() != TaskState::Finished {
+			let _ = self.cancel_task();
+		}
+
+		let header = self.task.header_ref();
+		let finished = self.first || header.state() == TaskState::Finished;
+		if !finished || header.corrupted || header.abandoned {
+			return None
+		}
+
+		/* Everything other than the task gets dropped as usual, and the task
+		 * has nothing left to clean up on its stack. */
+		let mut this = ManuallyDrop::new(self);
+		let task = unsafe {
+			std::ptr::drop_in_place(&mut this.buffer);
+			std::ptr::drop_in_place(&mut this.panic);
+			std::ptr::drop_in_place(&mut this.hash);
+			std::ptr::drop_in_place(&mut this.leftover);
+			std::ptr::read(&this.task)
+		};
+		Some(sys::into_stack(task))
+	}
+}
+
+/// A generator whose producer can be run over again, from the start, on the
+/// same stack.
+///
+/// The producer is a function that can be called any number of times. The
+/// generator iterates over the values of a single run of it, and, once the
+/// run is over, or whenever the consumer asks for it with
+/// [`RestartableGenerator::restart`], a new run starts on the stack the
+/// previous one ran on, which saves setting up a new one.
+///
+/// ```rust
+/// use yeet::RestartableGenerator;
+///
+/// let mut gen = RestartableGenerator::<u32>::new(|| yeet::yeet_all(0u32..3));
+/// assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 1, 2]);
+///
+/// gen.restart();
+/// assert_eq!(gen.next(), Some(0));
+/// ```
+pub struct RestartableGenerator<T: 'static> {
+	/// The function run by the producer.
+	func: Rc<dyn Fn()>,
+	/// The generator running the current run of the producer.
+	gen: Option<Generator<T>>,
+}
+impl<T: 'static> RestartableGenerator<T> {
+	/// Creates a new generator running the given function as its producer.
+	pub fn new(func: impl Fn() + 'static) -> Self {
+		let func = Rc::new(func) as Rc<dyn Fn()>;
+		let gen = Self::run(&func, Stack::new(sys::DEFAULT_STACK_SIZE));
+		Self {
+			func,
+			gen: Some(gen),
+		}
+	}
+
+	/// Starts a run of the given function on the given stack.
+	fn run(func: &Rc<dyn Fn()>, stack: Stack) -> Generator<T> {
+		let func = func.clone();
+		Generator::from_parts(Entry::Boxed(Box::new(move || func())), stack, None)
+	}
+
+	/// Cancels the current run of the producer, if it isn't done yet, and
+	/// starts over from the beginning.
+	pub fn restart(&mut self) {
+		let stack = self.gen.take()
+			.and_then(Generator::into_stack)
+			.unwrap_or_else(|| Stack::new(sys::DEFAULT_STACK_SIZE));
+		self.gen = Some(Self::run(&self.func, stack));
+	}
+
+	/// Turns this generator into an endless iterator, which restarts the
+	/// producer every time it is done, repeating its values forever.
+	///
+	/// Like [`Iterator::cycle`], the iterator stops if a run of the producer
+	/// yields no values at all, as it would otherwise restart it forever
+	/// without ever having anything to hand out. Runs of the producer don't
+	/// have to yield the same values.
+	///
+	/// ```rust
+	/// use yeet::RestartableGenerator;
+	///
+	/// let servers = RestartableGenerator::<&str>::new(|| {
+	///     yeet::yeet("alpha");
+	///     yeet::yeet("beta");
+	/// });
+	///
+	/// let picks = servers.cycle().take(5).collect::<Vec<_>>();
+	/// assert_eq!(picks, ["alpha", "beta", "alpha", "beta", "alpha"]);
+	/// ```
+	pub fn cycle(self) -> Cycle<T> {
+		Cycle {
+			inner: self,
+			yielded: false,
+		}
+	}
+}
+impl<T: 'static> Iterator for RestartableGenerator<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		self.gen.as_mut()?.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		match &self.gen {
+			Some(gen) => gen.size_hint(),
+			None => (0, Some(0)),
+		}
+	}
+}
+
+/// An endless iterator over the values of a producer, which gets restarted
+/// every time it is done.
+///
+/// See [`RestartableGenerator::cycle`].
+pub struct Cycle<T: 'static> {
+	/// The generator running the producer.
+	inner: RestartableGenerator<T>,
+	/// Whether the current run of the producer has yielded any values.
+	yielded: bool,
+}
+impl<T: 'static> Iterator for Cycle<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		if let Some(value) = self.inner.next() {
+			self.yielded = true;
+			return Some(value)
+		}
+		if !std::mem::take(&mut self.yielded) {
+			return None
+		}
+
+		self.inner.restart();
+		let value = self.inner.next()?;
+		self.yielded = true;
+		Some(value)
+	}
+}
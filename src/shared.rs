@@ -0,0 +1,93 @@
+use std::any::Any;
+use crate::{current_header, Generator};
+use crate::sys::AnyTask;
+
+/// State owned by a generator, which both of its sides may access.
+pub struct SharedState {
+	/// The state itself.
+	value: Box<dyn Any>,
+	/// Whether the producer is currently holding on to the state.
+	borrowed: bool,
+}
+impl SharedState {
+	/// Whether the producer is currently holding on to the state.
+	pub fn is_borrowed(&self) -> bool {
+		self.borrowed
+	}
+}
+
+impl<T: 'static> Generator<T> {
+	/// Gives this generator a piece of state that is shared between the
+	/// consumer and the producer, replacing any state it had before.
+	///
+	/// The producer may access the state through [`with_state`], whenever it
+	/// is running, and the consumer may access it through
+	/// [`Generator::shared_state_mut`], whenever the producer is not running. As only
+	/// one of the sides ever runs at a time, this is a sound way of sharing
+	/// scratch state between them, without reference counting or interior
+	/// mutability.
+	pub fn set_shared_state<S: 'static>(&mut self, state: S) {
+		self.task.header().shared = Some(SharedState {
+			value: Box::new(state),
+			borrowed: false,
+		})
+	}
+
+	/// The state shared with the producer, if it is of type `S`.
+	pub fn shared_state<S: 'static>(&self) -> Option<&S> {
+		self.task.header_ref().shared.as_ref()?.value.downcast_ref()
+	}
+
+	/// The state shared with the producer, if it is of type `S`.
+	pub fn shared_state_mut<S: 'static>(&mut self) -> Option<&mut S> {
+		self.task.header().shared.as_mut()?.value.downcast_mut()
+	}
+
+	/// Takes the state shared with the producer back, if it is of type `S`.
+	pub fn take_shared_state<S: 'static>(&mut self) -> Option<S> {
+		let header = self.task.header();
+		if !header.shared.as_ref()?.value.is::<S>() {
+			return None
+		}
+
+		let state = header.shared.take()?;
+		state.value.downcast().ok().map(|state| *state)
+	}
+}
+
+/// Gives the given function access to the state shared by the consumer.
+///
+/// State is shared with producers through [`Generator::set_shared_state`], and stays
+/// with the generator across resumes. Trying to yield from inside of the given
+/// function will cause it to panic.
+///
+/// # Panic
+/// This function will panic if it is not being called from inside a generator,
+/// if the consumer has not shared state of type `S` with the generator, or if
+/// the state is already being accessed.
+pub fn with_state<S: 'static, R>(f: impl FnOnce(&mut S) -> R) -> R {
+	let header = current_header();
+	let state = match unsafe { (*header).shared.as_mut() } {
+		Some(state) => state,
+		None => panic!("The consumer has not shared any state with this generator!")
+	};
+	if state.borrowed {
+		panic!("Tried to access shared state that is already being accessed!")
+	}
+	let value = match state.value.downcast_mut::<S>() {
+		Some(value) => value as *mut S,
+		None => panic!("Tried to access shared state of the wrong type!")
+	};
+
+	/* Same as with lent values, yielding from inside the function must fail. */
+	struct Release(*mut SharedState);
+	impl Drop for Release {
+		fn drop(&mut self) {
+			unsafe { (*self.0).borrowed = false }
+		}
+	}
+	state.borrowed = true;
+	let _release = Release(state as *mut _);
+
+	f(unsafe { &mut *value })
+}
@@ -0,0 +1,117 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use crate::{current_header, Generator};
+use crate::sys::AnyTask;
+
+/// A secondary output of a producer, along with the type of the values that go
+/// through it.
+///
+/// Side channels let producers hand out values other than the ones they yield,
+/// such as diagnostics, without having to make them part of the type of the
+/// generator. Channels are told apart by the type implementing this trait,
+/// which is usually a marker type with no values of its own.
+///
+/// ```rust
+/// use yeet::{Generator, SideChannel};
+///
+/// struct Log;
+/// impl SideChannel for Log {
+///     type Item = String;
+/// }
+///
+/// let mut gen = Generator::<u32>::from_fn_ptr(|| {
+///     for i in 0..3u32 {
+///         yeet::yeet_to::<Log>(format!("yielding {i}"));
+///         yeet::yeet(i);
+///     }
+/// });
+/// let log = gen.side::<Log>();
+///
+/// assert_eq!(gen.next(), Some(0));
+/// assert_eq!(log.collect::<Vec<_>>(), ["yielding 0"]);
+/// ```
+pub trait SideChannel: 'static {
+	/// The type of the values that go through the channel.
+	type Item: 'static;
+}
+
+/// Queue of the values sent through a side channel that are yet to be taken.
+type Queue<C> = RefCell<VecDeque<<C as SideChannel>::Item>>;
+
+impl<T: 'static> Generator<T> {
+	/// Opens a handle to the side channel `C` of the producer.
+	///
+	/// Values sent through the channel with [`yeet_to`] pile up until they are
+	/// taken out through any of its handles. Values sent through channels no
+	/// handle has been opened to yet get dropped.
+	///
+	/// [`yeet_to`]: crate::yeet_to
+	pub fn side<C: SideChannel>(&mut self) -> Side<C> {
+		let sides = &mut self.task.header().sides;
+		let queue = match sides.iter().find(|(ty, _)| *ty == TypeId::of::<C>()) {
+			Some((_, queue)) => queue.clone(),
+			None => {
+				let queue = Rc::new(Queue::<C>::default()) as Rc<dyn Any>;
+				sides.push((TypeId::of::<C>(), queue.clone()));
+				queue
+			}
+		};
+
+		Side { queue: queue.downcast().unwrap() }
+	}
+}
+
+/// Handle to a side channel of a producer, through which the consumer takes
+/// the values sent through it.
+///
+/// Iterating over the handle takes the values that have been sent so far, and
+/// stops once there are none left, without resuming the producer. Handles may
+/// outlive their generator, in which case they still hold whatever was sent
+/// before the generator went away.
+pub struct Side<C: SideChannel> {
+	/// The values sent through the channel.
+	queue: Rc<Queue<C>>,
+}
+impl<C: SideChannel> Side<C> {
+	/// The number of values waiting to be taken.
+	pub fn len(&self) -> usize {
+		self.queue.borrow().len()
+	}
+
+	/// Whether there are no values waiting to be taken.
+	pub fn is_empty(&self) -> bool {
+		self.queue.borrow().is_empty()
+	}
+}
+impl<C: SideChannel> Iterator for Side<C> {
+	type Item = C::Item;
+
+	fn next(&mut self) -> Option<C::Item> {
+		self.queue.borrow_mut().pop_front()
+	}
+}
+impl<C: SideChannel> Clone for Side<C> {
+	fn clone(&self) -> Self {
+		Self { queue: self.queue.clone() }
+	}
+}
+
+/// Sends the given value through the side channel `C` of the producer.
+///
+/// Unlike [`yeet`], this doesn't suspend the producer. The value gets queued
+/// up for the consumer to take through a [`Side`] handle, and gets dropped if
+/// the consumer hasn't opened one.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+///
+/// [`yeet`]: crate::yeet
+pub fn yeet_to<C: SideChannel>(value: C::Item) {
+	let header = current_header();
+	let sides = unsafe { &(*header).sides };
+	if let Some((_, queue)) = sides.iter().find(|(ty, _)| *ty == TypeId::of::<C>()) {
+		queue.downcast_ref::<Queue<C>>().unwrap().borrow_mut().push_back(value)
+	}
+}
@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+use crate::{current_header, Generator};
+use crate::registry::TaskState;
+
+impl<T: 'static> Generator<T> {
+	/// The point in time the producer is sleeping until, if it is suspended in
+	/// [`sleep`] or [`sleep_until`].
+	///
+	/// Resuming the producer any earlier than that just has it suspend again.
+	///
+	/// [`sleep`]: crate::sleep
+	/// [`sleep_until`]: crate::sleep_until
+	pub fn deadline(&self) -> Option<Instant> {
+		let header = self.task.header_ref();
+		match header.state() {
+			TaskState::Suspended => header.deadline,
+			_ => None,
+		}
+	}
+
+	/// Blocks the thread until the deadline of the producer passes, if it is
+	/// sleeping, or until the given point in time, whichever comes first.
+	pub(crate) fn wait_deadline(&self, limit: Option<Instant>) {
+		let Some(deadline) = self.deadline() else { return };
+		let deadline = limit.map_or(deadline, |limit| deadline.min(limit));
+		std::thread::sleep(deadline.saturating_duration_since(Instant::now()))
+	}
+}
+
+/// Suspends the producer until the given amount of time has passed.
+///
+/// See [`sleep_until`]. Durations too long to be represented as a point in time
+/// have the producer sleep forever, a day at a time, for as long as it keeps
+/// getting resumed.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn sleep(duration: Duration) {
+	match Instant::now().checked_add(duration) {
+		Some(deadline) => sleep_until(deadline),
+		None => loop {
+			sleep(Duration::from_secs(24 * 60 * 60))
+		},
+	}
+}
+
+/// Suspends the producer until the given point in time.
+///
+/// The producer yields control back to the consumer, as with [`yield_now`],
+/// along with the deadline, which the consumer can find out through
+/// [`Generator::deadline`] in order to resume the producer once it passes.
+/// Consumers driving the generator through [`Generator::next`] block the thread
+/// until then, while a [`Reactor`] keeps driving its other generators in the
+/// meantime. Resuming the producer early has it suspend again, so it never
+/// returns before the deadline.
+///
+/// [`yield_now`]: crate::yield_now
+/// [`Reactor`]: crate::Reactor
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+pub fn sleep_until(deadline: Instant) {
+	while Instant::now() < deadline {
+		unsafe { (*current_header()).deadline = Some(deadline) }
+		crate::yield_now();
+
+		/* The task may have moved while it was suspended. */
+		unsafe { (*current_header()).deadline = None }
+	}
+}
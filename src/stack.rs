@@ -0,0 +1,95 @@
+use crate::{top_task, GeneratorBuilder};
+
+/// How many bytes of its stack the current task has left.
+///
+/// This is the distance between where the stack of the task currently is and
+/// the bottom of its stack, which is how far it may still grow before the task
+/// overflows it. Recursive producers may use this to decide whether to go on
+/// recursing on their own stacks, or to carry on on the stack of a child task.
+/// The frames of the functions called after this returns also take up some of
+/// what it reports, so producers should keep some room to spare.
+///
+/// # Panic
+/// This function panics if it is not being called from inside a generator.
+#[inline(never)]
+pub fn remaining_stack() -> usize {
+	let task = match top_task() {
+		Some(top) => top,
+		None => panic!("Tried to query the stack of the current task from outside a generator!")
+	};
+
+	let marker = 0u8;
+	let current = &marker as *const u8 as usize;
+	current.saturating_sub(unsafe { (*task).stack_floor() })
+}
+
+/// Calls the given function, moving over to a fresh stack first if the
+/// current task has less than `red_zone` bytes of its stack left.
+///
+/// Deeply recursive code, such as a recursive descent parser, can wrap its
+/// recursive calls in this to run safely on a small stack: as long as every
+/// level of the recursion takes up less than the red zone, the recursion
+/// carries on on the stack of a child task, of the size generators get by
+/// default, whenever it is about to run out of stack, rather than overflowing
+/// it. The function only runs on the child task for as long as it takes to
+/// return, so it may borrow from the caller.
+///
+/// Being a task of its own, the child task is also what functions acting on
+/// the current task act on when called from the function while it runs on the
+/// fresh stack, rather than the task of the caller. Errors passed to
+/// [`report`] and hints passed to [`size_hint`] get lost once the function
+/// returns, and [`with_state`] panics, as the child task has no state shared
+/// with it.
+///
+/// Outside of a generator, where how much stack is left isn't known, the
+/// function always runs on a fresh stack.
+///
+/// [`report`]: crate::report
+/// [`size_hint`]: crate::size_hint
+/// [`with_state`]: crate::with_state
+///
+/// ```rust
+/// fn depth(n: u64) -> u64 {
+///     match n {
+///         0 => 0,
+///         n => yeet::maybe_grow(32 * 1024, || 1 + depth(n - 1)),
+///     }
+/// }
+///
+/// assert_eq!(depth(100_000), 100_000);
+/// ```
+///
+/// # Panic
+/// Panics raised by the function propagate to the caller. The child task is
+/// a task of its own, so the function must not yield: values yielded from it
+/// never reach the consumer of the caller, and the yield panics instead.
+pub fn maybe_grow<R>(red_zone: usize, func: impl FnOnce() -> R) -> R {
+	if top_task().is_some() && remaining_stack() >= red_zone {
+		func()
+	} else {
+		grow(func)
+	}
+}
+
+/// Values yielded from a function that has been moved to a stack of its own,
+/// which there can't be any of.
+enum Grown {}
+
+/// Calls the given function on a fresh stack.
+fn grow<R>(func: impl FnOnce() -> R) -> R {
+	let mut result = None;
+	let slot = &mut result;
+	let run: Box<dyn FnOnce() + '_> = Box::new(move || *slot = Some(func()));
+
+	/* The function never outlives this call, as the task finishes running it
+	 * before we take the result, and the function is gone once a panic in it
+	 * has propagated to us. */
+	let run = unsafe { std::mem::transmute::<Box<dyn FnOnce() + '_>, Box<dyn FnOnce() + 'static>>(run) };
+	let mut task = GeneratorBuilder::new().build_closure::<Grown>(run);
+
+	/* Panics in the function propagate out of here on their own. */
+	match task.next() {
+		Some(grown) => match grown {},
+		None => result.unwrap(),
+	}
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+use crate::Generator;
+
+/// Statistics about the activity of a generator.
+///
+/// These are collected for every generator, and may be read at any time with
+/// [`Generator::stats`]. Only the time spent running the producer requires
+/// opting in, through [`GeneratorBuilder::measure_time`], as measuring it adds
+/// a noticeable cost to every context switch.
+///
+/// [`GeneratorBuilder::measure_time`]: crate::GeneratorBuilder::measure_time
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+	/// The number of times the producer has been resumed by the consumer.
+	pub resumes: u64,
+	/// The number of values the producer has handed over to the consumer.
+	pub yielded: u64,
+	/// The number of cancellation requests the producer has received.
+	pub cancels: u64,
+	/// The deepest the stack of the producer has been at any of the points at
+	/// which it yielded, in bytes.
+	///
+	/// Calls that return before the producer yields are not accounted for, so
+	/// this is a lower bound on the actual amount of stack in use. Builds with
+	/// the `small` feature don't keep track of it, and leave this at zero.
+	pub stack_used: usize,
+	/// The total time spent running the producer, including the time spent
+	/// running any producers it drives in turn.
+	///
+	/// This is always zero unless the generator was created with time
+	/// measurement enabled.
+	pub time_in_producer: Duration,
+}
+
+impl<T: 'static> Generator<T> {
+	/// Statistics about the activity of this generator so far.
+	pub fn stats(&self) -> Stats {
+		self.task.header_ref().stats
+	}
+}
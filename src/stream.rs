@@ -0,0 +1,84 @@
+//! Conversion of generators into asynchronous streams.
+//!
+//! This module defines the [`IntoStream`] trait, which libraries working in
+//! asynchronous contexts may rely on to accept any of the generator types
+//! provided by this crate, and implements it for all of them.
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use crate::{Generator, Resume, ThreadGenerator};
+
+/// Conversion into a [`Stream`].
+pub trait IntoStream {
+	/// The type of the values in the stream.
+	type Item;
+	/// The stream being converted into.
+	type IntoStream: Stream<Item = Self::Item>;
+
+	/// Converts this value into a stream.
+	fn into_stream(self) -> Self::IntoStream;
+}
+
+impl<T: 'static> IntoStream for Generator<T> {
+	type Item = T;
+	type IntoStream = GeneratorStream<T>;
+
+	fn into_stream(self) -> GeneratorStream<T> {
+		GeneratorStream { gen: self }
+	}
+}
+
+impl<T: std::marker::Send + 'static> IntoStream for ThreadGenerator<T> {
+	type Item = T;
+	type IntoStream = ThreadStream<T>;
+
+	fn into_stream(self) -> ThreadStream<T> {
+		ThreadStream { gen: self }
+	}
+}
+
+/// A [`Generator`] as a [`Stream`].
+///
+/// The producer runs on the thread polling the stream, for as long as it takes
+/// it to yield the next value, so it should not block. Checkpoints reached by
+/// the producer with [`yield_now`] are reported as the stream not being ready,
+/// giving the executor a chance to run other tasks in between.
+///
+/// [`yield_now`]: crate::yield_now
+pub struct GeneratorStream<T: 'static> {
+	gen: Generator<T>,
+}
+/* Values are never pinned while they're in the generator. */
+impl<T: 'static> Unpin for GeneratorStream<T> {}
+impl<T: 'static> Stream for GeneratorStream<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		match self.get_mut().gen.resume() {
+			Resume::Value(value) => Poll::Ready(Some(value)),
+			Resume::Complete => Poll::Ready(None),
+			Resume::Pending => {
+				/* The producer is ready to carry on right away. */
+				cx.waker().wake_by_ref();
+				Poll::Pending
+			}
+		}
+	}
+}
+
+/// A [`ThreadGenerator`] as a [`Stream`].
+///
+/// The producer keeps running on its own thread. While there are no values
+/// ready, the stream hands its waker over to the producer thread, which wakes
+/// it up as soon as it sends the next value, or finishes, so an executor
+/// driving it never has to spin.
+pub struct ThreadStream<T: std::marker::Send + 'static> {
+	gen: ThreadGenerator<T>,
+}
+impl<T: std::marker::Send + 'static> Stream for ThreadStream<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		self.get_mut().gen.poll_value(cx)
+	}
+}
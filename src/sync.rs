@@ -0,0 +1,534 @@
+//! Synchronization primitives that know about yield points.
+//!
+//! Holding on to a lock across a yield is a classic source of deadlocks: the
+//! producer gets suspended with the lock held, and the consumer, or another
+//! producer driven by it, then tries to take the same lock on the same thread,
+//! and waits forever. The wrappers in this module keep track of how many of
+//! their guards each task is holding, and the crate checks that count every
+//! time a value is yielded, reacting according to the [`HeldGuardPolicy`] for
+//! the current thread.
+//!
+//! The tracking is only performed in builds with debug assertions enabled. In
+//! other builds, the wrappers behave just like the types they wrap.
+//!
+//! # Safe by Construction
+//! Alternatively, [`TaskCell`] and [`TaskMutex`] can be used to share state
+//! between iterations of a producer in a way that can never deadlock. Rather
+//! than relying on checks that only happen in debug builds, these types always
+//! poison themselves when a task yields while holding one of their guards, and
+//! any further attempt at accessing them fails immediately, rather than waiting
+//! for a guard that won't be released until the producer is resumed.
+use std::cell::{Cell, RefCell, Ref, RefMut};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LockResult, OnceLock, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError, TryLockResult};
+use crate::{try_current_header, TaskId};
+
+/// What to do when a task yields while holding tracked guards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum HeldGuardPolicy {
+	/// Panic at the yield point.
+	#[default]
+	Panic,
+	/// Log a warning, and carry on.
+	///
+	/// With the `debug-log` feature enabled, the warning goes through the log
+	/// crate, at the warn level, under the `yeet` target. Otherwise, it gets
+	/// printed to the standard error stream, unconditionally.
+	Log,
+	/// Carry on as if nothing had happened.
+	Ignore,
+}
+
+thread_local! {
+	/// The number of tracked guards being held by each task on this thread.
+	static HELD: RefCell<Vec<(TaskId, usize)>> = const { RefCell::new(Vec::new()) };
+	/// The policy for the current thread.
+	static POLICY: Cell<HeldGuardPolicy> = const { Cell::new(HeldGuardPolicy::Panic) };
+	/// The poison flags of the task-aware primitives that have guards alive,
+	/// along with the tasks holding the guards.
+	static POISONABLE: RefCell<Vec<(TaskId, Arc<AtomicBool>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sets what happens when a task on the current thread yields while holding
+/// tracked guards.
+pub fn set_held_guard_policy(policy: HeldGuardPolicy) {
+	POLICY.set(policy)
+}
+
+/// The number of tracked guards being held by the currently running task.
+///
+/// This is always zero outside of generators, and in builds without debug
+/// assertions.
+pub fn held_guards() -> usize {
+	let Some(task) = current_task() else { return 0 };
+	HELD.with_borrow(|held| {
+		held.iter()
+			.find(|(id, _)| *id == task)
+			.map(|(_, count)| *count)
+			.unwrap_or(0)
+	})
+}
+
+/// Checks that the current task may yield.
+pub(crate) fn check_yield() {
+	poison_held();
+	if !cfg!(debug_assertions) {
+		return
+	}
+
+	let held = held_guards();
+	if held == 0 {
+		return
+	}
+	match POLICY.get() {
+		HeldGuardPolicy::Panic =>
+			panic!("Tried to yield while holding {held} tracked lock guard(s)!"),
+		#[cfg(feature = "debug-log")]
+		HeldGuardPolicy::Log =>
+			log::warn!(target: "yeet", "yielding while holding {held} tracked lock guard(s)"),
+		#[cfg(not(feature = "debug-log"))]
+		HeldGuardPolicy::Log =>
+			eprintln!("warning: yielding while holding {held} tracked lock guard(s)"),
+		HeldGuardPolicy::Ignore => {}
+	}
+}
+
+/// The identifier of the currently running task, if there is one.
+fn current_task() -> Option<TaskId> {
+	try_current_header().map(|header| unsafe { (*header).id })
+}
+
+/// Accounts for a single guard being held by a task.
+///
+/// The count is kept per task identifier rather than in the task itself, so
+/// that guards that get moved between tasks are still accounted for correctly.
+struct Held(Option<TaskId>);
+impl Held {
+	/// Counts a new guard against the current task.
+	fn acquire() -> Self {
+		if !cfg!(debug_assertions) {
+			return Self(None)
+		}
+
+		let task = current_task();
+		if let Some(task) = task {
+			HELD.with_borrow_mut(|held| {
+				match held.iter_mut().find(|(id, _)| *id == task) {
+					Some((_, count)) => *count += 1,
+					None => held.push((task, 1)),
+				}
+			})
+		}
+
+		Self(task)
+	}
+}
+impl Drop for Held {
+	fn drop(&mut self) {
+		let Some(task) = self.0 else { return };
+		HELD.with_borrow_mut(|held| {
+			if let Some(index) = held.iter().position(|(id, _)| *id == task) {
+				held[index].1 -= 1;
+				if held[index].1 == 0 {
+					held.swap_remove(index);
+				}
+			}
+		})
+	}
+}
+
+/// Wraps a result from the standard library, attaching tracking to its guard.
+fn track<G, U>(result: LockResult<G>, wrap: impl FnOnce(G) -> U) -> LockResult<U> {
+	match result {
+		Ok(guard) => Ok(wrap(guard)),
+		Err(poison) => Err(PoisonError::new(wrap(poison.into_inner()))),
+	}
+}
+
+/// Wraps a result from the standard library, attaching tracking to its guard.
+fn track_try<G, U>(result: TryLockResult<G>, wrap: impl FnOnce(G) -> U) -> TryLockResult<U> {
+	match result {
+		Ok(guard) => Ok(wrap(guard)),
+		Err(TryLockError::Poisoned(poison)) =>
+			Err(TryLockError::Poisoned(PoisonError::new(wrap(poison.into_inner())))),
+		Err(TryLockError::WouldBlock) => Err(TryLockError::WouldBlock),
+	}
+}
+
+/// A [`Mutex`] whose guards are tracked across yield points.
+#[derive(Debug, Default)]
+pub struct TrackedMutex<T: ?Sized> {
+	inner: Mutex<T>,
+}
+impl<T> TrackedMutex<T> {
+	/// Creates a new mutex holding the given value.
+	pub const fn new(value: T) -> Self {
+		Self { inner: Mutex::new(value) }
+	}
+
+	/// Consumes the mutex, returning the value it holds.
+	pub fn into_inner(self) -> LockResult<T> {
+		self.inner.into_inner()
+	}
+}
+impl<T: ?Sized> TrackedMutex<T> {
+	/// Acquires the mutex, blocking until it is available. See [`Mutex::lock`].
+	pub fn lock(&self) -> LockResult<TrackedMutexGuard<'_, T>> {
+		track(self.inner.lock(), |guard| TrackedMutexGuard { guard, _held: Held::acquire() })
+	}
+
+	/// Attempts to acquire the mutex without blocking. See [`Mutex::try_lock`].
+	pub fn try_lock(&self) -> TryLockResult<TrackedMutexGuard<'_, T>> {
+		track_try(self.inner.try_lock(), |guard| TrackedMutexGuard { guard, _held: Held::acquire() })
+	}
+
+	/// Returns a mutable reference to the value, which requires no locking.
+	pub fn get_mut(&mut self) -> LockResult<&mut T> {
+		self.inner.get_mut()
+	}
+}
+
+/// Guard of a [`TrackedMutex`].
+pub struct TrackedMutexGuard<'a, T: ?Sized> {
+	guard: MutexGuard<'a, T>,
+	_held: Held,
+}
+impl<T: ?Sized> Deref for TrackedMutexGuard<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.guard
+	}
+}
+impl<T: ?Sized> DerefMut for TrackedMutexGuard<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.guard
+	}
+}
+
+/// A [`RwLock`] whose guards are tracked across yield points.
+#[derive(Debug, Default)]
+pub struct TrackedRwLock<T: ?Sized> {
+	inner: RwLock<T>,
+}
+impl<T> TrackedRwLock<T> {
+	/// Creates a new lock holding the given value.
+	pub const fn new(value: T) -> Self {
+		Self { inner: RwLock::new(value) }
+	}
+
+	/// Consumes the lock, returning the value it holds.
+	pub fn into_inner(self) -> LockResult<T> {
+		self.inner.into_inner()
+	}
+}
+impl<T: ?Sized> TrackedRwLock<T> {
+	/// Acquires shared access to the lock. See [`RwLock::read`].
+	pub fn read(&self) -> LockResult<TrackedReadGuard<'_, T>> {
+		track(self.inner.read(), |guard| TrackedReadGuard { guard, _held: Held::acquire() })
+	}
+
+	/// Acquires exclusive access to the lock. See [`RwLock::write`].
+	pub fn write(&self) -> LockResult<TrackedWriteGuard<'_, T>> {
+		track(self.inner.write(), |guard| TrackedWriteGuard { guard, _held: Held::acquire() })
+	}
+
+	/// Returns a mutable reference to the value, which requires no locking.
+	pub fn get_mut(&mut self) -> LockResult<&mut T> {
+		self.inner.get_mut()
+	}
+}
+
+/// Shared guard of a [`TrackedRwLock`].
+pub struct TrackedReadGuard<'a, T: ?Sized> {
+	guard: RwLockReadGuard<'a, T>,
+	_held: Held,
+}
+impl<T: ?Sized> Deref for TrackedReadGuard<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.guard
+	}
+}
+
+/// Exclusive guard of a [`TrackedRwLock`].
+pub struct TrackedWriteGuard<'a, T: ?Sized> {
+	guard: RwLockWriteGuard<'a, T>,
+	_held: Held,
+}
+impl<T: ?Sized> Deref for TrackedWriteGuard<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.guard
+	}
+}
+impl<T: ?Sized> DerefMut for TrackedWriteGuard<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.guard
+	}
+}
+
+/// Poisons all of the task-aware primitives with guards held by the current
+/// task.
+fn poison_held() {
+	if POISONABLE.with_borrow(Vec::is_empty) {
+		return
+	}
+
+	let Some(task) = current_task() else { return };
+	POISONABLE.with_borrow_mut(|poisonable| {
+		/* Guards that got forgotten never take their flags out, and once the
+		 * primitive is gone as well, nothing but us is left holding them. */
+		poisonable.retain(|(_, flag)| Arc::strong_count(flag) > 1);
+		for (_, flag) in poisonable.iter().filter(|(id, _)| *id == task) {
+			flag.store(true, Ordering::Release)
+		}
+	})
+}
+
+/// The poison flag of a task-aware primitive.
+///
+/// The flag gets shared with the registrations of the guards, which may outlive
+/// the primitive if the guards get forgotten, so it lives on the heap. It only
+/// gets allocated once a guard is taken inside of a task, which lets the
+/// primitives be created in constant contexts.
+#[derive(Debug, Default)]
+struct PoisonFlag(OnceLock<Arc<AtomicBool>>);
+impl PoisonFlag {
+	/// Creates a new flag, which is not set.
+	const fn new() -> Self {
+		Self(OnceLock::new())
+	}
+
+	/// Whether the flag is set.
+	fn get(&self) -> bool {
+		self.0.get().is_some_and(|flag| flag.load(Ordering::Acquire))
+	}
+
+	/// Clears the flag.
+	fn clear(&self) {
+		if let Some(flag) = self.0.get() {
+			flag.store(false, Ordering::Release)
+		}
+	}
+}
+
+/// Registers the poison flag of a task-aware primitive for as long as one of
+/// its guards is alive.
+///
+/// The registration only remembers where the flag is, to find it again, so that
+/// forgetting the guard doesn't keep the flag alive.
+struct Poisonable(Option<(TaskId, *const AtomicBool)>);
+impl Poisonable {
+	/// Registers the given flag against the current task.
+	fn register(flag: &PoisonFlag) -> Self {
+		let Some(task) = current_task() else { return Self(None) };
+		let flag = flag.0.get_or_init(|| Arc::new(AtomicBool::new(false)));
+		let entry = (task, Arc::as_ptr(flag));
+		POISONABLE.with_borrow_mut(|poisonable| poisonable.push((task, flag.clone())));
+
+		Self(Some(entry))
+	}
+}
+impl Drop for Poisonable {
+	fn drop(&mut self) {
+		let Some((task, flag)) = self.0 else { return };
+		POISONABLE.with_borrow_mut(|poisonable| {
+			let index = poisonable.iter()
+				.rposition(|(id, other)| *id == task && Arc::as_ptr(other) == flag);
+			if let Some(index) = index {
+				poisonable.swap_remove(index);
+			}
+		})
+	}
+}
+
+/// Error returned when accessing a task-aware primitive that has been poisoned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Poisoned;
+impl fmt::Display for Poisoned {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("guard was held across a yield point or a panic")
+	}
+}
+impl std::error::Error for Poisoned {}
+
+/// Error returned when borrowing a [`TaskCell`] fails.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BorrowError {
+	/// The cell has been poisoned.
+	Poisoned,
+	/// The cell is already borrowed in a way that conflicts with the request.
+	Borrowed,
+}
+impl fmt::Display for BorrowError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BorrowError::Poisoned => fmt::Display::fmt(&Poisoned, f),
+			BorrowError::Borrowed => f.write_str("cell is already borrowed"),
+		}
+	}
+}
+impl std::error::Error for BorrowError {}
+
+/// A [`RefCell`] that gets poisoned if a task yields while borrowing it.
+#[derive(Debug, Default)]
+pub struct TaskCell<T: ?Sized> {
+	poisoned: PoisonFlag,
+	inner: RefCell<T>,
+}
+impl<T> TaskCell<T> {
+	/// Creates a new cell holding the given value.
+	pub const fn new(value: T) -> Self {
+		Self {
+			poisoned: PoisonFlag::new(),
+			inner: RefCell::new(value),
+		}
+	}
+
+	/// Consumes the cell, returning the value it holds, regardless of whether
+	/// it has been poisoned.
+	pub fn into_inner(self) -> T {
+		self.inner.into_inner()
+	}
+}
+impl<T: ?Sized> TaskCell<T> {
+	/// Borrows the value immutably.
+	pub fn borrow(&self) -> Result<TaskRef<'_, T>, BorrowError> {
+		if self.is_poisoned() {
+			return Err(BorrowError::Poisoned)
+		}
+		let inner = self.inner.try_borrow().map_err(|_| BorrowError::Borrowed)?;
+
+		Ok(TaskRef { inner, _poisonable: Poisonable::register(&self.poisoned) })
+	}
+
+	/// Borrows the value mutably.
+	pub fn borrow_mut(&self) -> Result<TaskRefMut<'_, T>, BorrowError> {
+		if self.is_poisoned() {
+			return Err(BorrowError::Poisoned)
+		}
+		let inner = self.inner.try_borrow_mut().map_err(|_| BorrowError::Borrowed)?;
+
+		Ok(TaskRefMut { inner, _poisonable: Poisonable::register(&self.poisoned) })
+	}
+
+	/// Whether a task has yielded while borrowing this cell.
+	pub fn is_poisoned(&self) -> bool {
+		self.poisoned.get()
+	}
+
+	/// Clears the poisoned state of this cell.
+	pub fn clear_poison(&self) {
+		self.poisoned.clear()
+	}
+
+	/// Returns a mutable reference to the value, which requires no borrowing.
+	pub fn get_mut(&mut self) -> &mut T {
+		self.inner.get_mut()
+	}
+}
+
+/// Immutable borrow of a [`TaskCell`].
+pub struct TaskRef<'a, T: ?Sized> {
+	inner: Ref<'a, T>,
+	_poisonable: Poisonable,
+}
+impl<T: ?Sized> Deref for TaskRef<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.inner
+	}
+}
+
+/// Mutable borrow of a [`TaskCell`].
+pub struct TaskRefMut<'a, T: ?Sized> {
+	inner: RefMut<'a, T>,
+	_poisonable: Poisonable,
+}
+impl<T: ?Sized> Deref for TaskRefMut<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.inner
+	}
+}
+impl<T: ?Sized> DerefMut for TaskRefMut<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.inner
+	}
+}
+
+/// A [`Mutex`] that gets poisoned if a task yields while holding its guard.
+///
+/// Unlike with [`Mutex`], attempting to lock a poisoned [`TaskMutex`] fails
+/// immediately, without waiting for the lock to be released, as the task that
+/// holds the guard might never get to release it.
+#[derive(Debug, Default)]
+pub struct TaskMutex<T: ?Sized> {
+	poisoned: PoisonFlag,
+	inner: Mutex<T>,
+}
+impl<T> TaskMutex<T> {
+	/// Creates a new mutex holding the given value.
+	pub const fn new(value: T) -> Self {
+		Self {
+			poisoned: PoisonFlag::new(),
+			inner: Mutex::new(value),
+		}
+	}
+
+	/// Consumes the mutex, returning the value it holds, regardless of whether
+	/// it has been poisoned.
+	pub fn into_inner(self) -> T {
+		self.inner.into_inner().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+impl<T: ?Sized> TaskMutex<T> {
+	/// Acquires the mutex, blocking until it is available.
+	///
+	/// Fails if a task has yielded while holding the mutex, or if a thread
+	/// has panicked while holding it.
+	pub fn lock(&self) -> Result<TaskMutexGuard<'_, T>, Poisoned> {
+		if self.is_poisoned() {
+			return Err(Poisoned)
+		}
+		let inner = self.inner.lock().map_err(|_| Poisoned)?;
+
+		Ok(TaskMutexGuard { inner, _poisonable: Poisonable::register(&self.poisoned) })
+	}
+
+	/// Whether a task has yielded while holding this mutex, or a thread has
+	/// panicked while holding it.
+	pub fn is_poisoned(&self) -> bool {
+		self.poisoned.get() || self.inner.is_poisoned()
+	}
+
+	/// Clears the poisoned state of this mutex.
+	pub fn clear_poison(&self) {
+		self.poisoned.clear();
+		self.inner.clear_poison();
+	}
+
+	/// Returns a mutable reference to the value, which requires no locking.
+	pub fn get_mut(&mut self) -> &mut T {
+		self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+/// Guard of a [`TaskMutex`].
+pub struct TaskMutexGuard<'a, T: ?Sized> {
+	inner: MutexGuard<'a, T>,
+	_poisonable: Poisonable,
+}
+impl<T: ?Sized> Deref for TaskMutexGuard<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		&self.inner
+	}
+}
+impl<T: ?Sized> DerefMut for TaskMutexGuard<'_, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.inner
+	}
+}
@@ -1,5 +1,6 @@
-use std::arch::{asm, global_asm};
-use crate::sys::{PageAlign, Task};
+use std::arch::naked_asm;
+use crate::registry::SavedContext;
+use crate::sys::Task;
 
 /// Contains the register state of a given coroutine at the time of a context
 /// switch.
@@ -13,6 +14,9 @@ struct SnapshotUnaligned {
 	regs: [u64; 32],
 	pc: u64,
 	sp: u64,
+	/// The lower halves of V8 through V15, which are the only parts of the
+	/// vector registers the callee has to preserve.
+	fp_regs: [u64; 8],
 }
 
 /// Adds an alignment requirement to [`SnapshotUnaligned`] that allows instances
@@ -26,111 +30,123 @@ unsafe extern "C" fn abi_wrap_generator_start<T: 'static>(task: *mut Task<T>) ->
 	super::generator_start(task)
 } 
 
+/// Known-ABI wrapping for [`super::context_start`].
+unsafe extern "C" fn abi_wrap_context_start(arg: *mut ()) -> ! {
+	super::context_start(arg)
+}
+
 /// See [`super::start`].
 pub unsafe fn impl_start<T: 'static>(task: *mut Task<T>) {
-	let tx_snap = (*task).tx_snap.as_mut_ptr();
+	/* Set the PC to the proper specialization of `_generator_start`. Its
+	 * argument is the task pointer handed over by the first switch. */
+	prepare(
+		(*task).tx_snap.as_mut_ptr(),
+		(*task).stack.top(),
+		abi_wrap_generator_start::<T> as *const () as usize as u64,
+		super::return_address(&(*task).header));
+}
+
+/// See [`super::prepare_context`].
+pub unsafe fn impl_prepare_context(snap: *mut Snapshot, top: usize) {
+	prepare(snap, top, abi_wrap_context_start as *const () as usize as u64, 0)
+}
+
+/// Sets up the given snapshot to start running the function at the given
+/// address, with the stack starting off from the given address, as if it had
+/// been called from the given return address.
+unsafe fn prepare(snap: *mut Snapshot, top: usize, pc: u64, ret: u64) {
+	/* Stacks may be aligned to anything, but the top they hand out always
+	 * satisfies the ABI. */
+	debug_assert_eq!(top % 16, 0, "Misaligned top of stack");
 
-	/* Set SP to the top of the stack region in the task. */
-	(&raw mut (*tx_snap).0.sp)
-		.write_unaligned(((*task).stack.as_ptr() as usize + (*task).stack.len() * size_of::<PageAlign>()) as u64);
+	(&raw mut (*snap).0.sp).write_unaligned(top as u64);
+	(&raw mut (*snap).0.pc).write_unaligned(pc);
 
-	/* Set the PC to the proper specialization of `_generator_start`. */
-	(&raw mut (*tx_snap).0.pc)
-		.write_unaligned(abi_wrap_generator_start::<T> as usize as u64);
+	/* Start with no frame to unwind into. */
+	(&raw mut (*snap).0.regs[29]).write_unaligned(0);
+	(&raw mut (*snap).0.regs[30]).write_unaligned(ret);
+}
 
-	/* Set the first argument of `_generator_start` to this generator instance. */
-	(&raw mut (*tx_snap).0.regs[0])
-		.write_unaligned(task as usize as u64);
+/// See [`super::saved_context`].
+pub unsafe fn impl_saved_context<T>(task: &Task<T>) -> Option<SavedContext> {
+	let tx_snap = task.tx_snap.as_ptr();
+	Some(SavedContext {
+		pc: (&raw const (*tx_snap).0.pc).read_unaligned() as usize,
+		sp: (&raw const (*tx_snap).0.sp).read_unaligned() as usize,
+		fp: (&raw const (*tx_snap).0.regs[29]).read_unaligned() as usize,
+	})
 }
 
-global_asm!(r#"
-	.global arm64_do_switch_ctx
-arm64_do_switch_ctx:
-	/* Save X30 as the program counter in the `from` snapshot. */
-	STR X30, [X1, #256]
-	
-	/* Load the context of the `to` snapshot. */
-	LDR      X3,  [X2, #24]
-	LDP X4,  X5,  [X2, #32]
-	LDP X6,  X7,  [X2, #48]
-	LDP X8,  X9,  [X2, #64]
-	LDP X10, X11, [X2, #80]
-	LDP X12, X13, [X2, #96]
-	LDP X14, X15, [X2, #112]
-	LDP X16, X17, [X2, #128]
-	LDP X18, X19, [X2, #144]
-	LDP X20, X21, [X2, #160]
-	LDP X22, X23, [X2, #176]
-	LDP X24, X25, [X2, #192]
-	LDP X26, X27, [X2, #208]
-	LDP X28, X29, [X2, #224]
-	LDP X30, X31, [X2, #240]
-
-	/* Load the return address into IP0. */
-	LDR X16, [X2, #256]
-
-	/* Load the stack pointer. */
-	LDR X1, [X2, #264]
-	MOV SP, X1
-
-	/* Jump to resume execution. */
-	BR X16
-"#);
+/// Switches from the context being saved into `from` over to the context saved
+/// in `to`, handing `task` over to the other side.
+///
+/// This follows the AAPCS64 calling convention, so only the registers it has
+/// the callee preserve need saving, and the compiler takes care of everything
+/// else at the call site. The other side resumes by returning from its own
+/// call to this function, with `task` as the return value, or, if it is only
+/// just starting, by entering its start function, with `task` as its argument.
+#[unsafe(naked)]
+unsafe extern "C" fn switch(task: *mut (), from: *mut Snapshot, to: *const Snapshot) -> *mut () {
+	naked_asm!(r#"
+		/* Populate the origin snapshot structure. The origin resumes by
+		 * returning to our caller. */
+		STP X19, X20, [X1, #152]
+		STP X21, X22, [X1, #168]
+		STP X23, X24, [X1, #184]
+		STP X25, X26, [X1, #200]
+		STP X27, X28, [X1, #216]
+		STR X29,      [X1, #232]
+		STR X30,      [X1, #256]
+		MOV X3, SP
+		STR X3,       [X1, #264]
+		STP D8,  D9,  [X1, #272]
+		STP D10, D11, [X1, #288]
+		STP D12, D13, [X1, #304]
+		STP D14, D15, [X1, #320]
+
+		/* Load the context of the `to` snapshot. */
+		LDP X19, X20, [X2, #152]
+		LDP X21, X22, [X2, #168]
+		LDP X23, X24, [X2, #184]
+		LDP X25, X26, [X2, #200]
+		LDP X27, X28, [X2, #216]
+		LDR X29,      [X2, #232]
+		LDR X3,       [X2, #264]
+		MOV SP, X3
+		LDP D8,  D9,  [X2, #272]
+		LDP D10, D11, [X2, #288]
+		LDP D12, D13, [X2, #304]
+		LDP D14, D15, [X2, #320]
+
+		/* Resume execution, handing the task over in X0, which is both the
+		 * return value and the first argument. The link register only matters
+		 * to contexts that are starting, and is clobbered by the call for
+		 * those that are resuming. */
+		LDR X30,      [X2, #240]
+		LDR X16,      [X2, #256]
+		BR X16
+	"#)
+}
 
 /// See [`super::switch_ctx`].
-pub unsafe fn impl_switch_ctx<T>(mut task: *mut Task<T>, yi: bool) -> *mut Task<T> {
-	let (mut to, mut from) = if !yi {
+pub unsafe fn impl_switch_ctx<T>(task: *mut Task<T>, yi: bool) -> *mut Task<T> {
+	let (to, from) = if !yi {
 		(
-			(*task).tx_snap.as_mut_ptr(),
+			(*task).tx_snap.as_ptr(),
 			(*task).rx_snap.as_mut_ptr(),
 		)
 	} else {
 		(
-			(*task).rx_snap.as_mut_ptr(),
+			(*task).rx_snap.as_ptr(),
 			(*task).tx_snap.as_mut_ptr(),
 		)
 	};
 
-	asm!(
-		r#"
-			/* Populate the origin snapshot structure. */
-			STR      X3,  [X1, #24]
-			STP X4,  X5,  [X1, #32]
-			STP X6,  X7,  [X1, #48]
-			STP X8,  X9,  [X1, #64]
-			STP X10, X11, [X1, #80]
-			STP X12, X13, [X1, #96]
-			STP X14, X15, [X1, #112]
-			STP X16, X17, [X1, #128]
-			STP X18, X19, [X1, #144]
-			STP X20, X21, [X1, #160]
-			STP X22, X23, [X1, #176]
-			STP X24, X25, [X1, #192]
-			STP X26, X27, [X1, #208]
-			STP X28, X29, [X1, #224]
-			STP X30, X31, [X1, #240]
-
-			/* Store the stack pointer. */
-			MOV X3, SP
-			STR X3, [X1, #264]
-
-			/* Call the second half of the context switch function, which both
-			 * restores most of the context of the `to` function and makes
-			 * preparations a resume to return after the BL. */
-			BL arm64_do_switch_ctx
-
-			/* X16 is still clobbered at this point. Restore it. */
-			LDR X16, [X2, #128]
-		"#,
-		inout("x0") task,
-		inout("x1") from,
-		inout("x2") to,
-	);
-
-	let _ = from;
-	let _ = to;
-
 	/* Return the new pointer to be used for the task if this was a yield. */
-	task
+	switch(task as *mut (), from, to) as *mut Task<T>
 }
 
+/// See [`super::switch_context`].
+pub unsafe fn impl_switch_context(arg: *mut (), from: *mut Snapshot, to: *const Snapshot) -> *mut () {
+	switch(arg, from, to)
+}
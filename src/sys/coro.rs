@@ -0,0 +1,98 @@
+use std::num::NonZeroUsize;
+use corosensei::{Coroutine, CoroutineResult, Yielder};
+use corosensei::stack::{Stack, StackPointer};
+use crate::registry::SavedContext;
+use crate::sys::Task;
+
+/// Contains the state of the producer side of a task.
+///
+/// Unlike the assembly backends, this backend doesn't need to keep snapshots of
+/// the consumer task, as [`corosensei`] saves the state of the resuming side on
+/// the stack of the coroutine. Only the producer snapshot is ever used.
+///
+/// The task pointer is passed in as the resume value of the coroutine, and the
+/// producer gets a new one every time it gets resumed, just like with the
+/// assembly backends.
+pub struct Snapshot {
+	/// The coroutine running the producer.
+	///
+	/// This never gets dropped, as it would force the coroutine to unwind. The
+	/// producer is never left in a state where it would need to be unwound by
+	/// the time the task goes away, so forgetting about it is fine.
+	coroutine: Coroutine<usize, (), (), TaskStack>,
+	/// The yielder of the coroutine, which lives on the stack of the producer.
+	yielder: *const Yielder<usize, ()>,
+}
+
+/// View of the stack region of a task in the form [`corosensei`] expects.
+///
+/// The region itself is owned by the [`Task`], which outlives the coroutine.
+struct TaskStack {
+	/// The highest address in the stack, where it starts.
+	top: usize,
+	/// The lowest address in the stack, past which it must not grow.
+	limit: usize,
+}
+unsafe impl Stack for TaskStack {
+	fn base(&self) -> StackPointer {
+		NonZeroUsize::new(self.top).unwrap()
+	}
+
+	fn limit(&self) -> StackPointer {
+		NonZeroUsize::new(self.limit).unwrap()
+	}
+
+	#[cfg(windows)]
+	fn teb_fields(&self) -> corosensei::stack::StackTebFields {
+		corosensei::stack::StackTebFields {
+			StackBase: self.top,
+			StackLimit: self.limit,
+			DeallocationStack: self.limit,
+			GuaranteedStackBytes: 0,
+		}
+	}
+
+	#[cfg(windows)]
+	fn update_teb_fields(&mut self, _: usize, _: usize) {}
+}
+
+/// See [`super::start`].
+pub unsafe fn impl_start<T: 'static>(task: *mut Task<T>) {
+	let stack = TaskStack {
+		top: (*task).stack.top(),
+		limit: (*task).stack.base(),
+	};
+	let coroutine = Coroutine::with_stack(stack, |yielder: &Yielder<usize, ()>, task: usize| {
+		let task = task as *mut Task<T>;
+
+		/* The yielder never moves for as long as the coroutine runs. */
+		(*task).tx_snap.assume_init_mut().yielder = yielder;
+		super::generator_start(task)
+	});
+
+	(*task).tx_snap.write(Snapshot {
+		coroutine,
+		yielder: std::ptr::null(),
+	});
+}
+
+/// See [`super::saved_context`].
+///
+/// The state of the producer is kept by [`corosensei`] in a format we don't
+/// know about, so there is nothing we can report.
+pub unsafe fn impl_saved_context<T>(_: &Task<T>) -> Option<SavedContext> {
+	None
+}
+
+/// See [`super::switch_ctx`].
+pub unsafe fn impl_switch_ctx<T>(task: *mut Task<T>, yi: bool) -> *mut Task<T> {
+	if !yi {
+		match (*task).tx_snap.assume_init_mut().coroutine.resume(task as usize) {
+			CoroutineResult::Yield(()) => task,
+			CoroutineResult::Return(()) => unreachable!("generator tasks never return"),
+		}
+	} else {
+		let yielder = (*task).tx_snap.assume_init_ref().yielder;
+		(*yielder).suspend(()) as *mut Task<T>
+	}
+}
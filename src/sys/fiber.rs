@@ -0,0 +1,132 @@
+use std::arch::asm;
+use std::cell::Cell;
+use std::ffi::c_void;
+use windows_sys::Win32::System::Threading::{
+	ConvertFiberToThread,
+	ConvertThreadToFiber,
+	CreateFiberEx,
+	IsThreadAFiber,
+	SwitchToFiber,
+};
+use crate::registry::SavedContext;
+use crate::sys::{Stack, Task};
+
+/// Contains the fiber a side of a task runs on.
+///
+/// The producer fiber is owned by the [`Stack`] of the task, and the consumer
+/// fiber is whichever fiber was running when the task got entered, which may be
+/// the producer fiber of another task.
+pub struct Snapshot {
+	/// The fiber to switch to in order to resume this side of the task.
+	fiber: *mut c_void,
+}
+
+thread_local! {
+	/// The task most recently entered on this thread.
+	///
+	/// Fibers are handed a parameter once, when they are created, but the task
+	/// may have moved by the time the producer gets resumed, so the consumer
+	/// passes the pointer along through here on every switch.
+	static TASK: Cell<usize> = const { Cell::new(0) };
+
+	/// Turns this thread back from a fiber when it exits, if we were the ones
+	/// to turn it into one.
+	static CONVERTED: Converted = const { Converted(Cell::new(false)) };
+}
+
+/// Guard turning the thread it belongs to back from a fiber.
+struct Converted(Cell<bool>);
+impl Drop for Converted {
+	fn drop(&mut self) {
+		if self.0.get() {
+			unsafe { ConvertFiberToThread(); }
+		}
+	}
+}
+
+/// Reads the field at the given offset in the thread information block of the
+/// current thread.
+unsafe fn tib(offset: usize) -> usize {
+	let value: usize;
+	#[cfg(target_arch = "x86_64")]
+	asm!("mov {}, gs:[{}]", out(reg) value, in(reg) offset, options(nostack, readonly, preserves_flags));
+	#[cfg(target_arch = "aarch64")]
+	asm!("ldr {}, [x18, {}]", out(reg) value, in(reg) offset, options(nostack, readonly, preserves_flags));
+	value
+}
+
+/// The fiber currently running on this thread.
+///
+/// This is what `GetCurrentFiber` does in the Windows headers, which has no
+/// exported symbol we could link against.
+///
+/// # Safety
+/// The current thread must have been turned into a fiber.
+unsafe fn current_fiber() -> *mut c_void {
+	tib(0x20) as *mut c_void
+}
+
+/// The fiber currently running on this thread, turning the thread into one if
+/// it isn't one yet.
+unsafe fn this_fiber() -> *mut c_void {
+	if IsThreadAFiber() != 0 {
+		return current_fiber()
+	}
+
+	let fiber = ConvertThreadToFiber(std::ptr::null());
+	if fiber.is_null() {
+		panic!("Could not turn the thread into a fiber: {}", std::io::Error::last_os_error())
+	}
+	CONVERTED.with(|converted| converted.0.set(true));
+
+	fiber
+}
+
+/// Entry point of the producer fiber of tasks yielding values of type `T`.
+unsafe extern "system" fn fiber_start<T: 'static>(_: *mut c_void) {
+	let task = TASK.get() as *mut Task<T>;
+
+	/* Now that the fiber runs, we know where the system put its stack. */
+	if let Stack::Fiber { top, .. } = &mut (*task).stack {
+		*top = tib(0x08);
+	}
+
+	super::generator_start(task)
+}
+
+/// See [`super::start`].
+///
+/// Whatever stack the task was given gets replaced with one of the same size,
+/// allocated by the system along with the fiber.
+pub unsafe fn impl_start<T: 'static>(task: *mut Task<T>) {
+	let len = (*task).stack.len();
+	let fiber = CreateFiberEx(0, len, 0, Some(fiber_start::<T>), std::ptr::null());
+	if fiber.is_null() {
+		panic!("Could not create a fiber with a {len} byte stack: {}", std::io::Error::last_os_error())
+	}
+
+	(*task).stack = Stack::Fiber { fiber, top: 0, len };
+	(*task).tx_snap.write(Snapshot { fiber });
+}
+
+/// See [`super::saved_context`].
+///
+/// The registers of a fiber are saved by the system in a structure we don't
+/// get to look into, so there is nothing we can report.
+pub unsafe fn impl_saved_context<T>(_: &Task<T>) -> Option<SavedContext> {
+	None
+}
+
+/// See [`super::switch_ctx`].
+pub unsafe fn impl_switch_ctx<T>(task: *mut Task<T>, yi: bool) -> *mut Task<T> {
+	if !yi {
+		(*task).rx_snap.write(Snapshot { fiber: this_fiber() });
+		TASK.set(task as usize);
+		SwitchToFiber((*task).tx_snap.assume_init_ref().fiber);
+
+		task
+	} else {
+		SwitchToFiber((*task).rx_snap.assume_init_ref().fiber);
+		TASK.get() as *mut Task<T>
+	}
+}
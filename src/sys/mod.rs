@@ -1,21 +1,67 @@
+use std::any::{Any, TypeId};
 use std::mem::MaybeUninit;
 use std::panic::AssertUnwindSafe;
-use std::pin::Pin;
-use crate::{Send, Yield, yield_internal};
+use crate::{Send, TaskId, Yield, yield_internal, yield_to};
+use crate::adapt::Batch;
+use crate::cancel::{CancelBound, CancelToken, Cancelled, Overrun};
+use crate::hook::LocalHook;
+use crate::leak::Census;
+use crate::lend::Lend;
+use crate::registry::{Record, SavedContext, TaskState};
+use crate::shared::SharedState;
+use crate::sys::signal_stack::SignalStack;
+use crate::stats::Stats;
+use std::ops::Range;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
-#[cfg(target_arch = "x86_64")]
+pub use stack::{Canary, Stack, DEFAULT_STACK_SIZE, MIN_STACK_SIZE};
+
+mod stack;
+pub mod signal_stack;
+pub mod thread_name;
+
+/* Backends are picked in order of preference: corosensei and fibers when they
+ * are asked for, then ucontext when it is asked for or when there is no port of
+ * our own context switching code to the target, and our own code otherwise. */
+#[cfg(feature = "corosensei")]
+mod coro;
+#[cfg(feature = "corosensei")]
+use coro as _sys;
+
+#[cfg(all(not(feature = "corosensei"), windows, feature = "fibers"))]
+mod fiber;
+#[cfg(all(not(feature = "corosensei"), windows, feature = "fibers"))]
+use fiber as _sys;
+
+#[cfg(all(not(feature = "corosensei"), unix, any(feature = "ucontext", not(any(target_arch = "x86_64", target_arch = "aarch64")))))]
+mod ucontext;
+#[cfg(all(not(feature = "corosensei"), unix, any(feature = "ucontext", not(any(target_arch = "x86_64", target_arch = "aarch64")))))]
+use ucontext as _sys;
+
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), target_arch = "x86_64"))]
 mod x64;
-#[cfg(target_arch = "x86_64")]
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), target_arch = "x86_64"))]
 use x64 as _sys;
 
-#[cfg(target_arch = "aarch64")]
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), target_arch = "aarch64"))]
 mod arm64;
-#[cfg(target_arch = "aarch64")]
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), target_arch = "aarch64"))]
 use arm64 as _sys;
 
 /// Every generator comprises a consumer task and a producer task, with a
 /// channel for sending data from one to the other. This structure provides the
 /// storage for that data.
+///
+/// Tasks live inline in their [`Generator`], snapshots included, rather than
+/// behind a pointer, so the stack region is the only allocation a task needs.
+/// Nothing in here may be pointed to across switches, as the generator, and
+/// the task along with it, may move between resumes. The snapshot of the
+/// consumer, in particular, gets written anew every time the task is entered.
+///
+/// [`Generator`]: crate::Generator
 #[repr(C)]
 pub struct Task<T> {
 	/// Storage for the context snapshot of the consumer task.
@@ -27,11 +73,410 @@ pub struct Task<T> {
 	/// Storage for the data being sent from consumer to producer.
 	data_in: MaybeUninit<Send>,
 	/// Storage for the generator function that we want to execute.
-	func: Option<fn()>,
+	func: Option<Entry>,
 	/// Stack region that belongs to the generator.
-	stack: Pin<Box<[PageAlign]>>,
+	stack: Stack,
 	/// Whether this task has already been started.
 	started: bool,
+	/// State of the task that does not depend on the type of its values.
+	header: Header,
+	/// Predicate deciding which values yielded by the producer get handed to
+	/// the consumer.
+	pub filter: Option<Filter<T>>,
+	/// Function mapping values of another type yielded by the producer into
+	/// values of the type expected by the consumer, along with that type.
+	pub map: Option<(TypeId, Map<T>)>,
+	/// Values yielded by the producer that are waiting to be picked up by the
+	/// consumer all at once.
+	pub batch: Option<Batch<T>>,
+	/// Marker telling live tasks apart from dead ones, and tasks yielding
+	/// values of this type apart from tasks yielding values of any other.
+	#[cfg(debug_assertions)]
+	magic: u64,
+	/// The number of times this task has been entered.
+	#[cfg(debug_assertions)]
+	generation: u64,
+}
+
+/// Marker carried by live tasks, before it gets mixed with their value type.
+#[cfg(debug_assertions)]
+const MAGIC: u64 = 0x7965_6574_7461_736b;
+
+/// Marker carried by tasks that have been dropped.
+#[cfg(debug_assertions)]
+const DEAD: u64 = 0xdead_7965_6574_dead;
+
+/// The marker carried by live tasks yielding values of type `T`.
+#[cfg(debug_assertions)]
+fn magic<T>() -> u64 {
+	/* FNV-1a over the name of the type. */
+	std::any::type_name::<T>().bytes()
+		.fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+		^ MAGIC
+}
+impl<T> Task<T> {
+	/// The unique identifier of this task.
+	pub fn id(&self) -> TaskId {
+		self.header.id
+	}
+
+	/// The type-erased state of this task.
+	pub fn header_ref(&self) -> &Header {
+		&self.header
+	}
+
+	/// Records how deep the stack of the producer currently is.
+	///
+	/// This must only be called from the producer side of this task. Builds
+	/// with the `small` feature don't keep track of it.
+	#[inline(always)]
+	pub fn note_stack_depth(&mut self) {
+		if cfg!(feature = "small") {
+			return
+		}
+
+		let marker = 0u8;
+		let depth = self.stack.top().saturating_sub(&marker as *const u8 as usize);
+		self.header.stats.stack_used = self.header.stats.stack_used.max(depth);
+	}
+
+	/// Leaks the stack of this task, along with everything the producer has
+	/// on it, which never gets dropped.
+	pub fn leak_stack(&mut self) {
+		self.stack.leak()
+	}
+
+	/// The range of addresses spanned by the stack of this task.
+	pub fn stack_bounds(&self) -> Range<usize> {
+		self.stack.base()..self.stack.base() + self.stack.len()
+	}
+
+	/// The lowest address on the stack of this task the producer may use.
+	pub fn stack_floor(&self) -> usize {
+		self.stack.floor()
+	}
+
+	/// Checks that the producer hasn't written over either end of its stack.
+	pub fn check_canaries(&self) -> Result<(), Canary> {
+		self.stack.check_canaries()
+	}
+
+	/// Checks that this is a live task yielding values of type `T`, catching
+	/// dangling and mistyped task pointers before they get used.
+	///
+	/// # Panic
+	/// In debug builds, this function panics if the check fails. It does
+	/// nothing otherwise.
+	#[inline(always)]
+	pub fn validate(&self, action: &str) {
+		#[cfg(debug_assertions)]
+		match self.magic {
+			magic if magic == self::magic::<T>() => {}
+			DEAD => panic!("Tried to {action} a task that has already been dropped!"),
+			_ => panic!("Tried to {action} a task that is either corrupted or not of type {}!", std::any::type_name::<T>()),
+		}
+		#[cfg(not(debug_assertions))]
+		let _ = action;
+	}
+
+	/// Marks this task as dropped, so that any pointers to it that are left
+	/// get caught if they are ever used, for as long as the memory it lived in
+	/// doesn't get reused.
+	pub fn kill(&mut self) {
+		#[cfg(debug_assertions)]
+		{
+			self.magic = DEAD;
+		}
+	}
+}
+
+/// State associated with a task that does not depend on the type of the values
+/// being yielded by it.
+///
+/// Most of the operations that a producer may perform, other than yielding a
+/// value, have no way of knowing the type of the values the consumer expects.
+/// Those operations work through this structure instead of through [`Task`].
+pub struct Header {
+	/// The unique identifier of this task.
+	pub id: TaskId,
+	/// The name of this task, if it was given one.
+	pub name: Option<Rc<str>>,
+	/// The thread this task was created on, which is the only one it may run
+	/// on, as its producer may hold on to values tied to that thread.
+	pub thread: u64,
+	/// The current state of this task.
+	state: TaskState,
+	/// The entry of this task in the registry, if it has one.
+	pub record: Option<Rc<Record>>,
+	/// The entry of this task in the census of live generators, if it has one.
+	pub census: Option<Census>,
+	/// The value lent to the producer by the consumer for the current resume.
+	pub lent: Option<Lend>,
+	/// The state shared between the consumer and the producer.
+	pub shared: Option<SharedState>,
+	/// Hooks observing the switches into and out of this task.
+	pub hooks: Vec<LocalHook>,
+	/// Whether the backtrace of the consumer should be attached to panics.
+	pub capture_backtraces: bool,
+	/// Whether the identity of this task should be attached to panics.
+	pub identify_panics: bool,
+	/// Statistics about the activity of this task.
+	pub stats: Stats,
+	/// Whether the time spent running the producer should be measured.
+	pub measure_time: bool,
+	/// Whether the thread should be named after this task while it runs.
+	pub name_thread: bool,
+	/// The metadata most recently reported by the producer.
+	pub report: Option<Box<dyn Any>>,
+	/// The tokens that cancel this task.
+	pub tokens: Vec<CancelToken>,
+	/// The cancellation this task is going through, if it is being cancelled.
+	pub cancelled: Option<Cancelled>,
+	/// The generators the producer has handed over to the consumer.
+	pub detached: Vec<Box<dyn Any>>,
+	/// The task this task was last driven from, if any.
+	pub parent: Option<TaskId>,
+	/// How many generators deep this task is.
+	pub depth: usize,
+	/// Whether the producer should be leaked, rather than cancelled, if the
+	/// generator gets dropped while the consumer is unwinding.
+	pub leak_on_unwind: bool,
+	/// How far the producer may get once cancelled, and what happens to it
+	/// past that.
+	pub cancel_bound: Option<(CancelBound, Overrun)>,
+	/// How many more values the producer expects to yield.
+	pub hint: (usize, Option<usize>),
+	/// Whether the task should switch back to the consumer as soon as it
+	/// starts, before running the producer.
+	pub park: bool,
+	/// The number of values yielded by the producer that are yet to be
+	/// discarded, as requested by the consumer.
+	pub skip: usize,
+	/// How many more values the consumer intends to take, if it has said so.
+	pub demand: Option<usize>,
+	/// The alternate signal stack installed while the producer runs, if the
+	/// task has one.
+	pub signal_stack: Option<SignalStack>,
+	/// The descriptor the producer is waiting on, if it is waiting on one.
+	#[cfg(unix)]
+	pub waiting: Option<crate::io::Wait>,
+	/// The point in time the producer is sleeping until, if it is sleeping.
+	pub deadline: Option<Instant>,
+	/// Whether the stack of the task has been found to be written over, in
+	/// which case the producer must never run again.
+	pub corrupted: bool,
+	/// The queues of the side channels the consumer has opened, by the type
+	/// of their channel.
+	pub sides: Vec<(TypeId, Rc<dyn Any>)>,
+	/// The errors reported by the producer that the consumer is yet to take.
+	pub errors: Vec<Box<dyn std::error::Error + std::marker::Send + Sync>>,
+	/// The header of the task below this one in the stack of executing tasks,
+	/// or null if this task is at the bottom, while this task is running.
+	pub below: *mut Header,
+	/// The task this is the header of, as of the last time it was entered.
+	pub this: Option<*mut dyn AnyTask>,
+	/// The number of guards deferring the cancellation of the producer that
+	/// it is currently inside of.
+	pub guards: usize,
+	/// Whether the producer has been cancelled while inside of a guard, and
+	/// has to start unwinding once it leaves the last one.
+	pub deferred: bool,
+	/// Whether the producer has been cancelled in a build that can't unwind,
+	/// and has been left suspended where it was for good.
+	pub abandoned: bool,
+	/// Whether the producer has started unwinding from its cancellation.
+	pub unwinding: bool,
+	/// Whether the values yielded by the producer go through a mapping before
+	/// they get to the consumer, which is only known to the typed task.
+	pub mapped: bool,
+}
+impl Header {
+	/// Creates the state for a new task.
+	fn new() -> Self {
+		Self {
+			id: TaskId::new(),
+			name: None,
+			thread: thread_id(),
+			state: TaskState::Created,
+			record: None,
+			census: None,
+			lent: None,
+			shared: None,
+			hooks: Vec::new(),
+			capture_backtraces: false,
+			identify_panics: false,
+			stats: Stats::default(),
+			measure_time: false,
+			name_thread: false,
+			report: None,
+			tokens: Vec::new(),
+			cancelled: None,
+			detached: Vec::new(),
+			parent: None,
+			depth: 1,
+			leak_on_unwind: false,
+			cancel_bound: None,
+			hint: (0, None),
+			park: false,
+			skip: 0,
+			demand: None,
+			signal_stack: None,
+			#[cfg(unix)]
+			waiting: None,
+			deadline: None,
+			corrupted: false,
+			sides: Vec::new(),
+			errors: Vec::new(),
+			below: std::ptr::null_mut(),
+			this: None,
+			guards: 0,
+			deferred: false,
+			abandoned: false,
+			unwinding: false,
+			mapped: false,
+		}
+	}
+
+	/// The current state of this task.
+	pub fn state(&self) -> TaskState {
+		self.state
+	}
+
+	/// Whether the producer is holding on to a value it can't yield with.
+	pub fn is_borrowing(&self) -> bool {
+		self.lent.as_ref().is_some_and(Lend::is_borrowed)
+			|| self.shared.as_ref().is_some_and(SharedState::is_borrowed)
+	}
+
+	/// Takes note that the producer has yielded the given number of values,
+	/// which counts towards its size hint, and towards the demand of the
+	/// consumer.
+	pub fn count_yielded(&mut self, count: usize) {
+		self.stats.yielded += count as u64;
+
+		let (lower, upper) = self.hint;
+		self.hint = (lower.saturating_sub(count), upper.map(|upper| upper.saturating_sub(count)));
+		self.demand = self.demand.map(|demand| demand.saturating_sub(count));
+	}
+
+	/// Updates the task this task is being driven from, and how deep it is.
+	pub fn set_parent(&mut self, parent: Option<TaskId>, depth: usize) {
+		self.parent = parent;
+		self.depth = depth;
+		if let Some(record) = &self.record {
+			record.set_parent(parent, depth)
+		}
+	}
+
+	/// Checks whether any of the tokens attached to this task has been
+	/// cancelled, in which case the task starts going through its
+	/// cancellation.
+	///
+	/// Producers that are already unwinding are never asked to stop again.
+	pub fn check_tokens(&mut self) -> bool {
+		/* Finding out whether we're unwinding takes a thread local, which most
+		 * tasks, that have no tokens to begin with, needn't pay for. */
+		if self.tokens.is_empty() {
+			return false
+		}
+		let Some(cancelled) = self.tokens.iter().find_map(CancelToken::cancellation) else {
+			return false
+		};
+		if std::thread::panicking() {
+			return false
+		}
+
+		self.cancelled = Some(cancelled);
+		true
+	}
+
+	/// Updates the state of this task.
+	pub fn set_state(&mut self, state: TaskState) {
+		self.state = state;
+		if let Some(record) = &self.record {
+			record.set_state(state)
+		}
+		if state == TaskState::Finished {
+			/* Finished generators hold on to nothing worth reporting. */
+			self.census = None;
+		}
+	}
+}
+
+/// Type-erased view of a [`Task`].
+///
+/// This is what gets stored in the stack of executing tasks. It can be upcast
+/// to [`Any`] to recover the concrete [`Task`], when the type of the values is
+/// known, and it gives access to the [`Header`] of the task when it is not.
+pub trait AnyTask: Any {
+	/// The type-erased state of this task.
+	fn header(&mut self) -> &mut Header;
+
+	/// The range of addresses spanned by the stack of this task.
+	fn stack_bounds(&self) -> Range<usize>;
+
+	/// The lowest address on the stack of this task the producer may use.
+	fn stack_floor(&self) -> usize;
+
+	/// Checks that this is a live task, as with [`Task::validate`].
+	fn validate(&self, action: &str);
+
+	/// Exits this task without a value, and returns the data sent by the
+	/// consumer once it gets resumed.
+	///
+	/// # Safety
+	/// This must only be called from the producer side of this task.
+	unsafe fn exit_pending(&mut self) -> Send;
+
+	/// Maps the value in the `Option` of the given type, exits this task with
+	/// the mapped value, and returns the data sent by the consumer once it gets
+	/// resumed.
+	///
+	/// Returns `None`, leaving the value untouched, if values of the given type
+	/// are not mapped by this task.
+	///
+	/// # Safety
+	/// This must only be called from the producer side of this task, and
+	/// `value` must point to an `Option` of the given type, holding a value.
+	unsafe fn exit_mapped(&mut self, ty: TypeId, value: *mut ()) -> Option<Send>;
+}
+impl<T: 'static> AnyTask for Task<T> {
+	fn header(&mut self) -> &mut Header {
+		&mut self.header
+	}
+
+	fn stack_bounds(&self) -> Range<usize> {
+		Task::stack_bounds(self)
+	}
+
+	fn stack_floor(&self) -> usize {
+		Task::stack_floor(self)
+	}
+
+	fn validate(&self, action: &str) {
+		Task::validate(self, action)
+	}
+
+	unsafe fn exit_pending(&mut self) -> Send {
+		self.note_stack_depth();
+		exit(self, Yield::Pending).1
+	}
+
+	unsafe fn exit_mapped(&mut self, ty: TypeId, value: *mut ()) -> Option<Send> {
+		if self.map.as_ref().is_none_or(|(source, _)| *source != ty) {
+			return None
+		}
+
+		/* Same as with the filter, the function is taken out while it runs. */
+		let (source, mut map) = self.map.take()?;
+		let value = map(value);
+		self.map = Some((source, map));
+
+		Some(match value {
+			Some(value) => yield_to(self, Yield::Value(value)),
+			None => Send::Continue,
+		})
+	}
 }
 
 /// Executes the generator.
@@ -44,11 +489,25 @@ unsafe fn generator_start<T: 'static>(task: *mut Task<T>) -> ! {
 	/* We can assert unwind safety here as we'll just abort the process if we
 	 * catch a panic. No data should be accessed at all. */
 	let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+		/* The consumer may have asked for values to be skipped right from the
+		 * first resume. */
+		if let Send::Skip(skip) = (*task).data_in.assume_init_read() {
+			(*task).header.skip = skip;
+		}
+
+		/* Tasks started ahead of time hand control back right away, and the
+		 * producer only runs once they get resumed again. */
+		if std::mem::take(&mut (*task).header.park) {
+			if let Send::Cancel = (*task).exit_pending() {
+				(*task).func = None;
+			}
+		}
+
 		/* Let the generator function run. */
 		if let Some(func) = (&mut *task).func.take() {
 			/* It is _absolutely_ not safe to let the unwind continue beyond this
 			 * point. There's nothing above this function in the call stack. */
-			if let Err(what) = std::panic::catch_unwind(func) {
+			if let Err(what) = std::panic::catch_unwind(AssertUnwindSafe(|| func.call())) {
 				/* Let the runtime on the consumer side propagate the panic. */
 				let _ = yield_internal::<T>(Yield::Panic(what));
 			}
@@ -65,29 +524,131 @@ unsafe fn generator_start<T: 'static>(task: *mut Task<T>) -> ! {
 	std::process::abort()
 }
 
-/// Used to align our stack.
-#[repr(align(0x10000))]
-#[derive(Copy, Clone)]
-struct PageAlign(#[allow(dead_code)] u8);
+/// Predicate run by the producer on the values it yields.
+pub type Filter<T> = Box<dyn FnMut(&T) -> bool>;
+
+/// Function run by the producer on the values it yields, which takes them out
+/// of a type-erased `Option`, and returns the mapped value, if it should be
+/// handed to the consumer.
+pub type Map<T> = Box<dyn FnMut(*mut ()) -> Option<T>>;
+
+/// The function at the root of a generator task.
+pub enum Entry {
+	/// A plain function pointer, which needs no allocation of its own.
+	Ptr(fn()),
+	/// A boxed closure, which may carry state along with it.
+	Boxed(Box<dyn FnOnce()>),
+	/// A function pointer whose signature has been erased, along with the
+	/// function that knows how to call it.
+	Typed(*const (), fn(*const ())),
+}
+impl Entry {
+	/// Runs the function.
+	fn call(self) {
+		match self {
+			Entry::Ptr(func) => func(),
+			Entry::Boxed(func) => func(),
+			Entry::Typed(func, call) => call(func),
+		}
+	}
+}
+
+/// An identifier for the current thread, unique for the lifetime of the process.
+///
+/// This gets checked every time a task is entered, and going through
+/// [`std::thread::current`] would take a reference count on the handle of the
+/// thread each time, on top of its thread local. This only takes a thread local
+/// without a destructor, which costs next to nothing.
+pub fn thread_id() -> u64 {
+	thread_local! {
+		static ID: Cell<u64> = const { Cell::new(0) };
+	}
+	static NEXT: AtomicU64 = AtomicU64::new(1);
+
+	let id = ID.get();
+	if id != 0 {
+		return id
+	}
+	let id = NEXT.fetch_add(1, Ordering::Relaxed);
+	ID.set(id);
+	id
+}
+
+/// Sets up a new task to run the given generator function on the given stack.
+pub fn new_task<T>(func: Entry, stack: Stack) -> Task<T> {
+	assemble(Some(func), stack, Header::new(), None)
+}
+
+/// Turns a task that hasn't been started yet into one that hands values of
+/// another type to the consumer, mapping the values yielded by the producer
+/// with the given function.
+///
+/// # Panic
+/// This function panics if the task has already been started.
+pub fn map_task<T: 'static, U>(task: Task<T>, mut func: impl FnMut(T) -> U + 'static) -> Task<U> {
+	assert!(!task.started, "Tried to map the values of a task that has already started!");
+	let Task { func: entry, stack, header, filter, map, .. } = task;
+
+	/* Values go through the mapping and the filter that were already in place,
+	 * if any, before going through the new function. */
+	let mut filter = filter.unwrap_or_else(|| Box::new(|_| true));
+	let (source, mut map) = map.unwrap_or_else(|| (TypeId::of::<T>(), Box::new(|value| {
+		unsafe { (*(value as *mut Option<T>)).take() }
+	})));
+	let map: Map<U> = Box::new(move |value| {
+		map(value)
+			.filter(|value| filter(value))
+			.map(&mut func)
+	});
 
-/// Sets up a new task to run the given generator function.
-pub fn new_task<T>(func: fn()) -> Task<T> {
+	assemble(entry, stack, header, Some((source, map)))
+}
+
+/// Takes the stack back out of a task, so that another task can run on it.
+///
+/// Whatever the producer left on the stack is forgotten, so the task must
+/// either have never been started, or have finished, at which point the
+/// frames at the base of the stack hold nothing that needs dropping.
+pub fn into_stack<T>(task: Task<T>) -> Stack {
+	let Task { stack, .. } = task;
+	stack
+}
+
+/// Puts together a task that hasn't been started yet.
+fn assemble<T>(func: Option<Entry>, stack: Stack, mut header: Header, map: Option<(TypeId, Map<T>)>) -> Task<T> {
+	stack.write_canaries();
+	header.mapped = map.is_some();
 	Task {
 		rx_snap: MaybeUninit::uninit(),
 		tx_snap: MaybeUninit::zeroed(),
 		data_out: MaybeUninit::uninit(),
 		data_in: MaybeUninit::uninit(),
-		func: Some(func),
-		stack: Box::into_pin(vec![PageAlign(0); 2048 * 1024 / size_of::<PageAlign>()].into_boxed_slice()),
+		func,
+		stack,
 		started: false,
+		header,
+		filter: None,
+		map,
+		batch: None,
+		#[cfg(debug_assertions)]
+		magic: magic::<T>(),
+		#[cfg(debug_assertions)]
+		generation: 0,
 	}
 }
 
 /// Enters a task with a given payload.
 /// 
 /// # Panic
-/// This function is guaranteed to never panic.
+/// This function only ever panics before entering the task, if the task fails
+/// [`Task::validate`].
 pub unsafe fn enter<T: 'static>(task: *mut Task<T>, data: Send) -> Yield<T> {
+	(*task).validate("enter");
+	#[cfg(debug_assertions)]
+	{
+		(*task).generation += 1;
+	}
+
 	/* Set up the initial thread state of the task. */
 	if !(*task).started {
 		start(task);
@@ -108,6 +669,10 @@ pub unsafe fn enter<T: 'static>(task: *mut Task<T>, data: Send) -> Yield<T> {
 
 /// Exits a task with a given payload.
 pub unsafe fn exit<T>(task: *mut Task<T>, data: Yield<T>) -> (*mut Task<T>, Send) {
+	(*task).validate("exit");
+	#[cfg(debug_assertions)]
+	let generation = (*task).generation;
+
 	/* Send in the data for the consumer. */
 	(*task).data_out.write(data);
 
@@ -117,7 +682,32 @@ pub unsafe fn exit<T>(task: *mut Task<T>, data: Yield<T>) -> (*mut Task<T>, Send
 	 * moved around by the consumer. */
 	let new_task = switch_ctx(task, true);
 
-	(new_task, (*new_task).data_in.assume_init_read())
+	/* The task we come back to must be the one we left, entered once more. */
+	(*new_task).validate("resume");
+	#[cfg(debug_assertions)]
+	if (*new_task).generation != generation + 1 {
+		panic!("Resumed a task through a pointer to another one!")
+	}
+
+	/* Requests to skip values get handled by the runtime, and never make it
+	 * to the producer. */
+	match (*new_task).data_in.assume_init_read() {
+		Send::Skip(skip) => {
+			(*new_task).header.skip = skip;
+			(new_task, Send::Continue)
+		}
+		send => (new_task, send),
+	}
+}
+
+/// Reads the registers saved for the producer of a task that has been started
+/// and is not currently running, if the backend supports it.
+pub fn saved_context<T>(task: &Task<T>) -> Option<SavedContext> {
+	if !task.started {
+		return None
+	}
+
+	unsafe { _sys::impl_saved_context(task) }
 }
 
 /// Sets a task up for execution with [`switch_ctx`].
@@ -133,3 +723,47 @@ unsafe fn start<T: 'static>(task: *mut Task<T>) {
 unsafe fn switch_ctx<T>(task: *mut Task<T>, yielding: bool) -> *mut Task<T> {
 	_sys::impl_switch_ctx(task, yielding)
 }
+
+/// The address the start function of the given task should appear to have been
+/// called from, or zero if it should appear to have been called from nowhere.
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn return_address(header: &Header) -> u64 {
+	#[cfg(unix)]
+	return crate::perf::return_address(header.name.as_deref()) as u64;
+
+	#[cfg(not(unix))]
+	{
+		let _ = header;
+		0
+	}
+}
+
+/// Storage for the registers of a bare context, saved by [`switch_context`].
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub use _sys::Snapshot;
+
+/// Sets a snapshot up to start a bare context, running [`context_start`] on a
+/// stack starting off from the given address, which must be aligned to 16
+/// bytes.
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub unsafe fn prepare_context(snap: *mut Snapshot, top: usize) {
+	_sys::impl_prepare_context(snap, top)
+}
+
+/// Switches from the context being saved into `from` over to the context saved
+/// in `to`, handing `arg` over to the other side, outside of the protocol of
+/// tasks.
+///
+/// The other side gets `arg` either as the return value of its own call to
+/// this function, or, if it is only just starting, as the argument to
+/// [`context_start`].
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub unsafe fn switch_context(arg: *mut (), from: *mut Snapshot, to: *const Snapshot) -> *mut () {
+	_sys::impl_switch_context(arg, from, to)
+}
+
+/// The function at the root of the call stack of bare contexts.
+#[cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+unsafe fn context_start(arg: *mut ()) -> ! {
+	crate::raw::context_start(arg)
+}
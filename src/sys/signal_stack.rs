@@ -0,0 +1,78 @@
+//! Alternate signal stacks for tasks.
+//!
+//! Signals delivered while a producer runs get handled on the stack of the
+//! producer, unless the handler was installed with `SA_ONSTACK`, in which case
+//! it runs on the alternate signal stack of the thread. Tasks may be given an
+//! alternate stack of their own, which gets installed for as long as their
+//! producer runs, so that handlers don't have to fit in whatever room is left
+//! on the stack of the producer. On systems without alternate signal stacks,
+//! all of these functions do nothing.
+#[cfg(unix)]
+use crate::sys::Stack;
+
+/// An alternate signal stack belonging to a task.
+#[cfg(unix)]
+pub struct SignalStack(Stack);
+
+/// An alternate signal stack belonging to a task.
+#[cfg(not(unix))]
+pub struct SignalStack;
+
+impl SignalStack {
+	/// Allocates a new alternate signal stack of at least the given size.
+	#[cfg(unix)]
+	pub fn new(size: usize) -> Self {
+		Self(Stack::new(size.max(libc::MINSIGSTKSZ)))
+	}
+
+	/// Allocates a new alternate signal stack of at least the given size.
+	#[cfg(not(unix))]
+	pub fn new(_: usize) -> Self {
+		Self
+	}
+}
+
+/// The alternate signal stack a thread had before it was replaced.
+#[cfg(unix)]
+pub struct SavedStack(libc::stack_t);
+
+/// The alternate signal stack a thread had before it was replaced.
+#[cfg(not(unix))]
+pub struct SavedStack;
+
+/// Installs the given stack as the alternate signal stack of the current
+/// thread, returning the one it had before, if it could be replaced.
+///
+/// The stack can't be replaced while a signal handler is running on it.
+#[cfg(unix)]
+pub fn install(stack: &SignalStack) -> Option<SavedStack> {
+	let new = libc::stack_t {
+		ss_sp: stack.0.base() as *mut libc::c_void,
+		ss_flags: 0,
+		ss_size: stack.0.len(),
+	};
+	let mut saved = SavedStack(unsafe { std::mem::zeroed() });
+	match unsafe { libc::sigaltstack(&new, &mut saved.0) } {
+		0 => Some(saved),
+		_ => None,
+	}
+}
+
+/// Gives the current thread back the alternate signal stack it had before it
+/// was replaced.
+#[cfg(unix)]
+pub fn restore(saved: SavedStack) {
+	unsafe { libc::sigaltstack(&saved.0, std::ptr::null_mut()); }
+}
+
+/// Installs the given stack as the alternate signal stack of the current
+/// thread, returning the one it had before, if it could be replaced.
+#[cfg(not(unix))]
+pub fn install(_: &SignalStack) -> Option<SavedStack> {
+	None
+}
+
+/// Gives the current thread back the alternate signal stack it had before it
+/// was replaced.
+#[cfg(not(unix))]
+pub fn restore(_: SavedStack) {}
@@ -0,0 +1,466 @@
+#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+use std::alloc::Layout;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Size of the stacks allocated for tasks, unless otherwise requested.
+#[cfg(not(feature = "small"))]
+pub const DEFAULT_STACK_SIZE: usize = 2048 * 1024;
+
+/// Size of the stacks allocated for tasks, unless otherwise requested.
+#[cfg(feature = "small")]
+pub const DEFAULT_STACK_SIZE: usize = 64 * 1024;
+
+/// The most stacks each thread keeps around for reuse.
+#[cfg(all(feature = "small", not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+const POOL_CAPACITY: usize = 8;
+
+#[cfg(all(feature = "small", not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+thread_local! {
+	/// Stacks that were freed on this thread, ready to be handed out again.
+	static POOL: std::cell::RefCell<Vec<Stack>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// The smallest region that can be used as a stack: a page, on the targets with
+/// the smallest pages, plus room for the canaries, for the frame record of the
+/// first function on the stack, and for rounding the top of the stack down to
+/// the alignment the ABI asks for.
+pub const MIN_STACK_SIZE: usize = TOUCH_INTERVAL + 64;
+
+/// Value canaries get derived from, by mixing in their address.
+const CANARY: u64 = 0x7965_6574_6361_6e61;
+
+/// Room left at the top of every stack for its upper canary, which keeps the
+/// stack pointer aligned.
+const CANARY_ROOM: usize = 16;
+
+/// Granularity at which stacks get touched by [`Stack::touch`].
+///
+/// This is the smallest page size on all of the supported targets, so touching
+/// every address at this interval is enough to reach every page of the stack.
+const TOUCH_INTERVAL: usize = 4096;
+
+/// The most stacks that may have guard pages at once.
+///
+/// Every guard page keeps the stack above it from getting merged into one
+/// mapping with its neighbours, so it costs the process two mappings out of the
+/// limit the system puts on their number, which is 65530 by default on Linux.
+/// Past this many, stacks go without guard pages, leaving the limit for the
+/// rest of the process to use.
+#[cfg(unix)]
+const MAX_GUARDED: usize = 16 * 1024;
+
+/// The number of stacks with guard pages alive.
+#[cfg(unix)]
+static GUARDED: AtomicUsize = AtomicUsize::new(0);
+
+/// The smallest alignment stacks may have, which is the alignment the stack
+/// pointer must have on all of the supported ABIs.
+#[cfg(not(all(windows, feature = "fibers", not(feature = "corosensei"))))]
+const MIN_STACK_ALIGNMENT: usize = 16;
+
+/// The size of the pages of memory on this system.
+///
+/// Systems we can't ask are assumed to have the smallest page size among all
+/// of the supported targets.
+pub fn page_size() -> usize {
+	#[cfg(unix)]
+	return unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+	#[cfg(windows)]
+	return unsafe {
+		let mut info = std::mem::zeroed::<windows_sys::Win32::System::SystemInformation::SYSTEM_INFO>();
+		windows_sys::Win32::System::SystemInformation::GetSystemInfo(&mut info);
+		info.dwPageSize as usize
+	};
+
+	#[cfg(not(any(unix, windows)))]
+	TOUCH_INTERVAL
+}
+
+/// One of the canaries guarding the ends of a stack.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Canary {
+	/// The canary at the lowest address, which the task reaches by
+	/// overflowing its stack.
+	Bottom,
+	/// The canary above the first frame of the task.
+	Top,
+}
+
+/// The memory region a task runs on.
+pub enum Stack {
+	/// Stack memory allocated and owned by us.
+	#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+	Owned {
+		/// The lowest address in the region.
+		base: *mut u8,
+		/// The layout the region was allocated with.
+		layout: Layout,
+	},
+	/// Stack memory allocated by the system for the fiber running the task.
+	///
+	/// The fiber only gets created when the task starts, and where its stack
+	/// lives is only known once it runs for the first time. Until then, the
+	/// region is reported as starting at address zero.
+	#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+	Fiber {
+		/// The fiber running the task, or null if it hasn't been created yet.
+		fiber: *mut std::ffi::c_void,
+		/// The highest address in the stack of the fiber, or zero if it hasn't
+		/// run yet.
+		top: usize,
+		/// The size the stack of the fiber may grow up to, in bytes.
+		len: usize,
+	},
+	/// Stack memory mapped by us.
+	///
+	/// The mapping reserves address space without reserving any memory to back
+	/// it, so only the pages the task actually reaches consume memory. This
+	/// allows for large numbers of tasks with large, but mostly unused, stacks.
+	///
+	/// The mapping starts a page below the region, with a guard page that can't
+	/// be accessed at all, so that tasks overflowing their stacks past the lower
+	/// canary fault, rather than write over whatever got mapped below them. The
+	/// page is left accessible when there are too many guard pages around
+	/// already, as per [`MAX_GUARDED`].
+	#[cfg(unix)]
+	Mapped {
+		/// The lowest address in the region, right above the guard page.
+		base: *mut u8,
+		/// The length of the region, in bytes.
+		len: usize,
+		/// Whether the guard page has been protected, and counts towards
+		/// [`MAX_GUARDED`].
+		guarded: bool,
+	},
+	/// Stack memory provided to us by the user, which we must not free.
+	External {
+		/// The lowest address in the region.
+		base: *mut u8,
+		/// The length of the region, in bytes.
+		len: usize,
+	}
+}
+impl Stack {
+	/// Allocates a new stack of at least the given size, aligned to the size of
+	/// a page.
+	pub fn new(size: usize) -> Self {
+		Self::with_alignment(size, page_size())
+	}
+
+	/// Allocates a new stack of at least the given size, aligned to the given
+	/// alignment.
+	///
+	/// The memory gets zeroed up front, unless the crate is built with the
+	/// `small` feature, which leaves it as it comes out of the allocator.
+	///
+	/// # Panic
+	/// This function panics if the alignment is not a power of two, or if the
+	/// memory could not be allocated.
+	#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+	pub fn with_alignment(size: usize, align: usize) -> Self {
+		let layout = Layout::from_size_align(size.max(1), align.max(MIN_STACK_ALIGNMENT))
+			.expect("Stacks must be aligned to a power of two!")
+			.pad_to_align();
+		#[cfg(feature = "small")]
+		if let Some(stack) = Self::reuse(layout.size(), layout.align()) {
+			return stack
+		}
+
+		let base = unsafe {
+			if cfg!(feature = "small") {
+				std::alloc::alloc(layout)
+			} else {
+				std::alloc::alloc_zeroed(layout)
+			}
+		};
+		if base.is_null() {
+			std::alloc::handle_alloc_error(layout)
+		}
+
+		Stack::Owned { base, layout }
+	}
+
+	/// Describes a new stack of at least the given size, which the system will
+	/// allocate once the fiber running the task gets created.
+	///
+	/// Fiber stacks are always aligned to whatever the system aligns them to,
+	/// so the alignment is ignored.
+	#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+	pub fn with_alignment(size: usize, _align: usize) -> Self {
+		Stack::Fiber { fiber: std::ptr::null_mut(), top: 0, len: size.max(1) }
+	}
+
+	/// Maps a new stack of at least the given size, aligned to the given
+	/// alignment.
+	///
+	/// Mappings always start at a page boundary, so alignments of up to a page
+	/// come for free. Larger alignments are had by mapping enough extra memory
+	/// to find an aligned region in it, and unmapping the rest. Either way, the
+	/// region gets an extra page below it, as its guard page.
+	///
+	/// # Panic
+	/// This function panics if the alignment is not a power of two, or if the
+	/// memory could not be mapped.
+	#[cfg(unix)]
+	pub fn with_alignment(size: usize, align: usize) -> Self {
+		#[cfg(any(target_os = "linux", target_os = "android"))]
+		const NORESERVE: libc::c_int = libc::MAP_NORESERVE;
+		#[cfg(not(any(target_os = "linux", target_os = "android")))]
+		const NORESERVE: libc::c_int = 0;
+
+		assert!(align.is_power_of_two(), "Stacks must be aligned to a power of two!");
+		let align = align.max(MIN_STACK_ALIGNMENT);
+		let page = page_size();
+		let len = size.max(1).next_multiple_of(page);
+		#[cfg(feature = "small")]
+		if let Some(stack) = Self::reuse(len, align) {
+			return stack
+		}
+
+		let extra = align.saturating_sub(page);
+		let base = unsafe {
+			libc::mmap(
+				std::ptr::null_mut(),
+				page + len + extra,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | NORESERVE,
+				-1,
+				0)
+		};
+		if base == libc::MAP_FAILED {
+			panic!("Could not map {} bytes of stack memory: {}", page + len + extra, std::io::Error::last_os_error())
+		}
+
+		/* Trim the extra memory off both ends of the aligned region and of its
+		 * guard page, which then gets cut off from the rest of the region. */
+		let start = base as usize;
+		let aligned = (start + page).next_multiple_of(align);
+		let guard = aligned - page;
+		unsafe {
+			if guard > start {
+				libc::munmap(base, guard - start);
+			}
+			let end = start + page + len + extra;
+			if end > aligned + len {
+				libc::munmap((aligned + len) as *mut libc::c_void, end - aligned - len);
+			}
+		}
+
+		/* Protecting the page may still fail if the process has run out of
+		 * mappings regardless, in which case the canary is all that's left. */
+		let guarded = GUARDED.fetch_add(1, Ordering::Relaxed) < MAX_GUARDED
+			&& unsafe { libc::mprotect(guard as *mut libc::c_void, page, libc::PROT_NONE) } == 0;
+		if !guarded {
+			GUARDED.fetch_sub(1, Ordering::Relaxed);
+		}
+
+		Stack::Mapped { base: aligned as *mut u8, len, guarded }
+	}
+
+	/// Takes a stack of the given length, aligned to the given alignment, out
+	/// of the pool of this thread, if there is one in it.
+	#[cfg(all(feature = "small", not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+	fn reuse(len: usize, align: usize) -> Option<Self> {
+		POOL.try_with(|pool| {
+			let mut pool = pool.try_borrow_mut().ok()?;
+			let index = pool.iter().position(|stack| stack.len() == len && stack.base() % align == 0)?;
+			Some(pool.swap_remove(index))
+		}).ok().flatten()
+	}
+
+	/// Moves this stack into the pool of this thread, rather than freeing it,
+	/// if the pool has room for it, and returns whether it did.
+	///
+	/// Whatever the task left on the stack stays there, so only stacks that
+	/// are being dropped may be recycled.
+	#[cfg(all(feature = "small", not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+	fn recycle(&mut self) -> bool {
+		if let Stack::External { .. } = self {
+			return false
+		}
+
+		POOL.try_with(|pool| {
+			let Ok(mut pool) = pool.try_borrow_mut() else { return false };
+			if pool.len() >= POOL_CAPACITY {
+				return false
+			}
+
+			let empty = Stack::External { base: std::ptr::null_mut(), len: 0 };
+			pool.push(std::mem::replace(self, empty));
+			true
+		}).unwrap_or(false)
+	}
+
+	/// Uses the given memory region as a stack.
+	///
+	/// # Safety
+	/// The region must be valid for reads and writes for as long as the stack
+	/// is in use, and must not be accessed by anything other than the task.
+	///
+	/// # Panic
+	/// This function panics if the region is smaller than [`MIN_STACK_SIZE`].
+	pub unsafe fn external(base: *mut u8, len: usize) -> Self {
+		assert!(len >= MIN_STACK_SIZE, "Stacks must be at least {MIN_STACK_SIZE} bytes long!");
+		Stack::External { base, len }
+	}
+
+	/// The lowest address in the stack region.
+	pub fn base(&self) -> usize {
+		match self {
+			#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+			Stack::Owned { base, .. } => *base as usize,
+			#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+			Stack::Fiber { top, len, .. } => top.saturating_sub(*len),
+			#[cfg(unix)]
+			Stack::Mapped { base, .. } => *base as usize,
+			Stack::External { base, .. } => *base as usize,
+		}
+	}
+
+	/// The length of the stack region, in bytes.
+	pub fn len(&self) -> usize {
+		match self {
+			#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+			Stack::Owned { layout, .. } => layout.size(),
+			#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+			Stack::Fiber { len, .. } => *len,
+			#[cfg(unix)]
+			Stack::Mapped { len, .. } => *len,
+			Stack::External { len, .. } => *len,
+		}
+	}
+
+	/// The address the stack pointer starts at, which is the end of the region
+	/// rounded down to the alignment required by all of the supported ABIs,
+	/// right below the upper canary.
+	pub fn top(&self) -> usize {
+		((self.base() + self.len()) & !0xf) - CANARY_ROOM
+	}
+
+	/// The lowest address the task may use, right above the lower canary.
+	pub fn floor(&self) -> usize {
+		match self.canaries() {
+			Some([bottom, _]) => bottom as usize + size_of::<u64>(),
+			None => self.base(),
+		}
+	}
+
+	/// The addresses of the canaries at the bottom and at the top of the
+	/// stack, if the stack has any.
+	///
+	/// Builds with the `small` feature leave stacks without canaries, so that
+	/// switches don't pay for checking them.
+	fn canaries(&self) -> Option<[*mut u64; 2]> {
+		if cfg!(feature = "small") {
+			return None
+		}
+
+		#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+		if let Stack::Fiber { .. } = self {
+			return None
+		}
+
+		let base = self.base().next_multiple_of(8);
+		Some([base as *mut u64, self.top() as *mut u64])
+	}
+
+	/// Writes the canaries at the bottom and at the top of the stack, which
+	/// the task must never write over.
+	pub fn write_canaries(&self) {
+		for canary in self.canaries().into_iter().flatten() {
+			unsafe { canary.write_volatile(CANARY ^ canary as u64) }
+		}
+	}
+
+	/// Checks the canaries at the bottom and at the top of the stack, and
+	/// returns which one got written over, if any did.
+	pub fn check_canaries(&self) -> Result<(), Canary> {
+		let Some([bottom, top]) = self.canaries() else { return Ok(()) };
+		if unsafe { bottom.read_volatile() } != CANARY ^ bottom as u64 {
+			return Err(Canary::Bottom)
+		}
+		if unsafe { top.read_volatile() } != CANARY ^ top as u64 {
+			return Err(Canary::Top)
+		}
+
+		Ok(())
+	}
+
+	/// Gives up ownership of the memory backing the stack, which never gets
+	/// freed from then on.
+	pub fn leak(&mut self) {
+		let external = Stack::External {
+			base: self.base() as *mut u8,
+			len: self.len(),
+		};
+		std::mem::forget(std::mem::replace(self, external))
+	}
+
+	/// Writes to every page in the stack, starting from the top, so that they
+	/// all get committed by the operating system right away, rather than when
+	/// the task first reaches them.
+	///
+	/// Fiber stacks don't exist yet by the time generators get built, and are
+	/// left for the system to commit as they grow.
+	pub fn touch(&self) {
+		#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+		if let Stack::Fiber { .. } = self {
+			return
+		}
+
+		let base = self.base();
+		let mut addr = self.top();
+		while addr > base + TOUCH_INTERVAL {
+			addr -= TOUCH_INTERVAL;
+			unsafe { ((addr + TOUCH_INTERVAL - 1) as *mut u8).write_volatile(0) }
+		}
+
+		/* The region might not start at a page boundary. */
+		unsafe { (base as *mut u8).write_volatile(0) }
+		if addr > base {
+			unsafe { ((addr - 1) as *mut u8).write_volatile(0) }
+		}
+	}
+}
+#[cfg(all(not(unix), not(all(windows, feature = "fibers", not(feature = "corosensei")))))]
+impl Drop for Stack {
+	fn drop(&mut self) {
+		#[cfg(feature = "small")]
+		if self.recycle() {
+			return
+		}
+
+		if let Stack::Owned { base, layout } = *self {
+			unsafe { std::alloc::dealloc(base, layout) }
+		}
+	}
+}
+#[cfg(unix)]
+impl Drop for Stack {
+	fn drop(&mut self) {
+		#[cfg(feature = "small")]
+		if self.recycle() {
+			return
+		}
+
+		if let Stack::Mapped { base, len, guarded } = *self {
+			let page = page_size();
+			unsafe { libc::munmap(base.sub(page) as *mut libc::c_void, page + len); }
+			if guarded {
+				GUARDED.fetch_sub(1, Ordering::Relaxed);
+			}
+		}
+	}
+}
+#[cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+impl Drop for Stack {
+	fn drop(&mut self) {
+		if let Stack::Fiber { fiber, .. } = *self {
+			if !fiber.is_null() {
+				unsafe { windows_sys::Win32::System::Threading::DeleteFiber(fiber) }
+			}
+		}
+	}
+}
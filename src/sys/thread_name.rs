@@ -0,0 +1,68 @@
+//! Naming of the operating system thread after the task running on it.
+//!
+//! Thread names are limited to 15 bytes on most systems, so longer task names
+//! get truncated. On systems where threads can't be named, all of these
+//! functions do nothing.
+
+/// The maximum length of a thread name, in bytes, not counting the terminator.
+const MAX_LEN: usize = 15;
+
+/// The name a thread had before it was renamed.
+#[cfg_attr(not(any(target_os = "linux", target_os = "android", target_os = "macos")), allow(dead_code))]
+pub struct SavedName([u8; MAX_LEN + 1]);
+
+/// Renames the current thread to the given name, returning its previous name,
+/// if it could be retrieved.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+pub fn set(name: &str) -> Option<SavedName> {
+	let mut saved = SavedName([0; MAX_LEN + 1]);
+	let status = unsafe {
+		libc::pthread_getname_np(
+			libc::pthread_self(),
+			saved.0.as_mut_ptr() as *mut libc::c_char,
+			saved.0.len())
+	};
+	if status != 0 {
+		return None
+	}
+
+	/* Interior NULs would cut the name short anyway. */
+	let mut buf = [0u8; MAX_LEN + 1];
+	let mut len = name.find('\0').unwrap_or(name.len()).min(MAX_LEN);
+	while !name.is_char_boundary(len) {
+		len -= 1;
+	}
+	buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+	apply(&buf);
+
+	Some(saved)
+}
+
+/// Gives the current thread back the name it had before it was renamed.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+pub fn restore(saved: SavedName) {
+	apply(&saved.0)
+}
+
+/// Sets the name of the current thread to the given NUL-terminated name.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn apply(name: &[u8; MAX_LEN + 1]) {
+	unsafe { libc::pthread_setname_np(libc::pthread_self(), name.as_ptr() as *const libc::c_char); }
+}
+
+/// Sets the name of the current thread to the given NUL-terminated name.
+#[cfg(target_os = "macos")]
+fn apply(name: &[u8; MAX_LEN + 1]) {
+	unsafe { libc::pthread_setname_np(name.as_ptr() as *const libc::c_char); }
+}
+
+/// Renames the current thread to the given name, returning its previous name,
+/// if it could be retrieved.
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn set(_: &str) -> Option<SavedName> {
+	None
+}
+
+/// Gives the current thread back the name it had before it was renamed.
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn restore(_: SavedName) {}
@@ -0,0 +1,78 @@
+use std::cell::Cell;
+use std::mem::size_of;
+use libc::{c_int, ucontext_t};
+use crate::registry::SavedContext;
+use crate::sys::Task;
+
+/* Not every target has these in the libc crate, even though every Unix we care
+ * about has them in its C library. */
+extern "C" {
+	fn getcontext(ucp: *mut ucontext_t) -> c_int;
+	fn makecontext(ucp: *mut ucontext_t, func: extern "C" fn(), argc: c_int, ...);
+	fn swapcontext(oucp: *mut ucontext_t, ucp: *const ucontext_t) -> c_int;
+}
+
+/// Points to the context a side of a task gets saved to.
+///
+/// Contexts may point into themselves, so they can't live in the task, which
+/// moves around. Instead, both of them are carved out of the top of the stack
+/// region of the task, and only pointers to them are kept here.
+pub struct Snapshot(*mut ucontext_t);
+
+thread_local! {
+	/// The task most recently entered on this thread.
+	///
+	/// Arguments to the entry point of a context are fixed when it gets made,
+	/// but the task may have moved by the time the producer gets resumed, so the
+	/// consumer passes the pointer along through here on every switch.
+	static TASK: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Entry point of the producer context of tasks yielding values of type `T`.
+extern "C" fn ucontext_start<T: 'static>() {
+	unsafe { super::generator_start(TASK.get() as *mut Task<T>) }
+}
+
+/// See [`super::start`].
+pub unsafe fn impl_start<T: 'static>(task: *mut Task<T>) {
+	/* Leave room for both contexts at the top of the stack. */
+	let base = (*task).stack.base();
+	let contexts = ((*task).stack.top() - 2 * size_of::<ucontext_t>()) & !0xf;
+	assert!(contexts > base, "The stack of the task is too small to hold its contexts!");
+
+	let rx = contexts as *mut ucontext_t;
+	let tx = rx.add(1);
+	if getcontext(tx) != 0 {
+		panic!("Could not get the current context: {}", std::io::Error::last_os_error())
+	}
+	(*tx).uc_stack.ss_sp = base as *mut libc::c_void;
+	(*tx).uc_stack.ss_size = contexts - base;
+	(*tx).uc_link = std::ptr::null_mut();
+	makecontext(tx, ucontext_start::<T>, 0);
+
+	(*task).rx_snap.write(Snapshot(rx));
+	(*task).tx_snap.write(Snapshot(tx));
+}
+
+/// See [`super::saved_context`].
+///
+/// The layout of the machine context differs between every target this backend
+/// runs on, so there is nothing we can report.
+pub unsafe fn impl_saved_context<T>(_: &Task<T>) -> Option<SavedContext> {
+	None
+}
+
+/// See [`super::switch_ctx`].
+pub unsafe fn impl_switch_ctx<T>(task: *mut Task<T>, yi: bool) -> *mut Task<T> {
+	let rx = (*task).rx_snap.assume_init_ref().0;
+	let tx = (*task).tx_snap.assume_init_ref().0;
+	if !yi {
+		TASK.set(task as usize);
+		swapcontext(rx, tx);
+
+		task
+	} else {
+		swapcontext(tx, rx);
+		TASK.get() as *mut Task<T>
+	}
+}
@@ -1,5 +1,6 @@
-use std::arch::{asm, global_asm};
-use crate::sys::{PageAlign, Task};
+use std::arch::naked_asm;
+use crate::registry::SavedContext;
+use crate::sys::Task;
 
 /// Contains the register state of a given coroutine at the time of a context
 /// switch.
@@ -25,99 +26,115 @@ unsafe extern "sysv64" fn abi_wrap_generator_start<T: 'static>(task: *mut Task<T
 	super::generator_start(task)
 }
 
+/// Known-ABI wrapping for [`super::context_start`].
+unsafe extern "sysv64" fn abi_wrap_context_start(arg: *mut ()) -> ! {
+	super::context_start(arg)
+}
+
 /// See [`super::start`].
 pub unsafe fn impl_start<T: 'static>(task: *mut Task<T>) {
-	let tx_snap = (*task).tx_snap.as_mut_ptr();
+	/* Set the PC to the proper specialization of `_generator_start`. Its
+	 * argument is the task pointer handed over by the first switch. */
+	prepare(
+		(*task).tx_snap.as_mut_ptr(),
+		(*task).stack.top(),
+		abi_wrap_generator_start::<T> as *const () as usize as u64,
+		super::return_address(&(*task).header));
+}
 
-	/* Set RSP and RBP to the top of the stack region in the task. */
-	let stack = ((*task).stack.as_ptr() as usize + (*task).stack.len() * size_of::<PageAlign>()) as u64;
-	(&raw mut (*tx_snap).0.regs[6]).write_unaligned(stack);
-	(&raw mut (*tx_snap).0.regs[7]).write_unaligned(stack);
+/// See [`super::prepare_context`].
+pub unsafe fn impl_prepare_context(snap: *mut Snapshot, top: usize) {
+	prepare(snap, top, abi_wrap_context_start as *const () as usize as u64, 0)
+}
 
-	/* Set the PC to the proper specialization of `_generator_start`. */
-	(&raw mut (*tx_snap).0.pc)
-		.write_unaligned(abi_wrap_generator_start::<T> as usize as u64);
+/// Sets up the given snapshot to start running the function at the given
+/// address, with the stack starting off from the given address, as if it had
+/// been called from the given return address.
+unsafe fn prepare(snap: *mut Snapshot, top: usize, pc: u64, ret: u64) {
+	/* Stacks may be aligned to anything, but the top they hand out always
+	 * satisfies the ABI. */
+	debug_assert_eq!(top % 16, 0, "Misaligned top of stack");
 
-	/* Set the first argument of `generator_start` to this generator instance. */
-	(&raw mut (*tx_snap).0.regs[4])
-		.write_unaligned(task as usize as u64);
+	/* Push the return address, as if the function had been called, by a
+	 * frame whose record, right above it, has nothing to unwind into. */
+	let record = top as u64 - 16;
+	(record as *mut [u64; 2]).write([0, 0]);
+	let stack = record - 8;
+	(stack as *mut u64).write(ret);
+	(&raw mut (*snap).0.regs[6]).write_unaligned(stack);
+	(&raw mut (*snap).0.regs[7]).write_unaligned(record);
+	(&raw mut (*snap).0.pc).write_unaligned(pc);
 }
 
-global_asm!(r#"
-	.global x64_do_switch_ctx
-x64_do_switch_ctx:
-	/* Save the return address as the PC of the `from` snapshot. */
-	POP QWORD PTR [RCX + 128]
-	
-	/* Load the context of the `to` snapshot. */
-	MOV RBX, QWORD PTR [RDX + 8]
-	MOV RDI, QWORD PTR [RDX + 32]
-	MOV RSI, QWORD PTR [RDX + 40]
-	MOV RSP, QWORD PTR [RDX + 48]
-	MOV RBP, QWORD PTR [RDX + 56]
-	MOV R8,  QWORD PTR [RDX + 64]
-	MOV R9,  QWORD PTR [RDX + 72]
-	MOV R10, QWORD PTR [RDX + 80]
-	MOV R11, QWORD PTR [RDX + 88]
-	MOV R12, QWORD PTR [RDX + 96]
-	MOV R13, QWORD PTR [RDX + 104]
-	MOV R14, QWORD PTR [RDX + 112]
-	MOV R15, QWORD PTR [RDX + 120]
+/// See [`super::saved_context`].
+pub unsafe fn impl_saved_context<T>(task: &Task<T>) -> Option<SavedContext> {
+	let tx_snap = task.tx_snap.as_ptr();
+	Some(SavedContext {
+		pc: (&raw const (*tx_snap).0.pc).read_unaligned() as usize,
+		sp: (&raw const (*tx_snap).0.regs[6]).read_unaligned() as usize,
+		fp: (&raw const (*tx_snap).0.regs[7]).read_unaligned() as usize,
+	})
+}
+
+/// Switches from the context being saved into `from` over to the context saved
+/// in `to`, handing `task` over to the other side.
+///
+/// This follows the System V calling convention, so only the registers it has
+/// the callee preserve need saving, and the compiler takes care of everything
+/// else at the call site. The other side resumes by returning from its own
+/// call to this function, with `task` as the return value, or, if it is only
+/// just starting, by entering its start function, with `task` as its argument.
+#[unsafe(naked)]
+unsafe extern "sysv64" fn switch(task: *mut (), from: *mut Snapshot, to: *const Snapshot) -> *mut () {
+	naked_asm!(r#"
+		/* Populate the origin snapshot structure. The origin resumes by
+		 * returning to our caller, with the return address popped off. */
+		MOV QWORD PTR [RSI + 8],   RBX
+		MOV QWORD PTR [RSI + 56],  RBP
+		MOV QWORD PTR [RSI + 96],  R12
+		MOV QWORD PTR [RSI + 104], R13
+		MOV QWORD PTR [RSI + 112], R14
+		MOV QWORD PTR [RSI + 120], R15
+		MOV RCX, QWORD PTR [RSP]
+		MOV QWORD PTR [RSI + 128], RCX
+		LEA RCX, [RSP + 8]
+		MOV QWORD PTR [RSI + 48],  RCX
+
+		/* Load the context of the `to` snapshot. */
+		MOV RBX, QWORD PTR [RDX + 8]
+		MOV RBP, QWORD PTR [RDX + 56]
+		MOV R12, QWORD PTR [RDX + 96]
+		MOV R13, QWORD PTR [RDX + 104]
+		MOV R14, QWORD PTR [RDX + 112]
+		MOV R15, QWORD PTR [RDX + 120]
+		MOV RSP, QWORD PTR [RDX + 48]
 
-	/* Call to resume execution. We don't ever expect the function to return,
-	 * but we do this to align the stack properly for Rust. */
-	CALL QWORD PTR [RDX + 128]
-"#);
+		/* Hand the task over, both as the return value and as the first
+		 * argument, and resume execution. */
+		MOV RAX, RDI
+		JMP QWORD PTR [RDX + 128]
+	"#)
+}
 
 /// See [`super::switch_ctx`].
-pub unsafe fn impl_switch_ctx<T>(mut task: *mut Task<T>, yi: bool) -> *mut Task<T> {
-	let (mut to, mut from) = if !yi {
+pub unsafe fn impl_switch_ctx<T>(task: *mut Task<T>, yi: bool) -> *mut Task<T> {
+	let (to, from) = if !yi {
 		(
-			(*task).tx_snap.as_mut_ptr(),
+			(*task).tx_snap.as_ptr(),
 			(*task).rx_snap.as_mut_ptr(),
 		)
 	} else {
 		(
-			(*task).rx_snap.as_mut_ptr(),
+			(*task).rx_snap.as_ptr(),
 			(*task).tx_snap.as_mut_ptr(),
 		)
 	};
 
-	asm!(
-		r#"
-			/* Populate the origin snapshot structure. */
-			MOV QWORD PTR [RCX + 8],   RBX
-			MOV QWORD PTR [RCX + 32],  RDI
-			MOV QWORD PTR [RCX + 40],  RSI
-			MOV QWORD PTR [RCX + 48],  RSP
-			MOV QWORD PTR [RCX + 56],  RBP
-			MOV QWORD PTR [RCX + 64],  R8
-			MOV QWORD PTR [RCX + 72],  R9
-			MOV QWORD PTR [RCX + 80],  R10
-			MOV QWORD PTR [RCX + 88],  R11
-			MOV QWORD PTR [RCX + 96],  R12
-			MOV QWORD PTR [RCX + 104], R13
-			MOV QWORD PTR [RCX + 112], R14
-			MOV QWORD PTR [RCX + 120], R15
-
-			/* Call the second half of the context switch function, which both
-			 * restores most of the context of the `to` function and makes
-			 * preparations a resume to return after the CALL. */
-			CALL x64_do_switch_ctx
-			
-			/* x64_do_switch_ctx CALLs this location. Get rid of the extra
-			 * value on the stack. */
-			ADD RSP, 8
-		"#,
-		inout("rax") task,
-		inout("rcx") from,
-		inout("rdx") to,
-	);
-
-	let _ = from;
-	let _ = to;
-
 	/* Return the new pointer to be used for the task if this was a yield. */
-	task
+	switch(task as *mut (), from, to) as *mut Task<T>
 }
 
+/// See [`super::switch_context`].
+pub unsafe fn impl_switch_context(arg: *mut (), from: *mut Snapshot, to: *const Snapshot) -> *mut () {
+	switch(arg, from, to)
+}
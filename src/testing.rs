@@ -0,0 +1,363 @@
+//! Utilities for testing code built on top of generators.
+//!
+//! Code that combines several generators, such as merges, selects, and
+//! pipelines, tends to be hard to test, as the order in which its producers
+//! get resumed is usually decided by the code under test. The [`StepDriver`]
+//! in this module instead lets tests resume a set of generators one step at a
+//! time, in an order they script themselves, and assert on the sequence of
+//! values that come out of them.
+//!
+//! ```rust
+//! use yeet::Generator;
+//! use yeet::testing::{Step, StepDriver};
+//!
+//! fn evens() { yeet::yeet_all((0..4u32).step_by(2)) }
+//! fn odds() { yeet::yeet_all((1..4u32).step_by(2)) }
+//!
+//! let mut driver = StepDriver::<u32>::new();
+//! driver.add("evens", Generator::from_fn_ptr(evens));
+//! driver.add("odds", Generator::from_fn_ptr(odds));
+//!
+//! driver.assert_script(&[
+//!     ("evens", Step::Yielded(0)),
+//!     ("odds", Step::Yielded(1)),
+//!     ("odds", Step::Yielded(3)),
+//!     ("evens", Step::Yielded(2)),
+//!     ("odds", Step::Finished),
+//! ]);
+//! ```
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
+use crate::{Generate, Generator, Resume};
+use crate::panic::Payload;
+use crate::record::{EventKind, Recorder, Recording};
+
+/// The outcome of resuming a generator once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<T> {
+	/// The generator has yielded the given value.
+	Yielded(T),
+	/// The generator is done yielding values.
+	Finished,
+}
+impl<T> From<Option<T>> for Step<T> {
+	fn from(value: Option<T>) -> Self {
+		match value {
+			Some(value) => Step::Yielded(value),
+			None => Step::Finished,
+		}
+	}
+}
+
+/// Drives a set of named generators one step at a time, in a scripted order.
+///
+/// Panics raised by the generators propagate out of the driver, as they would
+/// out of [`Generator::next`].
+pub struct StepDriver<T: 'static> {
+	/// The generators being driven, along with their names.
+	tasks: Vec<(String, Generator<T>)>,
+}
+impl<T: 'static> StepDriver<T> {
+	/// Creates a new driver with no generators.
+	pub fn new() -> Self {
+		Self { tasks: Vec::new() }
+	}
+
+	/// Adds a generator to be driven under the given name.
+	///
+	/// # Panic
+	/// This function panics if there is already a generator with that name.
+	pub fn add(&mut self, name: impl Into<String>, gen: Generator<T>) -> &mut Self {
+		let name = name.into();
+		if self.tasks.iter().any(|(other, _)| *other == name) {
+			panic!("There is already a generator named {name:?} in the driver!")
+		}
+
+		self.tasks.push((name, gen));
+		self
+	}
+
+	/// Removes the generator with the given name, handing it back.
+	pub fn remove(&mut self, name: &str) -> Option<Generator<T>> {
+		let index = self.tasks.iter().position(|(other, _)| other == name)?;
+		Some(self.tasks.remove(index).1)
+	}
+
+	/// Resumes the generator with the given name once.
+	///
+	/// # Panic
+	/// This function panics if there is no generator with that name.
+	pub fn step(&mut self, name: &str) -> Step<T> {
+		match self.tasks.iter_mut().find(|(other, _)| other == name) {
+			Some((_, gen)) => gen.next().into(),
+			None => panic!("There is no generator named {name:?} in the driver!")
+		}
+	}
+
+	/// Resumes the generators with the given names once each, in order, and
+	/// returns what each of them produced.
+	pub fn run(&mut self, script: &[&str]) -> Vec<Step<T>> {
+		script.iter()
+			.map(|name| self.step(name))
+			.collect()
+	}
+
+	/// Resumes the generators in the order they were resumed in the given
+	/// recording, and checks that every one of them does what it did back
+	/// then, stopping at the first one that doesn't.
+	///
+	/// Generators get matched to the tasks in the recording by name, and the
+	/// events of tasks that don't match any generator get skipped, as those are
+	/// expected to happen on their own, such as for generators driven by the
+	/// producers of the ones in the driver. Values only get compared if their
+	/// generator has opted in to having them hashed, both in the recording and
+	/// in the driver, through [`Generator::record_values`].
+	///
+	/// Panics raised by the producers are caught, and compared against the
+	/// recording as any other event.
+	pub fn replay(&mut self, recording: &Recording) -> Result<(), Divergence> {
+		let names = self.tasks.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+		let mut events = recording.events().iter().enumerate()
+			.filter(|(_, event)| event.name.as_ref().is_some_and(|name| names.contains(name)));
+
+		while let Some((_, resume)) = events.next() {
+			if resume.kind != EventKind::Resume {
+				continue
+			}
+			let name = resume.name.as_deref().unwrap();
+			let gen = &mut self.tasks.iter_mut().find(|(other, _)| other == name).unwrap().1;
+
+			let recorder = Recorder::start();
+			let _ = std::panic::catch_unwind(AssertUnwindSafe(|| gen.resume()));
+			let found = recorder.finish().events().iter()
+				.rev()
+				.find(|event| event.task == gen.id() && event.kind != EventKind::Resume)
+				.map_or(EventKind::Complete, |event| event.kind);
+
+			let Some((step, expected)) = events.find(|(_, event)| event.name.as_deref() == Some(name)) else { break };
+			let same = match (expected.kind, found) {
+				(EventKind::Yield(Some(expected)), EventKind::Yield(Some(found))) => expected == found,
+				(EventKind::Yield(_), EventKind::Yield(_)) => true,
+				(expected, found) => expected == found,
+			};
+			if !same {
+				return Err(Divergence {
+					step,
+					name: name.to_owned(),
+					expected: expected.kind,
+					found,
+				})
+			}
+		}
+
+		Ok(())
+	}
+}
+impl<T: PartialEq + Debug + 'static> StepDriver<T> {
+	/// Resumes the generator with the given name once, and asserts that it
+	/// produced the expected outcome.
+	#[track_caller]
+	pub fn assert_step(&mut self, name: &str, expected: Step<T>) {
+		let step = self.step(name);
+		assert_eq!(step, expected, "unexpected step from generator {name:?}");
+	}
+
+	/// Resumes the generators with the given names once each, in order, and
+	/// asserts that each of them produced the expected outcome.
+	#[track_caller]
+	pub fn assert_script(&mut self, script: &[(&str, Step<T>)]) {
+		for (i, (name, expected)) in script.iter().enumerate() {
+			let step = self.step(name);
+			assert_eq!(&step, expected, "unexpected step #{i} from generator {name:?}");
+		}
+	}
+}
+/// Error returned when a generator does something other than what it did in
+/// the recording being replayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+	/// The index of the event in the recording that didn't happen again.
+	pub step: usize,
+	/// The name of the generator that diverged.
+	pub name: String,
+	/// What the generator did in the recording.
+	pub expected: EventKind,
+	/// What the generator did this time around.
+	pub found: EventKind,
+}
+impl fmt::Display for Divergence {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "generator {:?} diverged at event #{}: expected {:?}, found {:?}",
+			self.name, self.step, self.expected, self.found)
+	}
+}
+impl std::error::Error for Divergence {}
+
+/// One of the steps a [`MockGenerator`] plays back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockStep<T> {
+	/// The producer yields the given value.
+	Yield(T),
+	/// The producer reaches a checkpoint, as with [`yield_now`].
+	///
+	/// [`yield_now`]: crate::yield_now
+	Pending,
+	/// The producer panics with the given message.
+	Panic(String),
+}
+
+/// Stand-in for a [`Generator`] that plays back a script of steps, for testing
+/// consumers written against [`Generate`] without running any producers.
+///
+/// The mock behaves as a generator whose producer goes through the steps in
+/// order, and finishes once it runs out of them. Panics get propagated to the
+/// consumer, after which the mock is done, as a poisoned generator would be.
+/// What the consumer did to the mock can be observed through a [`MockHandle`],
+/// which outlives the mock.
+///
+/// ```rust
+/// use yeet::Generate;
+/// use yeet::testing::{MockGenerator, MockStep};
+///
+/// /// Takes values until the first zero, cancelling the producer there.
+/// fn until_zero(mut gen: impl Generate<u32>) -> Vec<u32> {
+///     let mut values = Vec::new();
+///     while let Some(value) = gen.next_value() {
+///         if value == 0 {
+///             break
+///         }
+///         values.push(value);
+///     }
+///     let _ = gen.close();
+///     values
+/// }
+///
+/// let mock = MockGenerator::new([
+///     MockStep::Yield(2),
+///     MockStep::Pending,
+///     MockStep::Yield(0),
+///     MockStep::Yield(3),
+/// ]);
+/// let handle = mock.handle();
+///
+/// assert_eq!(until_zero(mock), [2]);
+/// assert!(handle.cancelled());
+/// assert_eq!(handle.resumes(), 3);
+/// ```
+pub struct MockGenerator<T> {
+	/// The steps left to play back.
+	steps: VecDeque<MockStep<T>>,
+	/// What has been done to the mock so far.
+	state: Rc<MockState>,
+}
+
+/// What has been done to a [`MockGenerator`].
+#[derive(Default)]
+struct MockState {
+	/// The number of times the mock has been resumed.
+	resumes: Cell<usize>,
+	/// Whether the mock has played back all of its steps, or panicked.
+	finished: Cell<bool>,
+	/// Whether the mock has been closed or dropped before it finished.
+	cancelled: Cell<bool>,
+}
+
+impl<T> MockGenerator<T> {
+	/// Creates a new mock playing back the given steps.
+	pub fn new(steps: impl IntoIterator<Item = MockStep<T>>) -> Self {
+		Self {
+			steps: steps.into_iter().collect(),
+			state: Default::default(),
+		}
+	}
+
+	/// Creates a new mock yielding the given values.
+	pub fn from_values(values: impl IntoIterator<Item = T>) -> Self {
+		Self::new(values.into_iter().map(MockStep::Yield))
+	}
+
+	/// A handle through which what is done to the mock can be observed, even
+	/// after it is gone.
+	pub fn handle(&self) -> MockHandle {
+		MockHandle { state: self.state.clone() }
+	}
+
+	/// The number of steps left to play back.
+	pub fn remaining(&self) -> usize {
+		self.steps.len()
+	}
+}
+impl<T> Generate<T> for MockGenerator<T> {
+	fn resume(&mut self) -> Resume<T> {
+		if self.state.finished.get() {
+			return Resume::Complete
+		}
+		self.state.resumes.set(self.state.resumes.get() + 1);
+
+		match self.steps.pop_front() {
+			Some(MockStep::Yield(value)) => Resume::Value(value),
+			Some(MockStep::Pending) => Resume::Pending,
+			Some(MockStep::Panic(message)) => {
+				self.state.finished.set(true);
+				std::panic::panic_any(message)
+			}
+			None => {
+				self.state.finished.set(true);
+				Resume::Complete
+			}
+		}
+	}
+
+	fn close(self) -> Result<(), Payload> {
+		/* Dropping takes care of it. */
+		Ok(())
+	}
+}
+impl<T> Iterator for MockGenerator<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		self.next_value()
+	}
+}
+impl<T> Drop for MockGenerator<T> {
+	fn drop(&mut self) {
+		if !self.state.finished.get() {
+			self.state.cancelled.set(true)
+		}
+	}
+}
+
+/// Observes what has been done to a [`MockGenerator`].
+#[derive(Clone)]
+pub struct MockHandle {
+	/// What has been done to the mock so far.
+	state: Rc<MockState>,
+}
+impl MockHandle {
+	/// The number of times the mock has been resumed, not counting the times
+	/// it was resumed after it had finished.
+	pub fn resumes(&self) -> usize {
+		self.state.resumes.get()
+	}
+
+	/// Whether the mock has played back all of its steps, or panicked.
+	pub fn finished(&self) -> bool {
+		self.state.finished.get()
+	}
+
+	/// Whether the mock has been closed or dropped before it finished, which
+	/// would have cancelled the producer of a generator.
+	pub fn cancelled(&self) -> bool {
+		self.state.cancelled.get()
+	}
+}
+
+impl<T: 'static> Default for StepDriver<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
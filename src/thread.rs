@@ -0,0 +1,269 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{self, AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "stream")]
+use std::sync::mpsc::TryRecvError;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+use crate::{Generator, Timeout};
+use crate::handoff::{self, TryRecv};
+use crate::panic::Payload;
+
+/// A generator whose producer runs on a dedicated thread.
+///
+/// Producers run by [`Generator`] share the thread of their consumer, which is
+/// what makes them cheap, but it also means a producer that blocks, waiting on
+/// a socket or on a disk, blocks its consumer along with it. This structure
+/// instead runs the producer as a regular generator on a thread of its own,
+/// and hands the values it yields over to the consumer through a bounded
+/// channel, so the producer can run ahead of the consumer by as many values as
+/// the channel holds.
+///
+/// Alternatively, generators created with [`ThreadGenerator::double_buffered`]
+/// hand values over through a pair of slots, so that the producer can work on
+/// the next value while the consumer works on the current one, which is
+/// cheaper than going through a channel.
+///
+/// Panics raised by the producer are propagated to the consumer, as they would
+/// be by [`Generator::next`]. Dropping this structure stops the producer the
+/// next time it yields a value, but it doesn't wait for that to happen.
+pub struct ThreadGenerator<T: std::marker::Send + 'static> {
+	/// The values coming out of the producer thread.
+	rx: Receiver<T>,
+	/// The task waiting on the producer thread, if the generator is being
+	/// polled as a stream.
+	#[cfg_attr(not(feature = "stream"), allow(dead_code))]
+	waker: Arc<WakerSlot>,
+}
+impl<T: std::marker::Send + 'static> ThreadGenerator<T> {
+	/// The number of values buffered by generators created without a specific
+	/// capacity.
+	pub const DEFAULT_CAPACITY: usize = 16;
+
+	/// Runs the given function as a producer on a new thread, buffering up to
+	/// [`ThreadGenerator::DEFAULT_CAPACITY`] values.
+	pub fn from_fn_ptr(func: fn()) -> Self {
+		Self::with_capacity(Self::DEFAULT_CAPACITY, func)
+	}
+
+	/// Runs the given function as a producer on a new thread, buffering up to
+	/// the given number of values.
+	///
+	/// # Panic
+	/// This function panics if the thread could not be spawned.
+	pub fn with_capacity(capacity: usize, func: fn()) -> Self {
+		let (tx, rx) = mpsc::sync_channel(capacity);
+		let waker = spawn(func, move |message| tx.send(message).is_ok());
+
+		Self { rx: Receiver::Channel(rx), waker }
+	}
+
+	/// Runs the given function as a producer on a new thread, handing values
+	/// over to the consumer through a pair of slots.
+	///
+	/// # Panic
+	/// This function panics if the thread could not be spawned.
+	pub fn double_buffered(func: fn()) -> Self {
+		let (tx, rx) = handoff::handoff();
+		let waker = spawn(func, move |message| tx.send(message).is_ok());
+
+		Self { rx: Receiver::Handoff(rx), waker }
+	}
+
+	/// Requests the next value from the producer, giving up if it doesn't yield
+	/// one within the given amount of time.
+	///
+	/// Unlike with [`Generator::next_timeout`], the producer runs on a thread
+	/// of its own, so it doesn't have to cooperate for the timeout to work.
+	/// The producer keeps running after a timeout, and the value it eventually
+	/// yields is returned by the next request.
+	pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<T>, Timeout> {
+		/* Timeouts too far out to be represented never run out. */
+		match self.rx.recv_until(Instant::now().checked_add(timeout)) {
+			TryRecv::Value(message) => Ok(message.open()),
+			TryRecv::Empty => Err(Timeout),
+			TryRecv::Closed => Ok(None),
+		}
+	}
+
+	/// Takes the next value from the producer, if it is ready, and otherwise
+	/// has the producer thread wake the given task once it is.
+	#[cfg(feature = "stream")]
+	pub(crate) fn poll_value(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		let poll = |rx: &Receiver<T>| match rx.try_recv() {
+			TryRecv::Value(message) => Poll::Ready(message.open()),
+			TryRecv::Empty => Poll::Pending,
+			TryRecv::Closed => Poll::Ready(None),
+		};
+		if let Poll::Ready(value) = poll(&self.rx) {
+			return Poll::Ready(value)
+		}
+
+		/* The producer may have sent a value after we last looked, but before
+		 * the waker was in place, so we have to look again. */
+		self.waker.register(cx.waker());
+		poll(&self.rx)
+	}
+}
+impl<T: std::marker::Send + 'static> Iterator for ThreadGenerator<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		match self.rx.recv_until(None) {
+			TryRecv::Value(message) => message.open(),
+			_ => None,
+		}
+	}
+}
+
+/// The receiving end of the link between the producer thread and the consumer.
+enum Receiver<T> {
+	/// A bounded channel.
+	Channel(mpsc::Receiver<Message<T>>),
+	/// A double-buffered handoff.
+	Handoff(handoff::Receiver<Message<T>>),
+}
+impl<T> Receiver<T> {
+	/// Receives a message, waiting until one is ready, or until the given
+	/// deadline, if there is one.
+	fn recv_until(&self, deadline: Option<Instant>) -> TryRecv<Message<T>> {
+		match self {
+			Receiver::Channel(rx) => {
+				let result = match deadline {
+					Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+					None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+				};
+				match result {
+					Ok(message) => TryRecv::Value(message),
+					Err(RecvTimeoutError::Timeout) => TryRecv::Empty,
+					Err(RecvTimeoutError::Disconnected) => TryRecv::Closed,
+				}
+			}
+			Receiver::Handoff(rx) => rx.recv_until(deadline),
+		}
+	}
+
+	/// Receives a message, if one is ready.
+	#[cfg(feature = "stream")]
+	fn try_recv(&self) -> TryRecv<Message<T>> {
+		match self {
+			Receiver::Channel(rx) => match rx.try_recv() {
+				Ok(message) => TryRecv::Value(message),
+				Err(TryRecvError::Empty) => TryRecv::Empty,
+				Err(TryRecvError::Disconnected) => TryRecv::Closed,
+			},
+			Receiver::Handoff(rx) => rx.try_recv(),
+		}
+	}
+}
+
+/// Spawns a thread running the given function as a producer, which hands
+/// messages over to the consumer through the given function, and returns the
+/// slot through which the consumer may ask to be woken up when it does.
+fn spawn<T: std::marker::Send + 'static>(
+	func: fn(),
+	mut send: impl FnMut(Message<T>) -> bool + std::marker::Send + 'static
+) -> Arc<WakerSlot> {
+	let waker = Arc::new(WakerSlot::default());
+	let slot = waker.clone();
+	std::thread::Builder::new()
+		.name("yeet-producer".into())
+		.spawn(move || {
+			pump(Generator::<T>::from_fn_ptr(func), |message| {
+				let sent = send(message);
+				slot.wake();
+				sent
+			});
+
+			/* The consumer finds out the producer is done once the sender goes
+			 * away, so that has to happen before it gets woken up. */
+			drop(send);
+			slot.wake();
+		})
+		.expect("Could not spawn producer thread");
+
+	waker
+}
+
+/// Holds the waker of the task waiting on the producer thread, if any.
+#[derive(Default)]
+struct WakerSlot {
+	/// Whether there is a waker in the slot, which spares the producer from
+	/// taking the lock after every value if nobody is waiting.
+	armed: AtomicBool,
+	/// The waker itself.
+	waker: Mutex<Option<Waker>>,
+}
+impl WakerSlot {
+	/// Puts the given waker in the slot, replacing whatever was there.
+	///
+	/// The consumer must check for values again after this, as values sent
+	/// before the waker was in place don't wake it.
+	#[cfg(feature = "stream")]
+	fn register(&self, waker: &Waker) {
+		let mut slot = self.waker.lock().unwrap_or_else(PoisonError::into_inner);
+		match &mut *slot {
+			Some(old) => old.clone_from(waker),
+			None => *slot = Some(waker.clone()),
+		}
+		self.armed.store(true, Ordering::Relaxed);
+		drop(slot);
+
+		/* Pairs with the fence in `wake`, so that either the consumer sees the
+		 * value that was sent, or the producer sees the waker. */
+		atomic::fence(Ordering::SeqCst);
+	}
+
+	/// Wakes the task in the slot, if there is one, emptying the slot.
+	fn wake(&self) {
+		atomic::fence(Ordering::SeqCst);
+		if !self.armed.load(Ordering::Relaxed) {
+			return
+		}
+
+		let waker = {
+			let mut slot = self.waker.lock().unwrap_or_else(PoisonError::into_inner);
+			self.armed.store(false, Ordering::Relaxed);
+			slot.take()
+		};
+		if let Some(waker) = waker {
+			waker.wake()
+		}
+	}
+}
+
+/// Messages sent from the producer thread to the consumer.
+enum Message<T> {
+	/// The producer has yielded the given value.
+	Value(T),
+	/// The producer has panicked with the given payload.
+	Panic(Payload),
+}
+impl<T> Message<T> {
+	/// Turns this message into a value, propagating panics.
+	fn open(self) -> Option<T> {
+		match self {
+			Message::Value(value) => Some(value),
+			Message::Panic(what) => std::panic::resume_unwind(what),
+		}
+	}
+}
+
+/// Drives the given generator, sending the values it yields to the consumer,
+/// until either it is done, or the consumer goes away.
+fn pump<T: 'static>(mut gen: Generator<T>, mut send: impl FnMut(Message<T>) -> bool) {
+	loop {
+		let next = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+		let message = match next {
+			Ok(Some(value)) => Message::Value(value),
+			Ok(None) => break,
+			Err(what) => Message::Panic(what),
+		};
+		let panicked = matches!(message, Message::Panic(_));
+		if !send(message) || panicked {
+			break
+		}
+	}
+}
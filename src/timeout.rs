@@ -0,0 +1,43 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use crate::{Generator, Resume};
+
+impl<T: 'static> Generator<T> {
+	/// Requests the next value from the generator, giving up if the producer
+	/// doesn't yield one within the given amount of time.
+	///
+	/// The producer runs on the current thread, so it can't be interrupted at
+	/// an arbitrary point. Instead, the deadline is checked every time the
+	/// producer reaches a checkpoint with [`yield_now`], at which point the
+	/// producer is left suspended and [`Timeout`] is returned. The producer
+	/// may then be resumed later, as if nothing had happened. Producers that
+	/// never reach a checkpoint can't be timed out this way. Producers that
+	/// [`sleep`] have the thread block until they wake up, or until the
+	/// deadline, whichever comes first.
+	///
+	/// [`sleep`]: crate::sleep
+	/// [`yield_now`]: crate::yield_now
+	pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<T>, Timeout> {
+		/* Timeouts too far out to be represented never run out. */
+		let deadline = Instant::now().checked_add(timeout);
+		loop {
+			match self.resume() {
+				Resume::Value(value) => break Ok(Some(value)),
+				Resume::Complete => break Ok(None),
+				Resume::Pending if deadline.is_some_and(|deadline| Instant::now() >= deadline) =>
+					break Err(Timeout),
+				Resume::Pending => self.wait_deadline(deadline),
+			}
+		}
+	}
+}
+
+/// Error returned when a producer fails to yield a value in time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Timeout;
+impl fmt::Display for Timeout {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("producer did not yield a value in time")
+	}
+}
+impl std::error::Error for Timeout {}
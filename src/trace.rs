@@ -0,0 +1,60 @@
+//! Logging of every switch between consumers and producers.
+//!
+//! With the `debug-log` feature enabled, every time a consumer enters a task,
+//! and every time it gets control back, a record gets logged at the trace
+//! level under the `yeet` target, saying which task it was, which way control
+//! went, and what got sent along with it. Values themselves are never logged,
+//! only what kind of payload went through.
+use std::fmt;
+use crate::{Send, Yield};
+use crate::sys::Header;
+
+/// Displays a task by its name, if it has one, and by its identifier.
+struct Label<'a>(&'a Header);
+impl fmt::Display for Label<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.0.name {
+			Some(name) => write!(f, "{name:?} (#{})", self.0.id.as_u64()),
+			None => write!(f, "#{}", self.0.id.as_u64()),
+		}
+	}
+}
+
+/// Displays the kind of data sent into a task.
+struct Sent<'a>(&'a Send);
+impl fmt::Display for Sent<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Send::Continue => f.write_str("continue"),
+			Send::Cancel => f.write_str("cancel"),
+			Send::Skip(n) => write!(f, "skip({n})"),
+		}
+	}
+}
+
+/// Displays the kind of data yielded by a task.
+struct Yielded<'a, T>(&'a Yield<T>);
+impl<T> fmt::Display for Yielded<'_, T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Yield::Value(_) => f.write_str("value"),
+			Yield::Batch(values) => write!(f, "batch({})", values.len()),
+			Yield::Pending => f.write_str("pending"),
+			Yield::StopIteration => f.write_str("stop"),
+			Yield::Panic(_) => f.write_str("panic"),
+		}
+	}
+}
+
+/* Nothing here gets formatted unless the record is going to be logged, so the
+ * switch path never allocates on account of logging. */
+
+/// Logs the consumer entering the given task with the given data.
+pub(crate) fn resume(header: &Header, data: &Send) {
+	log::trace!(target: "yeet", "resume {} with {} at depth {}", Label(header), Sent(data), header.depth);
+}
+
+/// Logs the given task switching back to the consumer with the given data.
+pub(crate) fn yielded<T>(header: &Header, data: &Yield<T>) {
+	log::trace!(target: "yeet", "yield {} with {}", Label(header), Yielded(data));
+}
@@ -0,0 +1,64 @@
+use crate::GeneratorBuilder;
+
+/// The outcome of a step of a computation run by [`trampoline`].
+pub enum Bounce<R> {
+	/// The computation is done, with the given result.
+	Done(R),
+	/// The computation continues with the given step.
+	Call(Box<dyn FnOnce() -> Bounce<R>>),
+}
+impl<R> Bounce<R> {
+	/// Continues the computation with the given step.
+	pub fn call(step: impl FnOnce() -> Bounce<R> + 'static) -> Self {
+		Self::Call(Box::new(step))
+	}
+}
+
+/// Runs a computation made up of steps that hand over to one another, until
+/// one of them is done, without the stack growing with every handover.
+///
+/// Every step either finishes the computation with [`Bounce::Done`], or asks
+/// for it to be continued with another step with [`Bounce::call`], which is
+/// what a tail call would do in plain recursion. Rather than being called from
+/// the step that asked for it, the continuation gets run by the driver in this
+/// function, on a fresh task, once the step that asked for it is gone. This
+/// turns deep, and even mutual, recursion into iteration: no matter how many
+/// steps the computation takes, there is only ever a single step on a stack at
+/// a time, and it never runs on the stack of the caller.
+///
+/// ```rust
+/// use yeet::Bounce;
+///
+/// fn is_even(n: u64) -> Bounce<bool> {
+///     if n == 0 { Bounce::Done(true) } else { Bounce::call(move || is_odd(n - 1)) }
+/// }
+/// fn is_odd(n: u64) -> Bounce<bool> {
+///     if n == 0 { Bounce::Done(false) } else { Bounce::call(move || is_even(n - 1)) }
+/// }
+///
+/// assert!(yeet::trampoline(|| is_even(10_000)));
+/// ```
+///
+/// # Panic
+/// Panics raised by any of the steps propagate to the caller.
+pub fn trampoline<R: 'static>(step: impl FnOnce() -> Bounce<R> + 'static) -> R {
+	let mut next: Box<dyn FnOnce() -> Bounce<R>> = Box::new(step);
+	loop {
+		let mut task = GeneratorBuilder::new()
+			.build_closure::<Bounce<R>>(move || crate::yeet(next()));
+
+		/* Panics in the step propagate out of here on their own. */
+		let bounce = match task.next() {
+			Some(bounce) => bounce,
+			None => unreachable!("a step of the computation finished without an outcome"),
+		};
+
+		/* Same as with the levels of a recursion, letting the step finish is
+		 * cheaper than cancelling it. */
+		let _ = task.next();
+		match bounce {
+			Bounce::Done(result) => break result,
+			Bounce::Call(step) => next = step,
+		}
+	}
+}
@@ -0,0 +1,104 @@
+use std::marker::PhantomData;
+use crate::{current_task, Generator, TaskId};
+use crate::sys::Entry;
+
+impl<T: 'static> Generator<T> {
+	/// Creates a new instance of this structure from a raw function pointer,
+	/// which gets handed a [`Yielder`] for values of type `T`.
+	///
+	/// Producers that yield through the [`Yielder`] can't yield values of the
+	/// wrong type, as the type of the yielder has to match the type of the
+	/// generator, so this never fails at runtime the way yielding through
+	/// [`yeet`] with the wrong type does. Like [`Generator::from_fn_ptr`], this
+	/// needs no allocation other than the stack.
+	///
+	/// ```rust
+	/// use yeet::{Generator, Yielder};
+	///
+	/// fn squares(y: Yielder<u64>) {
+	///     for i in 1..4 {
+	///         y.yeet(i * i)
+	///     }
+	/// }
+	///
+	/// let gen = Generator::from_typed_fn(squares);
+	/// assert_eq!(gen.collect::<Vec<_>>(), [1, 4, 9]);
+	/// ```
+	///
+	/// [`yeet`]: crate::yeet
+	pub fn from_typed_fn(func: fn(Yielder<T>)) -> Self {
+		Self::from_entry(entry(func))
+	}
+}
+
+/// Erases the type of the given function, so that it fits in an [`Entry`].
+pub(crate) fn entry<T: 'static>(func: fn(Yielder<T>)) -> Entry {
+	Entry::Typed(func as *const (), call_typed::<T>)
+}
+
+/// Calls a function taken by [`Generator::from_typed_fn`], once it has been
+/// erased into a pointer, with a yielder for the current task.
+fn call_typed<T: 'static>(func: *const ()) {
+	let func = unsafe { std::mem::transmute::<*const (), fn(Yielder<T>)>(func) };
+	func(Yielder {
+		task: current_task().unwrap().id,
+		_marker: PhantomData,
+	})
+}
+
+/// Handle through which a producer created with [`Generator::from_typed_fn`]
+/// yields values of type `T`.
+///
+/// Yielders belong to the producer they were handed to, and can't be used
+/// from anywhere else, not even from producers nested inside of it.
+pub struct Yielder<T> {
+	/// The task the yielder belongs to.
+	task: TaskId,
+	/// Yielders are tied to the thread of their task, and only yield values
+	/// of type `T`.
+	_marker: PhantomData<*const T>,
+}
+impl<T: 'static> Yielder<T> {
+	/// Yields the given value, as with [`yeet`].
+	///
+	/// # Panic
+	/// This function panics if it is not being called from the producer the
+	/// yielder was handed to.
+	///
+	/// [`yeet`]: crate::yeet
+	pub fn yeet(&self, value: T) {
+		self.check();
+		crate::yeet(value)
+	}
+
+	/// Yields all of the values in the given iterator, as with [`yeet_all`].
+	///
+	/// # Panic
+	/// This function panics if it is not being called from the producer the
+	/// yielder was handed to.
+	///
+	/// [`yeet_all`]: crate::yeet_all
+	pub fn yeet_all(&self, iter: impl IntoIterator<Item = T>) {
+		self.check();
+		crate::yeet_all(iter.into_iter())
+	}
+
+	/// Suspends the producer without yielding a value, as with [`yield_now`].
+	///
+	/// # Panic
+	/// This function panics if it is not being called from the producer the
+	/// yielder was handed to.
+	///
+	/// [`yield_now`]: crate::yield_now
+	pub fn yield_now(&self) {
+		self.check();
+		crate::yield_now()
+	}
+
+	/// Makes sure the yielder is being used from the producer it belongs to.
+	fn check(&self) {
+		if current_task().map(|task| task.id) != Some(self.task) {
+			panic!("Tried to yield through a yielder from outside of its producer!")
+		}
+	}
+}
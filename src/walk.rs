@@ -0,0 +1,116 @@
+//! Generators that walk over trees.
+//!
+//! Walking over a tree is the canonical use of generators, and the obvious
+//! way to write one, with a generator for every node, yielding all of the
+//! values of the generators of its children, costs a task and a stack per
+//! node, and a switch per value per level the value goes up. The generators in
+//! this module instead walk the whole tree from a single task, keeping track
+//! of where they are in it on the heap, so that trees of any depth cost a
+//! single stack, and every node comes out of the walk with a single switch.
+//!
+//! Trees are given as a root, and a function returning the children of a
+//! node, in order. Nodes are handed to the consumer by value, and are never
+//! cloned, so they'd usually be references to nodes kept somewhere else, or
+//! cheap handles to them.
+//!
+//! ```rust
+//! use std::rc::Rc;
+//!
+//! struct Node {
+//!     name: &'static str,
+//!     children: Vec<Rc<Node>>,
+//! }
+//!
+//! let leaf = |name| Rc::new(Node { name, children: vec![] });
+//! let root = Rc::new(Node {
+//!     name: "a",
+//!     children: vec![
+//!         Rc::new(Node { name: "b", children: vec![leaf("d"), leaf("e")] }),
+//!         leaf("c"),
+//!     ],
+//! });
+//! let children = |node: &Rc<Node>| node.children.clone();
+//!
+//! let names = |walk: yeet::Generator<Rc<Node>>| walk.map(|node| node.name).collect::<String>();
+//! assert_eq!(names(yeet::walk::depth_first(root.clone(), children)), "abdec");
+//! assert_eq!(names(yeet::walk::breadth_first(root.clone(), children)), "abcde");
+//! assert_eq!(names(yeet::walk::post_order(root.clone(), children)), "debca");
+//! ```
+//!
+//! # Graphs
+//! Walking over graphs that aren't trees visits nodes that can be reached in
+//! more ways than one once per way, and never finishes in graphs with cycles.
+//! Graphs can be walked as trees by having the function that lists children
+//! leave out the nodes it has already listed, which visits every node that can
+//! be reached from the root exactly once.
+use std::collections::VecDeque;
+use crate::{yeet, Generator};
+
+/// Walks over a tree in depth-first order, yielding every node before the
+/// nodes under it.
+pub fn depth_first<N, I, F>(root: N, mut children: F) -> Generator<N>
+	where N: 'static,
+		I: IntoIterator<Item = N>,
+		I::IntoIter: 'static,
+		F: FnMut(&N) -> I + 'static {
+
+	Generator::from_closure(move || {
+		let mut stack = vec![children(&root).into_iter()];
+		yeet(root);
+
+		while let Some(siblings) = stack.last_mut() {
+			match siblings.next() {
+				Some(node) => {
+					/* The children have to be listed before the node goes to
+					 * the consumer, as we don't get it back. */
+					stack.push(children(&node).into_iter());
+					yeet(node)
+				},
+				None => { stack.pop(); }
+			}
+		}
+	})
+}
+
+/// Walks over a tree in breadth-first order, yielding all of the nodes at
+/// any one depth before the nodes any deeper.
+pub fn breadth_first<N, I, F>(root: N, mut children: F) -> Generator<N>
+	where N: 'static,
+		I: IntoIterator<Item = N>,
+		F: FnMut(&N) -> I + 'static {
+
+	Generator::from_closure(move || {
+		let mut queue = VecDeque::from([root]);
+		while let Some(node) = queue.pop_front() {
+			queue.extend(children(&node));
+			yeet(node)
+		}
+	})
+}
+
+/// Walks over a tree in post-order, yielding every node after the nodes
+/// under it.
+pub fn post_order<N, I, F>(root: N, mut children: F) -> Generator<N>
+	where N: 'static,
+		I: IntoIterator<Item = N>,
+		I::IntoIter: 'static,
+		F: FnMut(&N) -> I + 'static {
+
+	Generator::from_closure(move || {
+		let below = children(&root).into_iter();
+		let mut stack = vec![(root, below)];
+
+		while let Some((_, below)) = stack.last_mut() {
+			match below.next() {
+				Some(node) => {
+					let below = children(&node).into_iter();
+					stack.push((node, below))
+				},
+				None => {
+					let (node, _) = stack.pop().unwrap();
+					yeet(node)
+				}
+			}
+		}
+	})
+}
@@ -0,0 +1,137 @@
+//! This module tests adapters that run on the producer side of a generator.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use yeet::Generator;
+use yeet::hook::Direction;
+
+fn gen() {
+	yeet::yeet_all(0..100u32)
+}
+
+#[test]
+fn filter() {
+	let gen = Generator::<u32>::from_fn_ptr(gen)
+		.filter_in_task(|value| value % 10 == 0);
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
+}
+
+#[test]
+fn stacked_filters() {
+	let gen = Generator::<u32>::from_fn_ptr(gen)
+		.filter_in_task(|value| value % 2 == 0)
+		.filter_in_task(|value| value % 3 == 0)
+		.filter_in_task(|value| *value < 20);
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 6, 12, 18]);
+}
+
+#[test]
+fn filtered_values_do_not_switch() {
+	let switches = Rc::new(Cell::new(0));
+	let mut gen = Generator::<u32>::from_fn_ptr(gen)
+		.filter_in_task(|value| value % 50 == 0);
+	gen.on_switch({
+		let switches = switches.clone();
+		move |event| if event.direction == Direction::Yield {
+			switches.set(switches.get() + 1)
+		}
+	});
+
+	assert_eq!(gen.by_ref().count(), 2);
+
+	/* Two values, plus the end of the iteration. */
+	assert_eq!(switches.get(), 3);
+}
+
+#[test]
+fn map() {
+	let gen = Generator::<u32>::from_fn_ptr(gen)
+		.map_in_task(|value| format!("#{value}"));
+	assert_eq!(gen.take(3).collect::<Vec<_>>(), &["#0", "#1", "#2"]);
+}
+
+#[test]
+fn map_same_type() {
+	let gen = Generator::<u32>::from_fn_ptr(gen)
+		.map_in_task(|value| value * 2);
+	assert_eq!(gen.take(3).collect::<Vec<_>>(), &[0, 2, 4]);
+}
+
+#[test]
+fn filter_map_filter() {
+	let gen = Generator::<u32>::from_fn_ptr(gen)
+		.filter_in_task(|value| value % 2 == 1)
+		.map_in_task(|value| value as u64 * 3)
+		.map_in_task(|value| value.to_string())
+		.filter_in_task(|value| value.ends_with('1'));
+	assert_eq!(gen.take(3).collect::<Vec<_>>(), &["21", "51", "81"]);
+}
+
+#[test]
+#[should_panic]
+fn map_started() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	gen.next();
+	let _ = gen.map_in_task(|value| value);
+}
+
+#[test]
+fn chunks() {
+	let gen = Generator::<u32>::from_fn_ptr(gen);
+	let chunks = gen.chunks(32).collect::<Vec<_>>();
+	assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), &[32, 32, 32, 4]);
+	assert_eq!(chunks.concat(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn chunks_after_peek() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen).filter_in_task(|value| *value < 5);
+	assert_eq!(gen.peek(), Some(&0));
+	assert_eq!(gen.chunks(2).collect::<Vec<_>>(), &[vec![0, 1], vec![2, 3], vec![4]]);
+}
+
+#[test]
+fn chunks_switch_once_per_chunk() {
+	let switches = Rc::new(Cell::new(0));
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	gen.on_switch({
+		let switches = switches.clone();
+		move |event| if event.direction == Direction::Yield {
+			switches.set(switches.get() + 1)
+		}
+	});
+
+	let mut chunks = gen.chunks(50);
+	assert_eq!(chunks.by_ref().count(), 2);
+
+	/* Two chunks, plus the end of the iteration. */
+	assert_eq!(switches.get(), 3);
+}
+
+#[test]
+fn flatten() {
+	fn count(n: u32) {
+		yeet::yeet_all(0u32..n)
+	}
+
+	let gen = Generator::<Generator<u32>>::from_fn_ptr(|| {
+		for n in 0..4u32 {
+			yeet::yeet(Generator::<u32>::from_fn_with(n, count))
+		}
+	});
+	assert_eq!(gen.flatten().collect::<Vec<_>>(), [0, 0, 1, 0, 1, 2]);
+}
+
+#[test]
+fn flatten_drives_inner_generators_directly() {
+	fn inner() {
+		let me = yeet::current_task().unwrap();
+		yeet::yeet((me.parent, me.depth))
+	}
+
+	type Position = (Option<yeet::TaskId>, usize);
+	let outer = Generator::<Generator<Position>>::from_fn_ptr(|| {
+		yeet::yeet(Generator::<Position>::from_fn_ptr(inner))
+	});
+	assert_eq!(outer.flatten().collect::<Vec<_>>(), [(None, 1)]);
+}
@@ -0,0 +1,61 @@
+//! Tests for the memory allocated by generators.
+//!
+//! The state of a task lives inline in its [`Generator`], so the memory the
+//! stack of the task runs on should be the only thing that gets allocated when
+//! a generator is created from a function pointer. On Unix, stacks are mapped
+//! straight from the operating system, so the global allocator should never be
+//! involved at all.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use yeet::Generator;
+
+/// Counts the allocations made by each thread.
+struct Counting;
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.set(ALLOCATIONS.get() + 1);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+thread_local! {
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations made by the current thread while running the given
+/// function.
+fn allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+	let before = ALLOCATIONS.get();
+	let result = f();
+	(result, ALLOCATIONS.get() - before)
+}
+
+fn count() {
+	yeet::yeet_all(0u32..3)
+}
+
+#[test]
+fn creation() {
+	let (gen, count) = allocations(|| Generator::<u32>::from_fn_ptr(count));
+	assert_eq!(count, if cfg!(unix) { 0 } else { 1 });
+	drop(gen);
+}
+
+#[test]
+#[cfg(not(feature = "corosensei"))]
+fn iteration() {
+	/* The first generator to run on a thread sets up some bookkeeping. */
+	Generator::<u32>::from_fn_ptr(count).for_each(drop);
+
+	let mut gen = Generator::<u32>::from_fn_ptr(count);
+	let (values, count) = allocations(|| [gen.next(), gen.next(), gen.next(), gen.next()]);
+	assert_eq!(values, [Some(0), Some(1), Some(2), None]);
+	assert_eq!(count, 0);
+}
@@ -0,0 +1,47 @@
+//! This module tests values allocated by producers in arenas owned by their
+//! consumers.
+
+use yeet::{Arena, ArenaRef, Generator};
+
+enum Expr {
+	Num(u32),
+	Add(ArenaRef<Expr>, ArenaRef<Expr>),
+}
+
+fn eval(arena: &Arena<Expr>, expr: ArenaRef<Expr>) -> u32 {
+	match arena[expr] {
+		Expr::Num(value) => value,
+		Expr::Add(lhs, rhs) => eval(arena, lhs) + eval(arena, rhs),
+	}
+}
+
+/// Yields the sums `1`, `1 + 2`, `1 + 2 + 3`, and so on.
+fn sums() {
+	let mut acc = yeet::alloc_in_consumer(Expr::Num(1));
+	yeet::yeet(acc);
+	for i in 2..=4 {
+		let num = yeet::alloc_in_consumer(Expr::Num(i));
+		acc = yeet::alloc_in_consumer(Expr::Add(acc, num));
+		yeet::yeet(acc);
+	}
+}
+
+#[test]
+fn tree() {
+	let mut arena = Arena::new();
+	let mut gen = Generator::<ArenaRef<Expr>>::from_fn_ptr(sums);
+
+	let mut results = Vec::new();
+	while let Some(expr) = gen.next_in(&mut arena) {
+		results.push(eval(&arena, expr));
+	}
+	assert_eq!(results, &[1, 3, 6, 10]);
+	assert_eq!(arena.len(), 7);
+}
+
+#[test]
+#[should_panic]
+fn no_arena() {
+	let mut gen = Generator::<ArenaRef<Expr>>::from_fn_ptr(sums);
+	gen.next();
+}
@@ -0,0 +1,51 @@
+//! This module tests passing startup parameters to producers.
+
+use yeet::{Generator, GeneratorBuilder};
+
+fn range((start, end): (u32, u32)) {
+	yeet::yeet_all(start..end)
+}
+
+#[test]
+fn from_fn_with() {
+	let gen = Generator::<u32>::from_fn_with((3, 6), range);
+	assert_eq!(gen.collect::<Vec<_>>(), &[3, 4, 5]);
+}
+
+#[test]
+fn from_closure() {
+	let words = vec!["a".to_owned(), "b".to_owned()];
+	let gen = Generator::<String>::from_closure(move || {
+		for word in words {
+			yeet::yeet(word)
+		}
+	});
+	assert_eq!(gen.collect::<Vec<_>>(), &["a", "b"]);
+}
+
+#[test]
+fn unstarted_closure_dropped() {
+	use std::rc::Rc;
+
+	let captured = Rc::new(());
+	let gen = Generator::<u32>::from_closure({
+		let captured = captured.clone();
+		move || drop(captured)
+	});
+	assert_eq!(Rc::strong_count(&captured), 2);
+	drop(gen);
+	assert_eq!(Rc::strong_count(&captured), 1);
+}
+
+#[test]
+fn builder() {
+	let gen = GeneratorBuilder::new()
+		.name("range")
+		.build_with::<u32, _>((0, 2), range);
+	assert_eq!(gen.name(), Some("range"));
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 1]);
+
+	let gen = GeneratorBuilder::new()
+		.build_closure::<u32>(|| yeet::yeet(7u32));
+	assert_eq!(gen.collect::<Vec<_>>(), &[7]);
+}
@@ -0,0 +1,46 @@
+//! This module tests slices lent by producers to their consumers.
+
+use yeet::LendingGenerator;
+
+fn lines() {
+	let mut buf = Vec::new();
+	for i in 0..3u8 {
+		buf.clear();
+		buf.extend(std::iter::repeat_n(b'a' + i, i as usize + 1));
+		yeet::yeet_borrowed(&buf);
+	}
+}
+
+#[test]
+fn borrowed() {
+	let mut gen = LendingGenerator::<u8>::from_fn_ptr(lines);
+	let mut seen = Vec::new();
+	while let Some(line) = gen.next() {
+		seen.push(String::from_utf8(line.to_vec()).unwrap());
+	}
+	assert_eq!(seen, &["a", "bb", "ccc"]);
+}
+
+#[test]
+fn no_copy() {
+	thread_local! {
+		static ADDR: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+	}
+	fn gen() {
+		let buf = [1u64; 512];
+		ADDR.set(buf.as_ptr() as usize);
+		yeet::yeet_borrowed(&buf);
+	}
+
+	let mut gen = LendingGenerator::<u64>::from_fn_ptr(gen);
+	let buf = gen.next().unwrap();
+	assert_eq!(buf.as_ptr() as usize, ADDR.get());
+	assert_eq!(buf.len(), 512);
+}
+
+#[test]
+#[should_panic]
+fn wrong_type() {
+	let mut gen = LendingGenerator::<u32>::from_fn_ptr(lines);
+	gen.next();
+}
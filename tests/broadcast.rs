@@ -0,0 +1,130 @@
+//! This module tests generators whose values go to every one of their consumers.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use yeet::{Broadcast, Lag};
+
+fn count() {
+	yeet::yeet_all(0u32..100)
+}
+
+#[test]
+fn every_consumer_gets_every_value() {
+	let first = Broadcast::<u32>::from_fn_ptr(8, Lag::Block, count);
+	let consumers = (0..4)
+		.map(|_| {
+			let consumer = first.subscribe();
+			std::thread::spawn(move || consumer.collect::<Vec<_>>())
+		})
+		.collect::<Vec<_>>();
+
+	assert_eq!(first.collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+	for consumer in consumers {
+		assert_eq!(consumer.join().unwrap(), (0..100).collect::<Vec<_>>());
+	}
+}
+
+#[test]
+fn blocks_on_slow_consumers() {
+	let resumed = Arc::new(AtomicU32::new(0));
+	let counter = resumed.clone();
+	let mut fast = Broadcast::<u32>::from_closure(4, Lag::Block, move || {
+		for i in 0u32..100 {
+			counter.fetch_add(1, Ordering::SeqCst);
+			yeet::yeet(i)
+		}
+	});
+	let mut slow = fast.subscribe();
+
+	assert_eq!(fast.by_ref().take(4).collect::<Vec<_>>(), [0, 1, 2, 3]);
+	std::thread::sleep(Duration::from_millis(20));
+	assert_eq!(slow.pending(), 4);
+	assert_eq!(fast.pending(), 0);
+	assert!(resumed.load(Ordering::SeqCst) <= 5);
+
+	/* The slow consumer can only get as far ahead of the fast one as the
+	 * history lets it, so they have to take turns. */
+	assert_eq!(slow.by_ref().take(8).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+	let fast = std::thread::spawn(move || fast.collect::<Vec<_>>());
+	assert_eq!(slow.collect::<Vec<_>>(), (8..100).collect::<Vec<_>>());
+	assert_eq!(fast.join().unwrap(), (4..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn drops_for_slow_consumers() {
+	let finished = Arc::new(AtomicBool::new(false));
+	let flag = finished.clone();
+	let mut first = Broadcast::<u32>::from_closure(4, Lag::DropOldest, move || {
+		yeet::yeet_all(0u32..100);
+		flag.store(true, Ordering::SeqCst)
+	});
+	let mut second = first.subscribe();
+
+	/* Nobody takes anything, so the producer runs through the whole history
+	 * without waiting on anyone. */
+	while !finished.load(Ordering::SeqCst) {
+		std::thread::sleep(Duration::from_millis(1));
+	}
+	assert_eq!(first.by_ref().collect::<Vec<_>>(), [96, 97, 98, 99]);
+	assert_eq!(first.missed(), 96);
+	assert_eq!(second.next(), Some(96));
+	assert_eq!(second.missed(), 96);
+}
+
+#[test]
+fn subscribers_start_where_they_were_opened() {
+	let mut first = Broadcast::<u32>::from_fn_ptr(16, Lag::Block, count);
+	assert_eq!(first.by_ref().take(3).collect::<Vec<_>>(), [0, 1, 2]);
+
+	let mut second = first.clone();
+	assert_eq!(second.next(), Some(3));
+	assert_eq!(first.next(), Some(3));
+}
+
+#[test]
+fn panic_goes_to_the_first_consumer() {
+	fn gen() {
+		yeet::yeet(0u32);
+		panic!("oops")
+	}
+
+	let mut first = Broadcast::<u32>::from_fn_ptr(4, Lag::Block, gen);
+	let mut second = first.subscribe();
+	assert_eq!(first.next(), Some(0));
+
+	let result = std::panic::catch_unwind(AssertUnwindSafe(|| first.next()));
+	assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "oops");
+	assert_eq!(second.next(), Some(0));
+	assert_eq!(second.next(), None);
+}
+
+#[test]
+fn dropping_cancels_the_producer() {
+	struct Guard(Arc<AtomicU32>);
+	impl Drop for Guard {
+		fn drop(&mut self) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	let dropped = Arc::new(AtomicU32::new(0));
+	let guard = Guard(dropped.clone());
+	let mut first = Broadcast::<u32>::from_closure(2, Lag::Block, move || {
+		let _guard = guard;
+		yeet::yeet_all(0u32..)
+	});
+	let second = first.subscribe();
+	assert_eq!(first.next(), Some(0));
+	drop(first);
+	drop(second);
+
+	for _ in 0..500 {
+		if dropped.load(Ordering::SeqCst) == 1 {
+			return
+		}
+		std::thread::sleep(Duration::from_millis(10));
+	}
+	panic!("The producer was never cancelled")
+}
@@ -0,0 +1,128 @@
+//! This module tests generators created through the builder.
+
+use yeet::GeneratorBuilder;
+
+fn count() {
+	for i in 0..4u32 {
+		yeet::yeet(i);
+	}
+}
+
+#[test]
+fn stack_size() {
+	let gen = GeneratorBuilder::new()
+		.stack_size(64 * 1024)
+		.build::<u32>(count);
+
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 1, 2, 3]);
+}
+
+#[test]
+fn external_stack() {
+	let mut memory = vec![0u64; 32 * 1024].into_boxed_slice();
+	let gen = unsafe {
+		GeneratorBuilder::new()
+			.with_stack(memory.as_mut_ptr() as *mut u8, memory.len() * size_of::<u64>())
+			.build::<u32>(count)
+	};
+
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 1, 2, 3]);
+	assert!(memory.iter().any(|word| *word != 0));
+}
+
+#[test]
+fn pretouch_stack() {
+	let gen = GeneratorBuilder::new()
+		.stack_size(256 * 1024)
+		.pretouch_stack(true)
+		.build::<u32>(count);
+
+	assert_eq!(gen.collect::<Vec<_>>(), &[0, 1, 2, 3]);
+}
+
+#[test]
+fn pretouch_external_stack() {
+	let mut memory = vec![0xffu8; 64 * 1024 + 123].into_boxed_slice();
+	let gen = unsafe {
+		GeneratorBuilder::new()
+			.with_stack(memory.as_mut_ptr(), memory.len())
+			.pretouch_stack(true)
+			.build::<u32>(count)
+	};
+
+	/* Every page in the region has been written to. */
+	assert!(memory.chunks(4096).all(|page| page.contains(&0)));
+	drop(gen);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn name_thread() {
+	fn comm() -> String {
+		std::fs::read_to_string("/proc/thread-self/comm").unwrap().trim_end().to_owned()
+	}
+	fn gen() {
+		yeet::yeet(comm());
+	}
+
+	let before = comm();
+	let mut gen = GeneratorBuilder::new()
+		.name("a-rather-long-task-name")
+		.name_thread(true)
+		.build::<String>(gen);
+
+	assert_eq!(gen.next().as_deref(), Some("a-rather-long-t"));
+	assert_eq!(comm(), before);
+}
+
+#[test]
+fn eager() {
+	let mut gen = GeneratorBuilder::new()
+		.eager(true)
+		.build::<u32>(count);
+	assert_eq!(gen.state(), yeet::registry::TaskState::Suspended);
+	assert_eq!(gen.stats().resumes, 1);
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.stats().resumes, 1);
+	assert_eq!(gen.collect::<Vec<_>>(), [1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "bad configuration")]
+fn eager_surfaces_early_panics() {
+	let _gen = GeneratorBuilder::new()
+		.eager(true)
+		.build::<u32>(|| panic!("bad configuration"));
+}
+
+#[test]
+fn stack_alignment() {
+	const ALIGN: usize = 1024 * 1024;
+	let gen = GeneratorBuilder::new()
+		.stack_size(64 * 1024)
+		.stack_alignment(ALIGN)
+		.build::<usize>(|| yeet::yeet(yeet::registry::current_task().unwrap().stack.start));
+
+	/* Fibers are aligned by the system, whatever we ask for. */
+	for start in gen {
+		if cfg!(not(all(windows, feature = "fibers"))) {
+			assert_eq!(start % ALIGN, 0);
+		}
+	}
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn stack_alignment_must_be_a_power_of_two() {
+	let _builder = GeneratorBuilder::new().stack_alignment(3000);
+}
+
+#[test]
+#[should_panic(expected = "Stacks must be at least")]
+fn tiny_external_stack() {
+	let mut memory = [0u8; 256];
+	unsafe {
+		let _ = GeneratorBuilder::new().with_stack(memory.as_mut_ptr(), memory.len());
+	}
+}
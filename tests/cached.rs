@@ -0,0 +1,74 @@
+//! This module tests generators whose values get cached for replaying.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use yeet::Generator;
+
+thread_local! {
+	static YIELDED: Cell<u32> = const { Cell::new(0) };
+}
+
+fn counting() {
+	for i in 0u32..5 {
+		YIELDED.set(YIELDED.get() + 1);
+		yeet::yeet(i)
+	}
+}
+
+#[test]
+fn replays_without_resuming() {
+	YIELDED.set(0);
+	let cached = Generator::<u32>::from_fn_ptr(counting).cached();
+
+	assert_eq!(cached.replay().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+	assert_eq!(cached.replay().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+	assert_eq!(YIELDED.get(), 5);
+	assert!(cached.is_complete());
+}
+
+#[test]
+fn only_as_far_as_the_furthest_cursor() {
+	YIELDED.set(0);
+	let mut a = Generator::<u32>::from_fn_ptr(counting).cached();
+	let mut b = a.replay();
+
+	assert_eq!(a.next(), Some(0));
+	assert_eq!(a.next(), Some(1));
+	assert_eq!(YIELDED.get(), 2);
+
+	assert_eq!(b.next(), Some(0));
+	assert_eq!(b.next(), Some(1));
+	assert_eq!(b.next(), Some(2));
+	assert_eq!(YIELDED.get(), 3);
+	assert_eq!(a.cached_len(), 3);
+	assert!(!a.is_complete());
+
+	/* Clones carry on from where they were cloned. */
+	let mut c = b.clone();
+	assert_eq!(c.next(), Some(3));
+	assert_eq!(b.next(), Some(3));
+	assert_eq!(a.size_hint().0, 2);
+}
+
+#[test]
+fn shares_values_by_clone() {
+	let cached = Generator::<Rc<str>>::from_fn_ptr(|| {
+		yeet::yeet(Rc::<str>::from("shared"))
+	}).cached();
+
+	let first = cached.replay().next().unwrap();
+	let second = cached.replay().next().unwrap();
+	assert!(Rc::ptr_eq(&first, &second));
+}
+
+#[test]
+fn outlives_panics() {
+	let cached = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet(1u32);
+		panic!("producer gave out")
+	}).cached();
+
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cached.replay().count()));
+	assert!(result.is_err());
+	assert_eq!(cached.replay().collect::<Vec<_>>(), [1]);
+}
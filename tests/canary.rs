@@ -0,0 +1,57 @@
+//! This module tests the canaries guarding the ends of task stacks.
+#![cfg(not(any(all(windows, feature = "fibers"), feature = "small")))]
+
+use std::panic::AssertUnwindSafe;
+use yeet::{Generator, GeneratorBuilder};
+
+/// Writes over the bottom of the stack of the current task, as an overflowing
+/// producer would.
+fn scribble_bottom() {
+	let stack = yeet::current_task().unwrap().stack;
+	unsafe { std::ptr::write_bytes(stack.start as *mut u8, 0xaa, 64) }
+}
+
+/// Writes over the last bytes at the top of the stack of the current task.
+fn scribble_top() {
+	let stack = yeet::current_task().unwrap().stack;
+	let top = (stack.end & !0xf) - 16;
+	unsafe { std::ptr::write_bytes(top as *mut u8, 0xaa, 16) }
+}
+
+#[test]
+fn intact_stacks_pass() {
+	let gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0..100u32));
+	assert_eq!(gen.count(), 100);
+}
+
+#[test]
+fn overflow_is_caught() {
+	let mut gen = GeneratorBuilder::new()
+		.name("overflowing")
+		.build::<u32>(|| {
+			scribble_bottom();
+			yeet::yeet(1u32);
+		});
+	let id = gen.id().as_u64();
+
+	let panic = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())).unwrap_err();
+	let message = panic.downcast_ref::<String>().unwrap();
+	assert_eq!(*message, format!("The producer of generator \"overflowing\" (#{id}) overflowed its stack!"));
+
+	/* The generator never runs again, not even to be cancelled. */
+	assert_eq!(gen.next(), None);
+	assert!(gen.close().is_ok());
+}
+
+#[test]
+fn top_is_caught() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		scribble_top();
+		yeet::yeet(1u32);
+	});
+	let id = gen.id().as_u64();
+
+	let panic = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())).unwrap_err();
+	let message = panic.downcast_ref::<String>().unwrap();
+	assert_eq!(*message, format!("The producer of generator #{id} wrote over the top of its stack!"));
+}
@@ -0,0 +1,359 @@
+//! Tests for cancelling trees of generators with tokens.
+use std::cell::Cell;
+use yeet::{CancelToken, Generator, GeneratorBuilder, Resume};
+
+thread_local! {
+	static DROPPED: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Counts how many times it gets dropped.
+struct Guard;
+impl Drop for Guard {
+	fn drop(&mut self) {
+		DROPPED.set(DROPPED.get() + 1)
+	}
+}
+
+fn forever() {
+	let _guard = Guard;
+	yeet::yeet_all(0u32..)
+}
+
+#[test]
+fn cancels_on_next_yield() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(forever);
+	gen.attach(&token);
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(1));
+	assert!(!token.is_cancelled());
+
+	DROPPED.set(0);
+	token.cancel();
+	assert!(token.is_cancelled());
+	assert_eq!(gen.next(), None);
+	assert_eq!(DROPPED.get(), 1);
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn cancels_at_checkpoints() {
+	let token = CancelToken::new();
+	let mut gen = GeneratorBuilder::new()
+		.cancel_token(&token)
+		.build::<u32>(|| loop { yeet::yield_now() });
+
+	assert_eq!(gen.resume(), Resume::Pending);
+	token.clone().cancel();
+	assert_eq!(gen.resume(), Resume::Complete);
+}
+
+#[test]
+fn tears_down_a_tree() {
+	fn stage(token: CancelToken) {
+		let _guard = Guard;
+		let mut inner = Generator::<u32>::from_fn_ptr(forever);
+		inner.attach(&token);
+		for value in inner {
+			yeet::yeet(value)
+		}
+	}
+
+	let token = CancelToken::new();
+	let mut gens = (0..3)
+		.map(|_| {
+			let mut gen = Generator::<u32>::from_fn_with(token.clone(), stage);
+			gen.attach(&token);
+			gen
+		})
+		.collect::<Vec<_>>();
+	for gen in &mut gens {
+		assert_eq!(gen.next(), Some(0));
+	}
+
+	DROPPED.set(0);
+	token.cancel();
+	for gen in &mut gens {
+		assert_eq!(gen.next(), None);
+	}
+	assert_eq!(DROPPED.get(), 6);
+}
+
+#[test]
+fn cancelled_from_another_thread() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(forever);
+	gen.attach(&token);
+
+	let remote = token.clone();
+	std::thread::spawn(move || remote.cancel()).join().unwrap();
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn any_token_cancels() {
+	let first = CancelToken::new();
+	let second = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(forever);
+	gen.attach(&first).attach(&second);
+
+	assert_eq!(gen.next(), Some(0));
+	second.cancel();
+	assert_eq!(gen.next(), None);
+}
+
+/// Reasons a producer may be cancelled for.
+#[derive(Debug, PartialEq)]
+enum Why {
+	Shutdown,
+	Timeout,
+}
+
+thread_local! {
+	static SEEN: std::cell::RefCell<Vec<Option<String>>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Records the reason the producer was cancelled for when dropped.
+struct Witness;
+impl Drop for Witness {
+	fn drop(&mut self) {
+		let seen = yeet::cancellation().map(|cancelled| {
+			cancelled.reason::<Why>().map(|why| format!("{why:?}")).unwrap_or_default()
+		});
+		SEEN.with_borrow_mut(|all| all.push(seen))
+	}
+}
+
+fn witnessed() {
+	let _witness = Witness;
+	yeet::yeet_all(0u32..)
+}
+
+#[test]
+fn reasons_reach_destructors() {
+	SEEN.with_borrow_mut(Vec::clear);
+
+	let mut gen = Generator::<u32>::from_fn_ptr(witnessed);
+	gen.next();
+	assert!(gen.cancel_with(Why::Shutdown).is_ok());
+
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(witnessed);
+	gen.attach(&token);
+	gen.next();
+	token.cancel_with(Why::Timeout);
+	token.cancel_with(Why::Shutdown);
+	assert_eq!(gen.next(), None);
+
+	let mut gen = Generator::<u32>::from_fn_ptr(witnessed);
+	gen.next();
+	drop(gen);
+
+	let mut gen = Generator::<u32>::from_fn_ptr(|| { let _witness = Witness; });
+	assert_eq!(gen.next(), None);
+
+	SEEN.with_borrow(|seen| assert_eq!(seen, &[
+		Some("Shutdown".into()),
+		Some("Timeout".into()),
+		Some(String::new()),
+		None,
+	]));
+}
+
+#[test]
+fn reasons_come_too_late_after_cancel() {
+	SEEN.with_borrow_mut(Vec::clear);
+
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(witnessed);
+	gen.attach(&token);
+	gen.next();
+	token.cancel();
+	token.cancel_with(Why::Shutdown);
+	assert_eq!(gen.next(), None);
+
+	SEEN.with_borrow(|seen| assert_eq!(seen, &[Some(String::new())]));
+}
+
+#[test]
+fn catch_unwind_can_recognize_cancellation() {
+	thread_local! {
+		static CAUGHT: Cell<bool> = const { Cell::new(false) };
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		if let Err(what) = std::panic::catch_unwind(|| yeet::yeet_all(0u32..)) {
+			if let Some(cancelled) = what.downcast_ref::<yeet::Cancelled>() {
+				assert!(!cancelled.has_reason());
+				CAUGHT.set(true);
+				std::panic::resume_unwind(what)
+			}
+		}
+	});
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+	assert!(CAUGHT.get());
+}
+
+#[test]
+fn no_cancellation_outside_generators() {
+	assert!(yeet::cancellation().is_none());
+}
+
+thread_local! {
+	static STEPS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Stands in for a C function calling back into the producer.
+extern "C" fn for_each(count: u32, callback: extern "C" fn(u32)) {
+	for i in 0..count {
+		callback(i);
+		STEPS.set(STEPS.get() + 1)
+	}
+}
+
+extern "C" fn callback(value: u32) {
+	yeet::yeet(value)
+}
+
+#[test]
+fn guard_defers_cancellation() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let _guard = Guard;
+		yeet::ffi_guard(|| for_each(3, callback));
+		unreachable!("the producer should have unwound at the end of the guard")
+	});
+	assert_eq!(gen.next(), Some(0));
+
+	STEPS.set(0);
+	DROPPED.set(0);
+	drop(gen);
+	assert_eq!(STEPS.get(), 3);
+	assert_eq!(DROPPED.get(), 1);
+}
+
+#[test]
+fn guard_defers_tokens() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::ffi_guard(|| {
+			yeet::ffi_guard(|| for_each(2, callback));
+			assert!(yeet::cancellation().is_some());
+			STEPS.set(STEPS.get() + 10)
+		});
+		unreachable!("the producer should have unwound at the end of the guard")
+	});
+	gen.attach(&token);
+	assert_eq!(gen.next(), Some(0));
+
+	STEPS.set(0);
+	token.cancel();
+	assert_eq!(gen.next(), None);
+	assert_eq!(STEPS.get(), 12);
+}
+
+#[test]
+fn guard_outside_generators() {
+	assert_eq!(yeet::ffi_guard(|| 42), 42);
+}
+
+#[test]
+fn try_yeet_returns_cancellation() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let _guard = Guard;
+		for i in 0u32.. {
+			if let Err(cancelled) = yeet::try_yeet(i) {
+				assert!(cancelled.reason::<&str>().is_none());
+				STEPS.set(STEPS.get() + 1);
+				return
+			}
+		}
+	});
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(1));
+
+	STEPS.set(0);
+	DROPPED.set(0);
+	assert!(gen.close().is_ok());
+	assert_eq!(STEPS.get(), 1);
+	assert_eq!(DROPPED.get(), 1);
+}
+
+#[test]
+fn try_yield_now_sees_tokens() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet(0u32);
+		while yeet::try_yield_now().is_ok() {}
+		yeet::yeet(1u32);
+	});
+	gen.attach(&token);
+	assert_eq!(gen.resume(), Resume::Value(0));
+	assert_eq!(gen.resume(), Resume::Pending);
+
+	token.cancel();
+	assert_eq!(gen.resume(), Resume::Complete);
+}
+
+thread_local! {
+	static JOURNAL: std::cell::RefCell<Vec<&'static str>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn transaction() {
+	yeet::shield(|| {
+		for step in ["begin", "write", "commit"] {
+			JOURNAL.with_borrow_mut(|journal| journal.push(step));
+			yeet::yeet(0u32);
+		}
+	});
+	JOURNAL.with_borrow_mut(|journal| journal.push("after"));
+}
+
+#[test]
+fn shield_completes_critical_section() {
+	JOURNAL.with_borrow_mut(Vec::clear);
+	let mut gen = Generator::<u32>::from_fn_ptr(transaction);
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+	JOURNAL.with_borrow(|journal| assert_eq!(journal, &["begin", "write", "commit"]));
+}
+
+#[test]
+fn shield_defers_tokens() {
+	JOURNAL.with_borrow_mut(Vec::clear);
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(transaction);
+	gen.attach(&token);
+	assert_eq!(gen.next(), Some(0));
+
+	token.cancel();
+	assert_eq!(gen.next(), None);
+	JOURNAL.with_borrow(|journal| assert_eq!(journal, &["begin", "write", "commit"]));
+}
+
+#[test]
+fn shield_without_cancellation() {
+	JOURNAL.with_borrow_mut(Vec::clear);
+	let gen = Generator::<u32>::from_fn_ptr(transaction);
+	assert_eq!(gen.count(), 3);
+	JOURNAL.with_borrow(|journal| assert_eq!(journal, &["begin", "write", "commit", "after"]));
+}
+
+#[test]
+fn shield_nests_with_ffi_guard() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::shield(|| {
+			yeet::ffi_guard(|| for_each(2, callback));
+			STEPS.set(STEPS.get() + 10);
+			yeet::yeet(5u32);
+			STEPS.set(STEPS.get() + 10);
+		});
+		unreachable!("the producer should have unwound at the end of the shield")
+	});
+	assert_eq!(gen.next(), Some(0));
+
+	STEPS.set(0);
+	drop(gen);
+	assert_eq!(STEPS.get(), 22);
+}
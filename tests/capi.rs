@@ -0,0 +1,50 @@
+//! This module tests the C interface.
+#![cfg(feature = "capi")]
+
+use std::ffi::c_void;
+use std::ptr;
+use yeet::capi::*;
+
+#[test]
+fn count() {
+	extern "C" fn producer(ctx: *mut c_void) {
+		let count = ctx as usize;
+		for i in 0..count {
+			if yeet_yield(i as *mut c_void) != 0 {
+				return
+			}
+		}
+	}
+
+	unsafe {
+		let gen = yeet_generator_new(producer, 3 as *mut c_void);
+		let mut out = ptr::null_mut();
+		for i in 0..3 {
+			assert_eq!(yeet_generator_next(gen, &mut out), YEET_VALUE);
+			assert_eq!(out as usize, i);
+		}
+		assert_eq!(yeet_generator_next(gen, &mut out), YEET_DONE);
+		yeet_generator_free(gen);
+	}
+}
+
+#[test]
+fn cancel() {
+	static mut CANCELLED: bool = false;
+	extern "C" fn producer(_: *mut c_void) {
+		loop {
+			if yeet_yield(ptr::null_mut()) != 0 {
+				unsafe { CANCELLED = true };
+				return
+			}
+		}
+	}
+
+	unsafe {
+		let gen = yeet_generator_new(producer, ptr::null_mut());
+		let mut out = ptr::null_mut();
+		assert_eq!(yeet_generator_next(gen, &mut out), YEET_VALUE);
+		yeet_generator_free(gen);
+		assert!(*ptr::addr_of!(CANCELLED));
+	}
+}
@@ -0,0 +1,103 @@
+//! This module tests the C interface to the bare context switch.
+
+#![cfg(all(feature = "capi-switch", not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+
+use std::ffi::c_void;
+use std::ptr;
+use yeet::capi::*;
+use yeet::raw::{self, Context, Stack, Transfer};
+
+const STACK_SIZE: usize = 64 * 1024;
+
+extern "C" fn incrementer(mut transfer: YeetTransfer) {
+	loop {
+		let value = unsafe { *(transfer.data as *mut u64) } + 1;
+		let mut out = value;
+		transfer = unsafe { yeet_switch(transfer.context, &mut out as *mut u64 as *mut c_void) };
+	}
+}
+
+fn doubler(mut transfer: Transfer) -> ! {
+	loop {
+		let value = unsafe { *(transfer.data as *mut u64) } * 2;
+		let mut out = value;
+		transfer = unsafe { raw::switch(transfer.context, &mut out as *mut u64 as *mut ()) };
+	}
+}
+
+/// Switches to the given context with the given value, and returns what it
+/// hands back, along with the context.
+unsafe fn round_trip(context: Context, value: u64, through_c: bool) -> (Context, u64) {
+	let mut value = value;
+	let data = &mut value as *mut u64;
+	let transfer = match through_c {
+		true => yeet_switch(context, data as *mut c_void),
+		false => raw::switch(context, data as *mut ()),
+	};
+	(transfer.context, *(transfer.data as *mut u64))
+}
+
+#[test]
+fn c_context() {
+	let mut stack = vec![0u8; STACK_SIZE];
+	let mut context = unsafe { yeet_make_context(stack.as_mut_ptr() as *mut c_void, stack.len(), incrementer) };
+	for i in 0u64..10 {
+		let (next, value) = unsafe { round_trip(context, i, true) };
+		assert_eq!(value, i + 1);
+		context = next;
+	}
+}
+
+#[test]
+fn contexts_cross_sides() {
+	let mut c_stack = vec![0u8; STACK_SIZE];
+	let rust_stack = Stack::new(STACK_SIZE);
+	let mut c_context = unsafe { yeet_make_context(c_stack.as_mut_ptr() as *mut c_void, c_stack.len(), incrementer) };
+	let mut rust_context = unsafe { raw::make_context(&rust_stack, doubler) };
+
+	/* Each context gets switched to from both sides, in turns. */
+	for i in 0u64..10 {
+		let through_c = i % 2 == 0;
+		let (next, value) = unsafe { round_trip(c_context, i, through_c) };
+		assert_eq!(value, i + 1);
+		c_context = next;
+
+		let (next, value) = unsafe { round_trip(rust_context, i, !through_c) };
+		assert_eq!(value, i * 2);
+		rust_context = next;
+	}
+}
+
+#[test]
+fn raw_pointers_round_trip() {
+	let stack = Stack::new(STACK_SIZE);
+	let context = unsafe { raw::make_context(&stack, doubler) };
+	let context = unsafe { Context::from_raw(context.into_raw()) };
+	let (_, value) = unsafe { round_trip(context, 21, true) };
+	assert_eq!(value, 42);
+}
+
+#[test]
+fn snapshot_layout() {
+	#[cfg(target_arch = "x86_64")]
+	const SIZE: usize = 136;
+	#[cfg(target_arch = "aarch64")]
+	const SIZE: usize = 336;
+	#[cfg(target_arch = "x86_64")]
+	const SP: usize = 48;
+	#[cfg(target_arch = "aarch64")]
+	const SP: usize = 264;
+
+	assert_eq!(YEET_SNAPSHOT_SIZE, SIZE);
+
+	/* The context switching back to us is suspended on its own stack. */
+	let stack = Stack::new(STACK_SIZE);
+	let bounds = stack.bounds();
+	let context = unsafe { raw::make_context(&stack, doubler) };
+	let (context, _) = unsafe { round_trip(context, 0, false) };
+
+	let snapshot = context.into_raw() as *const u8;
+	assert!(bounds.contains(&(snapshot as usize)));
+	let sp = unsafe { ptr::read_unaligned(snapshot.add(SP) as *const usize) };
+	assert!(bounds.contains(&sp));
+}
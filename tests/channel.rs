@@ -0,0 +1,54 @@
+//! This module tests the bridges between generators and crossbeam channels.
+#![cfg(feature = "crossbeam")]
+
+use yeet::Generator;
+
+fn count() {
+	yeet::yeet_all(0..100u32)
+}
+
+#[test]
+fn into_channel() {
+	let (rx, pump) = Generator::<u32>::from_fn_ptr(count).into_channel(4);
+	let consumer = std::thread::spawn(move || rx.iter().sum::<u32>());
+
+	pump.run();
+	assert_eq!(consumer.join().unwrap(), (0..100).sum());
+}
+
+#[test]
+fn receiver_dropped() {
+	let (rx, mut pump) = Generator::<u32>::from_fn_ptr(count).into_channel(1);
+	assert!(pump.step());
+	assert_eq!(rx.recv(), Ok(0));
+	drop(rx);
+	assert!(!pump.step());
+}
+
+#[test]
+fn from_crossbeam() {
+	let (tx, rx) = crossbeam_channel::bounded(4);
+	let producer = std::thread::spawn(move || {
+		for i in 0..100u32 {
+			tx.send(i).unwrap()
+		}
+	});
+
+	let gen = Generator::from_crossbeam(rx).filter_in_task(|value| value % 2 == 0);
+	assert_eq!(gen.count(), 50);
+	producer.join().unwrap();
+}
+
+#[test]
+fn across_threads() {
+	/* Stitch two generators on different threads together. */
+	let (rx, pump) = Generator::<u32>::from_fn_ptr(count).into_channel(8);
+	let stage = std::thread::spawn(move || {
+		Generator::from_crossbeam(rx)
+			.map_in_task(|value| value * 2)
+			.sum::<u32>()
+	});
+
+	pump.run();
+	assert_eq!(stage.join().unwrap(), (0..100).map(|value| value * 2).sum());
+}
@@ -0,0 +1,162 @@
+//! Tests for closing generators explicitly, rather than dropping them.
+use std::time::{Duration, Instant};
+use yeet::{CancelBound, Generator, GeneratorBuilder, Overrun};
+
+/// Panics once it's done being cancelled.
+fn armed() {
+	let _ = std::panic::catch_unwind(|| yeet::yeet_all(0u32..));
+	panic!("bomb went off")
+}
+
+#[test]
+fn close_reports_panics() {
+	let mut gen = Generator::<u32>::from_fn_ptr(armed);
+	assert_eq!(gen.next(), Some(0));
+
+	let what = gen.close().unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"bomb went off"));
+}
+
+#[test]
+fn drop_ignores_panics() {
+	let mut gen = Generator::<u32>::from_fn_ptr(armed);
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+}
+
+#[test]
+fn close_clean_generators() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..));
+	assert_eq!(gen.next(), Some(0));
+	assert!(gen.close().is_ok());
+
+	let gen = Generator::<u32>::from_fn_ptr(armed);
+	assert!(gen.close().is_ok());
+
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet(0u32));
+	assert_eq!(gen.by_ref().count(), 1);
+	assert!(gen.close().is_ok());
+}
+
+/// Yields the given number of times when dropped, forever if there's no count.
+struct Yields(Option<u32>);
+impl Drop for Yields {
+	fn drop(&mut self) {
+		match self.0 {
+			Some(count) => for i in 0..count { yeet::yeet(i) },
+			None => loop { yeet::yeet(0u32) },
+		}
+	}
+}
+
+#[test]
+fn destructors_may_yield() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let _yields = Yields(Some(3));
+		yeet::yeet_all(10u32..)
+	});
+	assert_eq!(gen.next(), Some(10));
+	assert!(gen.close().is_ok());
+}
+
+#[test]
+fn yield_bound_leaks_stuck_producers() {
+	let mut gen = GeneratorBuilder::new()
+		.cancel_bound(CancelBound::Yields(100), Overrun::Leak)
+		.name("stuck")
+		.build::<u32>(|| {
+			let _yields = Yields(None);
+			yeet::yeet_all(10u32..)
+		});
+	assert_eq!(gen.next(), Some(10));
+
+	let what = gen.close().unwrap_err();
+	let message = what.downcast_ref::<String>().unwrap();
+	assert!(message.contains("\"stuck\""));
+	assert!(message.contains("past the bound on its cancellation"));
+}
+
+#[test]
+fn time_bound_leaks_stuck_producers() {
+	let mut gen = GeneratorBuilder::new()
+		.cancel_bound(CancelBound::Time(Duration::from_millis(20)), Overrun::Leak)
+		.build::<u32>(|| {
+			let _ = std::panic::catch_unwind(|| yeet::yeet(0u32));
+			loop {
+				let _ = std::panic::catch_unwind(|| yeet::yeet(1u32));
+			}
+		});
+	assert_eq!(gen.next(), Some(0));
+
+	let started = Instant::now();
+	drop(gen);
+	assert!(started.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn bounds_leave_well_behaved_producers_alone() {
+	let mut gen = GeneratorBuilder::new()
+		.cancel_bound(CancelBound::Yields(3), Overrun::Abort)
+		.build::<u32>(|| {
+			let _yields = Yields(Some(3));
+			yeet::yeet_all(10u32..)
+		});
+	assert_eq!(gen.next(), Some(10));
+	assert!(gen.close().is_ok());
+}
+
+fn leaves_three() {
+	let _yields = Yields(Some(3));
+	yeet::yeet_all(10u32..)
+}
+
+#[test]
+fn leftovers_are_discarded_by_default() {
+	let mut gen = Generator::<u32>::from_fn_ptr(leaves_three);
+	assert_eq!(gen.next(), Some(10));
+	let leftovers = gen.collect_leftovers();
+	gen.discard_leftovers();
+	assert!(gen.close().is_ok());
+	assert!(leftovers.is_empty());
+}
+
+#[test]
+fn leftovers_reach_callbacks() {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	let seen = Rc::new(RefCell::new(Vec::new()));
+	let mut gen = Generator::<u32>::from_fn_ptr(leaves_three);
+	let record = seen.clone();
+	gen.on_leftover(move |value| record.borrow_mut().push(value));
+
+	assert_eq!(gen.next(), Some(10));
+	drop(gen);
+	assert_eq!(*seen.borrow(), [0, 1, 2]);
+}
+
+#[test]
+fn leftovers_get_collected() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let _yields = Yields(Some(2));
+		let _batch = Batch;
+		yeet::yeet_all(10u32..)
+	});
+	let leftovers = gen.collect_leftovers();
+	let shared = gen.collect_leftovers();
+
+	assert_eq!(gen.next(), Some(10));
+	assert_eq!(gen.next(), Some(11));
+	assert!(gen.close().is_ok());
+	assert_eq!(shared.len(), 4);
+	assert_eq!(leftovers.collect::<Vec<_>>(), [7, 8, 0, 1]);
+	assert!(shared.is_empty());
+}
+
+/// Yields a batch of values when dropped.
+struct Batch;
+impl Drop for Batch {
+	fn drop(&mut self) {
+		yeet::yeet_vec(vec![7u32, 8])
+	}
+}
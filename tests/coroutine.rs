@@ -0,0 +1,39 @@
+//! This module tests the resume interface shaped after the Coroutine trait.
+
+use std::pin::{pin, Pin};
+use yeet::Generator;
+use yeet::coroutine::{Coroutine, CoroutineState};
+
+/// Drives any coroutine to completion, collecting what it yields.
+fn drain<C: Coroutine<Yield = u32, Return = ()>>(coroutine: C) -> Vec<u32> {
+	let mut coroutine = pin!(coroutine);
+	let mut values = Vec::new();
+	while let CoroutineState::Yielded(value) = coroutine.as_mut().resume(()) {
+		values.push(value)
+	}
+
+	values
+}
+
+#[test]
+fn generator() {
+	fn gen() {
+		yeet::yeet(0u32);
+		yeet::yield_now();
+		yeet::yeet_vec(vec![1u32, 2]);
+	}
+
+	assert_eq!(drain(Generator::<u32>::from_fn_ptr(gen)), [0, 1, 2]);
+}
+
+#[test]
+fn complete_again() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32));
+	let mut gen = Pin::new(&mut gen);
+	assert_eq!(gen.as_mut().resume(()), CoroutineState::Yielded(1));
+	assert_eq!(gen.as_mut().resume(()), CoroutineState::Complete(()));
+	assert_eq!(gen.as_mut().resume(()), CoroutineState::Complete(()));
+
+	/* The inherent resume is still there for unpinned generators. */
+	assert_eq!(gen.get_mut().resume(), yeet::Resume::Complete);
+}
@@ -0,0 +1,68 @@
+//! This module tests the symbols exported for debuggers.
+#![cfg(feature = "debugger")]
+
+use std::mem::MaybeUninit;
+use yeet::GeneratorBuilder;
+use yeet::debug::*;
+
+#[test]
+fn enumerate() {
+	fn gen() {
+		yeet::yeet(0u8);
+	}
+
+	yeet::registry::enable();
+	let mut gen = GeneratorBuilder::new()
+		.name("debuggee")
+		.build::<u8>(gen);
+	assert_eq!(gen.next(), Some(0));
+
+	assert_eq!(yeet_debug_task_count(), 1);
+	let mut task = MaybeUninit::uninit();
+	unsafe {
+		assert!(yeet_debug_task_get(0, task.as_mut_ptr()));
+		assert!(!yeet_debug_task_get(1, task.as_mut_ptr()));
+	}
+	let task = unsafe { task.assume_init() };
+	let name = unsafe { std::slice::from_raw_parts(task.name, task.name_len) };
+
+	assert_eq!(task.id, gen.id().as_u64());
+	assert_eq!(name, b"debuggee");
+	assert_eq!(task.state, 2);
+	if cfg!(not(feature = "corosensei")) {
+		/* The corosensei backend doesn't expose the saved registers. */
+		assert!(task.sp >= task.stack_base && task.sp < task.stack_base + task.stack_len);
+		assert_ne!(task.pc, 0);
+	}
+	assert_eq!(size_of::<YeetDebugTask>(), 72);
+}
+
+#[test]
+fn dropped_tasks_are_skipped() {
+	yeet::registry::enable();
+	let first = GeneratorBuilder::new().build::<u8>(|| yeet::yeet(0u8));
+	let second = GeneratorBuilder::new().build::<u8>(|| yeet::yeet(0u8));
+	drop(first);
+
+	/* The slot of the first task is left for the thread to prune. */
+	assert_eq!(yeet_debug_task_count(), 2);
+	let mut task = MaybeUninit::uninit();
+	unsafe {
+		assert!(!yeet_debug_task_get(0, task.as_mut_ptr()));
+		assert!(yeet_debug_task_get(1, task.as_mut_ptr()));
+		assert_eq!(task.assume_init().id, second.id().as_u64());
+	}
+	assert_eq!(yeet_debug_task_count(), 2);
+}
+
+#[test]
+fn scratch_record() {
+	yeet::registry::enable();
+	let gen = GeneratorBuilder::new().build::<u8>(|| yeet::yeet(0u8));
+
+	unsafe {
+		let scratch = &raw mut YEET_DEBUG_SCRATCH;
+		assert!(yeet_debug_task_get(0, scratch));
+		assert_eq!(scratch.read().id, gen.id().as_u64());
+	}
+}
@@ -0,0 +1,59 @@
+//! This module tests the logging of switches between consumers and producers.
+#![cfg(feature = "debug-log")]
+
+use std::sync::Mutex;
+use log::{Level, Log, Metadata, Record};
+use yeet::GeneratorBuilder;
+
+/// Logger keeping every record logged by the crate on the testing thread.
+struct Capture;
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+impl Log for Capture {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.target() == "yeet"
+	}
+
+	fn log(&self, record: &Record) {
+		if self.enabled(record.metadata()) && std::thread::current().name() == Some("logged") {
+			assert_eq!(record.level(), Level::Trace);
+			LINES.lock().unwrap().push(record.args().to_string());
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+#[test]
+fn logs_every_switch() {
+	log::set_logger(&Capture).unwrap();
+	log::set_max_level(log::LevelFilter::Trace);
+
+	std::thread::Builder::new()
+		.name("logged".into())
+		.spawn(|| {
+			let mut gen = GeneratorBuilder::new()
+				.name("numbers")
+				.build::<u32>(|| {
+					yeet::yeet(1u32);
+					yeet::yield_now();
+				});
+			let id = gen.id().as_u64();
+			assert_eq!(gen.next(), Some(1));
+			assert_eq!(gen.nth(1), None);
+
+			let lines = LINES.lock().unwrap();
+			assert_eq!(*lines, [
+				format!("resume \"numbers\" (#{id}) with continue at depth 1"),
+				format!("yield \"numbers\" (#{id}) with value"),
+				format!("resume \"numbers\" (#{id}) with skip(1) at depth 1"),
+				format!("yield \"numbers\" (#{id}) with pending"),
+				format!("resume \"numbers\" (#{id}) with continue at depth 1"),
+				format!("yield \"numbers\" (#{id}) with stop"),
+			]);
+		})
+		.unwrap()
+		.join()
+		.unwrap();
+}
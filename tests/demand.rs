@@ -0,0 +1,39 @@
+//! Tests for telling producers how many values the consumer intends to take.
+use yeet::Generator;
+
+/// Yields values for as long as there is demand for them.
+fn on_demand() {
+	let mut next = 0u32;
+	while yeet::remaining_demand() != Some(0) {
+		yeet::yeet(next);
+		next += 1;
+	}
+}
+
+#[test]
+fn producer_sees_demand() {
+	let mut gen = Generator::<Option<usize>>::from_fn_ptr(|| loop {
+		yeet::yeet(yeet::remaining_demand())
+	});
+	assert_eq!(gen.next(), Some(None));
+
+	gen.set_demand(3);
+	assert_eq!(gen.next(), Some(Some(3)));
+	assert_eq!(gen.next(), Some(Some(2)));
+
+	gen.clear_demand();
+	assert_eq!(gen.next(), Some(None));
+}
+
+#[test]
+fn producer_stops_when_demand_runs_out() {
+	let mut gen = Generator::<u32>::from_fn_ptr(on_demand);
+	gen.set_demand(4);
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic]
+fn outside_generator() {
+	yeet::remaining_demand();
+}
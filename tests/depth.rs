@@ -0,0 +1,51 @@
+//! Tests for limiting how deep the tree of generators may grow.
+use yeet::{DepthExceeded, Generator, GeneratorBuilder};
+
+/// Recurses until it can't create a generator anymore, and yields how deep it
+/// got.
+fn recurse() {
+	match GeneratorBuilder::new().stack_size(64 * 1024).try_build::<usize>(recurse) {
+		Ok(inner) => yeet::yeet_all(inner),
+		Err(error) => yeet::yeet(error.depth - 1),
+	}
+}
+
+#[test]
+fn limits_recursion() {
+	yeet::set_max_depth(Some(8));
+	assert_eq!(yeet::max_depth(), Some(8));
+
+	let gen = Generator::<usize>::try_from_fn_ptr(recurse).unwrap();
+	assert_eq!(gen.collect::<Vec<_>>(), [8]);
+
+	yeet::set_max_depth(None);
+}
+
+#[test]
+fn reports_depth_and_limit() {
+	yeet::set_max_depth(Some(1));
+
+	let mut gen = Generator::<Result<(), DepthExceeded>>::from_fn_ptr(|| {
+		yeet::yeet(Generator::<u8>::try_from_fn_ptr(|| {}).map(drop))
+	});
+	let error = gen.next().unwrap().unwrap_err();
+	assert_eq!(error, DepthExceeded { depth: 2, limit: 1 });
+	assert_eq!(error.to_string(), "generator would be 2 deep, past the limit of 1");
+
+	yeet::set_max_depth(None);
+}
+
+#[test]
+fn zero_forbids_all_generators() {
+	yeet::set_max_depth(Some(0));
+	assert!(GeneratorBuilder::new().try_build_closure::<u8>(|| {}).is_err());
+	yeet::set_max_depth(None);
+	assert!(GeneratorBuilder::new().try_build_closure::<u8>(|| {}).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "past the depth limit")]
+fn infallible_constructors_panic() {
+	yeet::set_max_depth(Some(0));
+	let _ = Generator::<u8>::from_fn_ptr(|| {});
+}
@@ -0,0 +1,86 @@
+//! Tests for detaching generators from the producers that own them.
+use yeet::{Detach, Generator};
+
+fn count() {
+	yeet::yeet_all(0u32..5)
+}
+
+#[test]
+fn detached_generators_reach_the_consumer() {
+	let mut gen = Generator::<()>::from_fn_ptr(|| {
+		let mut child = Generator::<u32>::from_fn_ptr(count);
+		assert_eq!(child.next(), Some(0));
+		yeet::detach(child);
+		yeet::yeet(());
+	});
+
+	assert_eq!(gen.next(), Some(()));
+	let mut children = gen.take_detached::<u32>();
+	assert_eq!(children.len(), 1);
+	assert_eq!(children[0].by_ref().collect::<Vec<_>>(), [1, 2, 3, 4]);
+	assert!(gen.take_detached::<u32>().is_empty());
+}
+
+#[test]
+fn detached_generators_are_taken_by_type() {
+	let mut gen = Generator::<()>::from_fn_ptr(|| {
+		yeet::detach(Generator::<u32>::from_fn_ptr(count));
+		yeet::detach(Generator::<String>::from_fn_ptr(|| yeet::yeet(String::from("a"))));
+		yeet::detach(Generator::<u32>::from_fn_ptr(count));
+	});
+
+	assert_eq!(gen.next(), None);
+	assert!(gen.take_detached::<u8>().is_empty());
+	assert_eq!(gen.take_detached::<u32>().len(), 2);
+
+	let mut strings = gen.take_detached::<String>();
+	assert_eq!(strings[0].next().as_deref(), Some("a"));
+}
+
+#[test]
+fn guard_detaches_on_cancel() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let mut child = Detach::new(Generator::<u32>::from_fn_ptr(count));
+		for value in child.by_ref() {
+			yeet::yeet(value * 10)
+		}
+	});
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(10));
+
+	let mut children = gen.into_detached::<u32>();
+	assert_eq!(children.len(), 1);
+	assert_eq!(children[0].by_ref().collect::<Vec<_>>(), [2, 3, 4]);
+}
+
+#[test]
+fn guard_drops_normally_without_unwinding() {
+	let mut gen = Generator::<()>::from_fn_ptr(|| {
+		let mut child = Detach::new(Generator::<u32>::from_fn_ptr(count));
+		assert_eq!(child.next(), Some(0));
+		drop(child);
+		yeet::yeet(());
+	});
+
+	assert_eq!(gen.next(), Some(()));
+	assert!(gen.into_detached::<u32>().is_empty());
+}
+
+#[test]
+fn into_inner_gives_up_on_detaching() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		let child = Detach::new(Generator::<u32>::from_fn_ptr(count)).into_inner();
+		for value in child {
+			yeet::yeet(value)
+		}
+	});
+
+	assert_eq!(gen.next(), Some(0));
+	assert!(gen.into_detached::<u32>().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "outside a generator")]
+fn detach_outside_generator() {
+	yeet::detach(Generator::<u32>::from_fn_ptr(count));
+}
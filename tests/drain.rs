@@ -0,0 +1,61 @@
+//! This module tests running generators to completion with drain.
+
+use yeet::{CancelToken, Generator};
+
+#[test]
+fn drains_everything() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..5));
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.peek(), Some(&1));
+
+	assert_eq!(gen.drain(), [1, 2, 3, 4]);
+	assert_eq!(gen.next(), None);
+	assert!(gen.drain().is_empty());
+}
+
+#[test]
+fn drains_batches_and_checkpoints() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_vec(vec![0u32, 1, 2]);
+		yeet::yield_now();
+		yeet::yeet(3u32);
+		yeet::yeet_slice(&[4u32, 5]);
+	});
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.drain(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn drain_with_callback() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(1u32..=100));
+	let mut sum = 0;
+	gen.drain_with(|value| sum += value);
+	assert_eq!(sum, 5050);
+}
+
+#[test]
+fn drain_stops_at_cancellation() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..));
+	gen.attach(&token);
+
+	let mut taken = Vec::new();
+	let cancel = token.clone();
+	gen.drain_with(|value| {
+		taken.push(value);
+		if value == 3 {
+			cancel.cancel()
+		}
+	});
+	assert_eq!(taken, [0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "ran out")]
+fn drain_propagates_panics() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet(0u32);
+		panic!("ran out")
+	});
+	gen.drain();
+}
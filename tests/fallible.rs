@@ -0,0 +1,29 @@
+//! This module tests generators of results as fallible iterators.
+#![cfg(feature = "fallible-iterator")]
+
+use fallible_iterator::FallibleIterator;
+use yeet::Generator;
+
+fn parse() {
+	for word in ["1", "2", "three", "4"] {
+		yeet::yeet(word.parse::<u32>().map_err(|_| word.to_owned()))
+	}
+}
+
+#[test]
+fn stops_at_error() {
+	let mut iter = Generator::<Result<u32, String>>::from_fn_ptr(parse).fallible();
+	assert_eq!(iter.next(), Ok(Some(1)));
+	assert_eq!(iter.next(), Ok(Some(2)));
+	assert_eq!(iter.next(), Err("three".to_owned()));
+}
+
+#[test]
+fn adapters() {
+	let iter = Generator::<Result<u32, String>>::from_fn_ptr(parse).fallible();
+	let sum = iter.take(2).fold(0, |acc, value| Ok(acc + value));
+	assert_eq!(sum, Ok(3));
+
+	let iter = Generator::<Result<u32, String>>::from_fn_ptr(parse).fallible();
+	assert_eq!(iter.collect::<Vec<_>>(), Err("three".to_owned()));
+}
@@ -0,0 +1,119 @@
+//! This module tests generators shared between threads.
+
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use yeet::SharedGenerator;
+
+fn count() {
+	yeet::yeet_all(0u32..1000)
+}
+
+#[test]
+fn values() {
+	let gen = SharedGenerator::<u32>::from_fn_ptr(count);
+	assert_eq!(gen.collect::<Vec<_>>(), (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn every_value_goes_to_one_consumer() {
+	let gen = SharedGenerator::<u32>::from_fn_ptr(count);
+	let taken = std::thread::scope(|scope| {
+		let workers = (0..4)
+			.map(|_| scope.spawn(|| (&gen).collect::<Vec<_>>()))
+			.collect::<Vec<_>>();
+		workers.into_iter()
+			.flat_map(|worker| worker.join().unwrap())
+			.collect::<Vec<_>>()
+	});
+
+	assert_eq!(taken.len(), 1000);
+	assert_eq!(taken.into_iter().collect::<HashSet<_>>(), (0..1000).collect());
+}
+
+#[test]
+fn cloned_handles() {
+	let gen = SharedGenerator::<u32>::from_fn_ptr(count);
+	let workers = (0..4)
+		.map(|_| {
+			let gen = gen.clone();
+			std::thread::spawn(move || gen.count())
+		})
+		.collect::<Vec<_>>();
+	drop(gen);
+
+	let total = workers.into_iter().map(|worker| worker.join().unwrap()).sum::<usize>();
+	assert_eq!(total, 1000);
+}
+
+#[test]
+fn runs_on_demand() {
+	let resumed = Arc::new(AtomicU32::new(0));
+	let counter = resumed.clone();
+	let mut gen = SharedGenerator::<u32>::from_closure(move || {
+		for i in 0u32.. {
+			counter.fetch_add(1, Ordering::SeqCst);
+			yeet::yeet(i)
+		}
+	});
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(1));
+	std::thread::sleep(Duration::from_millis(20));
+	assert_eq!(resumed.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn runs_on_other_thread() {
+	fn gen() {
+		yeet::yeet(std::thread::current().id())
+	}
+
+	let mut gen = SharedGenerator::from_fn_ptr(gen);
+	assert_ne!(gen.next(), Some(std::thread::current().id()));
+}
+
+#[test]
+fn panic_goes_to_one_consumer() {
+	fn gen() {
+		yeet::yeet(0u32);
+		panic!("oops")
+	}
+
+	let mut gen = SharedGenerator::<u32>::from_fn_ptr(gen);
+	let other = gen.clone();
+	assert_eq!(gen.next(), Some(0));
+
+	let result = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next()));
+	assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "oops");
+	assert_eq!((&other).next(), None);
+}
+
+#[test]
+fn dropping_cancels_the_producer() {
+	struct Guard(Arc<AtomicU32>);
+	impl Drop for Guard {
+		fn drop(&mut self) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	let dropped = Arc::new(AtomicU32::new(0));
+	let guard = Guard(dropped.clone());
+	let mut gen = SharedGenerator::<u32>::from_closure(move || {
+		let _guard = guard;
+		yeet::yeet_all(0u32..)
+	});
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+
+	for _ in 0..500 {
+		if dropped.load(Ordering::SeqCst) == 1 {
+			return
+		}
+		std::thread::sleep(Duration::from_millis(10));
+	}
+	panic!("The producer was never cancelled")
+}
@@ -0,0 +1,37 @@
+//! This module tests running tasks on Windows fibers.
+#![cfg(all(windows, feature = "fibers", not(feature = "corosensei")))]
+
+use yeet::Generator;
+
+fn inner() {
+	yeet::yeet_all(0u32..3)
+}
+
+fn outer() {
+	for value in Generator::<u32>::from_fn_ptr(inner) {
+		yeet::yeet(value * 10);
+	}
+}
+
+#[test]
+fn nested() {
+	let gen = Generator::<u32>::from_fn_ptr(outer);
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 10, 20]);
+}
+
+#[test]
+fn moved_between_resumes() {
+	let mut gens = vec![Generator::<u32>::from_fn_ptr(inner)];
+	assert_eq!(gens[0].next(), Some(0));
+
+	let mut gen = gens.pop().unwrap();
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.stats().stack_used > 0, cfg!(not(feature = "small")));
+}
+
+#[test]
+fn panics_propagate() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| panic!("oops"));
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+	assert!(result.is_err());
+}
@@ -0,0 +1,95 @@
+//! This module tests the compatibility layer with the API of genawaiter.
+#![cfg(feature = "genawaiter")]
+
+use std::panic::AssertUnwindSafe;
+use std::task::Poll;
+use yeet::genawaiter::GeneratorState;
+use yeet::genawaiter::rc::{Co, Gen};
+
+#[test]
+fn iterate() {
+	let gen = Gen::new(|co| async move {
+		for i in 0..3u32 {
+			co.yield_(i).await;
+		}
+	});
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn resume_arguments() {
+	async fn echo(co: Co<String, u32>) -> u32 {
+		let mut last = 0;
+		for _ in 0..2 {
+			last = co.yield_(format!("last was {last}")).await;
+		}
+		last * 2
+	}
+
+	let mut gen = Gen::new(echo);
+	assert_eq!(gen.resume_with(100), GeneratorState::Yielded("last was 0".into()));
+	assert_eq!(gen.resume_with(1), GeneratorState::Yielded("last was 1".into()));
+	assert_eq!(gen.resume_with(2), GeneratorState::Complete(4));
+
+	let result = std::panic::catch_unwind(AssertUnwindSafe(|| gen.resume_with(3)));
+	assert!(result.is_err());
+}
+
+#[test]
+fn helpers() {
+	/* Producers may hand their handle down to other async functions. */
+	async fn pair(co: &Co<u32>, a: u32, b: u32) {
+		co.yield_(a).await;
+		co.yield_(b).await;
+	}
+
+	let mut gen = Gen::new(|co| async move {
+		pair(&co, 1, 2).await;
+		let clone = co.clone();
+		pair(&clone, 3, 4).await;
+		"done"
+	});
+
+	let mut values = Vec::new();
+	let output = loop {
+		match gen.resume() {
+			GeneratorState::Yielded(value) => values.push(value),
+			GeneratorState::Complete(output) => break output,
+		}
+	};
+	assert_eq!(values, [1, 2, 3, 4]);
+	assert_eq!(output, "done");
+}
+
+#[test]
+fn other_futures() {
+	/* A future that isn't ready the first time it gets polled. */
+	let mut polled = false;
+	let not_yet = std::future::poll_fn(move |_| {
+		if std::mem::replace(&mut polled, true) {
+			Poll::Ready(7u32)
+		} else {
+			Poll::Pending
+		}
+	});
+
+	let gen = Gen::new(|co| async move {
+		let value = not_yet.await;
+		co.yield_(value).await;
+	});
+	assert_eq!(gen.collect::<Vec<_>>(), [7]);
+}
+
+#[test]
+fn coroutine() {
+	use std::pin::Pin;
+	use yeet::coroutine::{Coroutine, CoroutineState};
+
+	let mut gen = Gen::new(|co: Co<u32, u32>| async move {
+		let arg = co.yield_(1).await;
+		arg + 1
+	});
+	let mut gen = Pin::new(&mut gen);
+	assert_eq!(gen.as_mut().resume(0), CoroutineState::Yielded(1));
+	assert_eq!(gen.as_mut().resume(41), CoroutineState::Complete(42));
+}
@@ -0,0 +1,106 @@
+//! This module tests groups of generators whose values get merged and tagged
+//! with the member they came from.
+
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+use yeet::{Generator, GeneratorGroup};
+
+fn range(range: std::ops::Range<u32>) -> Generator<u32> {
+	Generator::from_fn_with(range, yeet::yeet_all)
+}
+
+#[test]
+fn round_robin() {
+	let mut group = GeneratorGroup::new();
+	let a = group.insert(range(0..3));
+	let b = group.insert(range(10..11));
+	let c = group.insert(range(20..22));
+	assert_eq!(group.len(), 3);
+
+	assert_eq!(group.by_ref().collect::<Vec<_>>(), [
+		(a, 0), (b, 10), (c, 20),
+		(a, 1), (c, 21),
+		(a, 2),
+	]);
+	assert!(group.is_empty());
+}
+
+#[test]
+fn insert_and_remove() {
+	let mut group = GeneratorGroup::new();
+	let a = group.insert(range(0..10));
+	let b = group.insert(range(10..20));
+
+	assert_eq!(group.next(), Some((a, 0)));
+	assert_eq!(group.next(), Some((b, 10)));
+
+	let mut removed = group.remove(a).unwrap();
+	assert!(!group.contains(a));
+	assert!(group.remove(a).is_none());
+	assert_eq!(removed.next(), Some(1));
+
+	/* Indices of removed members get reused. */
+	let c = group.insert(range(30..31));
+	assert_eq!(c, a);
+	assert_eq!(group.indices().collect::<Vec<_>>(), [a, b]);
+
+	assert_eq!(group.next(), Some((c, 30)));
+	assert_eq!(group.next(), Some((b, 11)));
+	assert_eq!(group.next(), Some((b, 12)));
+	assert_eq!(group.len(), 1);
+}
+
+#[test]
+fn checkpoints() {
+	fn busy() {
+		for i in 0..2u32 {
+			yeet::yield_now();
+			yeet::yield_now();
+			yeet::yeet(i);
+		}
+	}
+
+	let mut group = GeneratorGroup::new();
+	let a = group.insert(Generator::from_fn_ptr(busy));
+	let b = group.insert(range(10..13));
+
+	/* Members that reach a checkpoint lose the rest of their turn. */
+	assert_eq!(group.collect::<Vec<_>>(), [
+		(b, 10), (b, 11), (a, 0),
+		(b, 12), (a, 1),
+	]);
+}
+
+#[test]
+fn sleeping() {
+	fn sleepy() {
+		yeet::sleep(Duration::from_millis(50));
+		yeet::yeet(0u32);
+	}
+
+	let start = Instant::now();
+	let mut group = GeneratorGroup::new();
+	let a = group.insert(Generator::from_fn_ptr(sleepy));
+	let b = group.insert(range(10..12));
+
+	assert_eq!(group.collect::<Vec<_>>(), [(b, 10), (b, 11), (a, 0)]);
+	assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn panics() {
+	fn faulty() {
+		yeet::yeet(0u32);
+		panic!("faulty")
+	}
+
+	let mut group = GeneratorGroup::new();
+	let a = group.insert(Generator::from_fn_ptr(faulty));
+	let b = group.insert(range(10..12));
+
+	assert_eq!(group.next(), Some((a, 0)));
+	assert_eq!(group.next(), Some((b, 10)));
+	assert!(std::panic::catch_unwind(AssertUnwindSafe(|| group.next())).is_err());
+	assert!(!group.contains(a));
+	assert_eq!(group.collect::<Vec<_>>(), [(b, 11)]);
+}
@@ -0,0 +1,87 @@
+//! Tests for forwarding size hints from producers to consumers.
+use yeet::Generator;
+
+#[test]
+fn forwards_lower_bound_from_yeet_all() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..10));
+	assert_eq!(gen.size_hint(), (0, None));
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.size_hint(), (9, None));
+	assert_eq!(gen.nth(4), Some(5));
+	assert_eq!(gen.size_hint(), (4, None));
+
+	assert_eq!(gen.by_ref().count(), 4);
+	assert_eq!(gen.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn collect_reserves_up_front() {
+	let gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..1000));
+	let values = gen.collect::<Vec<_>>();
+	assert_eq!(values.len(), 1000);
+	assert_eq!(values.capacity(), 1000);
+}
+
+#[test]
+fn explicit_hints_count_down() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::size_hint(3, Some(3));
+		yeet::yeet(1u32);
+		yeet::yeet_slice(&[2u32, 3]);
+	});
+
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.size_hint(), (2, Some(2)));
+	assert_eq!(gen.next(), Some(2));
+	assert_eq!(gen.size_hint(), (1, Some(1)));
+	assert_eq!(gen.peek(), Some(&3));
+	assert_eq!(gen.size_hint(), (1, Some(1)));
+	assert_eq!(gen.next(), Some(3));
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn ignored_when_filtering() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0u32..10))
+		.filter_in_task(|value| value % 2 == 0);
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.size_hint(), (0, None));
+}
+
+#[test]
+fn reset_after_yeet_all() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_all(0u32..2);
+		yeet::yield_now();
+		yeet::yeet(2u32);
+	});
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.size_hint(), (1, None));
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.resume(), yeet::Resume::Pending);
+	assert_eq!(gen.size_hint(), (0, None));
+}
+
+#[test]
+fn explicit_hints_survive_yeet_all() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::size_hint(4, Some(4));
+		yeet::yeet_all(0u32..2);
+		yeet::yield_now();
+		yeet::yeet_all(2u32..4);
+	});
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.size_hint(), (1, None));
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.resume(), yeet::Resume::Pending);
+	assert_eq!(gen.size_hint(), (2, Some(2)));
+	assert_eq!(gen.by_ref().count(), 2);
+	assert_eq!(gen.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn empty_yeet_all_outside_generators() {
+	yeet::yeet_all(std::iter::empty::<u32>());
+}
@@ -0,0 +1,49 @@
+//! This module tests instrumentation hooks.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use yeet::Generator;
+use yeet::hook::{self, Direction, SwitchEvent};
+
+fn gen() {
+	yeet::yeet(0u8);
+	yeet::yeet(1u8);
+}
+
+#[test]
+fn per_generator() {
+	let events = Rc::new(RefCell::new(Vec::new()));
+
+	let mut gen = Generator::<u8>::from_fn_ptr(gen);
+	let id = gen.id();
+	let log = events.clone();
+	gen.on_switch(move |event| log.borrow_mut().push(*event));
+
+	assert_eq!(gen.by_ref().count(), 2);
+
+	let expected = [Direction::Resume, Direction::Yield].repeat(3)
+		.into_iter()
+		.map(|direction| SwitchEvent { task: id, direction })
+		.collect::<Vec<_>>();
+	assert_eq!(*events.borrow(), expected);
+}
+
+#[test]
+fn global() {
+	let mut gen = Generator::<u8>::from_fn_ptr(gen);
+	let id = gen.id();
+
+	let events = Arc::new(Mutex::new(Vec::new()));
+	let log = events.clone();
+	let hook = hook::add_hook(move |event| if event.task == id {
+		log.lock().unwrap().push(event.direction)
+	});
+
+	assert_eq!(gen.next(), Some(0));
+	assert!(hook::remove_hook(hook));
+	assert_eq!(gen.next(), Some(1));
+
+	assert_eq!(*events.lock().unwrap(), &[Direction::Resume, Direction::Yield]);
+	assert!(!hook::remove_hook(hook));
+}
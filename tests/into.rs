@@ -0,0 +1,47 @@
+//! This module tests the conversion of values into generators.
+
+use yeet::{Generator, IntoGenerator};
+
+fn collect<M>(source: impl IntoGenerator<u32, M>) -> Vec<u32> {
+	source.into_generator().collect()
+}
+
+#[test]
+fn generators() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_all(0..3u32));
+	assert_eq!(gen.next(), Some(0));
+
+	/* Generators get handed back as they are, so they keep their state. */
+	let id = gen.id();
+	let gen = gen.into_generator();
+	assert_eq!(gen.id(), id);
+	assert_eq!(collect(gen), [1, 2]);
+}
+
+#[test]
+fn closures() {
+	fn producer() {
+		yeet::yeet(1u32);
+		yeet::yeet(2u32);
+	}
+
+	let values = vec![3u32, 4];
+	assert_eq!(collect(move || yeet::yeet_all(values.into_iter())), [3, 4]);
+	assert_eq!(collect(producer as fn()), [1, 2]);
+}
+
+#[test]
+fn iterables() {
+	assert_eq!(collect(vec![1, 2, 3]), [1, 2, 3]);
+	assert_eq!(collect([4, 5]), [4, 5]);
+	assert_eq!(collect((0..10).filter(|i| i % 3 == 0)), [0, 3, 6, 9]);
+	assert!(collect(None).is_empty());
+}
+
+#[test]
+fn size_hint() {
+	/* The producer forwards the size hint of the iterator as it goes. */
+	let mut gen: Generator<u32> = vec![1, 2, 3].into_generator();
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.size_hint(), (2, None));
+}
@@ -0,0 +1,93 @@
+//! This module tests the non-blocking I/O helpers for producers.
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use yeet::{Generator, Resume};
+use yeet::io::{Interest, Wait};
+
+/// Creates a pair of connected sockets, the second of which is non-blocking.
+fn pair() -> (UnixStream, UnixStream) {
+	let (theirs, ours) = UnixStream::pair().unwrap();
+	ours.set_nonblocking(true).unwrap();
+	(theirs, ours)
+}
+
+#[test]
+fn read_waits_for_data() {
+	let (mut theirs, ours) = pair();
+	let fd = ours.as_raw_fd();
+	let mut gen = Generator::<Vec<u8>>::from_closure(move || {
+		let mut ours = ours;
+		let mut buf = [0u8; 3];
+		yeet::io::read_exact(&mut ours, &mut buf).unwrap();
+		yeet::yeet(buf.to_vec());
+	});
+
+	assert!(matches!(gen.resume(), Resume::Pending));
+	assert_eq!(gen.waiting_on(), Some(Wait { fd, interest: Interest::Readable }));
+
+	/* Resuming early just has it wait again. */
+	assert!(matches!(gen.resume(), Resume::Pending));
+
+	theirs.write_all(b"ab").unwrap();
+	assert!(matches!(gen.resume(), Resume::Pending));
+	theirs.write_all(b"c").unwrap();
+	assert!(matches!(gen.resume(), Resume::Value(value) if value == b"abc"));
+	assert_eq!(gen.waiting_on(), None);
+}
+
+#[test]
+fn write_waits_for_room() {
+	let (mut theirs, ours) = pair();
+	let fd = ours.as_raw_fd();
+	let mut gen = Generator::<usize>::from_closure(move || {
+		let mut ours = ours;
+		let data = vec![7u8; 4 * 1024 * 1024];
+		yeet::io::write_all(&mut ours, &data).unwrap();
+		yeet::yeet(data.len());
+	});
+
+	let mut received = 0;
+	let mut buf = vec![0u8; 64 * 1024];
+	let written = loop {
+		match gen.resume() {
+			Resume::Value(written) => break written,
+			Resume::Pending => {
+				let wait = gen.waiting_on().unwrap();
+				assert_eq!(wait, Wait { fd, interest: Interest::Writable });
+				received += theirs.read(&mut buf).unwrap();
+			}
+			Resume::Complete => unreachable!(),
+		}
+	};
+
+	theirs.set_nonblocking(true).unwrap();
+	while let Ok(read) = theirs.read(&mut buf) {
+		received += read;
+	}
+	assert_eq!(written, received);
+}
+
+#[test]
+fn block_times_out() {
+	let (_theirs, ours) = pair();
+	let wait = Wait { fd: ours.as_raw_fd(), interest: Interest::Readable };
+	assert!(!wait.block(Some(std::time::Duration::from_millis(10))).unwrap());
+}
+
+#[test]
+fn closed_counts_as_ready() {
+	let (theirs, ours) = pair();
+	let mut gen = Generator::<usize>::from_closure(move || {
+		let mut ours = ours;
+		let mut buf = [0u8; 8];
+		yeet::yeet(yeet::io::read(&mut ours, &mut buf).unwrap());
+	});
+
+	assert!(matches!(gen.resume(), Resume::Pending));
+	drop(theirs);
+	assert!(gen.waiting_on().unwrap().block(None).unwrap());
+	assert_eq!(gen.next(), Some(0));
+}
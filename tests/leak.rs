@@ -0,0 +1,67 @@
+//! This module tests the census of live generators.
+
+use std::time::Duration;
+use yeet::{Generator, GeneratorBuilder, TaskId};
+
+/// Whether the generator with the given identifier shows up in a report.
+fn reported(id: TaskId) -> bool {
+	yeet::leak_report().tasks().iter().any(|task| task.id == id)
+}
+
+fn gen() {
+	yeet::yeet_all(0..3u32)
+}
+
+#[test]
+fn forgotten() {
+	yeet::leak::enable();
+
+	let mut gen = GeneratorBuilder::new()
+		.name("forgotten")
+		.build::<u32>(gen);
+	assert_eq!(gen.next(), Some(0));
+	assert!(reported(gen.id()));
+
+	let report = yeet::leak_report();
+	let task = report.tasks().iter().find(|task| task.id == gen.id()).unwrap();
+	assert_eq!(task.name.as_deref(), Some("forgotten"));
+	assert_eq!(task.thread, std::thread::current().id());
+	assert!(report.to_string().contains("\"forgotten\""));
+	assert!(report.older_than(Duration::ZERO).any(|task| task.id == gen.id()));
+	assert!(!report.older_than(Duration::from_secs(3600)).any(|task| task.id == gen.id()));
+
+	let id = gen.id();
+	drop(gen);
+	assert!(!reported(id));
+}
+
+#[test]
+fn finished() {
+	yeet::leak::enable();
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert!(reported(gen.id()));
+	gen.by_ref().for_each(drop);
+	assert!(!reported(gen.id()));
+}
+
+#[test]
+fn across_threads() {
+	yeet::leak::enable();
+
+	let (tx, rx) = std::sync::mpsc::channel();
+	let thread = std::thread::spawn(move || {
+		let gen = Generator::<u32>::from_fn_ptr(gen);
+		tx.send(gen.id()).unwrap();
+		std::thread::park();
+	});
+
+	let id = rx.recv().unwrap();
+	let report = yeet::leak_report();
+	let task = report.tasks().iter().find(|task| task.id == id).unwrap();
+	assert_eq!(task.thread, thread.thread().id());
+
+	thread.thread().unpark();
+	thread.join().unwrap();
+	assert!(!reported(id));
+}
@@ -0,0 +1,47 @@
+//! This module tests values lent by the consumer to the producer.
+
+use yeet::Generator;
+
+#[test]
+fn fill_buffer() {
+	fn gen() {
+		for i in 0..3u8 {
+			let len = yeet::with_lent(|buf: &mut [u8]| {
+				buf.fill(i);
+				buf.len()
+			});
+			yeet::yeet(len);
+		}
+	}
+
+	let mut gen = Generator::<usize>::from_fn_ptr(gen);
+	let mut buf = [0xffu8; 4];
+	for i in 0..3u8 {
+		assert_eq!(gen.next_lending(&mut buf[..]), Some(4));
+		assert_eq!(buf, [i; 4]);
+	}
+	assert_eq!(gen.next_lending(&mut buf[..]), None);
+}
+
+#[test]
+#[should_panic]
+fn not_lent() {
+	fn gen() {
+		yeet::with_lent(|_: &mut u32| {});
+		yeet::yeet(0u8);
+	}
+
+	let mut gen = Generator::<u8>::from_fn_ptr(gen);
+	let _ = gen.next();
+}
+
+#[test]
+#[should_panic]
+fn yield_while_borrowed() {
+	fn gen() {
+		yeet::with_lent(|_: &mut u32| yeet::yeet(0u8));
+	}
+
+	let mut gen = Generator::<u8>::from_fn_ptr(gen);
+	let _ = gen.next_lending(&mut 0u32);
+}
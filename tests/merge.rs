@@ -0,0 +1,94 @@
+//! This module tests the sorted merge of sorted generators.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use yeet::Generator;
+
+fn sorted(values: Vec<u32>) -> Generator<u32> {
+	Generator::from_fn_with(values, |values| yeet::yeet_all(values.into_iter()))
+}
+
+#[test]
+fn merge() {
+	let gens = vec![
+		sorted(vec![1, 5, 9]),
+		sorted(vec![]),
+		sorted(vec![2, 3, 4, 10, 11]),
+		sorted(vec![0, 6]),
+	];
+
+	let merged = yeet::merge_sorted(gens, u32::cmp);
+	assert_eq!(merged.collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 6, 9, 10, 11]);
+}
+
+#[test]
+fn reversed() {
+	let gens = [vec![9, 5, 1], vec![8, 2]].map(sorted);
+
+	let merged = yeet::merge_sorted(gens, |a: &u32, b| b.cmp(a));
+	assert_eq!(merged.collect::<Vec<_>>(), [9, 8, 5, 2, 1]);
+}
+
+#[test]
+fn ties_keep_their_order() {
+	fn tagged(tag: char, keys: Vec<u32>) -> Generator<(u32, char)> {
+		Generator::from_fn_with((tag, keys), |(tag, keys)| {
+			yeet::yeet_all(keys.into_iter().map(|key| (key, tag)))
+		})
+	}
+
+	let gens = [tagged('a', vec![1, 2]), tagged('b', vec![1, 2]), tagged('c', vec![2])];
+	let merged = yeet::merge_sorted(gens, |a, b| a.0.cmp(&b.0));
+	assert_eq!(
+		merged.map(|(_, tag)| tag).collect::<String>(),
+		"ababc");
+}
+
+#[test]
+fn lazy() {
+	let resumes = Rc::new(Cell::new(0));
+	let counted = |values: Vec<u32>| {
+		let resumes = resumes.clone();
+		Generator::from_closure(move || {
+			for value in values {
+				resumes.set(resumes.get() + 1);
+				yeet::yeet(value)
+			}
+		})
+	};
+
+	let mut merged = yeet::merge_sorted([counted(vec![1, 3]), counted(vec![2, 4])], u32::cmp);
+	assert_eq!(resumes.get(), 0);
+	assert_eq!(merged.next(), Some(1));
+	assert_eq!(resumes.get(), 3);
+	assert_eq!(merged.size_hint().0, 2);
+	assert_eq!(merged.live(), 2);
+}
+
+#[test]
+fn cancels_the_rest() {
+	struct Guard(Rc<Cell<u32>>);
+	impl Drop for Guard {
+		fn drop(&mut self) {
+			self.0.set(self.0.get() + 1)
+		}
+	}
+
+	let dropped = Rc::new(Cell::new(0));
+	let guarded = |values: Vec<u32>| {
+		let guard = Guard(dropped.clone());
+		Generator::from_closure(move || {
+			let _guard = guard;
+			yeet::yeet_all(values.into_iter())
+		})
+	};
+
+	let mut merged = yeet::merge_sorted([guarded(vec![1, 4]), guarded(vec![2]), guarded(vec![3, 5])], u32::cmp);
+	assert_eq!(merged.next(), Some(1));
+	assert_eq!(merged.next(), Some(2));
+	assert_eq!(dropped.get(), 1);
+	assert_eq!(merged.live(), 2);
+
+	assert!(merged.close().is_ok());
+	assert_eq!(dropped.get(), 3);
+}
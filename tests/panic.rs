@@ -0,0 +1,69 @@
+//! This module tests the reporting of panics raised by producers.
+
+use std::panic::AssertUnwindSafe;
+use yeet::{GeneratorBuilder, TaskPanic};
+
+fn inner() {
+	panic!("inner panic")
+}
+
+fn outer() {
+	let inner = GeneratorBuilder::new()
+		.capture_backtraces(true)
+		.build::<u8>(inner);
+	yeet::yeet_all(inner);
+}
+
+#[test]
+fn stitched_backtraces() {
+	let gen = GeneratorBuilder::new()
+		.capture_backtraces(true)
+		.build::<u8>(outer);
+
+	let what = std::panic::catch_unwind(AssertUnwindSafe(|| gen.count())).unwrap_err();
+	let panic = what.downcast::<TaskPanic>().unwrap();
+
+	assert_eq!(panic.message(), Some("inner panic"));
+	assert_eq!(panic.backtraces().len(), 2);
+	assert_eq!(panic.payload().downcast_ref::<&str>(), Some(&"inner panic"));
+}
+
+#[test]
+fn not_captured() {
+	let gen = yeet::Generator::<u8>::from_fn_ptr(inner);
+
+	let what = std::panic::catch_unwind(AssertUnwindSafe(|| gen.count())).unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"inner panic"));
+}
+
+#[test]
+fn identified() {
+	fn leaf() {
+		yeet::yeet(1u8);
+		yeet::yeet(2u8);
+		panic!("leaf panic")
+	}
+	fn middle() {
+		let leaf = GeneratorBuilder::new()
+			.name("leaf")
+			.identify_panics(true)
+			.build::<u8>(leaf);
+		yeet::yeet_all(leaf);
+	}
+
+	let gen = GeneratorBuilder::new()
+		.name("middle")
+		.identify_panics(true)
+		.build::<u8>(middle);
+
+	let what = std::panic::catch_unwind(AssertUnwindSafe(|| gen.count())).unwrap_err();
+	let panic = what.downcast::<TaskPanic>().unwrap();
+	let task = panic.task().unwrap();
+
+	assert_eq!(task.name.as_deref(), Some("leaf"));
+	assert_eq!(task.depth, 2);
+	assert_eq!(task.yields, 2);
+	assert!(panic.backtraces().is_empty());
+	assert!(panic.to_string().starts_with("generator \"leaf\" (#"));
+	assert!(panic.to_string().ends_with("depth 2, after 2 yields) panicked: leaf panic"));
+}
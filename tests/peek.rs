@@ -0,0 +1,53 @@
+//! This module tests looking ahead at the values of a generator.
+
+use yeet::{Generator, Resume};
+
+fn gen() {
+	yeet::yeet_all(0..3u32)
+}
+
+#[test]
+fn peek_then_next() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.peek(), Some(&0));
+	assert_eq!(gen.peek(), Some(&0));
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.peek(), Some(&2));
+	assert_eq!(gen.resume(), Resume::Value(2));
+	assert_eq!(gen.peek(), None);
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn peek_skips_pending() {
+	fn gen() {
+		yeet::yield_now();
+		yeet::yeet(7u32);
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.peek(), Some(&7));
+	assert_eq!(gen.resume(), Resume::Value(7));
+	assert_eq!(gen.resume(), Resume::Complete);
+}
+
+#[test]
+fn lookahead_parser() {
+	fn digits() {
+		yeet::yeet_all("12+345".chars())
+	}
+
+	let mut gen = Generator::<char>::from_fn_ptr(digits);
+	let mut numbers = Vec::new();
+	while gen.peek().is_some() {
+		let mut number = 0;
+		while let Some(digit) = gen.peek().and_then(|c| c.to_digit(10)) {
+			number = number * 10 + digit;
+			gen.next();
+		}
+		numbers.push(number);
+		gen.next();
+	}
+	assert_eq!(numbers, &[12, 345]);
+}
@@ -0,0 +1,74 @@
+//! This module tests the return addresses given to tasks for profilers.
+#![cfg(all(unix, not(feature = "corosensei"), not(feature = "ucontext"), any(target_arch = "x86_64", target_arch = "aarch64")))]
+
+use yeet::GeneratorBuilder;
+
+/// The address the given symbol was given in the perf map of the process.
+fn address_of(symbol: &str) -> usize {
+	let map = std::fs::read_to_string(format!("/tmp/perf-{}.map", std::process::id())).unwrap();
+	let line = map.lines()
+		.find(|line| line.split_once(' ').unwrap().1.split_once(' ').unwrap().1 == symbol)
+		.unwrap();
+	usize::from_str_radix(line.split(' ').next().unwrap(), 16).unwrap()
+}
+
+#[test]
+fn named_in_perf_map() {
+	yeet::perf::enable().unwrap();
+	let mut gen = GeneratorBuilder::new()
+		.name("perf-named")
+		.build::<u32>(|| yeet::yeet(0u32));
+	assert_eq!(gen.next(), Some(0));
+
+	let address = address_of("yeet::task::perf-named");
+	assert_eq!(yeet::perf::task_at(address).as_deref(), Some("perf-named"));
+	assert_eq!(yeet::perf::task_at(address + 1).as_deref(), Some("perf-named"));
+	assert_eq!(yeet::perf::task_at(0), None);
+}
+
+#[test]
+fn names_share_addresses() {
+	yeet::perf::enable().unwrap();
+	for _ in 0..2 {
+		let gen = GeneratorBuilder::new()
+			.name("perf-shared")
+			.build::<u32>(|| yeet::yeet(0u32));
+		assert_eq!(gen.count(), 1);
+	}
+
+	let map = std::fs::read_to_string(format!("/tmp/perf-{}.map", std::process::id())).unwrap();
+	assert_eq!(map.lines().filter(|line| line.ends_with(" yeet::task::perf-shared")).count(), 1);
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn return_address_below_start() {
+	yeet::perf::enable().unwrap();
+	let mut gen = GeneratorBuilder::new()
+		.name("perf-return")
+		.build::<usize>(|| {
+			/* The return address sits right below the frame record above the
+			 * start function, which sits right below the upper canary. */
+			let stack = yeet::current_task().unwrap().stack;
+			let top = (stack.end & !15) - 16;
+			yeet::yeet(unsafe { ((top - 24) as *const usize).read() })
+		});
+
+	assert_eq!(gen.next(), Some(address_of("yeet::task::perf-return")));
+}
+
+#[test]
+fn unwinding_still_works() {
+	yeet::perf::enable().unwrap();
+	let mut gen = GeneratorBuilder::new()
+		.name("perf-unwind")
+		.build::<String>(|| {
+			let backtrace = std::backtrace::Backtrace::force_capture();
+			yeet::yeet(backtrace.to_string());
+			panic!("unwound");
+		});
+
+	assert!(gen.next().is_some());
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+	assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "unwound");
+}
@@ -0,0 +1,56 @@
+//! Tests for the state generators are left in after their producer panics.
+use yeet::{Generator, Panicked, Resume};
+
+fn faulty() {
+	yeet::yeet(1u32);
+	panic!("faulty producer");
+}
+
+#[test]
+fn try_next_catches_panics() {
+	let mut gen = Generator::<u32>::from_fn_ptr(faulty);
+	assert!(!gen.is_poisoned());
+	assert_eq!(gen.try_next(), Ok(Some(1)));
+	assert_eq!(gen.try_next(), Err(Panicked));
+	assert!(gen.is_poisoned());
+	assert_eq!(gen.try_next(), Err(Panicked));
+	assert_eq!(gen.next(), None);
+
+	let what = gen.take_panic().unwrap();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"faulty producer"));
+	assert!(gen.take_panic().is_none());
+}
+
+#[test]
+fn next_poisons_after_propagating() {
+	let mut gen = Generator::<u32>::from_fn_ptr(faulty);
+	assert_eq!(gen.next(), Some(1));
+
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+	assert!(result.is_err());
+	assert!(gen.is_poisoned());
+	assert!(gen.take_panic().is_none());
+	assert_eq!(gen.next(), None);
+	assert_eq!(gen.resume(), Resume::Complete);
+	assert_eq!(gen.try_next(), Err(Panicked));
+}
+
+#[test]
+fn clear_poison() {
+	let mut gen = Generator::<u32>::from_fn_ptr(faulty);
+	assert_eq!(gen.try_next(), Ok(Some(1)));
+	assert_eq!(gen.try_next(), Err(Panicked));
+
+	gen.clear_poison();
+	assert!(!gen.is_poisoned());
+	assert!(gen.take_panic().is_none());
+	assert_eq!(gen.try_next(), Ok(None));
+}
+
+#[test]
+fn completed_generators_are_not_poisoned() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32));
+	assert_eq!(gen.try_next(), Ok(Some(1)));
+	assert_eq!(gen.try_next(), Ok(None));
+	assert!(!gen.is_poisoned());
+}
@@ -0,0 +1,94 @@
+//! Tests for driving generators on a pool of threads.
+use std::collections::HashSet;
+use yeet::GeneratorPool;
+
+fn count(n: u32) {
+	for i in 0..n {
+		yeet::yeet(i)
+	}
+}
+
+#[test]
+fn merges_all_values() {
+	let mut pool = GeneratorPool::<u32>::new(3);
+	for i in 0..10u32 {
+		pool.spawn_with(i * 100, |base: u32| {
+			for j in 0..5 {
+				yeet::yeet(base + j)
+			}
+		});
+	}
+	assert_eq!(pool.live(), 10);
+
+	let values = pool.by_ref().collect::<HashSet<_>>();
+	let expected = (0..10u32)
+		.flat_map(|i| (0..5u32).map(move |j| i * 100 + j))
+		.collect::<HashSet<_>>();
+	assert_eq!(values, expected);
+	assert_eq!(pool.live(), 0);
+}
+
+#[test]
+fn generators_stay_on_their_thread() {
+	let mut pool = GeneratorPool::<(u32, String)>::new(4);
+	for i in 0..16u32 {
+		pool.spawn_with(i, |i| {
+			for _ in 0..8 {
+				let name = std::thread::current().name().unwrap().to_owned();
+				yeet::yeet((i, name))
+			}
+		});
+	}
+
+	let mut threads = std::collections::HashMap::new();
+	for (i, name) in pool {
+		assert_eq!(threads.entry(i).or_insert_with(|| name.clone()), &name);
+	}
+	assert_eq!(threads.len(), 16);
+}
+
+#[test]
+fn can_spawn_after_draining() {
+	let mut pool = GeneratorPool::<u32>::new(2);
+	pool.spawn_with(3, count);
+	assert_eq!(pool.by_ref().count(), 3);
+	assert_eq!(pool.next(), None);
+
+	pool.spawn_with(4, count);
+	assert_eq!(pool.by_ref().count(), 4);
+}
+
+#[test]
+fn propagates_panics() {
+	let mut pool = GeneratorPool::<u32>::new(2);
+	pool.spawn(|| panic!("pool producer"));
+
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pool.next()));
+	let what = result.unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"pool producer"));
+	assert_eq!(pool.live(), 0);
+}
+
+#[test]
+fn dropping_the_pool_stops_the_threads() {
+	let mut pool = GeneratorPool::<u32>::new(2);
+	pool.spawn(|| loop { yeet::yeet(0u32) });
+	assert_eq!(pool.next(), Some(0));
+	drop(pool);
+}
+
+#[test]
+fn new_generators_go_to_the_least_loaded_thread() {
+	let mut pool = GeneratorPool::<u32>::new(2);
+	pool.spawn_with(1, count);
+	pool.spawn(|| loop { yeet::yeet(0u32) });
+	assert_eq!(pool.load().collect::<Vec<_>>(), [1, 1]);
+
+	/* Wait for the short generator to be done. */
+	while pool.load().collect::<Vec<_>>() != [0, 1] {
+		pool.next();
+	}
+
+	pool.spawn_with(1, count);
+	assert_eq!(pool.load().collect::<Vec<_>>(), [1, 1]);
+}
@@ -0,0 +1,107 @@
+//! This module tests the attribution of time to the producers it is spent in.
+
+use std::time::Duration;
+use yeet::GeneratorBuilder;
+use yeet::profile::{ProfileEntry, ProfileReport};
+
+/// The entry of the report for the given task name.
+fn task<'a>(report: &'a ProfileReport, name: &str) -> &'a ProfileEntry {
+	report.tasks().iter().find(|task| task.name() == name).unwrap()
+}
+
+/// The entry of the report for the given stack of task names.
+fn stack<'a>(report: &'a ProfileReport, path: &[&str]) -> &'a ProfileEntry {
+	report.stacks().iter().find(|stack| stack.path == path).unwrap()
+}
+
+#[test]
+fn activations() {
+	yeet::profile::enable();
+	let mut gen = GeneratorBuilder::new()
+		.name("profile-activations")
+		.build::<u32>(|| {
+			yeet::yeet(0u32);
+			yeet::yield_now();
+			std::thread::sleep(Duration::from_millis(10));
+			yeet::yeet(1u32);
+		});
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 1]);
+
+	let report = yeet::profile_report();
+	let task = task(&report, "profile-activations");
+	assert_eq!(task.path, ["profile-activations"]);
+	assert_eq!(task.activations, 4);
+	assert!(task.self_time >= Duration::from_millis(10));
+	assert_eq!(task.self_time, task.total);
+}
+
+#[test]
+fn nested_time_is_attributed_to_the_inner_producer() {
+	yeet::profile::enable();
+	let outer = GeneratorBuilder::new()
+		.name("profile-outer")
+		.build::<u32>(|| {
+			let mut inner = GeneratorBuilder::new()
+				.name("profile-inner")
+				.build::<u32>(|| {
+					std::thread::sleep(Duration::from_millis(20));
+					yeet::yeet(1u32);
+				});
+			yeet::yeet(inner.next().unwrap());
+		});
+	assert_eq!(outer.collect::<Vec<_>>(), [1]);
+
+	let report = yeet::profile_report();
+	let outer = stack(&report, &["profile-outer"]);
+	let inner = stack(&report, &["profile-outer", "profile-inner"]);
+	assert!(inner.self_time >= Duration::from_millis(20));
+	assert!(outer.total >= inner.total);
+	assert!(outer.self_time < Duration::from_millis(20));
+	assert_eq!(task(&report, "profile-inner").self_time, inner.self_time);
+
+	let folded = report.to_string();
+	let line = folded.lines().find(|line| line.starts_with("profile-outer;profile-inner ")).unwrap();
+	let micros = line.rsplit(' ').next().unwrap().parse::<u128>().unwrap();
+	assert_eq!(micros, inner.self_time.as_micros());
+}
+
+#[test]
+fn recursion_is_counted_once() {
+	fn recurse(depth: u32) {
+		let mut gen = GeneratorBuilder::new()
+			.name("profile-recursive")
+			.build_closure::<u32>(move || {
+				std::thread::sleep(Duration::from_millis(5));
+				if depth > 0 {
+					recurse(depth - 1)
+				}
+			});
+		assert_eq!(gen.next(), None);
+	}
+
+	yeet::profile::enable();
+	let outer = GeneratorBuilder::new()
+		.name("profile-recursion-root")
+		.build::<u32>(|| recurse(2));
+	outer.for_each(drop);
+
+	let report = yeet::profile_report();
+	let root = task(&report, "profile-recursion-root");
+	let recursive = task(&report, "profile-recursive");
+	assert!(recursive.activations >= 3);
+	assert!(recursive.self_time >= Duration::from_millis(15));
+	assert!(recursive.total <= root.total);
+	assert!(stack(&report, &["profile-recursion-root", "profile-recursive", "profile-recursive", "profile-recursive"]).activations > 0);
+}
+
+#[test]
+fn anonymous_tasks() {
+	yeet::profile::enable();
+	let gen = GeneratorBuilder::new()
+		.name("profile-anonymous-parent")
+		.build::<u32>(|| yeet::yeet_all(yeet::Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32))));
+	assert_eq!(gen.collect::<Vec<_>>(), [1]);
+
+	let report = yeet::profile_report();
+	assert!(stack(&report, &["profile-anonymous-parent", yeet::profile::ANONYMOUS]).activations > 0);
+}
@@ -0,0 +1,149 @@
+//! This module tests the raw tasks underneath generators.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use yeet::raw::{self, Resume, Stack, Suspend, Task};
+use yeet::registry::TaskState;
+
+const STACK_SIZE: usize = 64 * 1024;
+
+fn counter() {
+	let mut i = 0u32;
+	while raw::exit(i) == Resume::Continue {
+		i += 1;
+	}
+}
+
+#[test]
+fn suspends_with_values() {
+	let mut task = Task::<u32>::from_fn_ptr(Stack::new(STACK_SIZE), counter);
+	assert_eq!(task.state(), TaskState::Created);
+
+	for i in 0..10 {
+		assert!(matches!(task.resume(Resume::Continue), Suspend::Value(value) if value == i));
+		assert_eq!(task.state(), TaskState::Suspended);
+	}
+	assert!(matches!(task.resume(Resume::Cancel), Suspend::Done));
+	assert_eq!(task.state(), TaskState::Finished);
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Done));
+}
+
+#[test]
+fn suspends_without_values() {
+	let mut task = Task::<u32>::new(Stack::new(STACK_SIZE), || {
+		assert_eq!(raw::exit_pending(), Resume::Continue);
+		raw::exit(1u32);
+	});
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Pending));
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(1)));
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Done));
+}
+
+#[test]
+fn yeet_works_inside() {
+	let mut task = Task::<u32>::new(Stack::new(STACK_SIZE), || yeet::yeet(7u32));
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(7)));
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Done));
+}
+
+#[test]
+fn cancel_unwinds_yeet() {
+	let mut task = Task::<u32>::new(Stack::new(STACK_SIZE), || loop {
+		yeet::yeet(0u32)
+	});
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+	match task.resume(Resume::Cancel) {
+		Suspend::Panic(what) => assert!(what.is::<yeet::Cancelled>()),
+		_ => panic!("The task did not report its cancellation"),
+	}
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Done));
+}
+
+#[test]
+fn panics_are_reported() {
+	let mut task = Task::<u32>::new(Stack::new(STACK_SIZE), || panic!("oops"));
+	match task.resume(Resume::Continue) {
+		Suspend::Panic(what) => assert_eq!(*what.downcast::<&str>().unwrap(), "oops"),
+		_ => panic!("The task did not report its panic"),
+	}
+}
+
+#[test]
+fn dropping_cancels() {
+	struct Guard(Rc<Cell<bool>>);
+	impl Drop for Guard {
+		fn drop(&mut self) {
+			self.0.set(true)
+		}
+	}
+
+	let dropped = Rc::new(Cell::new(false));
+	let guard = Guard(dropped.clone());
+	let mut task = Task::<u32>::new(Stack::new(STACK_SIZE), move || {
+		let _guard = guard;
+		counter()
+	});
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+	assert!(!dropped.get());
+	drop(task);
+	assert!(dropped.get());
+}
+
+#[test]
+fn tasks_can_move() {
+	let mut tasks = (0..4)
+		.map(|_| Task::<u32>::from_fn_ptr(Stack::new(STACK_SIZE), counter))
+		.collect::<Vec<_>>();
+
+	/* Round-robin over the tasks, moving them around between switches. */
+	for round in 0..4u32 {
+		for task in &mut tasks {
+			assert!(matches!(task.resume(Resume::Continue), Suspend::Value(value) if value == round));
+		}
+		tasks.reverse();
+		let first = tasks.remove(0);
+		tasks.push(first);
+	}
+}
+
+#[test]
+fn runs_on_its_stack() {
+	let stack = Stack::new(STACK_SIZE);
+	let bounds = stack.bounds();
+	let mut task = Task::<usize>::new(stack, || {
+		let local = 0u8;
+		raw::exit(&local as *const u8 as usize);
+	});
+	assert_eq!(task.stack_bounds(), bounds);
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(addr) if bounds.contains(&addr)));
+	if let Some(context) = task.saved_context() {
+		assert!(bounds.contains(&context.sp));
+	}
+}
+
+#[test]
+fn stack_is_reusable() {
+	let task = Task::<u32>::from_fn_ptr(Stack::new(STACK_SIZE), counter);
+	let bounds = task.stack_bounds();
+	let stack = task.into_stack().unwrap();
+	assert_eq!(stack.bounds(), bounds);
+
+	let mut task = Task::<u32>::from_fn_ptr(stack, counter);
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+	let mut task = Task::<u32>::from_fn_ptr(task.into_stack().unwrap(), counter);
+	assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+	assert_eq!(task.stack_bounds(), bounds);
+}
+
+#[test]
+fn aligned_stack() {
+	for align in [16usize, 4096, 64 * 1024, 2 * 1024 * 1024] {
+		let stack = Stack::with_alignment(STACK_SIZE, align);
+		let bounds = stack.bounds();
+		assert_eq!(bounds.start % align, 0);
+		assert!(bounds.len() >= STACK_SIZE);
+
+		let mut task = Task::<u32>::from_fn_ptr(stack, counter);
+		assert!(matches!(task.resume(Resume::Continue), Suspend::Value(0)));
+	}
+}
@@ -0,0 +1,105 @@
+//! This module tests driving generators doing I/O with a reactor.
+#![cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+use yeet::{Generator, Reactor};
+
+/// Creates a generator that reads lines of the given length from the given
+/// socket, until it gets closed.
+fn lines(socket: UnixStream, len: usize) -> Generator<Vec<u8>> {
+	socket.set_nonblocking(true).unwrap();
+	Generator::from_closure(move || {
+		let mut socket = socket;
+		loop {
+			let mut line = vec![0u8; len];
+			match yeet::io::read_exact(&mut socket, &mut line) {
+				Ok(()) => yeet::yeet(line),
+				Err(_) => break,
+			}
+		}
+	})
+}
+
+#[test]
+fn merges_ready_producers() {
+	let mut reactor = Reactor::new().unwrap();
+	let mut writers = Vec::new();
+	for i in 0..8u8 {
+		let (theirs, ours) = UnixStream::pair().unwrap();
+		reactor.spawn(lines(ours, 2));
+		writers.push(std::thread::spawn(move || {
+			let mut theirs = theirs;
+			for j in 0..4u8 {
+				std::thread::sleep(Duration::from_millis(1));
+				theirs.write_all(&[i, j]).unwrap();
+			}
+		}));
+	}
+
+	let mut values = reactor.by_ref().collect::<Vec<_>>();
+	assert!(reactor.is_empty());
+	for writer in writers {
+		writer.join().unwrap();
+	}
+
+	/* Lines from each writer come out in the order they were written. */
+	for i in 0..8u8 {
+		let from = values.iter().filter(|line| line[0] == i).map(|line| line[1]).collect::<Vec<_>>();
+		assert_eq!(from, [0, 1, 2, 3]);
+	}
+	values.sort();
+	assert_eq!(values.len(), 32);
+}
+
+#[test]
+fn waits_for_readiness() {
+	let (theirs, ours) = UnixStream::pair().unwrap();
+	let mut reactor = Reactor::new().unwrap();
+	reactor.spawn(lines(ours, 1));
+
+	let writer = std::thread::spawn(move || {
+		let mut theirs = theirs;
+		std::thread::sleep(Duration::from_millis(20));
+		theirs.write_all(b"x").unwrap();
+	});
+
+	assert_eq!(reactor.next(), Some(b"x".to_vec()));
+	writer.join().unwrap();
+	assert_eq!(reactor.next(), None);
+	assert_eq!(reactor.waiting(), 0);
+}
+
+#[test]
+fn checkpoints_interleave() {
+	let mut reactor = Reactor::<Vec<u8>>::new().unwrap();
+	reactor.spawn(Generator::from_fn_ptr(|| {
+		for i in 0..3u8 {
+			yeet::yield_now();
+			yeet::yeet(vec![i]);
+		}
+	}));
+	reactor.spawn(Generator::from_fn_ptr(|| yeet::yeet(vec![10u8])));
+
+	assert_eq!(reactor.collect::<Vec<_>>(), [vec![10], vec![0], vec![1], vec![2]]);
+}
+
+#[test]
+fn spawn_after_done() {
+	let mut reactor = Reactor::new().unwrap();
+	assert_eq!(reactor.next(), None);
+
+	reactor.spawn(Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32)));
+	assert_eq!(reactor.len(), 1);
+	assert_eq!(reactor.by_ref().collect::<Vec<_>>(), [1]);
+	assert!(reactor.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "oops")]
+fn panics_propagate() {
+	let mut reactor = Reactor::<u32>::new().unwrap();
+	reactor.spawn(Generator::from_fn_ptr(|| panic!("oops")));
+	reactor.next();
+}
@@ -0,0 +1,129 @@
+//! This module tests recording and replaying the order generators run in.
+
+use yeet::{Generator, GeneratorBuilder};
+use yeet::record::{EventKind, Recorder};
+use yeet::testing::StepDriver;
+
+/// Creates a named generator yielding the given values, with hashing on.
+fn named(name: &str, values: Vec<u32>) -> Generator<u32> {
+	let mut gen = GeneratorBuilder::new()
+		.name(name)
+		.build_closure(move || yeet::yeet_all(values.into_iter()));
+	gen.record_values();
+	gen
+}
+
+#[test]
+fn records_nested_switches() {
+	let recorder = Recorder::start();
+	let mut outer = GeneratorBuilder::new()
+		.name("outer")
+		.build_closure::<u32>(|| {
+			let inner = GeneratorBuilder::new()
+				.name("inner")
+				.build::<u32>(|| yeet::yeet(1u32));
+			for value in inner {
+				yeet::yeet(value);
+			}
+		});
+	assert_eq!(outer.next(), Some(1));
+
+	let recording = recorder.finish();
+	let events = recording.events().iter()
+		.map(|event| (event.name.as_deref().unwrap(), event.kind))
+		.collect::<Vec<_>>();
+	assert_eq!(events, [
+		("outer", EventKind::Resume),
+		("inner", EventKind::Resume),
+		("inner", EventKind::Yield(None)),
+		("outer", EventKind::Yield(None)),
+	]);
+	assert_eq!(recording.to_string(), "\"outer\" resumed\n\"inner\" resumed\n\"inner\" yielded\n\"outer\" yielded\n");
+}
+
+#[test]
+fn hashes_values() {
+	let recorder = Recorder::start();
+	let mut a = named("a", vec![7]);
+	let mut b = named("b", vec![7, 8]);
+	a.next();
+	b.next();
+	b.next();
+
+	let recording = recorder.finish();
+	let hashes = recording.events().iter()
+		.filter_map(|event| match event.kind {
+			EventKind::Yield(hash) => hash,
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(hashes.len(), 3);
+	assert_eq!(hashes[0], hashes[1]);
+	assert_ne!(hashes[1], hashes[2]);
+}
+
+#[test]
+fn nested_recorders() {
+	let outer = Recorder::start();
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet(1u32));
+	{
+		let _dropped = Recorder::start();
+		gen.next();
+	}
+	let inner = Recorder::start();
+	gen.next();
+
+	assert_eq!(inner.finish().events().len(), 2);
+	assert_eq!(outer.finish().events().len(), 4);
+}
+
+#[test]
+fn replay_in_recorded_order() {
+	let recorder = Recorder::start();
+	let mut driver = StepDriver::new();
+	driver.add("a", named("a", vec![1, 2]));
+	driver.add("b", named("b", vec![3]));
+	driver.run(&["b", "a", "b", "a", "a"]);
+	let recording = recorder.finish();
+
+	let mut driver = StepDriver::new();
+	driver.add("a", named("a", vec![1, 2]));
+	driver.add("b", named("b", vec![3]));
+	assert_eq!(driver.replay(&recording), Ok(()));
+}
+
+#[test]
+fn replay_finds_divergence() {
+	let recorder = Recorder::start();
+	let mut driver = StepDriver::new();
+	driver.add("a", named("a", vec![1, 2]));
+	driver.run(&["a", "a", "a"]);
+	let recording = recorder.finish();
+
+	let mut driver = StepDriver::new();
+	driver.add("a", named("a", vec![1, 5]));
+	let divergence = driver.replay(&recording).unwrap_err();
+	assert_eq!(divergence.name, "a");
+	assert_eq!(divergence.step, 3);
+	assert!(matches!(divergence.expected, EventKind::Yield(Some(_))));
+
+	let mut driver = StepDriver::new();
+	driver.add("a", named("a", vec![1]));
+	let divergence = driver.replay(&recording).unwrap_err();
+	assert_eq!(divergence.found, EventKind::Complete);
+}
+
+#[test]
+fn replay_panics() {
+	let recorder = Recorder::start();
+	let mut gen = GeneratorBuilder::new()
+		.name("boom")
+		.build::<u32>(|| panic!("boom"));
+	let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| gen.next()));
+	let recording = recorder.finish();
+	assert_eq!(recording.events()[1].kind, EventKind::Panic);
+
+	let mut driver = StepDriver::new();
+	driver.add("boom", GeneratorBuilder::new().name("boom").build::<u32>(|| panic!("boom")));
+	assert_eq!(driver.replay(&recording), Ok(()));
+}
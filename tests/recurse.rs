@@ -0,0 +1,45 @@
+//! This module tests deep recursion on the stacks of child tasks.
+
+#[test]
+fn deeply() {
+	let sum = yeet::recurse(50_000u64, |n, rec| {
+		if n == 0 { 0 } else { n + rec.call(n - 1) }
+	});
+	assert_eq!(sum, 50_000 * 50_001 / 2);
+}
+
+#[test]
+fn over_tree() {
+	use std::rc::Rc;
+
+	enum Tree {
+		Leaf(u32),
+		Node(Rc<Tree>, Rc<Tree>),
+	}
+
+	fn build(depth: u32) -> Rc<Tree> {
+		match depth {
+			0 => Rc::new(Tree::Leaf(1)),
+			_ => Rc::new(Tree::Node(build(depth - 1), build(depth - 1))),
+		}
+	}
+
+	let leaves = yeet::recurse_with_stack_size(16 * 1024, build(10), |tree, rec| {
+		match &*tree {
+			Tree::Leaf(n) => *n,
+			Tree::Node(l, r) => rec.call(l.clone()) + rec.call(r.clone()),
+		}
+	});
+	assert_eq!(leaves, 1024);
+}
+
+#[test]
+#[should_panic(expected = "bottomed out")]
+fn propagates_panics() {
+	yeet::recurse(100u32, |n, rec| -> u32 {
+		if n == 0 {
+			panic!("bottomed out")
+		}
+		rec.call(n - 1)
+	});
+}
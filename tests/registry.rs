@@ -0,0 +1,96 @@
+//! This module tests the registry of live tasks.
+
+use yeet::{Generator, GeneratorBuilder};
+use yeet::registry::{self, TaskState};
+
+fn gen() {
+	yeet::yeet(0u8);
+}
+
+#[test]
+fn enumerate() {
+	registry::enable();
+
+	let mut named = GeneratorBuilder::new()
+		.name("named")
+		.build::<u8>(gen);
+	let unnamed = Generator::<u8>::from_fn_ptr(gen);
+	assert_eq!(named.name(), Some("named"));
+	assert_eq!(unnamed.name(), None);
+
+	let tasks = yeet::tasks().collect::<Vec<_>>();
+	assert_eq!(tasks.len(), 2);
+	assert_eq!(tasks[0].id, named.id());
+	assert_eq!(tasks[0].name.as_deref(), Some("named"));
+	assert_eq!(tasks[0].state, TaskState::Created);
+	assert_eq!(tasks[1].id, unnamed.id());
+	assert!(!tasks[1].stack.is_empty());
+
+	assert_eq!(named.next(), Some(0));
+	assert_eq!(named.state(), TaskState::Suspended);
+	assert_eq!(yeet::tasks().next().unwrap().state, TaskState::Suspended);
+
+	assert_eq!(named.next(), None);
+	assert_eq!(named.state(), TaskState::Finished);
+
+	drop(unnamed);
+	let tasks = yeet::tasks().collect::<Vec<_>>();
+	assert_eq!(tasks.len(), 1);
+	assert_eq!(tasks[0].state, TaskState::Finished);
+
+	drop(named);
+	assert_eq!(yeet::tasks().count(), 0);
+	registry::disable();
+}
+
+#[test]
+fn disabled() {
+	let _gen = Generator::<u8>::from_fn_ptr(gen);
+	assert!(!registry::is_enabled());
+	assert_eq!(yeet::tasks().count(), 0);
+}
+
+#[test]
+fn tree() {
+	registry::enable();
+	assert!(yeet::current_task().is_none());
+
+	fn leaf() {
+		let me = yeet::current_task().unwrap();
+		assert_eq!(me.depth, 3);
+		assert_eq!(me.state, TaskState::Running);
+		assert_eq!(me.children().count(), 0);
+		yeet::yeet((me.id, me.parent.unwrap()));
+	}
+
+	fn branch() {
+		let me = yeet::current_task().unwrap();
+		assert_eq!(me.name.as_deref(), Some("branch"));
+		assert_eq!(me.depth, 2);
+
+		let left = GeneratorBuilder::new().name("left").build::<(yeet::TaskId, yeet::TaskId)>(leaf);
+		let right = GeneratorBuilder::new().name("right").build::<(yeet::TaskId, yeet::TaskId)>(leaf);
+		let children = me.children().map(|child| child.name.unwrap()).collect::<Vec<_>>();
+		assert_eq!(children.iter().map(|name| &**name).collect::<Vec<_>>(), ["left", "right"]);
+
+		for (id, parent) in left.chain(right) {
+			assert_eq!(parent, me.id);
+			assert!(me.children().any(|child| child.id == id));
+			yeet::yeet(me.parent);
+		}
+	}
+
+	let mut root = GeneratorBuilder::new().name("root").build::<Option<yeet::TaskId>>(|| {
+		let branch = GeneratorBuilder::new().name("branch").build::<Option<yeet::TaskId>>(branch);
+		let root = yeet::current_task().unwrap();
+		assert_eq!(root.parent, None);
+		assert_eq!(root.depth, 1);
+		yeet::yeet_all(branch)
+	});
+	let id = root.id();
+	assert_eq!(root.next(), Some(Some(id)));
+	assert_eq!(root.next(), Some(Some(id)));
+	assert_eq!(root.next(), None);
+
+	registry::disable();
+}
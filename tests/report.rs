@@ -0,0 +1,77 @@
+//! This module tests the out-of-band metadata reported by producers.
+
+use yeet::Generator;
+
+#[derive(Debug, PartialEq)]
+struct Progress {
+	done: usize,
+	total: usize,
+}
+
+#[test]
+fn progress() {
+	fn gen() {
+		let items = [10u32, 20, 30];
+		for (i, item) in items.iter().enumerate() {
+			yeet::report(Progress { done: i, total: items.len() });
+			yeet::yeet(*item);
+		}
+		yeet::report(Progress { done: items.len(), total: items.len() });
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.last_report::<Progress>(), None);
+	for i in 0..3 {
+		assert!(gen.next().is_some());
+		assert_eq!(gen.last_report(), Some(&Progress { done: i, total: 3 }));
+	}
+	assert_eq!(gen.next(), None);
+	assert_eq!(gen.last_report(), Some(&Progress { done: 3, total: 3 }));
+}
+
+#[test]
+fn replaced_by_other_type() {
+	fn gen() {
+		yeet::report(1u64);
+		yeet::report("done");
+		yeet::yeet(());
+	}
+
+	let mut gen = Generator::<()>::from_fn_ptr(gen);
+	gen.next();
+	assert_eq!(gen.last_report::<u64>(), None);
+	assert_eq!(gen.last_report::<&str>(), Some(&"done"));
+}
+
+#[test]
+#[should_panic]
+fn outside_generator() {
+	yeet::report(0u32);
+}
+
+#[test]
+fn errors() {
+	fn gen() {
+		for s in ["1", "x", "3", "y"] {
+			match s.parse::<u32>() {
+				Ok(value) => yeet::yeet(value),
+				Err(error) => yeet::report_error(error),
+			}
+		}
+		yeet::report_error("done");
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert!(gen.take_errors().is_empty());
+	assert_eq!(gen.next(), Some(1));
+	assert!(gen.take_errors().is_empty());
+	assert_eq!(gen.next(), Some(3));
+	assert_eq!(gen.take_errors().len(), 1);
+	assert_eq!(gen.next(), None);
+
+	let errors = gen.take_errors().into_iter()
+		.map(|error| error.to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(errors, ["invalid digit found in string", "done"]);
+	assert!(gen.take_errors().is_empty());
+}
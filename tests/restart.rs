@@ -0,0 +1,100 @@
+//! This module tests generators whose producers get restarted on their stacks.
+
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::rc::Rc;
+use yeet::RestartableGenerator;
+
+thread_local! {
+	static STACKS: RefCell<Vec<Range<usize>>> = const { RefCell::new(Vec::new()) };
+	static DROPPED: Cell<u32> = const { Cell::new(0) };
+}
+
+struct Guard;
+impl Drop for Guard {
+	fn drop(&mut self) {
+		DROPPED.set(DROPPED.get() + 1)
+	}
+}
+
+fn recording() {
+	let task = yeet::current_task().unwrap();
+	STACKS.with_borrow_mut(|stacks| stacks.push(task.stack));
+	yeet::yeet_all(0u32..3)
+}
+
+#[test]
+fn runs_once_as_an_iterator() {
+	let gen = RestartableGenerator::<u32>::new(|| yeet::yeet_all(0u32..3));
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn cycles_through_the_values() {
+	let gen = RestartableGenerator::<u32>::new(|| yeet::yeet_all(0u32..3));
+	assert_eq!(gen.cycle().take(8).collect::<Vec<_>>(), [0, 1, 2, 0, 1, 2, 0, 1]);
+}
+
+#[test]
+fn reuses_the_stack() {
+	STACKS.with_borrow_mut(Vec::clear);
+	let gen = RestartableGenerator::<u32>::new(recording);
+	assert_eq!(gen.cycle().take(9).count(), 9);
+
+	let stacks = STACKS.take();
+	assert_eq!(stacks.len(), 3);
+	assert!(stacks.iter().all(|stack| *stack == stacks[0]));
+}
+
+#[test]
+fn reuses_the_stack_when_restarted_early() {
+	STACKS.with_borrow_mut(Vec::clear);
+	let mut gen = RestartableGenerator::<u32>::new(recording);
+	assert_eq!(gen.next(), Some(0));
+	gen.restart();
+	assert_eq!(gen.next(), Some(0));
+	gen.restart();
+	gen.restart();
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 1, 2]);
+
+	let stacks = STACKS.take();
+	assert_eq!(stacks.len(), 3);
+	assert!(stacks.iter().all(|stack| *stack == stacks[0]));
+}
+
+#[test]
+fn restarting_cancels_the_producer() {
+	DROPPED.set(0);
+	let mut gen = RestartableGenerator::<u32>::new(|| {
+		let _guard = Guard;
+		yeet::yeet_all(0u32..)
+	});
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(DROPPED.get(), 0);
+
+	gen.restart();
+	assert_eq!(DROPPED.get(), 1);
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+	assert_eq!(DROPPED.get(), 2);
+}
+
+#[test]
+fn cycle_stops_on_an_empty_run() {
+	let gen = RestartableGenerator::<u32>::new(|| {});
+	assert_eq!(gen.cycle().next(), None);
+}
+
+#[test]
+fn cycle_stops_once_a_run_is_empty() {
+	let runs = Rc::new(Cell::new(0u32));
+	let counter = runs.clone();
+	let gen = RestartableGenerator::<u32>::new(move || {
+		counter.set(counter.get() + 1);
+		if counter.get() <= 2 {
+			yeet::yeet(counter.get())
+		}
+	});
+	assert_eq!(gen.cycle().take(10).collect::<Vec<_>>(), [1, 2]);
+	assert_eq!(runs.get(), 3);
+}
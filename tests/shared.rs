@@ -0,0 +1,65 @@
+//! This module tests state shared between the consumer and the producer.
+
+use yeet::Generator;
+
+#[derive(Debug, Default, PartialEq)]
+struct Scratch {
+	buf: Vec<u32>,
+	resets: usize,
+}
+
+fn gen() {
+	for i in 0..3u32 {
+		let len = yeet::with_state(|scratch: &mut Scratch| {
+			scratch.buf.push(i);
+			scratch.buf.len()
+		});
+		yeet::yeet(len);
+	}
+}
+
+#[test]
+fn shared() {
+	let mut gen = Generator::<usize>::from_fn_ptr(gen);
+	gen.set_shared_state(Scratch::default());
+
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.next(), Some(2));
+
+	/* The consumer may touch the state in between resumes. */
+	let scratch = gen.shared_state_mut::<Scratch>().unwrap();
+	scratch.buf.clear();
+	scratch.resets += 1;
+
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.next(), None);
+	assert_eq!(gen.take_shared_state(), Some(Scratch { buf: vec![2], resets: 1 }));
+	assert_eq!(gen.shared_state::<Scratch>(), None);
+}
+
+#[test]
+fn wrong_type() {
+	let mut gen = Generator::<usize>::from_fn_ptr(gen);
+	gen.set_shared_state(0u32);
+	assert_eq!(gen.take_shared_state::<Scratch>(), None);
+	assert_eq!(gen.shared_state::<u32>(), Some(&0));
+}
+
+#[test]
+#[should_panic]
+fn missing() {
+	let mut gen = Generator::<usize>::from_fn_ptr(gen);
+	gen.next();
+}
+
+#[test]
+#[should_panic]
+fn yield_while_borrowed() {
+	fn gen() {
+		yeet::with_state(|_: &mut u32| yeet::yeet(0u32))
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	gen.set_shared_state(0u32);
+	gen.next();
+}
@@ -0,0 +1,71 @@
+//! This module tests side channels, through which producers send values other
+//! than the ones they yield.
+
+use yeet::{Generator, SideChannel};
+
+struct Log;
+impl SideChannel for Log {
+	type Item = String;
+}
+
+struct Errors;
+impl SideChannel for Errors {
+	type Item = u32;
+}
+
+fn producer() {
+	for i in 0..4u32 {
+		yeet::yeet_to::<Log>(format!("yielding {i}"));
+		if i % 2 == 1 {
+			yeet::yeet_to::<Errors>(i);
+		}
+		yeet::yeet(i);
+	}
+}
+
+#[test]
+fn channels_are_separate() {
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	let mut log = gen.side::<Log>();
+	let errors = gen.side::<Errors>();
+
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(log.len(), 2);
+	assert_eq!(log.next().as_deref(), Some("yielding 0"));
+
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [2, 3]);
+	assert_eq!(log.collect::<Vec<_>>(), ["yielding 1", "yielding 2", "yielding 3"]);
+	assert_eq!(errors.collect::<Vec<_>>(), [1, 3]);
+}
+
+#[test]
+fn unopened_channels_drop_values() {
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	assert_eq!(gen.next(), Some(0));
+
+	let log = gen.side::<Log>();
+	assert!(log.is_empty());
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(log.collect::<Vec<_>>(), ["yielding 1"]);
+}
+
+#[test]
+fn handles_share_their_queue() {
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	let mut first = gen.side::<Errors>();
+	let second = gen.side::<Errors>();
+
+	gen.by_ref().for_each(drop);
+	drop(gen);
+
+	assert_eq!(first.next(), Some(1));
+	assert_eq!(second.collect::<Vec<_>>(), [3]);
+	assert!(first.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn outside_of_generator() {
+	yeet::yeet_to::<Log>(String::new());
+}
@@ -0,0 +1,69 @@
+//! This module tests the alternate signal stacks of tasks.
+#![cfg(unix)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yeet::GeneratorBuilder;
+
+/// The address of a local in the most recent run of [`handler`].
+static HANDLED_AT: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn handler(_: libc::c_int) {
+	let marker = 0u8;
+	HANDLED_AT.store(&marker as *const u8 as usize, Ordering::SeqCst);
+}
+
+/// The alternate signal stack of the current thread, as an address range.
+fn current_signal_stack() -> Option<(usize, usize)> {
+	let mut current = unsafe { std::mem::zeroed::<libc::stack_t>() };
+	unsafe { libc::sigaltstack(std::ptr::null(), &mut current) };
+	if current.ss_flags & libc::SS_DISABLE != 0 {
+		return None
+	}
+	Some((current.ss_sp as usize, current.ss_sp as usize + current.ss_size))
+}
+
+#[test]
+fn installed_while_running() {
+	let before = current_signal_stack();
+	let mut gen = GeneratorBuilder::new()
+		.signal_stack(64 * 1024)
+		.build_closure::<Option<(usize, usize)>>(|| {
+			yeet::yeet(current_signal_stack());
+			yeet::yeet(current_signal_stack());
+		});
+
+	let inside = gen.next().unwrap().unwrap();
+	assert!(inside.1 - inside.0 >= 64 * 1024);
+	assert_ne!(Some(inside), before);
+	assert_eq!(current_signal_stack(), before);
+	assert_eq!(gen.next(), Some(Some(inside)));
+}
+
+#[test]
+fn handlers_run_on_it() {
+	unsafe {
+		let mut action = std::mem::zeroed::<libc::sigaction>();
+		action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+		action.sa_flags = libc::SA_ONSTACK;
+		libc::sigaction(libc::SIGUSR2, &action, std::ptr::null_mut());
+	}
+
+	let mut gen = GeneratorBuilder::new()
+		.signal_stack(64 * 1024)
+		.build_closure::<(usize, usize)>(|| {
+			unsafe { libc::raise(libc::SIGUSR2) };
+			yeet::yeet(current_signal_stack().unwrap());
+		});
+
+	let (start, end) = gen.next().unwrap();
+	let handled_at = HANDLED_AT.load(Ordering::SeqCst);
+	assert!((start..end).contains(&handled_at));
+}
+
+#[test]
+fn absent_by_default() {
+	let before = current_signal_stack();
+	let mut gen = GeneratorBuilder::new()
+		.build_closure::<Option<(usize, usize)>>(|| yeet::yeet(current_signal_stack()));
+	assert_eq!(gen.next(), Some(before));
+}
@@ -0,0 +1,102 @@
+//! Tests for skipping over values on the producer side.
+use yeet::{Generator, Resume};
+
+fn count() {
+	yeet::yeet_all(0u32..10)
+}
+
+#[test]
+fn skips_in_a_single_resume() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count);
+	assert_eq!(gen.resume_skipping(3), Resume::Value(3));
+	assert_eq!(gen.stats().resumes, 1);
+	assert_eq!(gen.stats().yielded, 4);
+
+	assert_eq!(gen.resume_skipping(4), Resume::Value(8));
+	assert_eq!(gen.stats().resumes, 2);
+	assert_eq!(gen.resume_skipping(0), Resume::Value(9));
+	assert_eq!(gen.resume_skipping(5), Resume::Complete);
+}
+
+#[test]
+fn skips_buffered_values_first() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_slice(&[0u32, 1, 2, 3]);
+		yeet::yeet_slice(&[4u32, 5, 6, 7]);
+	});
+	assert_eq!(gen.peek(), Some(&0));
+	assert_eq!(gen.resume_skipping(2), Resume::Value(2));
+	assert_eq!(gen.resume_skipping(3), Resume::Value(6));
+	assert_eq!(gen.stats().resumes, 2);
+}
+
+#[test]
+fn skips_across_checkpoints() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet(0u32);
+		yeet::yield_now();
+		yeet::yeet(1u32);
+		yeet::yeet(2u32);
+	});
+	assert_eq!(gen.resume_skipping(2), Resume::Pending);
+	assert_eq!(gen.resume(), Resume::Value(2));
+}
+
+#[test]
+fn skips_filtered_values_only_once() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count).filter_in_task(|value| value % 2 == 0);
+	assert_eq!(gen.resume_skipping(2), Resume::Value(4));
+}
+
+#[test]
+fn skips_mapped_values() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count).map_in_task(|value| value * 10);
+	assert_eq!(gen.resume_skipping(5), Resume::Value(50));
+}
+
+#[test]
+fn nth_skips_in_the_producer() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count);
+	assert_eq!(gen.nth(4), Some(4));
+	assert_eq!(gen.stats().resumes, 1);
+	assert_eq!(gen.nth(1), Some(6));
+	assert_eq!(gen.nth(10), None);
+}
+
+#[test]
+fn nth_across_checkpoints() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet(0u32);
+		yeet::yield_now();
+		yeet::yeet(1u32);
+	});
+	assert_eq!(gen.nth(1), Some(1));
+}
+
+#[test]
+fn skip_adapter_uses_nth() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count);
+	let values = gen.by_ref().skip(7).take(2).collect::<Vec<_>>();
+	assert_eq!(values, [7, 8]);
+	assert_eq!(gen.stats().resumes, 2);
+}
+
+#[test]
+fn skip_items() {
+	let mut gen = Generator::<u32>::from_fn_ptr(count);
+	assert_eq!(gen.skip_items(0), 0);
+	assert_eq!(gen.skip_items(3), 3);
+	assert_eq!(gen.next(), Some(3));
+	assert_eq!(gen.skip_items(2), 2);
+	assert_eq!(gen.peek(), Some(&6));
+	assert_eq!(gen.skip_items(10), 4);
+	assert_eq!(gen.skip_items(10), 0);
+}
+
+#[test]
+fn skip_items_with_buffered_values() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_slice(&[0u32, 1, 2, 3, 4]));
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.skip_items(2), 2);
+	assert_eq!(gen.skip_items(5), 2);
+}
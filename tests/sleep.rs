@@ -0,0 +1,88 @@
+//! This module tests producers that sleep.
+
+use std::time::{Duration, Instant};
+use yeet::{Generator, Resume};
+
+fn ticks() {
+	for i in 0..3u32 {
+		yeet::sleep(Duration::from_millis(10));
+		yeet::yeet(i);
+	}
+}
+
+#[test]
+fn resume_reports_the_deadline() {
+	let mut gen = Generator::<u32>::from_fn_ptr(ticks);
+	let before = Instant::now();
+	assert!(matches!(gen.resume(), Resume::Pending));
+
+	let deadline = gen.deadline().unwrap();
+	assert!(deadline >= before + Duration::from_millis(10));
+
+	/* Resuming early just has it sleep again. */
+	assert!(matches!(gen.resume(), Resume::Pending));
+	assert_eq!(gen.deadline(), Some(deadline));
+
+	std::thread::sleep(deadline - Instant::now());
+	assert!(matches!(gen.resume(), Resume::Value(0)));
+	assert_eq!(gen.deadline(), None);
+}
+
+#[test]
+fn next_blocks_until_the_deadline() {
+	let mut gen = Generator::<u32>::from_fn_ptr(ticks);
+	let before = Instant::now();
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 1, 2]);
+	assert!(before.elapsed() >= Duration::from_millis(30));
+
+	/* Every tick takes one resume to start sleeping and one to wake up. */
+	assert_eq!(gen.stats().resumes, 7);
+}
+
+#[test]
+fn sleep_until_the_past() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::sleep_until(Instant::now() - Duration::from_millis(1));
+		yeet::yeet(1u32);
+	});
+	assert!(matches!(gen.resume(), Resume::Value(1)));
+}
+
+#[test]
+fn timeout_while_sleeping() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::sleep(Duration::from_secs(60));
+		yeet::yeet(1u32);
+	});
+	assert!(gen.next_timeout(Duration::from_millis(10)).is_err());
+	assert!(gen.stats().resumes <= 3);
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+fn reactor_wakes_in_order() {
+	let mut reactor = yeet::Reactor::<u32>::new().unwrap();
+	for delay in [30u32, 10, 20] {
+		reactor.spawn(Generator::from_closure(move || {
+			yeet::sleep(Duration::from_millis(delay.into()));
+			yeet::yeet(delay);
+		}));
+	}
+
+	let before = Instant::now();
+	assert_eq!(reactor.next(), Some(10));
+	assert_eq!(reactor.sleeping(), 2);
+	assert_eq!(reactor.collect::<Vec<_>>(), [20, 30]);
+	assert!(before.elapsed() >= Duration::from_millis(30));
+}
+
+#[test]
+fn overlong_sleep_lasts_forever() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::sleep(Duration::MAX);
+		yeet::yeet(0u32);
+	});
+	assert!(matches!(gen.resume(), Resume::Pending));
+	assert!(gen.deadline().unwrap() > Instant::now() + Duration::from_secs(60 * 60));
+	assert!(matches!(gen.resume(), Resume::Pending));
+}
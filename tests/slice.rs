@@ -0,0 +1,72 @@
+//! Tests for yielding whole slices at once.
+use yeet::{CancelToken, Generator};
+
+#[test]
+fn single_switch() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_slice(&[1u32, 2, 3, 4]);
+		yeet::yeet_vec(Vec::<u32>::new());
+		yeet::yeet_vec(vec![5u32, 6]);
+	});
+
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.stats().resumes, 1);
+	assert_eq!(gen.peek(), Some(&2));
+	assert_eq!(gen.by_ref().take(3).collect::<Vec<_>>(), [2, 3, 4]);
+	assert_eq!(gen.stats().resumes, 1);
+	assert_eq!(gen.stats().yielded, 4);
+
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [5, 6]);
+	assert_eq!(gen.stats().resumes, 3);
+	assert_eq!(gen.stats().yielded, 6);
+}
+
+#[test]
+fn owned_values() {
+	let gen = Generator::<String>::from_fn_ptr(|| {
+		yeet::yeet_vec(vec![String::from("a"), String::from("b")])
+	});
+	assert_eq!(gen.collect::<Vec<_>>(), ["a", "b"]);
+}
+
+#[test]
+fn filtered_one_by_one() {
+	let gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_slice(&[1u32, 2, 3, 4, 5, 6]))
+		.filter_in_task(|value| value % 2 == 0);
+	assert_eq!(gen.collect::<Vec<_>>(), [2, 4, 6]);
+}
+
+#[test]
+fn mapped_one_by_one() {
+	let gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_slice(&[1u32, 2, 3]))
+		.map_in_task(|value| value.to_string());
+	assert_eq!(gen.collect::<Vec<_>>(), ["1", "2", "3"]);
+}
+
+#[test]
+fn chunked() {
+	let gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_slice(&[1u32, 2, 3, 4, 5]));
+	assert_eq!(gen.chunks(2).collect::<Vec<_>>(), [vec![1, 2], vec![3, 4], vec![5]]);
+}
+
+#[test]
+fn cancelled_by_token() {
+	let token = CancelToken::new();
+	let mut gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_slice(&[1u32, 2]);
+		yeet::yeet_slice(&[3u32, 4]);
+	});
+	gen.attach(&token);
+
+	assert_eq!(gen.next(), Some(1));
+	token.cancel();
+	assert_eq!(gen.next(), Some(2));
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+#[should_panic]
+fn wrong_type() {
+	let mut gen = Generator::<u32>::from_fn_ptr(|| yeet::yeet_slice(&[1u8]));
+	gen.next();
+}
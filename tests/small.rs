@@ -0,0 +1,30 @@
+//! This module tests the defaults of builds with the `small` feature.
+#![cfg(all(feature = "small", not(all(windows, feature = "fibers"))))]
+
+use yeet::Generator;
+
+fn stack() {
+	yeet::yeet(yeet::current_task().unwrap().stack);
+}
+
+#[test]
+fn small_stacks() {
+	let mut gen = Generator::<std::ops::Range<usize>>::from_fn_ptr(stack);
+	let bounds = gen.next().unwrap();
+	assert!(bounds.len() >= 64 * 1024);
+	assert!(bounds.len() < 128 * 1024);
+}
+
+#[test]
+fn stacks_are_reused() {
+	let first = Generator::<std::ops::Range<usize>>::from_fn_ptr(stack).next().unwrap();
+	let second = Generator::<std::ops::Range<usize>>::from_fn_ptr(stack).next().unwrap();
+	assert_eq!(first, second);
+}
+
+#[test]
+fn no_stack_depth() {
+	let mut gen = Generator::<std::ops::Range<usize>>::from_fn_ptr(stack);
+	gen.next();
+	assert_eq!(gen.stats().stack_used, 0);
+}
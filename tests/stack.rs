@@ -0,0 +1,126 @@
+//! This module tests the stacks allocated for tasks.
+
+use yeet::GeneratorBuilder;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn shallow() {
+	yeet::yeet(1u32);
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn many_large_stacks() {
+	/* This reserves 64 GiB of address space, but only touches a few pages of
+	 * every stack, which must not require that much memory to be committed. */
+	let mut gens = (0..1024)
+		.map(|_| GeneratorBuilder::new()
+			.stack_size(64 * 1024 * 1024)
+			.build::<u32>(shallow))
+		.collect::<Vec<_>>();
+
+	for gen in &mut gens {
+		assert_eq!(gen.next(), Some(1));
+	}
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn guard_page_below_stacks() {
+	let mut gen = GeneratorBuilder::new()
+		.stack_size(64 * 1024)
+		.build::<usize>(|| yeet::yeet(yeet::current_task().unwrap().stack.start));
+	let start = gen.next().unwrap();
+
+	/* The page right below the stack can be neither read nor written. */
+	let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+	let guard = maps.lines()
+		.find_map(|line| {
+			let (range, rest) = line.split_once(' ')?;
+			let (low, high) = range.split_once('-')?;
+			let low = usize::from_str_radix(low, 16).ok()?;
+			let high = usize::from_str_radix(high, 16).ok()?;
+			(low < start && start - 1 < high).then(|| rest[..4].to_owned())
+		})
+		.unwrap();
+	assert_eq!(guard, "---p");
+}
+
+#[test]
+fn remaining_stack_shrinks() {
+	fn depth(n: usize) -> usize {
+		let padding = std::hint::black_box([0u8; 1024]);
+		match n {
+			0 => yeet::remaining_stack() + padding[0] as usize,
+			n => depth(n - 1) + padding[0] as usize,
+		}
+	}
+
+	let mut gen = GeneratorBuilder::new()
+		.stack_size(256 * 1024)
+		.build_closure::<usize>(|| {
+			let shallow = yeet::remaining_stack();
+			assert!(shallow <= 256 * 1024);
+			assert!(shallow > 200 * 1024);
+			yeet::yeet(shallow);
+
+			let deep = depth(64);
+			assert!(deep < shallow - 64 * 1024);
+			yeet::yeet(deep)
+		});
+
+	assert!(gen.next().is_some());
+	assert!(gen.next().is_some());
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+#[should_panic(expected = "outside a generator")]
+fn remaining_stack_outside_generators() {
+	yeet::remaining_stack();
+}
+
+/// Sums the values in the list by recursing once per value, growing the stack
+/// as it runs out.
+fn sum(values: &[u64]) -> u64 {
+	match values {
+		[] => 0,
+		[first, rest @ ..] => {
+			let padding = std::hint::black_box([0u8; 256]);
+			first + padding[0] as u64 + yeet::maybe_grow(16 * 1024, || sum(rest))
+		}
+	}
+}
+
+#[test]
+fn maybe_grow_on_small_stacks() {
+	let mut gen = GeneratorBuilder::new()
+		.stack_size(64 * 1024)
+		.build_closure::<u64>(|| {
+			let values = (0..50_000).collect::<Vec<u64>>();
+			yeet::yeet(sum(&values))
+		});
+	assert_eq!(gen.next(), Some(50_000 * 49_999 / 2));
+}
+
+#[test]
+fn maybe_grow_outside_generators() {
+	let values = (0..50_000).collect::<Vec<u64>>();
+	assert_eq!(sum(&values), 50_000 * 49_999 / 2);
+}
+
+#[test]
+fn maybe_grow_stays_inline_with_room() {
+	let mut gen = GeneratorBuilder::new()
+		.build_closure::<bool>(|| {
+			let outer = yeet::current_task().unwrap().id;
+			let inner = yeet::maybe_grow(16 * 1024, || yeet::current_task().unwrap().id);
+			yeet::yeet(outer == inner)
+		});
+	assert_eq!(gen.next(), Some(true));
+}
+
+#[test]
+#[should_panic(expected = "ran dry")]
+fn maybe_grow_propagates_panics() {
+	yeet::maybe_grow(16 * 1024, || panic!("ran dry"));
+}
@@ -0,0 +1,47 @@
+//! Tests for starting generators ahead of time.
+use std::cell::Cell;
+use yeet::Generator;
+use yeet::registry::TaskState;
+
+thread_local! {
+	static RAN: Cell<bool> = const { Cell::new(false) };
+}
+
+fn producer() {
+	RAN.set(true);
+	yeet::yeet_all(0u32..3)
+}
+
+#[test]
+fn start_does_not_run_the_producer() {
+	RAN.set(false);
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	assert_eq!(gen.state(), TaskState::Created);
+
+	gen.start();
+	assert_eq!(gen.state(), TaskState::Suspended);
+	assert!(!RAN.get());
+
+	gen.start();
+	assert!(!RAN.get());
+
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 1, 2]);
+	assert!(RAN.get());
+}
+
+#[test]
+fn start_after_resuming_does_nothing() {
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	assert_eq!(gen.next(), Some(0));
+	gen.start();
+	assert_eq!(gen.next(), Some(1));
+}
+
+#[test]
+fn dropping_a_started_generator() {
+	RAN.set(false);
+	let mut gen = Generator::<u32>::from_fn_ptr(producer);
+	gen.start();
+	drop(gen);
+	assert!(!RAN.get());
+}
@@ -0,0 +1,65 @@
+//! This module tests the statistics collected for generators.
+
+use std::time::Duration;
+use yeet::{Generator, GeneratorBuilder, Stats};
+
+fn gen() {
+	yeet::yeet_all(0..10u32);
+	yeet::yield_now();
+}
+
+#[test]
+fn counters() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.stats(), Stats::default());
+
+	assert_eq!(gen.by_ref().count(), 10);
+	let stats = gen.stats();
+	assert_eq!(stats.resumes, 12);
+	assert_eq!(stats.yielded, 10);
+	assert_eq!(stats.cancels, 0);
+	assert_eq!(stats.stack_used > 0, cfg!(not(feature = "small")));
+	assert_eq!(stats.time_in_producer, Duration::ZERO);
+}
+
+#[test]
+fn filtered_values_not_counted() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen).filter_in_task(|value| value % 2 == 0);
+	assert_eq!(gen.by_ref().count(), 5);
+	assert_eq!(gen.stats().yielded, 5);
+}
+
+#[test]
+#[cfg(not(feature = "small"))]
+fn stack_depth() {
+	fn deep() {
+		fn recurse(depth: usize) {
+			let buf = [0u8; 1024];
+			std::hint::black_box(&buf);
+			if depth == 0 {
+				yeet::yeet(0u32)
+			} else {
+				recurse(depth - 1)
+			}
+		}
+		recurse(16)
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(deep);
+	gen.next();
+	assert!(gen.stats().stack_used >= 16 * 1024);
+}
+
+#[test]
+fn time_in_producer() {
+	fn slow() {
+		std::thread::sleep(Duration::from_millis(10));
+		yeet::yeet(0u32);
+	}
+
+	let mut gen = GeneratorBuilder::new()
+		.measure_time(true)
+		.build::<u32>(slow);
+	gen.next();
+	assert!(gen.stats().time_in_producer >= Duration::from_millis(10));
+}
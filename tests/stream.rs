@@ -0,0 +1,109 @@
+//! This module tests the conversion of generators into streams.
+#![cfg(feature = "stream")]
+
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+use std::time::Duration;
+use futures_core::Stream;
+use yeet::{Generator, ThreadGenerator};
+use yeet::stream::IntoStream;
+
+/// Polls the given stream to completion, counting how many times it wasn't
+/// ready.
+fn drain<S: Stream>(stream: S) -> (Vec<S::Item>, usize) {
+	let mut stream = pin!(stream);
+	let mut cx = Context::from_waker(Waker::noop());
+	let mut values = Vec::new();
+	let mut pending = 0;
+	loop {
+		match stream.as_mut().poll_next(&mut cx) {
+			Poll::Ready(Some(value)) => values.push(value),
+			Poll::Ready(None) => break (values, pending),
+			Poll::Pending => pending += 1,
+		}
+	}
+}
+
+/// Accepts anything that may be turned into a stream.
+fn collect<S: IntoStream>(source: S) -> Vec<S::Item> {
+	drain(source.into_stream()).0
+}
+
+fn gen() {
+	yeet::yeet(0u32);
+	yeet::yield_now();
+	yeet::yeet(1u32);
+}
+
+#[test]
+fn generator() {
+	let (values, pending) = drain(Generator::<u32>::from_fn_ptr(gen).into_stream());
+	assert_eq!(values, &[0, 1]);
+	assert_eq!(pending, 1);
+}
+
+#[test]
+fn thread_generator() {
+	assert_eq!(collect(ThreadGenerator::<u32>::from_fn_ptr(gen)), &[0, 1]);
+}
+
+/// Waker unparking the thread polling the stream, and counting how many times
+/// it did.
+struct Unpark {
+	thread: Thread,
+	wakes: AtomicUsize,
+}
+impl Wake for Unpark {
+	fn wake(self: Arc<Self>) {
+		self.wakes.fetch_add(1, Ordering::Relaxed);
+		self.thread.unpark()
+	}
+}
+
+#[test]
+fn thread_generator_wakes() {
+	fn slow() {
+		for i in 0..3u32 {
+			std::thread::sleep(Duration::from_millis(20));
+			yeet::yeet(i);
+		}
+	}
+
+	/* The generators are created one at a time, so that the producer of the
+	 * second one doesn't run ahead while the first one is being polled. */
+	for double_buffered in [false, true] {
+		let gen = match double_buffered {
+			false => ThreadGenerator::<u32>::from_fn_ptr(slow),
+			true => ThreadGenerator::double_buffered(slow),
+		};
+		let unpark = Arc::new(Unpark {
+			thread: std::thread::current(),
+			wakes: AtomicUsize::new(0),
+		});
+		let waker = Waker::from(unpark.clone());
+		let mut cx = Context::from_waker(&waker);
+
+		let mut stream = pin!(gen.into_stream());
+		let mut values = Vec::new();
+		let mut pending = 0;
+		loop {
+			match stream.as_mut().poll_next(&mut cx) {
+				Poll::Ready(Some(value)) => values.push(value),
+				Poll::Ready(None) => break,
+				Poll::Pending => {
+					pending += 1;
+					std::thread::park()
+				}
+			}
+		}
+
+		/* Every time the stream wasn't ready, it got woken up once the next
+		 * value was, rather than asking to be polled again right away. */
+		assert_eq!(values, &[0, 1, 2]);
+		assert!(pending <= 6, "polled {pending} times while not ready");
+		assert!(unpark.wakes.load(Ordering::Relaxed) > 0);
+	}
+}
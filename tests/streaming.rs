@@ -0,0 +1,35 @@
+//! This module tests lending generators as streaming iterators.
+#![cfg(feature = "streaming-iterator")]
+
+use streaming_iterator::StreamingIterator;
+use yeet::LendingGenerator;
+
+fn windows() {
+	let mut buf = [0u32; 3];
+	for i in 0..5 {
+		buf.rotate_left(1);
+		buf[2] = i;
+		yeet::yeet_borrowed(&buf);
+	}
+}
+
+#[test]
+fn adapters() {
+	let gen = LendingGenerator::<u32>::from_fn_ptr(windows);
+	let sums = gen
+		.filter(|window| window[0] != 0)
+		.map(|window| window.iter().sum::<u32>())
+		.cloned()
+		.collect::<Vec<_>>();
+	assert_eq!(sums, &[6, 9]);
+}
+
+#[test]
+fn next_then_get() {
+	let mut gen = LendingGenerator::<u32>::from_fn_ptr(windows);
+	assert_eq!(gen.get(), None);
+	assert_eq!(StreamingIterator::next(&mut gen), Some(&[0, 0, 0][..]));
+	assert_eq!(gen.get(), Some(&[0, 0, 0][..]));
+	assert_eq!(gen.next().as_deref(), Some(&[0, 0, 1][..]));
+	assert_eq!(gen.get(), Some(&[0, 0, 1][..]));
+}
@@ -0,0 +1,100 @@
+//! This module tests the bare context switching primitive.
+
+#![cfg(all(not(feature = "corosensei"), not(all(windows, feature = "fibers")), not(all(unix, feature = "ucontext")), any(target_arch = "x86_64", target_arch = "aarch64")))]
+
+use std::cell::Cell;
+use yeet::raw::{self, Context, Stack, Transfer};
+
+const STACK_SIZE: usize = 64 * 1024;
+
+fn doubler(mut transfer: Transfer) -> ! {
+	loop {
+		let value = unsafe { *(transfer.data as *mut u64) } * 2;
+		let mut out = value;
+		transfer = unsafe { raw::switch(transfer.context, &mut out as *mut u64 as *mut ()) };
+	}
+}
+
+#[test]
+fn ping_pong() {
+	let stack = Stack::new(STACK_SIZE);
+	let mut context = unsafe { raw::make_context(&stack, doubler) };
+
+	for i in 0u64..100 {
+		let mut value = i;
+		let transfer = unsafe { raw::switch(context, &mut value as *mut u64 as *mut ()) };
+		assert_eq!(unsafe { *(transfer.data as *mut u64) }, i * 2);
+		context = transfer.context;
+	}
+}
+
+#[test]
+fn runs_on_its_stack() {
+	fn report(transfer: Transfer) -> ! {
+		let local = 0u8;
+		unsafe { raw::switch(transfer.context, &local as *const u8 as *mut ()) };
+		unreachable!()
+	}
+
+	let stack = Stack::new(STACK_SIZE);
+	let context = unsafe { raw::make_context(&stack, report) };
+	let transfer = unsafe { raw::switch(context, std::ptr::null_mut()) };
+	assert!(stack.bounds().contains(&(transfer.data as usize)));
+}
+
+thread_local! {
+	static TRAIL: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Switches over to the context handed to it, which then switches straight
+/// back to the one that switched to it first.
+fn relay(transfer: Transfer) -> ! {
+	let main = transfer.context;
+	let next = unsafe { Box::from_raw(transfer.data as *mut Context) };
+
+	TRAIL.set(TRAIL.get() * 10 + 1);
+	let transfer = unsafe { raw::switch(*next, Box::into_raw(Box::new(main)) as *mut ()) };
+	TRAIL.set(TRAIL.get() * 10 + 3);
+	unsafe { raw::switch(transfer.context, std::ptr::null_mut()) };
+	unreachable!()
+}
+
+fn leaf(transfer: Transfer) -> ! {
+	let main = unsafe { Box::from_raw(transfer.data as *mut Context) };
+	TRAIL.set(TRAIL.get() * 10 + 2);
+	unsafe { raw::switch(*main, Box::into_raw(Box::new(transfer.context)) as *mut ()) };
+	unreachable!()
+}
+
+#[test]
+fn contexts_switch_between_each_other() {
+	let first = Stack::new(STACK_SIZE);
+	let second = Stack::new(STACK_SIZE);
+	let relay = unsafe { raw::make_context(&first, relay) };
+	let leaf = unsafe { raw::make_context(&second, leaf) };
+
+	/* Main hands the leaf to the relay, which switches to the leaf, which
+	 * comes back to main with the relay, which main then finishes off. */
+	TRAIL.set(0);
+	let transfer = unsafe { raw::switch(relay, Box::into_raw(Box::new(leaf)) as *mut ()) };
+	let relay = unsafe { Box::from_raw(transfer.data as *mut Context) };
+	assert_eq!(TRAIL.get(), 12);
+
+	unsafe { raw::switch(*relay, std::ptr::null_mut()) };
+	assert_eq!(TRAIL.get(), 123);
+}
+
+#[test]
+fn switches_inside_a_generator() {
+	let mut gen = yeet::Generator::<u64>::from_fn_ptr(|| {
+		let stack = Stack::new(STACK_SIZE);
+		let mut context = unsafe { raw::make_context(&stack, doubler) };
+		for i in 0u64..3 {
+			let mut value = i;
+			let transfer = unsafe { raw::switch(context, &mut value as *mut u64 as *mut ()) };
+			yeet::yeet(unsafe { *(transfer.data as *mut u64) });
+			context = transfer.context;
+		}
+	});
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 2, 4]);
+}
@@ -0,0 +1,140 @@
+//! This module tests the synchronization primitives that know about yields.
+#![cfg(debug_assertions)]
+
+use yeet::Generator;
+use yeet::sync::{self, HeldGuardPolicy, TrackedMutex, TrackedRwLock};
+
+static MUTEX: TrackedMutex<u32> = TrackedMutex::new(0);
+static RWLOCK: TrackedRwLock<u32> = TrackedRwLock::new(0);
+
+#[test]
+fn released_before_yield() {
+	fn gen() {
+		let value = {
+			let mut guard = MUTEX.lock().unwrap();
+			*guard += 1;
+			assert_eq!(sync::held_guards(), 1);
+			*guard
+		};
+		assert_eq!(sync::held_guards(), 0);
+		yeet::yeet(value);
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert!(gen.next().is_some());
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+#[should_panic]
+fn held_across_yield() {
+	fn gen() {
+		let guard = RWLOCK.read().unwrap();
+		yeet::yeet(*guard);
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	let _ = gen.next();
+}
+
+#[test]
+fn logged() {
+	fn gen() {
+		let guard = RWLOCK.read().unwrap();
+		yeet::yeet(*guard);
+	}
+
+	sync::set_held_guard_policy(HeldGuardPolicy::Log);
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.next(), Some(0));
+	assert_eq!(gen.next(), None);
+}
+
+mod task {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+	use yeet::Generator;
+	use yeet::sync::{BorrowError, Poisoned, TaskCell, TaskMutex};
+
+	thread_local! {
+		static CELL: Rc<TaskCell<Vec<u32>>> = Rc::new(TaskCell::new(Vec::new()));
+	}
+	static MUTEX: TaskMutex<u32> = TaskMutex::new(0);
+
+	#[test]
+	fn cell_shared_between_iterations() {
+		fn gen() {
+			for i in 0..3 {
+				let len = CELL.with(|cell| {
+					let mut values = cell.borrow_mut().unwrap();
+					values.push(i);
+					values.len()
+				});
+				yeet::yeet(len);
+			}
+		}
+
+		let gen = Generator::<usize>::from_fn_ptr(gen);
+		assert_eq!(gen.collect::<Vec<_>>(), &[1, 2, 3]);
+		CELL.with(|cell| assert_eq!(*cell.borrow().unwrap(), &[0, 1, 2]));
+	}
+
+	#[test]
+	fn cell_poisoned_by_yield() {
+		thread_local! {
+			static HELD: RefCell<Option<Rc<TaskCell<u32>>>> = const { RefCell::new(None) };
+		}
+		fn gen() {
+			let cell = HELD.with_borrow(|cell| cell.clone().unwrap());
+			let value = cell.borrow_mut().unwrap();
+			yeet::yeet(*value);
+		}
+
+		let cell = Rc::new(TaskCell::new(7));
+		HELD.set(Some(cell.clone()));
+
+		let mut gen = Generator::<u32>::from_fn_ptr(gen);
+		assert_eq!(gen.next(), Some(7));
+		assert!(cell.is_poisoned());
+		assert_eq!(cell.borrow().err(), Some(BorrowError::Poisoned));
+
+		drop(gen);
+		cell.clear_poison();
+		assert_eq!(*cell.borrow().unwrap(), 7);
+	}
+
+	#[test]
+	fn mutex_poisoned_by_yield() {
+		fn gen() {
+			let mut guard = MUTEX.lock().unwrap();
+			*guard += 1;
+			yeet::yeet(*guard);
+		}
+
+		let mut gen = Generator::<u32>::from_fn_ptr(gen);
+		assert_eq!(gen.next(), Some(1));
+
+		/* This would deadlock with a regular mutex. */
+		assert_eq!(MUTEX.lock().err(), Some(Poisoned));
+		drop(gen);
+	}
+
+	#[test]
+	fn forgotten_guard_outlived_by_yield() {
+		fn gen() {
+			let cell = Box::new(TaskCell::new(1u32));
+			std::mem::forget(cell.borrow_mut().unwrap());
+			drop(cell);
+			yeet::yeet(0u32);
+
+			let cell = TaskCell::new(2u32);
+			std::mem::forget(cell.borrow().unwrap());
+			yeet::yeet(0u32);
+			assert!(cell.is_poisoned());
+			yeet::yeet(cell.into_inner());
+		}
+
+		let gen = Generator::<u32>::from_fn_ptr(gen);
+		assert_eq!(gen.collect::<Vec<_>>(), &[0, 0, 2]);
+	}
+}
@@ -0,0 +1,115 @@
+//! This module tests the step driver.
+
+use std::panic::AssertUnwindSafe;
+use yeet::{Generate, Generator, Resume};
+use yeet::testing::{MockGenerator, MockStep, Step, StepDriver};
+
+fn count() {
+	yeet::yeet_all(0..2u32)
+}
+
+#[test]
+fn run() {
+	let mut driver = StepDriver::<u32>::new();
+	driver.add("a", Generator::from_fn_ptr(count));
+	driver.add("b", Generator::from_fn_ptr(count));
+
+	let steps = driver.run(&["a", "a", "b", "a", "a", "b"]);
+	assert_eq!(steps, &[
+		Step::Yielded(0),
+		Step::Yielded(1),
+		Step::Yielded(0),
+		Step::Finished,
+		Step::Finished,
+		Step::Yielded(1),
+	]);
+
+	let mut b = driver.remove("b").unwrap();
+	assert_eq!(b.next(), None);
+}
+
+#[test]
+#[should_panic]
+fn unknown() {
+	let mut driver = StepDriver::<u32>::new();
+	driver.step("missing");
+}
+
+#[test]
+#[should_panic]
+fn mismatch() {
+	let mut driver = StepDriver::<u32>::new();
+	driver.add("a", Generator::from_fn_ptr(count));
+	driver.assert_step("a", Step::Yielded(1));
+}
+
+/// Consumer under test, which sums values until it sees a zero.
+fn sum_until_zero(mut gen: impl Generate<u32>) -> u32 {
+	let mut sum = 0;
+	while let Some(value) = gen.next_value() {
+		if value == 0 {
+			break
+		}
+		sum += value
+	}
+
+	sum
+}
+
+#[test]
+fn mock_steps() {
+	let mut mock = MockGenerator::new([
+		MockStep::Yield(1),
+		MockStep::Pending,
+		MockStep::Yield(2),
+	]);
+	let handle = mock.handle();
+
+	assert_eq!(mock.resume(), Resume::Value(1));
+	assert_eq!(mock.resume(), Resume::Pending);
+	assert_eq!(mock.remaining(), 1);
+	assert_eq!(mock.resume(), Resume::Value(2));
+	assert!(!handle.finished());
+	assert_eq!(mock.resume(), Resume::Complete);
+	assert_eq!(mock.resume(), Resume::Complete);
+	assert!(handle.finished());
+	assert_eq!(handle.resumes(), 4);
+
+	drop(mock);
+	assert!(!handle.cancelled());
+}
+
+#[test]
+fn mock_cancellation() {
+	let mock = MockGenerator::from_values([3, 4, 0, 5]);
+	let handle = mock.handle();
+
+	assert_eq!(sum_until_zero(mock), 7);
+	assert!(handle.cancelled());
+	assert!(!handle.finished());
+
+	let mock = MockGenerator::from_values([3, 4]);
+	let handle = mock.handle();
+	assert_eq!(sum_until_zero(mock), 7);
+	assert!(!handle.cancelled());
+}
+
+#[test]
+fn mock_panic() {
+	let mut mock = MockGenerator::new([MockStep::Yield(1u32), MockStep::Panic("boom".into())]);
+	let handle = mock.handle();
+
+	assert_eq!(mock.next(), Some(1));
+	let payload = std::panic::catch_unwind(AssertUnwindSafe(|| mock.next())).unwrap_err();
+	assert_eq!(payload.downcast_ref::<String>().map(String::as_str), Some("boom"));
+	assert_eq!(mock.next(), None);
+	assert!(handle.finished());
+}
+
+#[test]
+fn generators_are_generate() {
+	let gen = Generator::<u32>::from_fn_ptr(|| {
+		yeet::yeet_all([1u32, 2, 0, 3].into_iter())
+	});
+	assert_eq!(sum_until_zero(gen), 3);
+}
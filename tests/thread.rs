@@ -0,0 +1,115 @@
+//! This module tests generators whose producers run on dedicated threads.
+
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use yeet::{ThreadGenerator, Timeout};
+
+fn count() {
+	yeet::yeet_all(0..100u32)
+}
+
+#[test]
+fn values() {
+	let gen = ThreadGenerator::<u32>::from_fn_ptr(count);
+	assert_eq!(gen.collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn runs_on_other_thread() {
+	fn gen() {
+		yeet::yeet(std::thread::current().id())
+	}
+
+	let mut gen = ThreadGenerator::from_fn_ptr(gen);
+	assert_ne!(gen.next(), Some(std::thread::current().id()));
+}
+
+#[test]
+fn blocking_producer() {
+	fn gen() {
+		yeet::yeet(0u32);
+		std::thread::sleep(Duration::from_millis(50));
+		yeet::yeet(1u32);
+	}
+
+	let mut gen = ThreadGenerator::<u32>::with_capacity(1, gen);
+	assert_eq!(gen.next_timeout(Duration::from_secs(10)), Ok(Some(0)));
+	assert_eq!(gen.next_timeout(Duration::from_millis(1)), Err(Timeout));
+	assert_eq!(gen.next_timeout(Duration::from_secs(10)), Ok(Some(1)));
+	assert_eq!(gen.next_timeout(Duration::from_secs(10)), Ok(None));
+}
+
+#[test]
+fn panic() {
+	fn gen() {
+		yeet::yeet(0u32);
+		panic!("producer panic")
+	}
+
+	let mut gen = ThreadGenerator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.next(), Some(0));
+	let what = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())).unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"producer panic"));
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn early_drop() {
+	fn endless() {
+		yeet::yeet_all(0u32..)
+	}
+
+	let mut gen = ThreadGenerator::<u32>::from_fn_ptr(endless);
+	assert_eq!(gen.next(), Some(0));
+	drop(gen);
+}
+
+#[test]
+fn unbounded_timeout() {
+	for mut gen in [ThreadGenerator::<u32>::from_fn_ptr(count), ThreadGenerator::double_buffered(count)] {
+		assert_eq!(gen.next_timeout(Duration::MAX), Ok(Some(0)));
+		assert_eq!(gen.by_ref().count(), 99);
+		assert_eq!(gen.next_timeout(Duration::MAX), Ok(None));
+	}
+}
+
+#[test]
+fn double_buffered() {
+	let gen = ThreadGenerator::<u32>::double_buffered(count);
+	assert_eq!(gen.collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn double_buffered_overlaps() {
+	use std::sync::atomic::{AtomicU32, Ordering};
+	static PRODUCED: AtomicU32 = AtomicU32::new(0);
+	fn gen() {
+		for i in 0..4u32 {
+			PRODUCED.store(i, Ordering::SeqCst);
+			yeet::yeet(i);
+		}
+	}
+
+	let mut gen = ThreadGenerator::<u32>::double_buffered(gen);
+	assert_eq!(gen.next(), Some(0));
+
+	/* While the consumer holds on to the first value, the producer fills both
+	 * slots, and then has to wait for one of them to be freed. */
+	std::thread::sleep(Duration::from_millis(50));
+	assert_eq!(PRODUCED.load(Ordering::SeqCst), 3);
+
+	assert_eq!(gen.next_timeout(Duration::from_secs(10)), Ok(Some(1)));
+	assert_eq!(gen.collect::<Vec<_>>(), &[2, 3]);
+}
+
+#[test]
+fn double_buffered_panic() {
+	fn gen() {
+		panic!("producer panic")
+	}
+
+	let mut gen = ThreadGenerator::<u32>::double_buffered(gen);
+	let what = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())).unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"producer panic"));
+	assert_eq!(gen.next(), None);
+}
@@ -0,0 +1,57 @@
+//! This module tests that generators refuse to be resumed on a thread other
+//! than the one they were created on.
+
+use std::panic::AssertUnwindSafe;
+use yeet::{Generator, GeneratorBuilder};
+
+/// Smuggles a generator across threads, which is exactly what the check is
+/// meant to catch.
+struct Smuggled(Generator<u32>);
+unsafe impl Send for Smuggled {}
+
+fn gen() {
+	yeet::yeet_all(0..3u32)
+}
+
+/// Resumes the given generator on another thread, returning the message of
+/// the panic it raised, along with the generator.
+fn resume_elsewhere(gen: Generator<u32>) -> (String, Generator<u32>) {
+	let mut smuggled = Smuggled(gen);
+	std::thread::spawn(move || {
+		let result = std::panic::catch_unwind(AssertUnwindSafe(|| smuggled.0.next()));
+		let payload = result.expect_err("resuming on another thread must panic");
+		let message = payload.downcast_ref::<String>().cloned().unwrap_or_default();
+
+		(message, smuggled)
+	}).join().map(|(message, smuggled)| (message, smuggled.0)).unwrap()
+}
+
+#[test]
+fn before_starting() {
+	let gen = GeneratorBuilder::new()
+		.name("checked")
+		.build::<u32>(gen);
+	let id = gen.id();
+
+	let (message, mut gen) = resume_elsewhere(gen);
+	assert_eq!(message, format!("Tried to resume generator \"checked\" (#{}) on a thread other than the one it was created on!", id.as_u64()));
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn after_starting() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.next(), Some(0));
+
+	let (message, mut gen) = resume_elsewhere(gen);
+	assert!(message.contains("on a thread other than the one it was created on"));
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn dropped_elsewhere() {
+	let mut gen = Smuggled(Generator::<u32>::from_fn_ptr(gen));
+	assert_eq!(gen.0.next(), Some(0));
+
+	std::thread::spawn(move || drop(gen)).join().unwrap();
+}
@@ -0,0 +1,31 @@
+//! This module tests resuming producers with a deadline.
+
+use std::time::{Duration, Instant};
+use yeet::{Generator, Timeout};
+
+fn slow() {
+	yeet::yeet(0u32);
+
+	let until = Instant::now() + Duration::from_millis(50);
+	while Instant::now() < until {
+		yeet::yield_now();
+	}
+	yeet::yeet(1u32);
+}
+
+#[test]
+fn timeout() {
+	let mut gen = Generator::<u32>::from_fn_ptr(slow);
+	assert_eq!(gen.next_timeout(Duration::from_millis(10)), Ok(Some(0)));
+	assert_eq!(gen.next_timeout(Duration::from_millis(10)), Err(Timeout));
+	assert_eq!(gen.next_timeout(Duration::from_secs(10)), Ok(Some(1)));
+	assert_eq!(gen.next_timeout(Duration::from_millis(10)), Ok(None));
+}
+
+#[test]
+fn unbounded() {
+	let mut gen = Generator::<u32>::from_fn_ptr(slow);
+	assert_eq!(gen.next_timeout(Duration::MAX), Ok(Some(0)));
+	assert_eq!(gen.next_timeout(Duration::MAX), Ok(Some(1)));
+	assert_eq!(gen.next_timeout(Duration::MAX), Ok(None));
+}
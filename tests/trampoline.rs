@@ -0,0 +1,41 @@
+//! This module tests trampolined computations.
+
+fn is_even(n: u64) -> yeet::Bounce<bool> {
+	if n == 0 { yeet::Bounce::Done(true) } else { yeet::Bounce::call(move || is_odd(n - 1)) }
+}
+
+fn is_odd(n: u64) -> yeet::Bounce<bool> {
+	if n == 0 { yeet::Bounce::Done(false) } else { yeet::Bounce::call(move || is_even(n - 1)) }
+}
+
+#[test]
+fn mutual_recursion() {
+	assert!(yeet::trampoline(|| is_even(20_000)));
+	assert!(!yeet::trampoline(|| is_even(20_001)));
+}
+
+#[test]
+fn steps_run_on_fresh_tasks() {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	let ids = Rc::new(RefCell::new(Vec::new()));
+	let record = ids.clone();
+	let steps = yeet::trampoline(move || {
+		record.borrow_mut().push(yeet::current_task().unwrap().id);
+		yeet::Bounce::call(move || {
+			record.borrow_mut().push(yeet::current_task().unwrap().id);
+			yeet::Bounce::Done(record.borrow().len())
+		})
+	});
+
+	let ids = ids.borrow();
+	assert_eq!(steps, 2);
+	assert_ne!(ids[0], ids[1]);
+}
+
+#[test]
+#[should_panic(expected = "gave up")]
+fn propagates_panics() {
+	yeet::trampoline(|| yeet::Bounce::<u32>::call(|| panic!("gave up")));
+}
@@ -0,0 +1,51 @@
+//! This module tests generators whose producers yield through a yielder.
+
+use std::panic::AssertUnwindSafe;
+use yeet::{Generator, GeneratorBuilder, Resume, Yielder};
+
+fn count(y: Yielder<u32>) {
+	y.yeet(0);
+	y.yield_now();
+	y.yeet_all(1..3);
+}
+
+#[test]
+fn yields_through_the_yielder() {
+	let mut gen = Generator::from_typed_fn(count);
+	assert_eq!(gen.resume(), Resume::Value(0));
+	assert_eq!(gen.resume(), Resume::Pending);
+	assert_eq!(gen.collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn builder() {
+	let gen = GeneratorBuilder::new()
+		.name("typed")
+		.build_typed(count);
+	assert_eq!(gen.name(), Some("typed"));
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 1, 2]);
+}
+
+#[test]
+fn mixes_with_untyped_yields() {
+	let gen = Generator::<u32>::from_typed_fn(|y| {
+		y.yeet(1);
+		yeet::yeet(2u32);
+	});
+	assert_eq!(gen.collect::<Vec<_>>(), [1, 2]);
+}
+
+#[test]
+fn nested_producers_can_not_use_it() {
+	let mut gen = Generator::<u32>::from_typed_fn(|y| {
+		let inner = Generator::<u32>::from_closure(move || y.yeet(1));
+		for value in inner {
+			yeet::yeet(value);
+		}
+	});
+
+	let panic = std::panic::catch_unwind(AssertUnwindSafe(|| gen.next())).unwrap_err();
+	assert_eq!(
+		panic.downcast_ref::<&str>(),
+		Some(&"Tried to yield through a yielder from outside of its producer!"));
+}
@@ -0,0 +1,46 @@
+//! This module tests running tasks on top of `swapcontext`.
+#![cfg(all(unix, feature = "ucontext", not(feature = "corosensei")))]
+
+use yeet::Generator;
+
+fn inner() {
+	yeet::yeet_all(0u32..3)
+}
+
+fn outer() {
+	for value in Generator::<u32>::from_fn_ptr(inner) {
+		yeet::yeet(value * 10);
+	}
+}
+
+#[test]
+fn nested() {
+	let gen = Generator::<u32>::from_fn_ptr(outer);
+	assert_eq!(gen.collect::<Vec<_>>(), [0, 10, 20]);
+}
+
+#[test]
+fn moved_between_resumes() {
+	let mut gens = vec![Generator::<u32>::from_fn_ptr(inner)];
+	assert_eq!(gens[0].next(), Some(0));
+
+	let mut gen = gens.pop().unwrap();
+	assert_eq!(gen.next(), Some(1));
+	assert_eq!(gen.next(), Some(2));
+	assert_eq!(gen.next(), None);
+}
+
+#[test]
+fn floating_point_survives_switches() {
+	let mut gen = Generator::<f64>::from_fn_ptr(|| {
+		let mut value = 1.5f64;
+		for _ in 0..3 {
+			yeet::yeet(value);
+			value *= 2.0;
+		}
+	});
+	let mut other = Generator::<f64>::from_fn_ptr(|| yeet::yeet(-0.25f64));
+	assert_eq!(gen.next(), Some(1.5));
+	assert_eq!(other.next(), Some(-0.25));
+	assert_eq!(gen.by_ref().collect::<Vec<_>>(), [3.0, 6.0]);
+}
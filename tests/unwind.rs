@@ -0,0 +1,95 @@
+//! Tests for dropping generators while the consumer is unwinding.
+use std::cell::Cell;
+use yeet::{Generator, GeneratorBuilder};
+
+thread_local! {
+	static DROPPED: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Counts how many times it gets dropped.
+struct Guard;
+impl Drop for Guard {
+	fn drop(&mut self) {
+		DROPPED.set(DROPPED.get() + 1)
+	}
+}
+
+/// Panics when dropped while unwinding, which aborts the process.
+struct Bomb;
+impl Drop for Bomb {
+	fn drop(&mut self) {
+		if std::thread::panicking() {
+			panic!("bomb went off")
+		}
+	}
+}
+
+fn deep(n: u32) {
+	let _guard = Guard;
+	if n > 0 {
+		let mut inner = Generator::<u32>::from_fn_with(n - 1, deep);
+		inner.next();
+	}
+	yeet::yeet_all(0u32..)
+}
+
+#[test]
+fn cancels_while_unwinding() {
+	DROPPED.set(0);
+	let result = std::panic::catch_unwind(|| {
+		let mut gen = Generator::<u32>::from_fn_with(4, deep);
+		gen.next();
+		panic!("consumer panicked");
+	});
+
+	assert!(result.is_err());
+	assert_eq!(DROPPED.get(), 5);
+}
+
+#[test]
+fn ignores_cancellation_panics_while_unwinding() {
+	let result = std::panic::catch_unwind(|| {
+		let mut gen = Generator::<u32>::from_fn_ptr(|| {
+			let _ = std::panic::catch_unwind(|| yeet::yeet_all(0u32..));
+			panic!("producer panicked while cancelled");
+		});
+		gen.next();
+		panic!("consumer panicked");
+	});
+
+	let what = result.unwrap_err();
+	assert_eq!(what.downcast_ref::<&str>(), Some(&"consumer panicked"));
+}
+
+#[test]
+fn leaks_while_unwinding() {
+	DROPPED.set(0);
+	let result = std::panic::catch_unwind(|| {
+		let mut gen = GeneratorBuilder::new()
+			.leak_on_unwind(true)
+			.build::<u32>(|| {
+				let _guard = Guard;
+				let _bomb = Bomb;
+				yeet::yeet_all(0u32..)
+			});
+		gen.next();
+		panic!("consumer panicked");
+	});
+
+	assert!(result.is_err());
+	assert_eq!(DROPPED.get(), 0);
+}
+
+#[test]
+fn cancels_when_not_unwinding() {
+	DROPPED.set(0);
+	let mut gen = GeneratorBuilder::new()
+		.leak_on_unwind(true)
+		.build::<u32>(|| {
+			let _guard = Guard;
+			yeet::yeet_all(0u32..)
+		});
+	gen.next();
+	drop(gen);
+	assert_eq!(DROPPED.get(), 1);
+}
@@ -0,0 +1,62 @@
+//! This module tests the generators that walk over trees.
+
+use std::collections::HashSet;
+use yeet::walk;
+
+/// Children of the nodes of a complete binary tree of the given depth, where
+/// the children of node `n` are `2n + 1` and `2n + 2`.
+fn binary(depth: u32) -> impl FnMut(&u32) -> Vec<u32> + 'static {
+	let last = (1 << depth) - 1;
+	move |n| [2 * n + 1, 2 * n + 2].into_iter().filter(|c| *c < last).collect()
+}
+
+#[test]
+fn orders() {
+	let dfs = walk::depth_first(0u32, binary(3)).collect::<Vec<_>>();
+	assert_eq!(dfs, [0, 1, 3, 4, 2, 5, 6]);
+
+	let bfs = walk::breadth_first(0u32, binary(3)).collect::<Vec<_>>();
+	assert_eq!(bfs, [0, 1, 2, 3, 4, 5, 6]);
+
+	let post = walk::post_order(0u32, binary(3)).collect::<Vec<_>>();
+	assert_eq!(post, [3, 4, 1, 5, 6, 2, 0]);
+}
+
+#[test]
+fn single_node() {
+	let none = |_: &u32| Vec::new();
+	assert_eq!(walk::depth_first(7u32, none).collect::<Vec<_>>(), [7]);
+	assert_eq!(walk::breadth_first(7u32, none).collect::<Vec<_>>(), [7]);
+	assert_eq!(walk::post_order(7u32, none).collect::<Vec<_>>(), [7]);
+}
+
+#[test]
+fn deep_trees() {
+	/* A path far deeper than a recursive walk could manage on its stack. */
+	let path = |n: &u32| (*n < 1_000_000).then_some(n + 1);
+	assert_eq!(walk::depth_first(0u32, path).count(), 1_000_001);
+	assert_eq!(walk::post_order(0u32, path).next(), Some(1_000_000));
+}
+
+#[test]
+fn lazy() {
+	/* Only as much of an infinite tree gets listed as gets walked. */
+	let mut dfs = walk::depth_first(0u64, |n| [n * 2 + 1, n * 2 + 2]);
+	assert_eq!(dfs.by_ref().take(4).collect::<Vec<_>>(), [0, 1, 3, 7]);
+
+	let bfs = walk::breadth_first(0u64, |n| [n * 2 + 1, n * 2 + 2]);
+	assert_eq!(bfs.take(5).collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn graphs_with_cycles() {
+	let edges = [(0u32, 1u32), (0, 2), (1, 2), (2, 0), (2, 3)];
+	let mut seen = HashSet::from([0u32]);
+	let children = move |n: &u32| edges.iter()
+		.filter(|(from, _)| from == n)
+		.map(|(_, to)| *to)
+		.filter(|to| seen.insert(*to))
+		.collect::<Vec<_>>();
+
+	assert_eq!(walk::breadth_first(0u32, children).collect::<Vec<_>>(), [0, 1, 2, 3]);
+}
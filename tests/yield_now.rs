@@ -0,0 +1,63 @@
+//! This module tests checkpoints reached by producers without yielding values.
+
+use yeet::{Generator, Resume};
+
+fn gen() {
+	yeet::yield_now();
+	yeet::yeet(1u32);
+	yeet::yield_now();
+	yeet::yield_now();
+	yeet::yeet(2u32);
+}
+
+#[test]
+fn resume() {
+	let mut gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.resume(), Resume::Pending);
+	assert_eq!(gen.resume(), Resume::Value(1));
+	assert_eq!(gen.resume(), Resume::Pending);
+	assert_eq!(gen.resume(), Resume::Pending);
+	assert_eq!(gen.resume(), Resume::Value(2));
+	assert_eq!(gen.resume(), Resume::Complete);
+	assert_eq!(gen.resume(), Resume::Complete);
+}
+
+#[test]
+fn next_skips_pending() {
+	let gen = Generator::<u32>::from_fn_ptr(gen);
+	assert_eq!(gen.collect::<Vec<_>>(), &[1, 2]);
+}
+
+#[test]
+fn cancel_at_checkpoint() {
+	use std::cell::Cell;
+	thread_local! {
+		static DROPPED: Cell<bool> = const { Cell::new(false) };
+	}
+	struct Flag;
+	impl Drop for Flag {
+		fn drop(&mut self) {
+			DROPPED.set(true)
+		}
+	}
+
+	fn spin() {
+		let _flag = Flag;
+		loop {
+			yeet::yield_now();
+		}
+	}
+
+	let mut gen = Generator::<u32>::from_fn_ptr(spin);
+	for _ in 0..10 {
+		assert_eq!(gen.resume(), Resume::Pending);
+	}
+	drop(gen);
+	assert!(DROPPED.get());
+}
+
+#[test]
+#[should_panic]
+fn outside_generator() {
+	yeet::yield_now();
+}